@@ -0,0 +1,355 @@
+//! The timer actor: the thread that owns the `App`.
+//!
+//! Nothing else touches the app directly. The main thread, the IPC listener
+//! and the webhook sender send it commands through an `AppHandle` and get
+//! their answers back over a channel; the menu is drawn from `MenuSnapshot`s
+//! the actor hands out. Between commands the actor ticks the timer, unless
+//! the event loop schedules the ticks (`TimerMode::EventLoop`).
+
+use crate::app::App;
+use crate::idle;
+use crate::models::TimerMode;
+use crate::timer::{Clock, SystemClock, Ticker, TimerMessage, TICK_INTERVAL};
+use crate::webhook;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// A command for the actor.
+pub enum Command {
+    /// Runs a function on the app.
+    Run(Box<dyn FnOnce(&mut App) + Send>),
+    /// Ticks right away instead of at the next scheduled tick.
+    Tick,
+    /// Stops the actor, which hands the app back.
+    Shutdown,
+}
+
+/// What woke the actor up.
+pub enum Wake {
+    /// The next tick is due.
+    Tick,
+    /// A command arrived before the tick.
+    Command(Command),
+    /// Every handle is gone.
+    Disconnected,
+}
+
+/// Sends commands to the actor. Cheap to clone; every thread that needs the
+/// app holds its own.
+#[derive(Clone)]
+pub struct AppHandle {
+    commands: Sender<Command>,
+}
+
+impl AppHandle {
+    /// Runs `f` on the actor and waits for its result. Panics if the actor
+    /// has stopped, which only happens on quit.
+    pub fn call<R: Send + 'static>(&self, f: impl FnOnce(&mut App) -> R + Send + 'static) -> R {
+        self.try_call(f).expect("timer actor has stopped")
+    }
+
+    /// Runs `f` on the actor and waits for its result, or returns None if
+    /// the actor has stopped.
+    pub fn try_call<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut App) -> R + Send + 'static,
+    ) -> Option<R> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(move |app| {
+            let _ = reply_tx.send(f(app));
+        });
+        reply_rx.recv().ok()
+    }
+
+    /// Runs `f` on the actor without waiting for it. Commands run in the
+    /// order they were sent.
+    pub fn send(&self, f: impl FnOnce(&mut App) + Send + 'static) {
+        let _ = self.commands.send(Command::Run(Box::new(f)));
+    }
+
+    /// Asks the actor to tick now, e.g. right after the Mac wakes up, so a
+    /// timer that ran out during sleep completes at once. Does nothing when
+    /// the event loop schedules the ticks.
+    pub fn tick_now(&self) {
+        let _ = self.commands.send(Command::Tick);
+    }
+}
+
+/// The actor thread.
+pub struct TimerActor {
+    handle: AppHandle,
+    thread: JoinHandle<App>,
+}
+
+impl TimerActor {
+    /// Moves `app` onto a new actor thread. In `TimerMode::Thread` the actor
+    /// ticks the timer every second and sends the results through `tx`.
+    pub fn spawn(app: App, tx: Sender<TimerMessage>, mode: TimerMode) -> Self {
+        let clock: Option<Arc<dyn Clock + Send + Sync>> = match mode {
+            TimerMode::Thread => Some(Arc::new(SystemClock)),
+            TimerMode::EventLoop => None,
+        };
+        Self::spawn_with(app, tx, clock)
+    }
+
+    /// Moves `app` onto a new actor thread that ticks on `clock`, or never
+    /// ticks without one.
+    pub fn spawn_with(
+        app: App,
+        tx: Sender<TimerMessage>,
+        clock: Option<Arc<dyn Clock + Send + Sync>>,
+    ) -> Self {
+        let (commands, inbox) = mpsc::channel();
+        let handle = AppHandle { commands };
+        let actor_handle = handle.clone();
+        let thread =
+            thread::spawn(move || run_actor(app, &inbox, &actor_handle, &tx, clock.as_deref()));
+        Self { handle, thread }
+    }
+
+    /// Returns a new handle to the actor.
+    pub fn handle(&self) -> AppHandle {
+        self.handle.clone()
+    }
+
+    /// Stops the actor and takes the app back. Commands sent before this
+    /// and a tick under way complete first, so the app is never left
+    /// half-updated. Returns None if the actor panicked.
+    pub fn shutdown(self) -> Option<App> {
+        let _ = self.handle.commands.send(Command::Shutdown);
+        match self.thread.join() {
+            Ok(app) => Some(app),
+            Err(_) => {
+                eprintln!("Timer actor panicked");
+                None
+            }
+        }
+    }
+}
+
+/// Runs commands as they arrive, and ticks every second in between when
+/// there is a clock. Returns the app once told to shut down.
+fn run_actor(
+    mut app: App,
+    inbox: &Receiver<Command>,
+    handle: &AppHandle,
+    tx: &Sender<TimerMessage>,
+    clock: Option<&(dyn Clock + Send + Sync)>,
+) -> App {
+    let mut ticker = clock.map(|clock| Ticker::new(clock.now()));
+    let mut next_tick = Instant::now() + TICK_INTERVAL;
+
+    loop {
+        let wake = match clock {
+            Some(clock) => clock.wait_for_tick(next_tick, inbox),
+            None => inbox.recv().map_or(Wake::Disconnected, Wake::Command),
+        };
+        match wake {
+            Wake::Tick | Wake::Command(Command::Tick) => {
+                // Like the event-loop timer, the next tick is a second after this one
+                next_tick = Instant::now() + TICK_INTERVAL;
+                let (Some(clock), Some(ticker)) = (clock, ticker.as_mut()) else {
+                    continue;
+                };
+                let output = ticker.step(&mut app, clock.now(), idle::seconds_since_last_input());
+                for msg in output.messages {
+                    let _ = tx.send(msg);
+                }
+                if output.summary_queued {
+                    webhook::spawn_flush(handle.clone());
+                }
+            }
+            Wake::Command(Command::Run(f)) => f(&mut app),
+            Wake::Command(Command::Shutdown) | Wake::Disconnected => return app,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::CompletionEvent;
+    use crate::models::TimerState;
+    use crate::persistence::Database;
+    use crate::test_utils::FakeClock;
+    use std::time::{Duration, SystemTime};
+
+    fn test_app() -> App {
+        let db = Database::new_in_memory().unwrap();
+        let mut app = App::new_with_db(db).unwrap();
+        app.start_pomodoro();
+        app
+    }
+
+    /// Runs an actor for `app` on a fake clock.
+    fn spawn_fake_actor(app: App) -> (TimerActor, Arc<FakeClock>, Receiver<TimerMessage>) {
+        let clock = Arc::new(FakeClock::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        let (tx, rx) = mpsc::channel();
+        let loop_clock: Arc<dyn Clock + Send + Sync> = clock.clone();
+        let actor = TimerActor::spawn_with(app, tx, Some(loop_clock));
+        (actor, clock, rx)
+    }
+
+    fn completions(rx: &Receiver<TimerMessage>) -> Vec<CompletionEvent> {
+        rx.try_iter()
+            .filter_map(|msg| match msg {
+                TimerMessage::Completed(event) => Some(event),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_actor_stops_without_waiting_out_the_tick() {
+        let (tx, _rx) = mpsc::channel();
+        let actor = TimerActor::spawn(test_app(), tx, TimerMode::Thread);
+        thread::sleep(Duration::from_millis(100));
+
+        let started = Instant::now();
+        let app = actor.shutdown().unwrap();
+        assert!(started.elapsed() < TICK_INTERVAL / 2);
+        assert!(app.state.is_pomodoro());
+    }
+
+    #[test]
+    fn test_commands_run_in_order() {
+        let (tx, _rx) = mpsc::channel();
+        let actor = TimerActor::spawn(test_app(), tx, TimerMode::EventLoop);
+        let handle = actor.handle();
+
+        handle.send(|app| app.pause());
+        let state = handle.call(|app| app.state.clone());
+        assert!(state.is_paused());
+
+        // Every command sent before the shutdown still runs
+        handle.send(|app| app.stop());
+        let app = actor.shutdown().unwrap();
+        assert!(app.state.is_idle());
+        assert!(handle.try_call(|app| app.state.clone()).is_none());
+    }
+
+    #[test]
+    fn test_event_loop_mode_never_ticks() {
+        let (tx, rx) = mpsc::channel();
+        let actor = TimerActor::spawn(test_app(), tx, TimerMode::EventLoop);
+        thread::sleep(TICK_INTERVAL + Duration::from_millis(200));
+
+        let remaining = actor.handle().call(|app| app.state.remaining_secs());
+        assert_eq!(remaining, Some(25 * 60));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_fake_clock_pomodoro_then_break() {
+        let (actor, clock, rx) = spawn_fake_actor(test_app());
+        let handle = actor.handle();
+
+        clock.advance(25 * 60 - 1);
+        assert!(completions(&rx).is_empty());
+        assert_eq!(handle.call(|app| app.state.remaining_secs()), Some(1));
+
+        clock.advance(1);
+        assert_eq!(
+            completions(&rx),
+            [CompletionEvent::PomodoroComplete {
+                count: 1,
+                is_long_break: false
+            }]
+        );
+        let (state, count) =
+            handle.call(|app| (app.state.clone(), app.session.pomodoros_completed_today));
+        assert_eq!(count, 1);
+        assert_eq!(
+            state,
+            TimerState::BreakActive {
+                is_long_break: false,
+                remaining_secs: 5 * 60,
+                total_secs: 5 * 60,
+            }
+        );
+
+        clock.advance(5 * 60);
+        assert_eq!(completions(&rx), [CompletionEvent::BreakComplete]);
+        let app = actor.shutdown().unwrap();
+        assert_eq!(app.state, TimerState::BreakFinished);
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+    }
+
+    #[test]
+    fn test_fake_clock_blinks_after_break() {
+        let (actor, clock, rx) = spawn_fake_actor(test_app());
+        let handle = actor.handle();
+        let blinks = |rx: &Receiver<TimerMessage>| -> Vec<bool> {
+            rx.try_iter()
+                .filter_map(|msg| match msg {
+                    TimerMessage::StateChanged(snapshot) => Some(snapshot.attention_blink),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        clock.advance(30 * 60);
+        rx.try_iter().count();
+        clock.advance(4);
+        assert_eq!(blinks(&rx), [false, true, false, true]);
+
+        handle.send(|app| app.mark_attention_seen());
+        clock.advance(3);
+        assert_eq!(blinks(&rx), [false]);
+        actor.shutdown();
+    }
+
+    #[test]
+    fn test_tick_now_catches_up_after_sleep() {
+        let (actor, clock, rx) = spawn_fake_actor(test_app());
+        let handle = actor.handle();
+        clock.advance(10);
+
+        // Asleep past the end of the pomodoro, then woken
+        clock.sleep(30 * 60);
+        handle.tick_now();
+        let state = handle.call(|app| app.state.clone());
+        assert!(state.is_break());
+        assert_eq!(
+            completions(&rx),
+            [CompletionEvent::PomodoroComplete {
+                count: 1,
+                is_long_break: false
+            }]
+        );
+        actor.shutdown();
+    }
+
+    #[test]
+    fn test_fake_clock_long_break_after_four_pomodoros() {
+        let (actor, clock, rx) = spawn_fake_actor(test_app());
+        let handle = actor.handle();
+
+        for count in 1..=4 {
+            if count > 1 {
+                handle.send(|app| app.start_pomodoro());
+            }
+            clock.advance(25 * 60);
+            let is_long_break = count == 4;
+            assert_eq!(
+                completions(&rx),
+                [CompletionEvent::PomodoroComplete {
+                    count,
+                    is_long_break
+                }]
+            );
+
+            let break_secs = if is_long_break { 15 * 60 } else { 5 * 60 };
+            let total_secs = handle.call(|app| app.state.total_secs());
+            assert_eq!(total_secs, Some(break_secs));
+            clock.advance(break_secs);
+            assert_eq!(completions(&rx), [CompletionEvent::BreakComplete]);
+        }
+        let app = actor.shutdown().unwrap();
+        assert_eq!(app.session.pomodoros_completed_today, 4);
+    }
+}
@@ -0,0 +1,4225 @@
+//! Main application state and logic.
+
+use crate::background::Background;
+use crate::focus::{FocusSwitch, ShortcutsFocus};
+use crate::format::{self, TemplateVars};
+use crate::heatmap;
+use crate::media::{AppleScriptMedia, MediaPauser};
+use crate::models::{
+    self, DailyStats, DailySummary, DayScore, GoalStatus, OutOfRange, SavedTimer, Session,
+    Settings, TaskHistory, TimerState, WeekStart, WeekTotals, WindDownPolicy,
+};
+use crate::persistence::{self, Database, DatabaseError};
+use crate::score::{self, DayActivity};
+use crate::settings_file::{self, SettingsFileError};
+use crate::stats_export::{self, ExportedDay, StatsExportError};
+use crate::timer::{Clock, SystemClock};
+use crate::webhook::{self, EventPayload, TimerEvent};
+use chrono::{DateTime, Days, Duration, Local, NaiveDate};
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
+use std::time::Duration as StdDuration;
+use thiserror::Error;
+
+/// Seconds at the end of a pomodoro during which the ticking sound plays.
+const TICKING_SECS: u32 = 60;
+
+/// Longest a pomodoro can be extended to, in minutes.
+const MAX_EXTENDED_POMODORO_MINS: u32 = 120;
+
+/// Most finished days queued for the daily webhook at once, so a long
+/// absence doesn't flood it.
+const MAX_MISSED_SUMMARIES: u64 = 31;
+
+/// Minutes between two notices about database writes that keep failing.
+const PERSIST_ERROR_NOTICE_MINS: i64 = 10;
+
+/// How many recent task labels the Task submenu offers.
+pub const RECENT_TASKS: u32 = 5;
+
+/// Why the app could not be set up.
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("Database error: {0}")]
+    Database(#[from] DatabaseError),
+}
+
+/// Why a pomodoro wasn't started. Start Anyway overrides both.
+#[derive(Error, Debug, Clone, Copy, PartialEq)]
+pub enum StartRefused {
+    #[error("Daily focus limit reached")]
+    DailyLimit,
+    /// The wind-down policy blocks pomodoros that would cross the end of
+    /// the day.
+    #[error("The pomodoro would run past the end of the day")]
+    PastEndOfDay,
+}
+
+/// Events that should trigger notifications/sounds on the main thread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompletionEvent {
+    PomodoroComplete {
+        count: u32,
+        is_long_break: bool,
+    },
+    /// The pomodoro reached zero and keeps counting up (overtime mode).
+    OvertimeStarted,
+    BreakComplete,
+}
+
+/// How a pomodoro starting now fits before the end of the day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindDown {
+    /// Runs its full length.
+    Full,
+    /// Shortened to end exactly at the end of the day.
+    Shortened { total_secs: u32 },
+    /// Not started because it would run past the end of the day.
+    Blocked,
+}
+
+/// Decides how a pomodoro started at `now` relates to the end of the day.
+/// Only pomodoros that would cross the end of the day are affected; one
+/// started after it has passed runs normally.
+pub fn wind_down_decision(settings: &Settings, now: DateTime<Local>) -> WindDown {
+    let Some(end_of_day) = settings.end_of_day else {
+        return WindDown::Full;
+    };
+    let Some(end) = now
+        .date_naive()
+        .and_time(end_of_day)
+        .and_local_timezone(Local)
+        .single()
+    else {
+        return WindDown::Full;
+    };
+
+    let left = (end - now).num_seconds();
+    if left <= 0 || left >= (settings.pomodoro_mins * 60) as i64 {
+        return WindDown::Full;
+    }
+    match settings.wind_down_policy {
+        WindDownPolicy::Shorten => WindDown::Shortened {
+            total_secs: left as u32,
+        },
+        WindDownPolicy::AllowOverrun => WindDown::Full,
+        WindDownPolicy::Block => WindDown::Blocked,
+    }
+}
+
+/// A database write that failed and is retried on the next tick. The
+/// session and settings are saved whole, so a retry writes their latest state.
+#[derive(Debug, Clone, PartialEq)]
+enum PendingWrite {
+    Session,
+    Settings,
+    Reset(Reset),
+    /// A finished day's session that wasn't saved when the day rolled over,
+    /// with the session as last saved. Saved against its own date.
+    SessionForDate {
+        session: Session,
+        saved: Session,
+    },
+    /// A reset of a finished day that wasn't saved when the day rolled over.
+    ResetDate(NaiveDate, Reset),
+    /// The pomodoro or break under way, saved on quit to be restored on the
+    /// next launch.
+    Timer,
+    /// A row of the pomodoro log. Unlike the others it's never replaced by a
+    /// later write, since every pomodoro gets its own row.
+    LogPomodoro {
+        date: NaiveDate,
+        mins: u32,
+        task: Option<String>,
+        interruptions: u32,
+    },
+}
+
+/// What a reset of today's statistics clears.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Reset {
+    /// The counts and the pomodoro log, keeping the cycle.
+    Counts,
+    /// The long break cycle, keeping the counts.
+    Cycle,
+    Everything,
+}
+
+impl Reset {
+    /// Clears this part of a session.
+    fn apply(self, session: &mut Session) {
+        match self {
+            Reset::Counts => session.reset_counts(),
+            Reset::Cycle => session.reset_cycle(),
+            Reset::Everything => session.reset_today(),
+        }
+    }
+
+    /// Clears this part of the statistics saved for `date`.
+    fn save(self, db: &Database, date: NaiveDate) -> Result<(), DatabaseError> {
+        match self {
+            Reset::Counts => db.reset_counts(date),
+            Reset::Cycle => db.reset_cycle_persisted(date),
+            Reset::Everything => db.reset_today(date),
+        }
+    }
+}
+
+/// Heads-up that the current timer is about to end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WarningEvent {
+    pub remaining_secs: u32,
+    pub is_break: bool,
+}
+
+/// Main application state, owned by the timer actor (see `actor`). Audio is
+/// handled separately on the main thread.
+pub struct App {
+    /// The running, paused or idle timer.
+    pub state: TimerState,
+    /// The user's settings, saved whenever they change.
+    pub settings: Settings,
+    /// Today's counts and the position in the long-break cycle.
+    pub session: Session,
+    /// Where settings, sessions and history are stored.
+    pub db: Database,
+    /// The label completed pomodoros are logged under, until changed.
+    pub task: Option<String>,
+    /// Interruptions marked during the current pomodoro.
+    pub pomodoro_interruptions: u32,
+    /// The session as last saved, so a save only adds what changed since.
+    saved_session: Session,
+    /// Whether "Start anyway" has been clicked once and awaits confirmation.
+    pub start_anyway_armed: bool,
+    /// Whether the current pause was triggered by idle detection rather than the user.
+    pub auto_paused: bool,
+    /// Whether to start the next pomodoro as soon as the current break ends.
+    pub auto_start_next: bool,
+    /// When the current pomodoro or break began (unchanged by pause/resume).
+    block_started_at: Option<DateTime<Local>>,
+    /// When the timer went idle after a break, for the next-pomodoro reminder.
+    waiting_since: Option<DateTime<Local>>,
+    /// When the next-pomodoro reminder last fired.
+    last_reminder_at: Option<DateTime<Local>>,
+    /// Whether a break ran out and nobody has looked at the menu since.
+    attention_pending: bool,
+    /// Whether the tray title shows the blink frame, see `blink_attention`.
+    blink_on: bool,
+    /// Whether the end-of-timer warning has fired for the current block.
+    warned: bool,
+    /// Remaining seconds at the last ticking sound, so each second ticks once.
+    last_tick_sound_secs: Option<u32>,
+    /// Why the last database write failed, until a retry succeeds.
+    pub last_persist_error: Option<String>,
+    /// Failed writes, retried in order on the next tick.
+    pending_writes: Vec<PendingWrite>,
+    /// When a failing write was last reported to the user.
+    persist_error_notified_at: Option<DateTime<Local>>,
+    /// Elapsed time not yet taken off the running timer (always under a second).
+    carry: StdDuration,
+    /// A second database found at startup that has not been merged yet.
+    pub stray_db: Option<PathBuf>,
+    /// Switches the system Focus mode during pomodoros.
+    pub focus: Background<FocusSwitch>,
+    /// When the current pomodoro ends if it was shortened to end with the day.
+    pub wind_down_ends_at: Option<DateTime<Local>>,
+    /// Pauses and resumes music around breaks.
+    pub media: Background<MediaPauser>,
+    /// This week's totals, so the menu doesn't query the database every tick.
+    cached_week_stats: Cell<Option<(WeekKey, WeekTotals)>>,
+    /// The rendered history heatmap. It depends on the same things as the
+    /// weekly totals.
+    cached_heatmap: RefCell<Option<(WeekKey, String)>>,
+    /// The all-time stats line, keyed by the day and today's count.
+    cached_all_time: RefCell<Option<((NaiveDate, u32), String)>>,
+    /// Recent tasks and today's breakdown, keyed by the day and today's count.
+    cached_tasks: RefCell<Option<((NaiveDate, u32), TaskHistory)>>,
+    /// Whether to show the Getting Started submenu: only on the first
+    /// launch, until a timer is started.
+    pub getting_started: bool,
+    /// The summary of the day that just ended, until it has been shown.
+    finished_day: Option<DailySummary>,
+    /// Where the current time comes from.
+    clock: Box<dyn Clock + Send>,
+}
+
+/// What the cached weekly totals depend on. A new day, a different first day
+/// of the week, or a change to today's counts makes the cache stale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WeekKey {
+    date: NaiveDate,
+    week_starts_on: WeekStart,
+    pomodoros_today: u32,
+    focus_mins_today: u32,
+}
+
+impl App {
+    /// Creates a new application instance.
+    pub fn new() -> Result<Self, AppError> {
+        let mut app = Self::new_with_clock(Database::new()?, Box::new(SystemClock))?;
+        app.stray_db = persistence::find_stray_database();
+        app.vacuum_if_due();
+        Ok(app)
+    }
+
+    /// Creates a new app with a custom database (for testing and the self-test).
+    pub fn new_with_db(db: Database) -> Result<Self, AppError> {
+        Self::new_with_clock(db, Box::new(SystemClock))
+    }
+
+    /// Creates a new app with a custom database that reads the time from
+    /// `clock` (for testing).
+    pub fn new_with_clock(db: Database, clock: Box<dyn Clock + Send>) -> Result<Self, AppError> {
+        let settings = db.load_settings()?;
+        let today = models::logical_date(clock.now_local(), settings.day_starts_at_hour);
+        let session = db.load_today_session(today)?;
+
+        let mut app = Self {
+            state: TimerState::Idle,
+            settings,
+            saved_session: session.clone(),
+            session,
+            db,
+            task: None,
+            pomodoro_interruptions: 0,
+            start_anyway_armed: false,
+            auto_paused: false,
+            auto_start_next: false,
+            block_started_at: None,
+            waiting_since: None,
+            last_reminder_at: None,
+            attention_pending: false,
+            blink_on: false,
+            warned: false,
+            last_tick_sound_secs: None,
+            last_persist_error: None,
+            pending_writes: Vec::new(),
+            persist_error_notified_at: None,
+            carry: StdDuration::ZERO,
+            stray_db: None,
+            focus: Background::spawn(FocusSwitch::new(Box::new(ShortcutsFocus))),
+            wind_down_ends_at: None,
+            media: Background::spawn(MediaPauser::new(Box::new(AppleScriptMedia))),
+            cached_week_stats: Cell::new(None),
+            cached_heatmap: RefCell::new(None),
+            cached_all_time: RefCell::new(None),
+            cached_tasks: RefCell::new(None),
+            getting_started: false,
+            finished_day: None,
+            clock,
+        };
+        app.take_first_run();
+        app.restore_timer();
+        app.finalize_scores();
+        Ok(app)
+    }
+
+    /// Shows Getting Started if this is the first launch, and saves that it
+    /// no longer is.
+    fn take_first_run(&mut self) {
+        if self.settings.first_run {
+            self.getting_started = true;
+            self.settings.first_run = false;
+            self.persist(PendingWrite::Settings);
+        }
+    }
+
+    /// Hides Getting Started once a timer has been started.
+    /// Returns true if it was hidden just now.
+    pub fn update_getting_started(&mut self) -> bool {
+        if self.getting_started && !self.state.is_idle() {
+            self.getting_started = false;
+            return true;
+        }
+        false
+    }
+
+    /// Starts a new pomodoro session.
+    /// Does nothing once the daily focus limit has been reached.
+    pub fn start_pomodoro(&mut self) {
+        let _ = self.try_start_pomodoro();
+    }
+
+    /// Starts a new pomodoro session, or says why it can't start.
+    pub fn try_start_pomodoro(&mut self) -> Result<(), StartRefused> {
+        self.start_pomodoro_at(self.clock.now_local())
+    }
+
+    /// Starts a new pomodoro at `now`, shortening or refusing it if it would
+    /// run past the end of the day.
+    pub fn start_pomodoro_at(&mut self, now: DateTime<Local>) -> Result<(), StartRefused> {
+        if let Some(refused) = self.start_refused_at(now) {
+            return Err(refused);
+        }
+        match wind_down_decision(&self.settings, now) {
+            WindDown::Shortened { total_secs } => {
+                let ends_at = now + Duration::seconds(total_secs as i64);
+                self.begin_pomodoro(total_secs, Some(ends_at));
+            }
+            WindDown::Full | WindDown::Blocked => {
+                self.begin_pomodoro(self.settings.pomodoro_mins * 60, None)
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns why a pomodoro started now would be refused, if it would.
+    pub fn start_refused(&self) -> Option<StartRefused> {
+        self.start_refused_at(self.clock.now_local())
+    }
+
+    fn start_refused_at(&self, now: DateTime<Local>) -> Option<StartRefused> {
+        if self.daily_limit_reached() {
+            return Some(StartRefused::DailyLimit);
+        }
+        (wind_down_decision(&self.settings, now) == WindDown::Blocked)
+            .then_some(StartRefused::PastEndOfDay)
+    }
+
+    /// Starts a pomodoro past the daily focus limit or the end of the day.
+    /// The first call only arms the override; a second call confirms it.
+    /// Returns true if a pomodoro was started.
+    pub fn start_anyway(&mut self) -> bool {
+        if !self.state.is_idle() {
+            self.start_anyway_armed = false;
+            return false;
+        }
+
+        if self.start_anyway_armed {
+            self.start_anyway_armed = false;
+            self.begin_pomodoro(self.settings.pomodoro_mins * 60, None);
+            true
+        } else {
+            self.start_anyway_armed = true;
+            false
+        }
+    }
+
+    /// Starts a full pomodoro past the daily focus limit or the end of the
+    /// day without arming first, for an override the user already confirmed,
+    /// such as the Start Anyway button of a notification.
+    /// Returns true if a pomodoro was started.
+    pub fn start_confirmed_anyway(&mut self) -> bool {
+        if !self.state.is_idle() {
+            return false;
+        }
+        self.start_anyway_armed = false;
+        self.begin_pomodoro(self.settings.pomodoro_mins * 60, None);
+        true
+    }
+
+    /// Starts a stopwatch that counts up until it is stopped or completed.
+    /// Does nothing unless the timer is idle or once the daily focus limit
+    /// has been reached.
+    pub fn start_stopwatch(&mut self) {
+        if !self.state.is_idle() || self.daily_limit_reached() {
+            return;
+        }
+        self.state = TimerState::StopwatchActive { elapsed_secs: 0 };
+        self.block_started_at = Some(self.clock.now_local());
+        self.carry = StdDuration::ZERO;
+        self.clear_waiting();
+    }
+
+    /// Returns this week's totals, using the configured first day of the week.
+    /// The database is only queried again once the day or today's counts change.
+    pub fn week_totals(&self) -> WeekTotals {
+        let key = WeekKey {
+            date: self.session.last_date,
+            week_starts_on: self.settings.week_starts_on,
+            pomodoros_today: self.session.pomodoros_completed_today,
+            focus_mins_today: self.session.total_focus_mins_today,
+        };
+        if let Some((cached_key, totals)) = self.cached_week_stats.get() {
+            if cached_key == key {
+                return totals;
+            }
+        }
+
+        let totals = self
+            .db
+            .get_week_totals(key.date, key.week_starts_on)
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load weekly stats: {}", e);
+                WeekTotals::new(key.week_starts_on.week_start(key.date))
+            });
+        self.cached_week_stats.set(Some((key, totals)));
+        totals
+    }
+
+    /// Returns yesterday's stats for the menu. They don't change while the
+    /// app runs, so callers only need this again after a day rollover.
+    pub fn yesterday_stats(&self) -> DailyStats {
+        self.db
+            .get_yesterday_stats(self.session.last_date)
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load yesterday's stats: {}", e);
+                DailyStats::new(self.session.last_date - Days::new(1))
+            })
+    }
+
+    /// Returns the heatmap of the last four weeks for the History submenu.
+    pub fn history_heatmap(&self) -> String {
+        let key = WeekKey {
+            date: self.session.last_date,
+            week_starts_on: self.settings.week_starts_on,
+            pomodoros_today: self.session.pomodoros_completed_today,
+            focus_mins_today: self.session.total_focus_mins_today,
+        };
+        if let Some((cached_key, text)) = self.cached_heatmap.borrow().as_ref() {
+            if *cached_key == key {
+                return text.clone();
+            }
+        }
+
+        let stats = self
+            .db
+            .get_last_n_days(
+                key.date,
+                heatmap::heatmap_days(key.date, key.week_starts_on),
+            )
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load history: {}", e);
+                Vec::new()
+            });
+        let text = heatmap::render_heatmap(&stats, key.week_starts_on);
+        *self.cached_heatmap.borrow_mut() = Some((key, text.clone()));
+        text
+    }
+
+    /// Returns the all-time stats line. Like the heatmap, the database is
+    /// only read again once the day or today's count changes.
+    pub fn all_time_stats(&self) -> String {
+        let key = (
+            self.session.last_date,
+            self.session.pomodoros_completed_today,
+        );
+        if let Some((cached_key, text)) = self.cached_all_time.borrow().as_ref() {
+            if *cached_key == key {
+                return text.clone();
+            }
+        }
+
+        let stats = self.db.get_all_stats().unwrap_or_else(|e| {
+            eprintln!("Failed to load all-time stats: {}", e);
+            Vec::new()
+        });
+        let text = format::format_all_time_stats(&stats);
+        *self.cached_all_time.borrow_mut() = Some((key, text.clone()));
+        text
+    }
+
+    /// Returns the recent task labels and today's pomodoros by task for the
+    /// Task submenus. Like the heatmap, only queried again once the day or
+    /// today's count changes.
+    pub fn task_history(&self) -> TaskHistory {
+        let key = (
+            self.session.last_date,
+            self.session.pomodoros_completed_today,
+        );
+        if let Some((cached_key, history)) = self.cached_tasks.borrow().as_ref() {
+            if *cached_key == key {
+                return history.clone();
+            }
+        }
+
+        let history = TaskHistory {
+            recent: self.db.recent_tasks(RECENT_TASKS).unwrap_or_else(|e| {
+                eprintln!("Failed to load recent tasks: {}", e);
+                Vec::new()
+            }),
+            today: self
+                .db
+                .stats_by_task(self.session.last_date)
+                .unwrap_or_else(|e| {
+                    eprintln!("Failed to load today's tasks: {}", e);
+                    Vec::new()
+                }),
+        };
+        *self.cached_tasks.borrow_mut() = Some((key, history.clone()));
+        history
+    }
+
+    /// Sets the task the next completed pomodoros are logged under.
+    /// A blank label clears it.
+    pub fn set_task(&mut self, task: Option<String>) {
+        self.task = task.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    }
+
+    /// Adds a completed pomodoro to the log under the current task.
+    fn log_pomodoro(&mut self, mins: u32, interruptions: u32) {
+        self.persist(PendingWrite::LogPomodoro {
+            date: self.session.last_date,
+            mins,
+            task: self.task.clone(),
+            interruptions,
+        });
+    }
+
+    /// Counts an interruption of the running pomodoro without pausing it.
+    /// Returns false if no pomodoro is running.
+    pub fn mark_interruption(&mut self) -> bool {
+        if !matches!(self.state, TimerState::PomodoroActive { .. }) {
+            return false;
+        }
+        self.pomodoro_interruptions += 1;
+        self.session.marked_interruptions_today += 1;
+        self.persist(PendingWrite::Session);
+        true
+    }
+
+    /// Returns true if today's focus time has reached the configured limit.
+    pub fn daily_limit_reached(&self) -> bool {
+        self.session
+            .daily_limit_reached(self.settings.max_daily_focus_mins)
+    }
+
+    fn begin_pomodoro(&mut self, total_secs: u32, wind_down_ends_at: Option<DateTime<Local>>) {
+        self.state = TimerState::PomodoroActive {
+            remaining_secs: total_secs,
+            total_secs,
+        };
+        self.wind_down_ends_at = wind_down_ends_at;
+        self.block_started_at = Some(self.clock.now_local());
+        self.pomodoro_interruptions = 0;
+        self.warned = false;
+        self.carry = StdDuration::ZERO;
+        self.clear_waiting();
+        self.send_event(TimerEvent::PomodoroStart, total_secs);
+        self.engage_focus();
+        self.resume_media();
+    }
+
+    /// Returns when the current pomodoro or break started.
+    /// Pausing and resuming does not change it.
+    pub fn current_block_started_at(&self) -> Option<DateTime<Local>> {
+        self.block_started_at
+    }
+
+    /// Returns when the current timer will end, as seen from `now`.
+    /// For a paused pomodoro this is the end time if it were resumed now,
+    /// so it shifts later the longer the pause lasts.
+    pub fn projected_end_at(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        self.state
+            .remaining_secs()
+            .map(|secs| now + Duration::seconds(secs as i64))
+    }
+
+    /// Pauses the current pomodoro or break. Pausing a pomodoro counts as
+    /// an interruption. Does nothing while strict mode locks a running
+    /// pomodoro.
+    pub fn pause(&mut self) {
+        if self.state.is_locked(self.settings.strict_mode) {
+            return;
+        }
+        if self.pause_timer() && self.state.is_pomodoro() {
+            self.session.interruptions_today += 1;
+            self.persist(PendingWrite::Session);
+        }
+    }
+
+    /// Pauses whatever is running. Returns true if something was paused.
+    fn pause_timer(&mut self) -> bool {
+        self.state = match self.state {
+            TimerState::PomodoroActive {
+                remaining_secs,
+                total_secs,
+            } => TimerState::PomodoroPaused {
+                remaining_secs,
+                total_secs,
+            },
+            TimerState::BreakActive {
+                is_long_break,
+                remaining_secs,
+                total_secs,
+            } => TimerState::BreakPaused {
+                is_long_break,
+                remaining_secs,
+                total_secs,
+            },
+            TimerState::StopwatchActive { elapsed_secs } => {
+                TimerState::StopwatchPaused { elapsed_secs }
+            }
+            _ => return false,
+        };
+        true
+    }
+
+    /// Resumes a paused pomodoro or break.
+    pub fn resume(&mut self) {
+        self.auto_paused = false;
+        self.state = match self.state {
+            TimerState::PomodoroPaused {
+                remaining_secs,
+                total_secs,
+            } => TimerState::PomodoroActive {
+                remaining_secs,
+                total_secs,
+            },
+            TimerState::BreakPaused {
+                is_long_break,
+                remaining_secs,
+                total_secs,
+            } => TimerState::BreakActive {
+                is_long_break,
+                remaining_secs,
+                total_secs,
+            },
+            TimerState::StopwatchPaused { elapsed_secs } => {
+                TimerState::StopwatchActive { elapsed_secs }
+            }
+            _ => return,
+        };
+        self.carry = StdDuration::ZERO;
+    }
+
+    /// Stops the current timer and returns to idle. A stopwatch is credited
+    /// to today's stats. Does nothing while strict mode locks a running pomodoro.
+    pub fn stop(&mut self) {
+        if self.state.is_locked(self.settings.strict_mode) {
+            return;
+        }
+        if self.state.is_stopwatch() {
+            self.finish_stopwatch();
+            return;
+        }
+        if self.state.is_pomodoro() {
+            let elapsed_secs = self.state.elapsed_secs().unwrap_or_default();
+            self.session.abandon_pomodoro(elapsed_secs / 60);
+            self.persist(PendingWrite::Session);
+        }
+        self.release_focus();
+        self.auto_paused = false;
+        self.auto_start_next = false;
+        self.state = TimerState::Idle;
+        self.block_started_at = None;
+        self.wind_down_ends_at = None;
+        self.clear_waiting();
+    }
+
+    /// Auto-pauses an active pomodoro once the system has been idle for the
+    /// configured time, and resumes it when the user returns. Walking away
+    /// isn't an interruption, so it doesn't count against the focus score.
+    /// Returns true if the state changed.
+    pub fn apply_idle(&mut self, idle_secs: f64) -> bool {
+        let threshold = self.settings.idle_pause_secs;
+        if threshold == 0 {
+            return false;
+        }
+
+        let is_idle = idle_secs >= threshold as f64;
+        match self.state {
+            TimerState::PomodoroActive { .. } if is_idle && !self.settings.strict_mode => {
+                self.pause_timer();
+                self.auto_paused = true;
+                true
+            }
+            TimerState::PomodoroPaused { .. } if !is_idle && self.auto_paused => {
+                self.resume();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Completes the current pomodoro early, or ends its overtime.
+    /// Returns a completion event if the pomodoro was active.
+    /// A stopwatch is credited to today's stats like with `stop`, without an event.
+    pub fn complete_early(&mut self) -> Option<CompletionEvent> {
+        if self.state.is_stopwatch() {
+            self.finish_stopwatch();
+            None
+        } else if matches!(
+            self.state,
+            TimerState::PomodoroActive { .. } | TimerState::Overtime { .. }
+        ) {
+            Some(self.finish_pomodoro())
+        } else {
+            None
+        }
+    }
+
+    /// Adds `extra_mins` to the running or paused pomodoro, up to a total of
+    /// two hours. The extra time counts toward today's focus minutes when
+    /// the pomodoro completes.
+    pub fn extend_pomodoro(&mut self, extra_mins: u32) {
+        let (TimerState::PomodoroActive {
+            remaining_secs,
+            total_secs,
+        }
+        | TimerState::PomodoroPaused {
+            remaining_secs,
+            total_secs,
+        }) = &mut self.state
+        else {
+            return;
+        };
+        let added = extra_mins
+            .saturating_mul(60)
+            .min((MAX_EXTENDED_POMODORO_MINS * 60).saturating_sub(*total_secs));
+        if added == 0 {
+            return;
+        }
+        *remaining_secs += added;
+        *total_secs += added;
+        // The pomodoro no longer ends when the warning or wind-down said
+        if *remaining_secs > self.settings.warn_before_end_secs {
+            self.warned = false;
+        }
+        self.wind_down_ends_at = None;
+    }
+
+    /// Takes the long break now, e.g. before a block of meetings: starts one
+    /// when idle, or turns a running or paused short break into one. An
+    /// upgraded break keeps the time already taken, with at least a minute
+    /// left. Resets the long break cycle like a long break earned by
+    /// pomodoros. Returns true if the timer changed.
+    pub fn start_long_break(&mut self) -> bool {
+        let long_secs = self.settings.long_break_mins * 60;
+        if self.state.is_idle() {
+            self.enter_break(true);
+        } else if let TimerState::BreakActive {
+            is_long_break: is_long_break @ false,
+            remaining_secs,
+            total_secs,
+        }
+        | TimerState::BreakPaused {
+            is_long_break: is_long_break @ false,
+            remaining_secs,
+            total_secs,
+        } = &mut self.state
+        {
+            let taken = *total_secs - *remaining_secs;
+            *is_long_break = true;
+            *remaining_secs = long_secs.saturating_sub(taken).max(60);
+            *total_secs = taken + *remaining_secs;
+            // The break no longer ends when the warning said
+            if *remaining_secs > self.settings.warn_before_end_secs {
+                self.warned = false;
+            }
+        } else {
+            return false;
+        }
+        self.session.reset_cycle();
+        self.persist(PendingWrite::Session);
+        true
+    }
+
+    /// Skips the current break, whether it is running or paused.
+    pub fn skip_break(&mut self) {
+        if self.state.is_break() {
+            self.session.skipped_breaks_today += 1;
+            self.persist(PendingWrite::Session);
+            self.finish_break();
+        }
+    }
+
+    /// Advances the running timer by the given wall-clock time.
+    /// Fractions of a second are carried over to the next call, so irregular
+    /// ticks add up exactly. Long gaps (e.g. system sleep) are subtracted in
+    /// one go; a timer that runs out completes immediately. Paused timers are
+    /// unaffected.
+    /// Returns (state_changed, optional_completion_event).
+    pub fn advance(&mut self, elapsed: StdDuration) -> (bool, Option<CompletionEvent>) {
+        if !matches!(
+            self.state,
+            TimerState::PomodoroActive { .. }
+                | TimerState::Overtime { .. }
+                | TimerState::BreakActive { .. }
+                | TimerState::StopwatchActive { .. }
+        ) {
+            return (false, None);
+        }
+
+        self.carry += elapsed;
+        let secs = u32::try_from(self.carry.as_secs()).unwrap_or(u32::MAX);
+        if secs == 0 {
+            return (false, None);
+        }
+        self.carry -= StdDuration::from_secs(secs as u64);
+
+        match &mut self.state {
+            TimerState::PomodoroActive {
+                remaining_secs,
+                total_secs,
+            } => {
+                if secs < *remaining_secs {
+                    *remaining_secs -= secs;
+                    (true, None)
+                } else if self.settings.overtime_enabled {
+                    // Time past zero already counts as overtime
+                    self.state = TimerState::Overtime {
+                        elapsed_overtime_secs: secs - *remaining_secs,
+                        total_secs: *total_secs,
+                    };
+                    (true, Some(CompletionEvent::OvertimeStarted))
+                } else {
+                    *remaining_secs = 0;
+                    (true, Some(self.finish_pomodoro()))
+                }
+            }
+            TimerState::Overtime {
+                elapsed_overtime_secs,
+                ..
+            } => {
+                *elapsed_overtime_secs = elapsed_overtime_secs.saturating_add(secs);
+                (true, None)
+            }
+            TimerState::StopwatchActive { elapsed_secs } => {
+                *elapsed_secs = elapsed_secs.saturating_add(secs);
+                (true, None)
+            }
+            TimerState::BreakActive { remaining_secs, .. } => {
+                *remaining_secs = remaining_secs.saturating_sub(secs);
+                if *remaining_secs == 0 {
+                    self.finish_break();
+                    // Only a break that ran out blinks, not a skipped one
+                    self.attention_pending = true;
+                    (true, Some(CompletionEvent::BreakComplete))
+                } else {
+                    (true, None)
+                }
+            }
+            _ => (false, None),
+        }
+    }
+
+    /// Advances the timer by one second.
+    /// Returns (state_changed, optional_completion_event).
+    #[cfg(test)]
+    pub fn tick(&mut self) -> (bool, Option<CompletionEvent>) {
+        self.advance(StdDuration::from_secs(1))
+    }
+
+    /// Returns a warning the first time a running timer gets within
+    /// `warn_before_end_secs` of its end. Fires at most once per pomodoro or
+    /// break, and never for timers shorter than the warning itself.
+    pub fn take_warning(&mut self) -> Option<WarningEvent> {
+        let threshold = self.settings.warn_before_end_secs;
+        if threshold == 0 || self.warned {
+            return None;
+        }
+
+        let (remaining_secs, total_secs, is_break) = match self.state {
+            TimerState::PomodoroActive {
+                remaining_secs,
+                total_secs,
+            } => (remaining_secs, total_secs, false),
+            TimerState::BreakActive {
+                remaining_secs,
+                total_secs,
+                ..
+            } if self.settings.warn_before_break_end => (remaining_secs, total_secs, true),
+            _ => return None,
+        };
+
+        if total_secs <= threshold || remaining_secs > threshold || remaining_secs == 0 {
+            return None;
+        }
+        self.warned = true;
+        Some(WarningEvent {
+            remaining_secs,
+            is_break,
+        })
+    }
+
+    /// Returns true when the ticking sound should play: once per second
+    /// during the last minute of a running pomodoro, if ticking is enabled.
+    /// Paused, stopped or finished pomodoros and breaks never tick.
+    pub fn take_tick_sound(&mut self) -> bool {
+        let TimerState::PomodoroActive { remaining_secs, .. } = self.state else {
+            self.last_tick_sound_secs = None;
+            return false;
+        };
+        if !self.settings.ticking_enabled
+            || remaining_secs == 0
+            || remaining_secs > TICKING_SECS
+            || self.last_tick_sound_secs == Some(remaining_secs)
+        {
+            return false;
+        }
+        self.last_tick_sound_secs = Some(remaining_secs);
+        true
+    }
+
+    fn finish_pomodoro(&mut self) -> CompletionEvent {
+        // Update session, crediting the block's own length (shorter for
+        // wind-down) plus any overtime
+        let pomodoro_secs = match self.state {
+            TimerState::Overtime {
+                elapsed_overtime_secs,
+                total_secs,
+            } => total_secs + elapsed_overtime_secs,
+            _ => self.state.total_secs().unwrap_or_default(),
+        };
+        // A wind-down block can end mid-minute; round up so even one shorter
+        // than a minute adds focus time along with its pomodoro
+        let pomodoro_mins = if self.wind_down_ends_at.is_some() {
+            pomodoro_secs.div_ceil(60)
+        } else {
+            pomodoro_secs / 60
+        };
+        self.session.complete_pomodoro(pomodoro_mins);
+        let interruptions = std::mem::take(&mut self.pomodoro_interruptions);
+        self.log_pomodoro(pomodoro_mins, interruptions);
+        self.persist(PendingWrite::Session);
+        self.send_event(TimerEvent::PomodoroComplete, pomodoro_secs);
+        self.wind_down_ends_at = None;
+        self.release_focus();
+
+        // Determine break type
+        let is_long = self
+            .session
+            .is_long_break_due(self.settings.pomodoros_for_long_break);
+        if is_long {
+            self.session.reset_cycle();
+        }
+        self.enter_break(is_long);
+
+        CompletionEvent::PomodoroComplete {
+            count: self.session.pomodoros_completed_today,
+            is_long_break: is_long,
+        }
+    }
+
+    /// Starts a break of the configured length, whether a pomodoro just
+    /// ended or the user asked for one.
+    fn enter_break(&mut self, is_long: bool) {
+        self.pause_media();
+        let break_mins = if is_long {
+            self.settings.long_break_mins
+        } else {
+            self.settings.short_break_mins
+        };
+        let total_secs = break_mins * 60;
+        self.state = TimerState::BreakActive {
+            is_long_break: is_long,
+            remaining_secs: total_secs,
+            total_secs,
+        };
+        self.block_started_at = Some(self.clock.now_local());
+        self.warned = false;
+        self.carry = StdDuration::ZERO;
+        self.clear_waiting();
+        self.send_event(TimerEvent::BreakStart, total_secs);
+    }
+
+    /// Credits the stopwatch to today's stats and returns to idle.
+    fn finish_stopwatch(&mut self) {
+        let elapsed_secs = self.state.stopwatch_secs().unwrap_or_default();
+        if self
+            .session
+            .complete_stopwatch(elapsed_secs, self.settings.stopwatch_min_mins)
+        {
+            self.log_pomodoro(elapsed_secs / 60, 0);
+        }
+        self.persist(PendingWrite::Session);
+        self.state = TimerState::Idle;
+        self.block_started_at = None;
+        self.carry = StdDuration::ZERO;
+    }
+
+    fn finish_break(&mut self) {
+        let break_secs = self.state.total_secs().unwrap_or_default();
+        self.send_event(TimerEvent::BreakComplete, break_secs);
+        self.state = TimerState::BreakFinished;
+        self.block_started_at = None;
+        self.waiting_since = Some(self.clock.now_local());
+        self.last_reminder_at = None;
+
+        if std::mem::take(&mut self.auto_start_next) {
+            self.start_pomodoro();
+        }
+    }
+
+    /// Turns on Focus for a pomodoro, unless the user already has one on.
+    /// Returns without waiting for the shortcut.
+    fn engage_focus(&self) {
+        if self.settings.focus_mode_enabled {
+            self.focus.run(FocusSwitch::engage);
+        }
+    }
+
+    /// Turns off the Focus we turned on. Must run whenever a pomodoro ends,
+    /// including on stop and quit, so notifications come back. Returns
+    /// without waiting for the shortcut; dropping the app waits for it.
+    pub fn release_focus(&self) {
+        self.focus.run(FocusSwitch::release);
+    }
+
+    /// Pauses any music that is playing, remembering which players we paused.
+    /// Returns without waiting for AppleScript.
+    fn pause_media(&self) {
+        if self.settings.control_media {
+            self.media.run(MediaPauser::pause);
+        }
+    }
+
+    /// Resumes the players we paused. Returns without waiting for AppleScript.
+    fn resume_media(&self) {
+        self.media.run(MediaPauser::resume);
+    }
+
+    /// Posts the event to its webhook, if one is configured.
+    fn send_event(&self, event: TimerEvent, duration_secs: u32) {
+        if let Some(url) = webhook::event_url(&self.settings, event) {
+            let payload = EventPayload {
+                event: event.name(),
+                timestamp: self.clock.now_local(),
+                count_today: self.session.pomodoros_completed_today,
+                duration_secs,
+                wind_down: self.wind_down_ends_at.is_some(),
+            };
+            webhook::spawn_event(url, payload);
+        }
+    }
+
+    fn clear_waiting(&mut self) {
+        self.waiting_since = None;
+        self.last_reminder_at = None;
+    }
+
+    /// Returns true if the "ready for the next pomodoro?" reminder should fire
+    /// at `now`, and records it as fired. Repeats every `idle_reminder_mins`
+    /// for as long as the timer stays idle after a break.
+    pub fn reminder_due(&mut self, now: DateTime<Local>) -> bool {
+        let mins = self.settings.idle_reminder_mins;
+        if mins == 0
+            || !self.settings.notifications_enabled
+            || !self.state.is_idle()
+            || self.session.pomodoros_completed_today == 0
+        {
+            return false;
+        }
+
+        let Some(since) = self.last_reminder_at.or(self.waiting_since) else {
+            return false;
+        };
+        if now - since >= Duration::minutes(mins as i64) {
+            self.last_reminder_at = Some(now);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Advances the tray title blink after a break ran out, alternating
+    /// between the plain title and `timer::ATTENTION_TITLE` on every call.
+    /// Stops once the state changes, the menu is opened or `attention_blink`
+    /// is turned off. Returns true if the title needs redrawing.
+    pub fn blink_attention(&mut self) -> bool {
+        if self.state != TimerState::BreakFinished {
+            self.attention_pending = false;
+        }
+        let on = self.attention_pending && self.settings.attention_blink && !self.blink_on;
+        std::mem::replace(&mut self.blink_on, on) != on
+    }
+
+    /// Whether the tray title should show its blink frame right now.
+    pub fn attention_blink_on(&self) -> bool {
+        self.blink_on && self.state == TimerState::BreakFinished
+    }
+
+    /// Stops the blinking, as the menu was opened.
+    pub fn mark_attention_seen(&mut self) {
+        self.attention_pending = false;
+    }
+
+    /// Saves what quitting would lose: a running stopwatch is credited like
+    /// a stop, a pomodoro or break under way is saved to be restored on the
+    /// next launch, and writes still waiting for a retry get one last try.
+    pub fn save_before_quit(&mut self) {
+        if self.state.is_stopwatch() {
+            self.finish_stopwatch();
+        }
+        self.persist(PendingWrite::Session);
+        self.persist(PendingWrite::Timer);
+    }
+
+    /// Returns the timer to restore on the next launch. A running pomodoro
+    /// or break comes back paused, since nobody knows how long Pomobar will
+    /// be gone. Overtime keeps counting up; nothing else is worth keeping.
+    fn timer_to_save(&self) -> Option<SavedTimer> {
+        let state = match self.state {
+            TimerState::PomodoroActive {
+                remaining_secs,
+                total_secs,
+            }
+            | TimerState::PomodoroPaused {
+                remaining_secs,
+                total_secs,
+            } => Some(TimerState::PomodoroPaused {
+                remaining_secs,
+                total_secs,
+            }),
+            TimerState::BreakActive {
+                is_long_break,
+                remaining_secs,
+                total_secs,
+            }
+            | TimerState::BreakPaused {
+                is_long_break,
+                remaining_secs,
+                total_secs,
+            } => Some(TimerState::BreakPaused {
+                is_long_break,
+                remaining_secs,
+                total_secs,
+            }),
+            TimerState::Overtime { .. } => Some(self.state.clone()),
+            _ => None,
+        };
+        state.map(|state| SavedTimer {
+            state,
+            block_started_at: self.block_started_at,
+        })
+    }
+
+    /// Picks up the pomodoro or break saved when Pomobar last quit today.
+    /// The long break cycle it belongs to comes back with today's session.
+    fn restore_timer(&mut self) {
+        match self.db.take_timer(self.session.last_date) {
+            Ok(Some(timer)) => {
+                self.state = timer.state;
+                self.block_started_at = timer.block_started_at;
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to restore the timer: {}", e),
+        }
+    }
+
+    /// Writes to the database, keeping the write for a retry if it fails.
+    fn persist(&mut self, write: PendingWrite) {
+        // Keep only the latest of each kind, so writes are retried in the order they last happened
+        if !matches!(write, PendingWrite::LogPomodoro { .. }) {
+            self.pending_writes.retain(|pending| *pending != write);
+        }
+        self.pending_writes.push(write);
+        self.retry_pending_writes();
+    }
+
+    /// Retries failed database writes in order, stopping at the first that
+    /// fails again. Returns true if this cleared the last one.
+    pub fn retry_pending_writes(&mut self) -> bool {
+        if self.pending_writes.is_empty() {
+            return false;
+        }
+        while let Some(write) = self.pending_writes.first() {
+            let result = match write {
+                PendingWrite::Session => self
+                    .db
+                    .save_session(&self.session, &self.saved_session)
+                    .map(|()| self.saved_session = self.session.clone()),
+                PendingWrite::Settings => self.db.save_settings(&self.settings),
+                PendingWrite::Reset(reset) => reset
+                    .save(&self.db, self.session.last_date)
+                    .map(|()| reset.apply(&mut self.saved_session)),
+                PendingWrite::SessionForDate { session, saved } => {
+                    self.db.save_session(session, saved)
+                }
+                PendingWrite::ResetDate(date, reset) => reset.save(&self.db, *date),
+                PendingWrite::Timer => self
+                    .db
+                    .save_timer(self.session.last_date, self.timer_to_save().as_ref()),
+                PendingWrite::LogPomodoro {
+                    date,
+                    mins,
+                    task,
+                    interruptions,
+                } => self
+                    .db
+                    .log_pomodoro(*date, *mins, task.as_deref(), *interruptions),
+            };
+            if let Err(e) = result {
+                let error = e.to_string();
+                if self.last_persist_error.as_ref() != Some(&error) {
+                    eprintln!("Failed to save data: {}", error);
+                    self.last_persist_error = Some(error);
+                }
+                return false;
+            }
+            self.pending_writes.remove(0);
+        }
+        self.last_persist_error = None;
+        true
+    }
+
+    /// Returns the write error to report at `now`, if there is one and none
+    /// was reported in the last ten minutes.
+    pub fn take_persist_error_notice(&mut self, now: DateTime<Local>) -> Option<String> {
+        let error = self.last_persist_error.as_ref()?;
+        if self
+            .persist_error_notified_at
+            .is_some_and(|at| now - at < Duration::minutes(PERSIST_ERROR_NOTICE_MINS))
+        {
+            return None;
+        }
+        self.persist_error_notified_at = Some(now);
+        Some(error.clone())
+    }
+
+    /// Updates a setting and saves to database. Leaves the settings
+    /// untouched if the change would put one out of range. Settings an older
+    /// version saved out of range were clamped on load, so they don't block
+    /// other changes.
+    pub fn update_setting<F>(&mut self, updater: F) -> Result<(), OutOfRange>
+    where
+        F: FnOnce(&mut Settings),
+    {
+        let mut settings = self.settings.clone();
+        updater(&mut settings);
+        settings.validate()?;
+        self.settings = settings;
+        self.persist(PendingWrite::Settings);
+        Ok(())
+    }
+
+    /// Logs a pomodoro done away from the timer, e.g. one forgotten to start.
+    /// Doesn't touch the running timer or the long break cycle.
+    pub fn log_manual_pomodoro(&mut self, mins: u32) {
+        self.check_day_rollover();
+        self.session.log_manual_pomodoro(mins);
+        self.log_pomodoro(mins, 0);
+        self.persist(PendingWrite::Session);
+    }
+
+    /// Replaces the settings with the ones exported to `path`, except for
+    /// those tied to this Mac (see `settings_file::keep_local`).
+    /// Leaves the current settings untouched if the file can't be read or
+    /// fails validation.
+    pub fn import_settings(&mut self, path: &Path) -> Result<(), SettingsFileError> {
+        let imported = settings_file::import(path)?;
+        self.update_setting(|s| *s = settings_file::keep_local(imported, s))?;
+        Ok(())
+    }
+
+    /// Exports the stats of every recorded day with its focus score to CSV
+    /// and JSON files in `dir`. Returns the paths of both files.
+    pub fn export_stats(&self, dir: &Path) -> Result<(PathBuf, PathBuf), StatsExportError> {
+        let mut days = Vec::new();
+        for stats in self.db.get_all_stats()? {
+            // Today isn't finalized yet, so it gets its score so far
+            let focus_score = if stats.date == self.session.last_date {
+                self.focus_score()
+            } else {
+                self.db.get_day_score(stats.date)?.and_then(|s| s.score)
+            };
+            days.push(ExportedDay { stats, focus_score });
+        }
+        stats_export::export(&days, dir)
+    }
+
+    /// Resets today's statistics.
+    pub fn reset_today(&mut self) {
+        self.reset(Reset::Everything);
+    }
+
+    /// Resets today's pomodoro count and focus minutes, keeping the cycle.
+    pub fn reset_counts(&mut self) {
+        self.reset(Reset::Counts);
+    }
+
+    /// Resets the long break cycle so the next break is a short one.
+    pub fn reset_cycle(&mut self) {
+        self.reset(Reset::Cycle);
+    }
+
+    /// Clears part of today's statistics, in memory and in the database.
+    fn reset(&mut self, reset: Reset) {
+        reset.apply(&mut self.session);
+        self.persist(PendingWrite::Reset(reset));
+    }
+
+    /// Queues the summary of `ended`, a day that just finished, for the daily
+    /// webhook, after those of the earlier days with activity that don't
+    /// have one yet, such as the days before Pomobar was closed through a
+    /// rollover. Each day is queued at most once, even across restarts.
+    /// Only the last `MAX_MISSED_SUMMARIES` days are caught up on, and the
+    /// very first summary covers only `ended`.
+    /// Returns true if any summary was queued.
+    pub fn queue_summaries_through(&mut self, ended: NaiveDate) -> bool {
+        if self.settings.daily_webhook_url.is_none() {
+            return false;
+        }
+        let Ok(last) = self.db.last_summary_date() else {
+            return false;
+        };
+        let oldest = ended - Days::new(MAX_MISSED_SUMMARIES - 1);
+        let first = last.map_or(ended, |last| (last + Days::new(1)).max(oldest));
+
+        let mut queued = false;
+        for date in first.iter_days().take_while(|date| *date <= ended) {
+            let summary = match self.daily_summary(date) {
+                Ok(summary) if summary.focus_score.is_some() || date == ended => summary,
+                Ok(_) => continue,
+                // Stop here so a later day's sent marker doesn't skip this one
+                Err(_) => break,
+            };
+            if self.db.queue_summary(&summary).is_err() {
+                break;
+            }
+            queued = true;
+        }
+        queued
+    }
+
+    /// Builds the summary of a finished day from the stored stats and score.
+    fn daily_summary(&self, date: NaiveDate) -> Result<DailySummary, DatabaseError> {
+        let stats = self.db.get_daily_stats(date)?;
+        Ok(DailySummary {
+            date,
+            completed_pomodoros: stats.completed_pomodoros,
+            total_focus_minutes: stats.total_focus_minutes,
+            streak_days: self.db.streak_ending(date)?,
+            focus_score: self.db.get_day_score(date)?.and_then(|s| s.score),
+            goal: Some(GoalStatus::new(
+                stats.completed_pomodoros,
+                self.settings.daily_goal,
+            )),
+        })
+    }
+
+    /// Returns the summary of the day that just ended, once, for the daily
+    /// summary notification. Days without activity have none.
+    pub fn take_finished_day(&mut self) -> Option<DailySummary> {
+        self.finished_day.take()
+    }
+
+    /// Advances the session to a new day if the date changed, finalizing the
+    /// finished day's score and queueing its summary.
+    /// Returns true if a summary was queued.
+    ///
+    /// A pomodoro, break or stopwatch under way keeps the day it started on:
+    /// the rollover waits until it has finished, so it counts toward that day.
+    /// Failed writes don't hold it up: they stay queued against the day they
+    /// belong to (see `save_session_for_date`).
+    pub fn check_day_rollover(&mut self) -> bool {
+        if !matches!(self.state, TimerState::Idle | TimerState::BreakFinished) {
+            return false;
+        }
+        let previous = self.session.last_date;
+        let today = self.today();
+        if today <= previous {
+            return false;
+        }
+        self.retry_pending_writes();
+        self.save_session_for_date();
+        self.session.check_day_rollover(today);
+        if self.session.last_date == previous {
+            return false;
+        }
+        self.finalize_scores();
+        self.finished_day = self
+            .daily_summary(previous)
+            .ok()
+            .filter(|summary| summary.focus_score.is_some());
+        self.queue_summaries_through(previous)
+    }
+
+    /// Ties the writes still pending for the session's day to that date, so
+    /// they can be retried after the session has moved on to the next day.
+    fn save_session_for_date(&mut self) {
+        let date = self.session.last_date;
+        for write in &mut self.pending_writes {
+            match write {
+                PendingWrite::Session => {
+                    *write = PendingWrite::SessionForDate {
+                        session: self.session.clone(),
+                        saved: self.saved_session.clone(),
+                    };
+                }
+                PendingWrite::Reset(reset) => *write = PendingWrite::ResetDate(date, *reset),
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the day that is under way, which begins at the configured
+    /// hour rather than at midnight.
+    pub fn today(&self) -> NaiveDate {
+        models::logical_date(self.clock.now_local(), self.settings.day_starts_at_hour)
+    }
+
+    /// Returns today's focus score so far (None before any activity).
+    pub fn focus_score(&self) -> Option<u32> {
+        score::focus_score(&DayActivity::from(&self.session), self.settings.daily_goal)
+    }
+
+    /// Stores the score of every finished day that doesn't have one yet,
+    /// computed with the current formula and goal. Covers days that ended
+    /// while Pomobar wasn't running.
+    fn finalize_scores(&self) {
+        let Ok(days) = self.db.unscored_days(self.session.last_date) else {
+            return;
+        };
+        for date in days {
+            let Ok(stats) = self.db.get_daily_stats(date) else {
+                continue;
+            };
+            let _ = self.db.save_day_score(&DayScore {
+                date,
+                score: score::focus_score(&DayActivity::from(&stats), self.settings.daily_goal),
+                formula_version: score::FORMULA_VERSION,
+            });
+        }
+    }
+
+    /// Vacuums the database at launch once the file is a month old, then
+    /// at most once a month.
+    fn vacuum_if_due(&self) {
+        let last_vacuum = self.db.last_vacuum().unwrap_or(None);
+        if persistence::vacuum_due(
+            persistence::database_created(),
+            last_vacuum,
+            self.clock.now_local(),
+        ) {
+            self.optimize_database();
+        }
+    }
+
+    /// Vacuums the database, logging any failure.
+    pub fn optimize_database(&self) {
+        if let Err(e) = self.db.vacuum() {
+            eprintln!("Failed to vacuum database: {}", e);
+        }
+    }
+
+    /// Folds the stray database's daily stats into the main one and renames
+    /// the stray file so it is not picked up again.
+    pub fn merge_stray_db(&mut self) -> Result<(), AppError> {
+        let Some(path) = self.stray_db.take() else {
+            return Ok(());
+        };
+
+        // Bring an older file up to the current columns, and fold its -wal
+        // file in, since that wouldn't follow the rename
+        Database::open(&path)?.checkpoint()?;
+        // Rename first so a failed rename can never lead to merging twice
+        let retired = persistence::retire_database(&path)?;
+        if let Err(e) = self.db.merge_daily_stats_from(&retired) {
+            let _ = std::fs::rename(&retired, &path);
+            self.stray_db = Some(path);
+            return Err(e.into());
+        }
+
+        // Pick up merged counts for today, keeping the current cycle
+        let cycle = self.session.pomodoros_in_cycle;
+        self.session = self.db.load_today_session(self.today())?;
+        self.session.pomodoros_in_cycle = cycle;
+        self.saved_session = self.session.clone();
+        Ok(())
+    }
+
+    /// Returns the number of consecutive days, up to today, with at least
+    /// one completed pomodoro.
+    pub fn streak_days(&self) -> u32 {
+        self.db.streak_ending(self.today()).unwrap_or_default()
+    }
+
+    /// Returns the placeholder values for notification templates.
+    pub fn template_vars(&self) -> TemplateVars {
+        format::template_vars(
+            self.session.pomodoros_completed_today,
+            self.settings.pomodoro_mins,
+            self.streak_days(),
+            self.task.as_deref(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::focus::{FocusControl, FocusError};
+    use crate::media::{MediaControl, MediaError, Player};
+    use crate::models::WeekStart;
+    use crate::persistence::Database;
+    use crate::test_utils::MockClock;
+    use chrono::{NaiveTime, TimeZone};
+    use proptest::prelude::*;
+    use std::sync::{Arc, Mutex};
+
+    fn create_test_app() -> App {
+        let db = Database::new_in_memory().unwrap();
+        App::new_with_db(db).unwrap()
+    }
+
+    /// Creates a test app whose clock starts at `now`.
+    fn create_test_app_at(now: DateTime<Local>) -> (App, MockClock) {
+        let clock = MockClock::new(now);
+        let db = Database::new_in_memory().unwrap();
+        let app = App::new_with_clock(db, Box::new(clock.clone())).unwrap();
+        (app, clock)
+    }
+
+    #[test]
+    fn test_first_launch_shows_getting_started_once() {
+        let mut app = create_test_app();
+        assert!(app.getting_started);
+        assert!(!app.db.load_settings().unwrap().first_run);
+
+        // Hidden once a timer starts, and stays hidden
+        assert!(!app.update_getting_started());
+        app.start_pomodoro();
+        assert!(app.update_getting_started());
+        app.stop();
+        assert!(!app.update_getting_started());
+        assert!(!app.getting_started);
+
+        // Not shown on the next launch
+        let App { db, .. } = app;
+        assert!(!App::new_with_db(db).unwrap().getting_started);
+    }
+
+    #[test]
+    fn test_extend_pomodoro() {
+        let mut app = create_test_app();
+        app.extend_pomodoro(5);
+        assert!(app.state.is_idle());
+
+        app.start_pomodoro();
+        app.extend_pomodoro(5);
+        assert_eq!(
+            app.state,
+            TimerState::PomodoroActive {
+                remaining_secs: 30 * 60,
+                total_secs: 30 * 60,
+            }
+        );
+
+        // Paused pomodoros can be extended too, up to two hours in total
+        app.pause();
+        app.extend_pomodoro(100);
+        assert_eq!(
+            app.state,
+            TimerState::PomodoroPaused {
+                remaining_secs: 120 * 60,
+                total_secs: 120 * 60,
+            }
+        );
+        app.extend_pomodoro(5);
+        assert_eq!(app.state.total_secs(), Some(120 * 60));
+    }
+
+    #[test]
+    fn test_extended_pomodoro_counts_its_full_length() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.extend_pomodoro(5);
+        assert!(app.advance(StdDuration::from_secs(25 * 60)).1.is_none());
+        assert!(app.advance(StdDuration::from_secs(5 * 60)).1.is_some());
+        assert_eq!(app.session.total_focus_mins_today, 30);
+        assert_eq!(
+            app.db
+                .get_daily_stats(app.session.last_date)
+                .unwrap()
+                .total_focus_minutes,
+            30
+        );
+    }
+
+    #[test]
+    fn test_extending_rearms_the_end_warning() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.state = TimerState::PomodoroActive {
+            remaining_secs: 30,
+            total_secs: 25 * 60,
+        };
+        assert!(app.take_warning().is_some());
+        app.extend_pomodoro(5);
+        app.state = TimerState::PomodoroActive {
+            remaining_secs: 30,
+            total_secs: 30 * 60,
+        };
+        assert!(app.take_warning().is_some());
+    }
+
+    #[test]
+    fn test_app_initial_state() {
+        let app = create_test_app();
+        assert!(app.state.is_idle());
+        let first_launched = Settings {
+            first_run: false,
+            ..Settings::default()
+        };
+        assert_eq!(app.settings, first_launched);
+    }
+
+    #[test]
+    fn test_start_pomodoro() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+        assert_eq!(app.state.remaining_secs(), Some(25 * 60));
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        // Tick a few times
+        for _ in 0..10 {
+            app.tick();
+        }
+
+        app.pause();
+        assert!(app.state.is_paused());
+        let remaining_before = app.state.remaining_secs().unwrap();
+
+        // Tick shouldn't change time when paused
+        app.tick();
+        assert_eq!(app.state.remaining_secs().unwrap(), remaining_before);
+
+        app.resume();
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+    }
+
+    #[test]
+    fn test_pause_and_resume_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.tick();
+
+        app.pause();
+        assert_eq!(
+            app.state,
+            TimerState::BreakPaused {
+                is_long_break: false,
+                remaining_secs: 299,
+                total_secs: 300,
+            }
+        );
+
+        // Ticks and long gaps leave a paused break alone
+        app.tick();
+        let (changed, event) = app.advance(StdDuration::from_secs(600));
+        assert!(!changed);
+        assert!(event.is_none());
+        assert_eq!(app.state.remaining_secs(), Some(299));
+
+        app.resume();
+        assert!(matches!(
+            app.state,
+            TimerState::BreakActive {
+                remaining_secs: 299,
+                ..
+            }
+        ));
+        app.tick();
+        assert_eq!(app.state.remaining_secs(), Some(298));
+    }
+
+    #[test]
+    fn test_skip_paused_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.pause();
+
+        app.skip_break();
+        assert!(matches!(app.state, TimerState::BreakFinished));
+    }
+
+    #[test]
+    fn test_paused_long_break_keeps_kind() {
+        let mut app = create_test_app();
+        app.settings.pomodoros_for_long_break = 1;
+        app.start_pomodoro();
+        app.complete_early();
+
+        app.pause();
+        app.resume();
+        assert!(matches!(
+            app.state,
+            TimerState::BreakActive {
+                is_long_break: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_pause_ignored_when_idle() {
+        let mut app = create_test_app();
+        app.pause();
+        assert!(app.state.is_idle());
+        app.resume();
+        assert!(app.state.is_idle());
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.stop();
+
+        assert!(app.state.is_idle());
+        assert!(matches!(app.state, TimerState::Idle));
+    }
+
+    #[test]
+    fn test_stop_adds_elapsed_minutes_to_interrupted_time() {
+        let mut app = create_test_app();
+        let mut expected = 0;
+        // Stopped right away, after 59 seconds, 10:30 and 24:59
+        for elapsed_secs in [0, 59, 10 * 60 + 30, 25 * 60 - 1] {
+            app.start_pomodoro();
+            app.advance(StdDuration::from_secs(elapsed_secs));
+            app.stop();
+            expected += elapsed_secs as u32 / 60;
+            assert_eq!(app.session.total_interrupted_mins, expected);
+        }
+        assert_eq!(expected, 34);
+        assert_eq!(app.session.abandoned_today, 4);
+
+        // Paused and overtime pomodoros count up to where they were stopped
+        app.start_pomodoro();
+        app.advance(StdDuration::from_secs(5 * 60));
+        app.pause();
+        app.stop();
+        assert_eq!(app.session.total_interrupted_mins, 39);
+
+        app.settings.overtime_enabled = true;
+        app.start_pomodoro();
+        app.advance(StdDuration::from_secs(27 * 60));
+        assert!(matches!(app.state, TimerState::Overtime { .. }));
+        app.stop();
+        assert_eq!(app.session.total_interrupted_mins, 66);
+
+        // Neither a break nor a stopwatch is interrupted focus
+        app.start_stopwatch();
+        app.advance(StdDuration::from_secs(3 * 60));
+        app.stop();
+        assert_eq!(app.session.total_interrupted_mins, 66);
+
+        assert_eq!(
+            app.db
+                .load_today_session(app.today())
+                .unwrap()
+                .total_interrupted_mins,
+            66
+        );
+    }
+
+    /// Starts a pomodoro, runs it to its end and returns the completion event.
+    fn run_pomodoro_to_end(app: &mut App) -> CompletionEvent {
+        app.start_pomodoro();
+        let pomodoro = StdDuration::from_secs(app.settings.pomodoro_mins as u64 * 60);
+        app.advance(pomodoro).1.unwrap()
+    }
+
+    /// Advances one second at a time and returns after which ticks the
+    /// ticking sound was due, as remaining seconds.
+    fn tick_sounds(app: &mut App, secs: u32) -> Vec<u32> {
+        let mut ticked = Vec::new();
+        for _ in 0..secs {
+            app.tick();
+            if app.take_tick_sound() {
+                ticked.push(app.state.remaining_secs().unwrap());
+            }
+        }
+        ticked
+    }
+
+    #[test]
+    fn test_tick_sound_only_in_last_minute() {
+        let mut app = create_test_app();
+        app.settings.ticking_enabled = true;
+        app.start_pomodoro();
+
+        assert!(tick_sounds(&mut app, 25 * 60 - 61).is_empty());
+        assert_eq!(
+            tick_sounds(&mut app, 60),
+            (1..=60).rev().collect::<Vec<_>>()
+        );
+        // Asking again within the same second doesn't tick twice
+        assert!(!app.take_tick_sound());
+
+        // The pomodoro completes; breaks never tick
+        app.tick();
+        assert!(app.state.is_break());
+        assert!(!app.take_tick_sound());
+        let break_secs = app.state.remaining_secs().unwrap();
+        app.settings.warn_before_break_end = true;
+        for _ in 0..break_secs - 1 {
+            app.tick();
+            assert!(!app.take_tick_sound());
+        }
+    }
+
+    #[test]
+    fn test_tick_sound_stops_on_pause_and_stop() {
+        let mut app = create_test_app();
+        app.settings.ticking_enabled = true;
+        app.start_pomodoro();
+        app.advance(StdDuration::from_secs(25 * 60 - 30));
+        assert!(app.take_tick_sound());
+
+        app.pause();
+        assert!(!app.take_tick_sound());
+        app.resume();
+        assert_eq!(tick_sounds(&mut app, 1), [29]);
+
+        app.stop();
+        assert!(!app.take_tick_sound());
+    }
+
+    #[test]
+    fn test_tick_sound_disabled_by_default() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.advance(StdDuration::from_secs(25 * 60 - 30));
+        assert!(!app.take_tick_sound());
+    }
+
+    #[test]
+    fn test_failed_session_write_is_retried() {
+        let mut app = create_test_app();
+        app.db.set_read_only(true);
+        run_pomodoro_to_end(&mut app);
+        assert!(app.last_persist_error.is_some());
+        assert_eq!(
+            app.db
+                .load_today_session(app.today())
+                .unwrap()
+                .pomodoros_completed_today,
+            0
+        );
+
+        // Still failing: nothing changes
+        assert!(!app.retry_pending_writes());
+        assert!(app.last_persist_error.is_some());
+
+        app.db.set_read_only(false);
+        assert!(app.retry_pending_writes());
+        assert_eq!(app.last_persist_error, None);
+        assert_eq!(
+            app.db
+                .load_today_session(app.today())
+                .unwrap()
+                .pomodoros_completed_today,
+            1
+        );
+        assert!(!app.retry_pending_writes());
+    }
+
+    #[test]
+    fn test_failed_pomodoro_log_is_retried() {
+        let mut app = create_test_app();
+        app.set_task(Some("Write report".to_string()));
+        app.db.set_read_only(true);
+        run_pomodoro_to_end(&mut app);
+        app.skip_break();
+        run_pomodoro_to_end(&mut app);
+
+        app.db.set_read_only(false);
+        assert!(app.retry_pending_writes());
+        // Both pomodoros are logged, even though their rows are the same
+        let stats = app.db.stats_by_task(app.today()).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].task.as_deref(), Some("Write report"));
+        assert_eq!(stats[0].pomodoros, 2);
+    }
+
+    #[test]
+    fn test_failed_writes_retried_in_order() {
+        let mut app = create_test_app();
+        app.db.set_read_only(true);
+        run_pomodoro_to_end(&mut app);
+        app.update_setting(|s| s.daily_goal = 3).unwrap();
+        app.reset_counts();
+
+        app.db.set_read_only(false);
+        assert!(app.retry_pending_writes());
+        assert_eq!(app.db.load_settings().unwrap().daily_goal, 3);
+        // The reset came after the pomodoro was saved, so it wins
+        assert_eq!(
+            app.db
+                .load_today_session(app.today())
+                .unwrap()
+                .pomodoros_completed_today,
+            0
+        );
+    }
+
+    #[test]
+    fn test_persist_error_notice_rate_limited() {
+        let mut app = create_test_app();
+        let now = Local.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        assert_eq!(app.take_persist_error_notice(now), None);
+
+        app.db.set_read_only(true);
+        app.update_setting(|s| s.daily_goal = 3).unwrap();
+        assert!(app.take_persist_error_notice(now).is_some());
+
+        // Failing again on every tick doesn't notify again for ten minutes
+        for secs in [1, 60, 9 * 60 + 59] {
+            assert!(!app.retry_pending_writes());
+            let later = now + Duration::seconds(secs);
+            assert_eq!(app.take_persist_error_notice(later), None);
+        }
+        assert!(app
+            .take_persist_error_notice(now + Duration::minutes(10))
+            .is_some());
+
+        app.db.set_read_only(false);
+        assert!(app.retry_pending_writes());
+        assert_eq!(
+            app.take_persist_error_notice(now + Duration::minutes(30)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_complete_early() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        let event = app.complete_early();
+
+        // Should transition to break and return event
+        assert!(app.state.is_break());
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+        assert!(matches!(
+            event,
+            Some(CompletionEvent::PomodoroComplete { count: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_skip_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        assert!(app.state.is_break());
+
+        app.skip_break();
+        assert!(matches!(app.state, TimerState::BreakFinished));
+    }
+
+    #[test]
+    fn test_pomodoro_completes_to_break() {
+        let mut app = create_test_app();
+        app.settings.pomodoro_mins = 1; // 1 minute for faster test
+
+        app.start_pomodoro();
+
+        // Tick through the entire pomodoro
+        let mut completion_event = None;
+        for _ in 0..61 {
+            let (_, event) = app.tick();
+            if event.is_some() {
+                completion_event = event;
+                break;
+            }
+        }
+
+        assert!(app.state.is_break());
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+        assert!(completion_event.is_some());
+    }
+
+    #[test]
+    fn test_auto_start_next_after_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.auto_start_next = true;
+
+        let (_, event) = app.advance(StdDuration::from_secs(5 * 60));
+        assert_eq!(event, Some(CompletionEvent::BreakComplete));
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+        assert!(!app.auto_start_next);
+    }
+
+    #[test]
+    fn test_auto_start_next_cleared_on_stop() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.auto_start_next = true;
+
+        app.stop();
+        assert!(!app.auto_start_next);
+    }
+
+    #[test]
+    fn test_long_break_after_threshold() {
+        let mut app = create_test_app();
+        app.settings.pomodoros_for_long_break = 2;
+
+        // Complete first pomodoro
+        app.start_pomodoro();
+        let event = app.complete_early();
+
+        // Should be short break
+        if let TimerState::BreakActive { is_long_break, .. } = &app.state {
+            assert!(!is_long_break);
+        } else {
+            panic!("Expected BreakActive state");
+        }
+        assert!(matches!(
+            event,
+            Some(CompletionEvent::PomodoroComplete {
+                is_long_break: false,
+                ..
+            })
+        ));
+
+        app.skip_break();
+        app.start_pomodoro();
+        let event = app.complete_early();
+
+        // Should be long break now
+        if let TimerState::BreakActive { is_long_break, .. } = &app.state {
+            assert!(*is_long_break);
+        } else {
+            panic!("Expected BreakActive state");
+        }
+        assert!(matches!(
+            event,
+            Some(CompletionEvent::PomodoroComplete {
+                is_long_break: true,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_update_setting() {
+        let mut app = create_test_app();
+        app.update_setting(|s| s.pomodoro_mins = 30).unwrap();
+
+        assert_eq!(app.settings.pomodoro_mins, 30);
+
+        // Verify it was saved
+        let loaded = app.db.load_settings().unwrap();
+        assert_eq!(loaded.pomodoro_mins, 30);
+    }
+
+    #[test]
+    fn test_update_setting_rejects_out_of_range() {
+        let mut app = create_test_app();
+        let before = app.settings.clone();
+        let error = app
+            .update_setting(|s| {
+                s.short_break_mins = 10;
+                s.pomodoro_mins = 0;
+            })
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Pomodoro must be between 1 and 180");
+
+        // Nothing of the change is applied or saved
+        assert_eq!(app.settings, before);
+        assert_eq!(app.db.load_settings().unwrap(), before);
+    }
+
+    #[test]
+    fn test_update_setting_with_stored_value_out_of_range() {
+        let db = Database::new_in_memory().unwrap();
+        let stored = Settings {
+            pomodoro_mins: 500,
+            pomodoros_for_long_break: 0,
+            ..Settings::default()
+        };
+        db.save_settings(&stored).unwrap();
+        let mut app = App::new_with_db(db).unwrap();
+        assert_eq!(app.settings.pomodoro_mins, 180);
+        assert_eq!(app.settings.pomodoros_for_long_break, 1);
+
+        // Other changes go through, but a zero-minute pomodoro still doesn't
+        app.update_setting(|s| s.strict_mode = true).unwrap();
+        assert!(app.settings.strict_mode);
+        assert!(app.update_setting(|s| s.pomodoro_mins = 0).is_err());
+        assert_eq!(app.settings.pomodoro_mins, 180);
+    }
+
+    #[test]
+    fn test_log_manual_pomodoro() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.log_manual_pomodoro(40);
+
+        // The running pomodoro and the cycle are untouched
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+        assert_eq!(app.session.pomodoros_in_cycle, 0);
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+
+        let stats = app.db.get_daily_stats(app.session.last_date).unwrap();
+        assert_eq!(stats.completed_pomodoros, 1);
+        assert_eq!(stats.total_focus_minutes, 40);
+
+        // Later saves keep the logged pomodoro
+        app.complete_early();
+        let stats = app.db.get_daily_stats(app.session.last_date).unwrap();
+        assert_eq!(stats.completed_pomodoros, 2);
+        assert_eq!(stats.total_focus_minutes, 40 + app.settings.pomodoro_mins);
+    }
+
+    #[test]
+    fn test_completed_pomodoros_are_logged_with_task() {
+        let mut app = create_test_app();
+        app.set_task(Some("  Write report ".to_string()));
+        assert_eq!(app.task.as_deref(), Some("Write report"));
+        run_pomodoro_to_end(&mut app);
+        app.log_manual_pomodoro(40);
+
+        app.set_task(Some(" ".to_string()));
+        assert_eq!(app.task, None);
+        app.skip_break();
+        run_pomodoro_to_end(&mut app);
+
+        let history = app.task_history();
+        assert_eq!(history.recent, ["Write report"]);
+        let tasks: Vec<_> = history
+            .today
+            .iter()
+            .map(|t| (t.task.as_deref(), t.pomodoros, t.focus_minutes))
+            .collect();
+        assert_eq!(tasks, [(Some("Write report"), 2, 65), (None, 1, 25)]);
+
+        // A label stays for the next pomodoros until changed
+        app.set_task(Some("Email".to_string()));
+        app.log_manual_pomodoro(25);
+        assert_eq!(app.task_history().recent, ["Email", "Write report"]);
+        assert!(app.template_vars().contains(&("task", "Email".to_string())));
+    }
+
+    #[test]
+    fn test_import_settings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("settings.json");
+        let exported = Settings {
+            pomodoro_mins: 45,
+            long_break_mins: 30,
+            ..Settings::default()
+        };
+        settings_file::export(&exported, &path).unwrap();
+
+        let mut app = create_test_app();
+        app.import_settings(&path).unwrap();
+        assert_eq!(app.settings, exported);
+        assert_eq!(app.db.load_settings().unwrap(), exported);
+    }
+
+    #[test]
+    fn test_import_settings_keeps_this_macs_webhook() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("settings.json");
+        let exported = Settings {
+            pomodoro_mins: 45,
+            daily_webhook_url: Some("http://other/daily".to_string()),
+            ..Settings::default()
+        };
+        settings_file::export(&exported, &path).unwrap();
+
+        let mut app = create_test_app();
+        let local = Some("http://localhost/daily".to_string());
+        app.update_setting(|s| s.daily_webhook_url = local.clone())
+            .unwrap();
+        app.import_settings(&path).unwrap();
+        assert_eq!(app.settings.pomodoro_mins, 45);
+        assert_eq!(app.settings.daily_webhook_url, local);
+    }
+
+    #[test]
+    fn test_import_malformed_settings_keeps_current() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::write(&path, "{\"pomodoro_mins\": 45,").unwrap();
+
+        let mut app = create_test_app();
+        app.update_setting(|s| s.pomodoro_mins = 30).unwrap();
+        let before = app.settings.clone();
+
+        assert!(app.import_settings(&path).is_err());
+        assert_eq!(app.settings, before);
+        assert_eq!(app.db.load_settings().unwrap(), before);
+    }
+
+    #[test]
+    fn test_reset_today() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+
+        app.reset_today();
+
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+        assert_eq!(app.session.total_focus_mins_today, 0);
+    }
+
+    #[test]
+    fn test_reset_counts_keeps_cycle() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        app.reset_counts();
+
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+        assert_eq!(app.session.total_focus_mins_today, 0);
+        assert_eq!(app.session.pomodoros_in_cycle, 1);
+        assert_eq!(
+            app.db
+                .load_today_session(app.today())
+                .unwrap()
+                .pomodoros_completed_today,
+            0
+        );
+    }
+
+    #[test]
+    fn test_start_long_break_when_idle() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.skip_break();
+        assert_eq!(app.session.pomodoros_in_cycle, 1);
+
+        assert!(app.start_long_break());
+        assert_eq!(
+            app.state,
+            TimerState::BreakActive {
+                is_long_break: true,
+                remaining_secs: 15 * 60,
+                total_secs: 15 * 60,
+            }
+        );
+        assert_eq!(app.session.pomodoros_in_cycle, 0);
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+        assert_eq!(
+            app.db
+                .load_today_session(app.today())
+                .unwrap()
+                .pomodoros_in_cycle,
+            0
+        );
+
+        // Already on a long break
+        assert!(!app.start_long_break());
+    }
+
+    #[test]
+    fn test_start_long_break_upgrades_short_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.advance(StdDuration::from_secs(2 * 60));
+
+        assert!(app.start_long_break());
+        assert_eq!(
+            app.state,
+            TimerState::BreakActive {
+                is_long_break: true,
+                remaining_secs: 13 * 60,
+                total_secs: 15 * 60,
+            }
+        );
+        assert_eq!(app.session.pomodoros_in_cycle, 0);
+    }
+
+    #[test]
+    fn test_start_long_break_upgrade_leaves_a_minute() {
+        let mut app = create_test_app();
+        app.settings.short_break_mins = 10;
+        app.settings.long_break_mins = 5;
+        app.start_pomodoro();
+        app.complete_early();
+        app.advance(StdDuration::from_secs(7 * 60));
+        app.pause();
+
+        assert!(app.start_long_break());
+        assert_eq!(
+            app.state,
+            TimerState::BreakPaused {
+                is_long_break: true,
+                remaining_secs: 60,
+                total_secs: 8 * 60,
+            }
+        );
+    }
+
+    #[test]
+    fn test_start_long_break_not_during_pomodoro() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        assert!(!app.start_long_break());
+        assert!(app.state.is_pomodoro());
+
+        app.stop();
+        app.start_stopwatch();
+        assert!(!app.start_long_break());
+        assert!(app.state.is_stopwatch());
+    }
+
+    #[test]
+    fn test_reset_cycle_keeps_counts() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        app.reset_cycle();
+
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+        assert_eq!(app.session.pomodoros_in_cycle, 0);
+        assert_eq!(
+            app.db
+                .load_today_session(app.today())
+                .unwrap()
+                .pomodoros_completed_today,
+            1
+        );
+    }
+
+    #[test]
+    fn test_tick_returns_correct_flags() {
+        let mut app = create_test_app();
+
+        // Idle state - no changes
+        let (changed, event) = app.tick();
+        assert!(!changed);
+        assert!(event.is_none());
+
+        // Active state - changes but not completed
+        app.start_pomodoro();
+        let (changed, event) = app.tick();
+        assert!(changed);
+        assert!(event.is_none());
+
+        // Paused state - no changes
+        app.pause();
+        let (changed, event) = app.tick();
+        assert!(!changed);
+        assert!(event.is_none());
+    }
+
+    #[test]
+    fn test_template_vars() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        let vars = app.template_vars();
+        assert_eq!(
+            vars,
+            [
+                ("count", "1".to_string()),
+                ("pomodoros", "1 pomodoro".to_string()),
+                ("duration", "25".to_string()),
+                ("streak", "1".to_string()),
+                ("task", String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_week_totals_include_today() {
+        let mut app = create_test_app();
+        app.settings.week_starts_on = WeekStart::Sunday;
+        app.start_pomodoro();
+        app.complete_early();
+
+        let totals = app.week_totals();
+        let today = Local::now().date_naive();
+        assert_eq!(totals.start, WeekStart::Sunday.week_start(today));
+        assert_eq!(totals.completed_pomodoros, 1);
+        assert_eq!(totals.total_focus_minutes, 25);
+    }
+
+    fn at(hour: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 1, 15, hour, min, 0).unwrap()
+    }
+
+    fn end_of_day_settings(policy: WindDownPolicy) -> Settings {
+        Settings {
+            end_of_day: NaiveTime::from_hms_opt(18, 0, 0),
+            wind_down_policy: policy,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn test_wind_down_decision() {
+        let settings = end_of_day_settings(WindDownPolicy::Shorten);
+
+        // Ends before or exactly at the end of the day
+        assert_eq!(wind_down_decision(&settings, at(17, 0)), WindDown::Full);
+        assert_eq!(wind_down_decision(&settings, at(17, 35)), WindDown::Full);
+        // Would run past it
+        assert_eq!(
+            wind_down_decision(&settings, at(17, 48)),
+            WindDown::Shortened {
+                total_secs: 12 * 60
+            }
+        );
+        // Already past the end of the day
+        assert_eq!(wind_down_decision(&settings, at(18, 0)), WindDown::Full);
+        assert_eq!(wind_down_decision(&settings, at(19, 30)), WindDown::Full);
+        // No end of day configured
+        assert_eq!(
+            wind_down_decision(&Settings::default(), at(17, 48)),
+            WindDown::Full
+        );
+    }
+
+    #[test]
+    fn test_wind_down_shortens_and_credits_actual_minutes() {
+        let mut app = create_test_app();
+        app.settings = end_of_day_settings(WindDownPolicy::Shorten);
+
+        assert!(app.start_pomodoro_at(at(17, 48)).is_ok());
+        assert_eq!(app.state.total_secs(), Some(12 * 60));
+        assert_eq!(app.wind_down_ends_at, Some(at(18, 0)));
+
+        app.advance(StdDuration::from_secs(12 * 60));
+        assert_eq!(app.session.total_focus_mins_today, 12);
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+        assert_eq!(app.wind_down_ends_at, None);
+    }
+
+    #[test]
+    fn test_wind_down_allow_overrun() {
+        let mut app = create_test_app();
+        app.settings = end_of_day_settings(WindDownPolicy::AllowOverrun);
+
+        assert!(app.start_pomodoro_at(at(17, 48)).is_ok());
+        assert_eq!(app.state.total_secs(), Some(25 * 60));
+        assert_eq!(app.wind_down_ends_at, None);
+    }
+
+    #[test]
+    fn test_wind_down_block() {
+        let mut app = create_test_app();
+        app.settings = end_of_day_settings(WindDownPolicy::Block);
+
+        assert_eq!(
+            app.start_pomodoro_at(at(17, 48)),
+            Err(StartRefused::PastEndOfDay)
+        );
+        assert!(app.state.is_idle());
+        // Start Anyway runs the full pomodoro
+        assert!(!app.start_anyway());
+        assert!(app.start_anyway());
+        assert_eq!(app.state.total_secs(), Some(25 * 60));
+        app.stop();
+        // Pomodoros that fit still start
+        assert!(app.start_pomodoro_at(at(17, 0)).is_ok());
+    }
+
+    #[test]
+    fn test_wind_down_under_a_minute_credits_a_minute() {
+        let mut app = create_test_app();
+        app.settings = end_of_day_settings(WindDownPolicy::Shorten);
+        let start = at(17, 59) + Duration::seconds(30);
+
+        app.start_pomodoro_at(start).unwrap();
+        assert_eq!(app.state.total_secs(), Some(30));
+        app.advance(StdDuration::from_secs(30));
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+        assert_eq!(app.session.total_focus_mins_today, 1);
+    }
+
+    #[test]
+    fn test_stop_clears_wind_down() {
+        let mut app = create_test_app();
+        app.settings = end_of_day_settings(WindDownPolicy::Shorten);
+        app.start_pomodoro_at(at(17, 48)).unwrap();
+
+        app.stop();
+        assert_eq!(app.wind_down_ends_at, None);
+    }
+
+    #[test]
+    fn test_overtime_starts_at_zero_without_break() {
+        let mut app = create_test_app();
+        app.settings.overtime_enabled = true;
+        app.start_pomodoro();
+
+        // The tick that crosses zero carries the extra seconds into overtime
+        let (changed, event) = app.advance(StdDuration::from_secs(25 * 60 + 3));
+        assert!(changed);
+        assert_eq!(event, Some(CompletionEvent::OvertimeStarted));
+        assert_eq!(
+            app.state,
+            TimerState::Overtime {
+                elapsed_overtime_secs: 3,
+                total_secs: 1500,
+            }
+        );
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+    }
+
+    #[test]
+    fn test_overtime_accumulates() {
+        let mut app = create_test_app();
+        app.settings.overtime_enabled = true;
+        app.start_pomodoro();
+        app.advance(StdDuration::from_secs(25 * 60));
+
+        for _ in 0..90 {
+            assert_eq!(app.tick(), (true, None));
+        }
+        assert_eq!(
+            app.state,
+            TimerState::Overtime {
+                elapsed_overtime_secs: 90,
+                total_secs: 1500,
+            }
+        );
+    }
+
+    #[test]
+    fn test_overtime_complete_credits_overtime() {
+        let mut app = create_test_app();
+        app.settings.overtime_enabled = true;
+        app.start_pomodoro();
+        app.advance(StdDuration::from_secs(25 * 60 + 7 * 60 + 30));
+
+        let event = app.complete_early();
+        assert!(matches!(
+            event,
+            Some(CompletionEvent::PomodoroComplete { count: 1, .. })
+        ));
+        assert!(app.state.is_break());
+        assert_eq!(app.session.total_focus_mins_today, 32);
+    }
+
+    #[test]
+    fn test_overtime_disabled_starts_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        let (_, event) = app.advance(StdDuration::from_secs(25 * 60 + 3));
+        assert!(matches!(
+            event,
+            Some(CompletionEvent::PomodoroComplete { .. })
+        ));
+        assert!(app.state.is_break());
+        assert_eq!(app.session.total_focus_mins_today, 25);
+    }
+
+    #[test]
+    fn test_stopwatch_counts_up_and_pauses() {
+        let mut app = create_test_app();
+        app.start_stopwatch();
+        assert_eq!(app.state, TimerState::StopwatchActive { elapsed_secs: 0 });
+
+        app.advance(StdDuration::from_secs(90));
+        app.pause();
+        assert_eq!(app.state, TimerState::StopwatchPaused { elapsed_secs: 90 });
+        assert_eq!(app.tick(), (false, None));
+
+        app.resume();
+        assert_eq!(app.tick(), (true, None));
+        assert_eq!(app.state, TimerState::StopwatchActive { elapsed_secs: 91 });
+        assert_eq!(app.session.interruptions_today, 0);
+    }
+
+    #[test]
+    fn test_stopwatch_stop_credits_minutes() {
+        let mut app = create_test_app();
+        app.start_stopwatch();
+        app.advance(StdDuration::from_secs(7 * 60 + 40));
+        app.stop();
+
+        // Below the 10 minute default: minutes only
+        assert!(app.state.is_idle());
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+        assert_eq!(app.session.total_focus_mins_today, 7);
+        assert_eq!(app.session.abandoned_today, 0);
+        assert_eq!(
+            app.db
+                .load_today_session(app.today())
+                .unwrap()
+                .total_focus_mins_today,
+            7
+        );
+    }
+
+    #[test]
+    fn test_quit_saves_stopwatch_and_pending_writes() {
+        let mut app = create_test_app();
+        app.start_stopwatch();
+        app.advance(StdDuration::from_secs(12 * 60));
+        app.db.set_read_only(true);
+        app.log_manual_pomodoro(25);
+        app.db.set_read_only(false);
+
+        app.save_before_quit();
+        assert!(app.state.is_idle());
+        let saved = app.db.load_today_session(app.today()).unwrap();
+        assert_eq!(saved.pomodoros_completed_today, 2);
+        assert_eq!(saved.total_focus_mins_today, 37);
+    }
+
+    /// Opens an app on the database file at `path`, as a launch would.
+    fn launch_app_on(path: &Path, clock: &MockClock) -> App {
+        let db = Database::open(path).unwrap();
+        App::new_with_clock(db, Box::new(clock.clone())).unwrap()
+    }
+
+    #[test]
+    fn test_resets_are_saved_for_the_next_launch() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let clock = MockClock::new(at(9, 0));
+
+        let mut app = launch_app_on(&path, &clock);
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        app.reset_cycle();
+        drop(app);
+
+        let mut app = launch_app_on(&path, &clock);
+        assert_eq!(app.session.pomodoros_completed_today, 2);
+        assert_eq!(app.session.pomodoros_in_cycle, 0);
+        assert_eq!(app.db.stats_by_task(app.today()).unwrap().len(), 1);
+        app.reset_counts();
+        drop(app);
+
+        let app = launch_app_on(&path, &clock);
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+        assert_eq!(app.session.total_focus_mins_today, 0);
+        assert!(app.db.stats_by_task(app.today()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_quit_saves_the_timer_for_the_next_launch() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let clock = MockClock::new(at(9, 0));
+
+        let mut app = launch_app_on(&path, &clock);
+        app.start_pomodoro();
+        app.advance(StdDuration::from_secs(5 * 60));
+        app.save_before_quit();
+        drop(app);
+
+        // A running pomodoro comes back paused where it was, with when it began
+        let mut app = launch_app_on(&path, &clock);
+        assert_eq!(
+            app.state,
+            TimerState::PomodoroPaused {
+                remaining_secs: 20 * 60,
+                total_secs: 25 * 60,
+            }
+        );
+        assert_eq!(app.current_block_started_at(), Some(at(9, 0)));
+        app.resume();
+        app.advance(StdDuration::from_secs(20 * 60));
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+        app.save_before_quit();
+        drop(app);
+
+        // So does the break that followed, but only on the next launch
+        let app = launch_app_on(&path, &clock);
+        assert!(matches!(app.state, TimerState::BreakPaused { .. }));
+        drop(app);
+        let app = launch_app_on(&path, &clock);
+        assert!(app.state.is_idle());
+    }
+
+    #[test]
+    fn test_long_break_stays_on_schedule_across_a_relaunch() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let clock = MockClock::new(at(9, 0));
+
+        let mut app = launch_app_on(&path, &clock);
+        for _ in 0..3 {
+            run_pomodoro_to_end(&mut app);
+            app.skip_break();
+        }
+        app.start_pomodoro();
+        app.advance(StdDuration::from_secs(5 * 60));
+        app.save_before_quit();
+        drop(app);
+
+        // The fourth pomodoro comes back with the three before it in the cycle
+        let mut app = launch_app_on(&path, &clock);
+        assert_eq!(app.session.pomodoros_in_cycle, 3);
+        app.resume();
+        app.advance(StdDuration::from_secs(20 * 60));
+        assert!(matches!(
+            app.state,
+            TimerState::BreakActive {
+                is_long_break: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_saved_timer_is_only_restored_on_its_day() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let clock = MockClock::new(at(21, 0));
+
+        let mut app = launch_app_on(&path, &clock);
+        app.start_pomodoro();
+        app.save_before_quit();
+        drop(app);
+
+        clock.advance(Duration::days(1));
+        let app = launch_app_on(&path, &clock);
+        assert!(app.state.is_idle());
+    }
+
+    #[test]
+    fn test_stopwatch_complete_counts_as_pomodoro() {
+        let mut app = create_test_app();
+        app.settings.stopwatch_min_mins = 20;
+        app.settings.strict_mode = true;
+        app.start_stopwatch();
+        app.advance(StdDuration::from_secs(72 * 60));
+
+        assert_eq!(app.complete_early(), None);
+        assert!(app.state.is_idle());
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+        assert_eq!(app.session.total_focus_mins_today, 72);
+        assert_eq!(
+            app.db
+                .load_today_session(app.today())
+                .unwrap()
+                .pomodoros_completed_today,
+            1
+        );
+    }
+
+    #[test]
+    fn test_stopwatch_needs_idle_timer() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.start_stopwatch();
+        assert!(app.state.is_pomodoro());
+
+        app.stop();
+        app.session.total_focus_mins_today = 600;
+        app.settings.max_daily_focus_mins = Some(600);
+        app.start_stopwatch();
+        assert!(app.state.is_idle());
+    }
+
+    #[test]
+    fn test_strict_mode_blocks_pause_and_stop() {
+        let mut app = create_test_app();
+        app.settings.strict_mode = true;
+        app.start_pomodoro();
+
+        app.pause();
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+        app.stop();
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+
+        // Idle detection doesn't pause it either
+        app.settings.idle_pause_secs = 60;
+        assert!(!app.apply_idle(120.0));
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+
+        // Completing early still works
+        assert!(app.complete_early().is_some());
+        assert!(app.state.is_break());
+    }
+
+    #[test]
+    fn test_strict_mode_allows_breaks_to_pause() {
+        let mut app = create_test_app();
+        app.settings.strict_mode = true;
+        app.start_pomodoro();
+        app.complete_early();
+
+        app.pause();
+        assert!(matches!(app.state, TimerState::BreakPaused { .. }));
+    }
+
+    #[test]
+    fn test_strict_mode_takes_effect_mid_pomodoro() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        app.settings.strict_mode = true;
+        app.pause();
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+
+        app.settings.strict_mode = false;
+        app.pause();
+        assert!(matches!(app.state, TimerState::PomodoroPaused { .. }));
+        app.stop();
+        assert!(app.state.is_idle());
+    }
+
+    #[test]
+    fn test_week_totals_cached_until_counts_change() {
+        let mut app = create_test_app();
+        assert_eq!(app.week_totals().completed_pomodoros, 0);
+
+        // A write the app doesn't know about isn't picked up from the cache
+        let mut other = app.session.clone();
+        other.pomodoros_completed_today = 3;
+        app.db.save_session(&other, &app.session).unwrap();
+        assert_eq!(app.week_totals().completed_pomodoros, 0);
+
+        // Completing a pomodoro changes today's counts and refreshes the
+        // totals, which include the other writer's pomodoros
+        app.start_pomodoro();
+        app.complete_early();
+        assert_eq!(app.week_totals().completed_pomodoros, 4);
+
+        // So does switching the first day of the week
+        app.settings.week_starts_on = WeekStart::Saturday;
+        let today = app.session.last_date;
+        assert_eq!(
+            app.week_totals().start,
+            WeekStart::Saturday.week_start(today)
+        );
+    }
+
+    #[test]
+    fn test_daily_limit_boundary() {
+        let mut app = create_test_app();
+        app.settings.max_daily_focus_mins = Some(50);
+        app.session.total_focus_mins_today = 49;
+
+        assert!(!app.daily_limit_reached());
+        app.start_pomodoro();
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+
+        // Completing this pomodoro pushes today's total past the limit
+        app.complete_early();
+        app.skip_break();
+        assert!(app.daily_limit_reached());
+
+        assert_eq!(app.start_refused(), Some(StartRefused::DailyLimit));
+        assert_eq!(app.try_start_pomodoro(), Err(StartRefused::DailyLimit));
+        assert!(matches!(app.state, TimerState::BreakFinished));
+    }
+
+    #[test]
+    fn test_daily_limit_resets_at_rollover() {
+        let (mut app, clock) = create_test_app_at(at(22, 0));
+        app.settings.max_daily_focus_mins = Some(50);
+        app.session.total_focus_mins_today = 50;
+        clock.advance(Duration::hours(3));
+
+        assert!(app.daily_limit_reached());
+
+        app.check_day_rollover();
+        assert!(!app.daily_limit_reached());
+
+        app.start_pomodoro();
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+    }
+
+    #[test]
+    fn test_start_anyway_requires_confirmation() {
+        let mut app = create_test_app();
+        app.settings.max_daily_focus_mins = Some(50);
+        app.session.total_focus_mins_today = 60;
+
+        // First click only arms the override
+        assert!(!app.start_anyway());
+        assert!(app.start_anyway_armed);
+        assert!(app.state.is_idle());
+
+        // Second click starts the pomodoro
+        assert!(app.start_anyway());
+        assert!(!app.start_anyway_armed);
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+    }
+
+    #[test]
+    fn test_start_anyway_ignored_when_not_idle() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        assert!(!app.start_anyway());
+        assert!(!app.start_anyway_armed);
+    }
+
+    #[test]
+    fn test_block_started_at_recorded() {
+        let mut app = create_test_app();
+        assert_eq!(app.current_block_started_at(), None);
+
+        let before = Local::now();
+        app.start_pomodoro();
+        let started = app.current_block_started_at().unwrap();
+        assert!(started >= before);
+
+        // A break is a new block
+        app.complete_early();
+        assert!(app.current_block_started_at().unwrap() >= started);
+
+        app.skip_break();
+        assert_eq!(app.current_block_started_at(), None);
+    }
+
+    #[test]
+    fn test_block_started_at_cleared_on_stop() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.stop();
+        assert_eq!(app.current_block_started_at(), None);
+    }
+
+    #[test]
+    fn test_pause_keeps_started_at_but_shifts_end() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        for _ in 0..60 {
+            app.tick();
+        }
+        let started = app.current_block_started_at();
+
+        let now = Local.with_ymd_and_hms(2024, 1, 15, 14, 0, 0).unwrap();
+        let end_before_pause = app.projected_end_at(now).unwrap();
+        assert_eq!(
+            end_before_pause,
+            Local.with_ymd_and_hms(2024, 1, 15, 14, 24, 0).unwrap()
+        );
+
+        app.pause();
+        assert_eq!(app.current_block_started_at(), started);
+
+        // Ten minutes into the pause the end time has moved by ten minutes
+        let later = now + Duration::minutes(10);
+        assert_eq!(
+            app.projected_end_at(later).unwrap(),
+            end_before_pause + Duration::minutes(10)
+        );
+
+        app.resume();
+        assert_eq!(app.current_block_started_at(), started);
+    }
+
+    #[test]
+    fn test_projected_end_at_idle() {
+        let app = create_test_app();
+        assert_eq!(app.projected_end_at(Local::now()), None);
+    }
+
+    #[test]
+    fn test_idle_auto_pause_and_resume() {
+        let mut app = create_test_app();
+        app.settings.idle_pause_secs = 120;
+        app.start_pomodoro();
+
+        assert!(!app.apply_idle(119.0));
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+
+        assert!(app.apply_idle(120.0));
+        assert!(app.state.is_paused());
+        assert!(app.auto_paused);
+
+        // Still idle: stays paused
+        assert!(!app.apply_idle(300.0));
+        assert!(app.state.is_paused());
+
+        // User returns
+        assert!(app.apply_idle(1.0));
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+        assert!(!app.auto_paused);
+    }
+
+    #[test]
+    fn test_idle_auto_pause_is_not_an_interruption() {
+        let mut app = create_test_app();
+        app.settings.idle_pause_secs = 120;
+        run_pomodoro_to_end(&mut app);
+        app.skip_break();
+        app.start_pomodoro();
+        let score = app.focus_score();
+        assert!(score.is_some());
+
+        assert!(app.apply_idle(120.0));
+        assert!(app.apply_idle(1.0));
+        assert_eq!(app.session.interruptions_today, 0);
+        assert_eq!(app.focus_score(), score);
+
+        // A pause the user makes still counts
+        app.pause();
+        assert_eq!(app.session.interruptions_today, 1);
+    }
+
+    #[test]
+    fn test_idle_does_not_resume_manual_pause() {
+        let mut app = create_test_app();
+        app.settings.idle_pause_secs = 120;
+        app.start_pomodoro();
+        app.pause();
+
+        assert!(!app.apply_idle(1.0));
+        assert!(app.state.is_paused());
+    }
+
+    #[test]
+    fn test_idle_detection_disabled_by_default() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        assert!(!app.apply_idle(10_000.0));
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+    }
+
+    #[test]
+    fn test_idle_ignored_during_break() {
+        let mut app = create_test_app();
+        app.settings.idle_pause_secs = 120;
+        app.start_pomodoro();
+        app.complete_early();
+
+        assert!(!app.apply_idle(600.0));
+        assert!(app.state.is_break());
+    }
+
+    #[test]
+    fn test_manual_resume_clears_auto_paused() {
+        let mut app = create_test_app();
+        app.settings.idle_pause_secs = 120;
+        app.start_pomodoro();
+        app.apply_idle(200.0);
+
+        app.resume();
+        assert!(!app.auto_paused);
+    }
+
+    #[test]
+    fn test_advance_after_short_sleep() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        let (_, event) = app.advance(StdDuration::from_secs(10 * 60));
+        assert!(event.is_none());
+        assert_eq!(app.state.remaining_secs(), Some(15 * 60));
+    }
+
+    #[test]
+    fn test_advance_completes_pomodoro() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        // Slept longer than the remaining time
+        let (_, event) = app.advance(StdDuration::from_secs(60 * 60));
+        assert!(matches!(
+            event,
+            Some(CompletionEvent::PomodoroComplete { count: 1, .. })
+        ));
+        assert!(app.state.is_break());
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+    }
+
+    #[test]
+    fn test_advance_completes_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        let (_, event) = app.advance(StdDuration::from_secs(5 * 60));
+        assert_eq!(event, Some(CompletionEvent::BreakComplete));
+        assert!(matches!(app.state, TimerState::BreakFinished));
+    }
+
+    #[test]
+    fn test_advance_ignores_paused_and_idle() {
+        let mut app = create_test_app();
+        assert_eq!(app.advance(StdDuration::from_secs(600)), (false, None));
+        assert!(app.state.is_idle());
+
+        app.start_pomodoro();
+        app.pause();
+        assert_eq!(app.advance(StdDuration::from_secs(600)), (false, None));
+        assert_eq!(app.state.remaining_secs(), Some(25 * 60));
+    }
+
+    #[test]
+    fn test_advance_carries_fractions() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        assert_eq!(app.advance(StdDuration::from_millis(600)), (false, None));
+        assert_eq!(app.state.remaining_secs(), Some(25 * 60));
+
+        // 0.6s + 0.6s crosses a whole second, leaving 0.2s carried over
+        assert_eq!(app.advance(StdDuration::from_millis(600)), (true, None));
+        assert_eq!(app.state.remaining_secs(), Some(25 * 60 - 1));
+
+        assert_eq!(app.advance(StdDuration::from_millis(800)), (true, None));
+        assert_eq!(app.state.remaining_secs(), Some(25 * 60 - 2));
+    }
+
+    #[test]
+    fn test_resume_drops_carried_fraction() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.advance(StdDuration::from_millis(900));
+
+        app.pause();
+        app.resume();
+        assert_eq!(app.advance(StdDuration::from_millis(500)), (false, None));
+        assert_eq!(app.state.remaining_secs(), Some(25 * 60));
+    }
+
+    fn run_until_warning(app: &mut App, max_ticks: u32) -> Vec<WarningEvent> {
+        let mut warnings = Vec::new();
+        for _ in 0..max_ticks {
+            app.tick();
+            warnings.extend(app.take_warning());
+        }
+        warnings
+    }
+
+    #[test]
+    fn test_warning_fires_once_at_threshold() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        let warnings = run_until_warning(&mut app, 25 * 60 - 60 - 1);
+        assert!(warnings.is_empty());
+
+        let warnings = run_until_warning(&mut app, 30);
+        assert_eq!(
+            warnings,
+            vec![WarningEvent {
+                remaining_secs: 60,
+                is_break: false
+            }]
+        );
+    }
+
+    #[test]
+    fn test_warning_not_repeated_after_pause_resume() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        run_until_warning(&mut app, 25 * 60 - 60);
+
+        app.pause();
+        app.resume();
+        assert!(run_until_warning(&mut app, 30).is_empty());
+    }
+
+    #[test]
+    fn test_warning_rearms_for_next_pomodoro() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        assert_eq!(run_until_warning(&mut app, 25 * 60 - 60).len(), 1);
+
+        app.stop();
+        app.start_pomodoro();
+        assert_eq!(run_until_warning(&mut app, 25 * 60 - 60).len(), 1);
+    }
+
+    #[test]
+    fn test_warning_disabled_or_too_short() {
+        let mut app = create_test_app();
+        app.settings.warn_before_end_secs = 0;
+        app.start_pomodoro();
+        assert!(run_until_warning(&mut app, 25 * 60).is_empty());
+
+        let mut app = create_test_app();
+        app.settings.warn_before_end_secs = 30 * 60;
+        app.start_pomodoro();
+        assert!(run_until_warning(&mut app, 25 * 60).is_empty());
+    }
+
+    #[test]
+    fn test_warning_for_breaks_only_when_enabled() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        assert!(run_until_warning(&mut app, 5 * 60).is_empty());
+
+        let mut app = create_test_app();
+        app.settings.warn_before_break_end = true;
+        app.start_pomodoro();
+        app.complete_early();
+        let warnings = run_until_warning(&mut app, 5 * 60);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].is_break);
+    }
+
+    #[test]
+    fn test_daily_summary_queued_once_per_day() {
+        let mut app = create_test_app();
+        app.settings.daily_webhook_url = Some("http://localhost/hook".to_string());
+        app.start_pomodoro();
+        app.complete_early();
+        let today = app.session.last_date;
+
+        assert!(app.queue_summaries_through(today));
+        assert!(!app.queue_summaries_through(today));
+
+        let queued = app.db.queued_summaries().unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].completed_pomodoros, 1);
+        assert_eq!(queued[0].streak_days, 1);
+
+        // Delivering does not allow a second summary for the same day
+        app.db.remove_queued_summary(today).unwrap();
+        assert!(!app.queue_summaries_through(today));
+    }
+
+    #[test]
+    fn test_daily_summary_has_the_goal_status() {
+        let mut app = create_test_app();
+        app.settings.daily_webhook_url = Some("http://localhost/hook".to_string());
+        app.settings.daily_goal = 3;
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        let today = app.session.last_date;
+
+        assert!(app.queue_summaries_through(today));
+        let queued = app.db.queued_summaries().unwrap();
+        assert_eq!(queued[0].goal, Some(GoalStatus::Missed { by: 2 }));
+    }
+
+    /// Stores stats and a score for `date`, as if Pomobar was used that day.
+    fn record_used_day(app: &App, date: NaiveDate) {
+        let session = Session {
+            pomodoros_completed_today: 1,
+            total_focus_mins_today: 25,
+            last_date: date,
+            ..Session::default()
+        };
+        app.db.save_session(&session, &Session::default()).unwrap();
+        app.db
+            .save_day_score(&DayScore {
+                date,
+                score: Some(40),
+                formula_version: score::FORMULA_VERSION,
+            })
+            .unwrap();
+    }
+
+    fn queued_dates(app: &App) -> Vec<NaiveDate> {
+        let queued = app.db.queued_summaries().unwrap();
+        queued.iter().map(|summary| summary.date).collect()
+    }
+
+    #[test]
+    fn test_missed_days_with_activity_each_get_a_summary() {
+        let mut app = create_test_app();
+        app.settings.daily_webhook_url = Some("http://localhost/hook".to_string());
+        let today = app.session.last_date;
+        let days_ago = |days| today - Days::new(days);
+        for days in [9, 6, 4] {
+            record_used_day(&app, days_ago(days));
+        }
+
+        // The first summary only covers the day that ended
+        assert!(app.queue_summaries_through(days_ago(6)));
+        assert_eq!(queued_dates(&app), [days_ago(6)]);
+        app.db.remove_queued_summary(days_ago(6)).unwrap();
+
+        // After that, every day with activity since the last summary, and
+        // the day that ended even without any
+        assert!(app.queue_summaries_through(days_ago(1)));
+        assert_eq!(queued_dates(&app), [days_ago(4), days_ago(1)]);
+        assert!(!app.queue_summaries_through(days_ago(1)));
+    }
+
+    #[test]
+    fn test_days_closed_through_each_get_a_summary() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let clock = MockClock::new(at(9, 0));
+
+        let mut app = launch_app_on(&path, &clock);
+        app.update_setting(|s| s.daily_webhook_url = Some("http://localhost/hook".to_string()))
+            .unwrap();
+        let first_day = app.session.last_date;
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        clock.advance(Duration::days(1));
+        assert!(app.check_day_rollover());
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        app.save_before_quit();
+        drop(app);
+
+        // Closed for three nights after the second day
+        clock.advance(Duration::days(3));
+        let mut app = launch_app_on(&path, &clock);
+        let yesterday = app.session.last_date - Days::new(1);
+        assert!(app.queue_summaries_through(yesterday));
+        assert_eq!(
+            queued_dates(&app),
+            [first_day, first_day + Days::new(1), yesterday]
+        );
+    }
+
+    #[test]
+    fn test_missed_summaries_are_capped() {
+        let mut app = create_test_app();
+        app.settings.daily_webhook_url = Some("http://localhost/hook".to_string());
+        let today = app.session.last_date;
+        let days_ago = |days| today - Days::new(days);
+        app.db
+            .queue_summary(&DailySummary {
+                date: days_ago(100),
+                completed_pomodoros: 0,
+                total_focus_minutes: 0,
+                streak_days: 0,
+                focus_score: None,
+                goal: None,
+            })
+            .unwrap();
+        app.db.remove_queued_summary(days_ago(100)).unwrap();
+        for days in [MAX_MISSED_SUMMARIES + 1, MAX_MISSED_SUMMARIES] {
+            record_used_day(&app, days_ago(days));
+        }
+
+        assert!(app.queue_summaries_through(days_ago(1)));
+        assert_eq!(
+            queued_dates(&app),
+            [days_ago(MAX_MISSED_SUMMARIES), days_ago(1)]
+        );
+    }
+
+    #[test]
+    fn test_daily_summary_needs_url() {
+        let mut app = create_test_app();
+        let today = app.session.last_date;
+        assert!(!app.queue_summaries_through(today));
+        assert!(app.db.queued_summaries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_day_rollover_queues_previous_day() {
+        let (mut app, clock) = create_test_app_at(at(21, 0));
+        app.settings.daily_webhook_url = Some("http://localhost/hook".to_string());
+        let yesterday = app.session.last_date;
+        clock.advance(Duration::hours(12));
+
+        assert!(app.check_day_rollover());
+        assert!(!app.check_day_rollover());
+        assert_eq!(app.db.queued_summaries().unwrap()[0].date, yesterday);
+    }
+
+    #[test]
+    fn test_rollover_hands_over_the_finished_day_once() {
+        let (mut app, clock) = create_test_app_at(at(9, 0));
+        let yesterday = app.session.last_date;
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        clock.advance(Duration::days(1));
+
+        app.check_day_rollover();
+        let summary = app.take_finished_day().unwrap();
+        assert_eq!(summary.date, yesterday);
+        assert_eq!(summary.completed_pomodoros, 1);
+        assert!(summary.focus_score.is_some());
+        assert_eq!(app.take_finished_day(), None);
+
+        // A day without activity has nothing to show
+        clock.advance(Duration::days(1));
+        app.check_day_rollover();
+        assert_eq!(app.take_finished_day(), None);
+    }
+
+    #[test]
+    fn test_export_stats_includes_focus_scores() {
+        let (mut app, clock) = create_test_app_at(at(9, 0));
+        let yesterday = app.session.last_date;
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        clock.advance(Duration::days(1));
+        app.check_day_rollover();
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let (csv_path, _) = app.export_stats(dir.path()).unwrap();
+        let csv = std::fs::read_to_string(csv_path).unwrap();
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(rows.len(), 2);
+
+        let finalized = app.db.get_day_score(yesterday).unwrap().unwrap();
+        let score = finalized.score.unwrap().to_string();
+        assert!(rows[0].starts_with(&yesterday.to_string()));
+        assert!(rows[0].ends_with(&format!(",{}", score)));
+        let today_score = app.focus_score().unwrap().to_string();
+        assert!(rows[1].ends_with(&format!(",{}", today_score)));
+    }
+
+    #[test]
+    fn test_rollover_at_exactly_midnight() {
+        let midnight = at(0, 0) + Duration::days(1);
+        let (mut app, clock) = create_test_app_at(at(9, 0));
+        app.session.pomodoros_completed_today = 3;
+
+        clock.set(midnight - Duration::seconds(1));
+        app.check_day_rollover();
+        assert_eq!(app.session.pomodoros_completed_today, 3);
+
+        clock.set(midnight);
+        app.check_day_rollover();
+        assert_eq!(app.session.last_date, midnight.date_naive());
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+    }
+
+    #[test]
+    fn test_rollover_at_exactly_the_day_start_hour() {
+        let four_am = at(4, 0) + Duration::days(1);
+        let (mut app, clock) = create_test_app_at(at(9, 0));
+        app.update_setting(|s| s.day_starts_at_hour = 4).unwrap();
+        app.session.pomodoros_completed_today = 3;
+
+        clock.set(four_am - Duration::seconds(1));
+        app.check_day_rollover();
+        assert_eq!(app.session.pomodoros_completed_today, 3);
+
+        clock.set(four_am);
+        app.check_day_rollover();
+        assert_eq!(app.session.last_date, four_am.date_naive());
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+    }
+
+    #[test]
+    fn test_rollover_across_several_days() {
+        let (mut app, clock) = create_test_app_at(at(9, 0));
+        app.settings.daily_webhook_url = Some("http://localhost/hook".to_string());
+        let first_day = app.session.last_date;
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+
+        // Away for three nights
+        clock.advance(Duration::days(3));
+        assert!(app.check_day_rollover());
+        assert_eq!(app.session.last_date, first_day + Duration::days(3));
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+
+        // Only the day that was actually used gets a summary and a score
+        let queued = app.db.queued_summaries().unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].date, first_day);
+        assert!(app.db.get_day_score(first_day).unwrap().is_some());
+        assert_eq!(
+            app.db.get_day_score(first_day + Duration::days(1)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rollover_keeps_both_days_in_the_database() {
+        let (mut app, clock) = create_test_app_at(at(22, 0));
+        let yesterday = app.session.last_date;
+        let today = yesterday.succ_opt().unwrap();
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+
+        clock.advance(Duration::hours(12));
+        app.check_day_rollover();
+        run_pomodoro_to_end(&mut app);
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+
+        let days = app.db.get_all_stats().unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!((days[0].date, days[0].completed_pomodoros), (yesterday, 1));
+        assert_eq!((days[1].date, days[1].completed_pomodoros), (today, 2));
+    }
+
+    #[test]
+    fn test_rollover_keeps_failed_writes_with_their_day() {
+        let (mut app, clock) = create_test_app_at(at(22, 0));
+        let yesterday = app.session.last_date;
+        let today = yesterday.succ_opt().unwrap();
+        app.db.set_read_only(true);
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+
+        // A failing database doesn't hold the new day up
+        clock.advance(Duration::hours(12));
+        app.check_day_rollover();
+        assert_eq!(app.session.last_date, today);
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        app.db.set_read_only(false);
+        assert!(app.retry_pending_writes());
+
+        let days = app.db.get_all_stats().unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!((days[0].date, days[0].completed_pomodoros), (yesterday, 1));
+        assert_eq!((days[1].date, days[1].completed_pomodoros), (today, 1));
+    }
+
+    #[test]
+    fn test_rollover_keeps_a_failed_reset_with_its_day() {
+        let (mut app, clock) = create_test_app_at(at(22, 0));
+        let yesterday = app.session.last_date;
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        app.db.set_read_only(true);
+        app.reset_today();
+
+        clock.advance(Duration::hours(12));
+        app.check_day_rollover();
+        run_pomodoro_to_end(&mut app);
+        app.stop();
+        app.db.set_read_only(false);
+        assert!(app.retry_pending_writes());
+
+        let days = app.db.get_all_stats().unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].date, yesterday.succ_opt().unwrap());
+    }
+
+    #[test]
+    fn test_pomodoro_across_midnight_counts_for_its_start_day() {
+        let (mut app, clock) = create_test_app_at(at(23, 50));
+        let yesterday = app.session.last_date;
+        let today = yesterday.succ_opt().unwrap();
+
+        app.start_pomodoro();
+        clock.advance(Duration::minutes(25));
+        assert!(!app.check_day_rollover());
+        assert_eq!(app.session.last_date, yesterday);
+        assert!(app.advance(StdDuration::from_secs(25 * 60)).1.is_some());
+        assert_eq!(
+            app.db
+                .get_daily_stats(yesterday)
+                .unwrap()
+                .completed_pomodoros,
+            1
+        );
+        assert_eq!(
+            app.db.get_daily_stats(today).unwrap().completed_pomodoros,
+            0
+        );
+
+        // The break that follows waits as well
+        app.check_day_rollover();
+        assert_eq!(app.session.pomodoros_completed_today, 1);
+        app.skip_break();
+        assert_eq!(app.db.get_daily_stats(yesterday).unwrap().skipped_breaks, 1);
+
+        app.check_day_rollover();
+        assert_eq!(app.session.last_date, today);
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+        assert_eq!(app.session.pomodoros_in_cycle, 1);
+    }
+
+    #[test]
+    fn test_stopwatch_across_midnight_counts_for_its_start_day() {
+        let (mut app, clock) = create_test_app_at(at(23, 45));
+        let yesterday = app.session.last_date;
+        let today = yesterday.succ_opt().unwrap();
+
+        app.start_stopwatch();
+        clock.advance(Duration::minutes(30));
+        app.advance(StdDuration::from_secs(30 * 60));
+        app.check_day_rollover();
+        app.stop();
+        assert_eq!(
+            app.db
+                .get_daily_stats(yesterday)
+                .unwrap()
+                .total_focus_minutes,
+            30
+        );
+
+        app.check_day_rollover();
+        assert_eq!(app.session.last_date, today);
+        assert_eq!(app.session.total_focus_mins_today, 0);
+    }
+
+    #[test]
+    fn test_focus_score_counts_interruptions_abandoned_and_skipped() {
+        let mut app = create_test_app();
+        assert_eq!(app.focus_score(), None);
+
+        app.start_pomodoro();
+        app.pause();
+        app.pause(); // already paused
+        app.resume();
+        app.complete_early();
+        app.pause(); // a paused break is not an interruption
+        app.skip_break();
+        app.start_pomodoro();
+        app.stop();
+        app.stop(); // already idle
+
+        assert_eq!(app.session.interruptions_today, 1);
+        assert_eq!(app.session.skipped_breaks_today, 1);
+        assert_eq!(app.session.abandoned_today, 1);
+        // 1 of 8 pomodoros: 9 progress, 30 - 4 - 10 - 5 discipline
+        assert_eq!(app.focus_score(), Some(20));
+
+        // The counters survive a restart
+        let loaded = app.db.load_today_session(app.today()).unwrap();
+        assert_eq!(loaded.interruptions_today, 1);
+        assert_eq!(loaded.abandoned_today, 1);
+        assert_eq!(loaded.skipped_breaks_today, 1);
+    }
+
+    #[test]
+    fn test_mark_interruption_keeps_the_timer_running() {
+        let mut app = create_test_app();
+        assert!(!app.mark_interruption());
+
+        app.start_pomodoro();
+        assert!(app.mark_interruption());
+        assert!(app.mark_interruption());
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+        assert_eq!(app.pomodoro_interruptions, 2);
+        assert_eq!(app.session.marked_interruptions_today, 2);
+        // Marking isn't pausing, so the focus score's count is untouched
+        assert_eq!(app.session.interruptions_today, 0);
+
+        app.pause();
+        assert!(!app.mark_interruption());
+        app.resume();
+        run_pomodoro_to_end(&mut app);
+        assert_eq!(app.pomodoro_interruptions, 0);
+        assert!(!app.mark_interruption()); // during the break
+
+        app.skip_break();
+        app.start_pomodoro();
+        app.mark_interruption();
+        assert_eq!(app.pomodoro_interruptions, 1);
+        assert_eq!(app.session.marked_interruptions_today, 3);
+    }
+
+    #[test]
+    fn test_marked_interruptions_persist_and_reset_at_rollover() {
+        let (mut app, clock) = create_test_app_at(at(21, 0));
+        app.start_pomodoro();
+        app.mark_interruption();
+        app.stop();
+
+        let loaded = app.db.load_today_session(app.today()).unwrap();
+        assert_eq!(loaded.marked_interruptions_today, 1);
+        let stats = app.db.get_daily_stats(app.session.last_date).unwrap();
+        assert_eq!(stats.marked_interruptions, 1);
+
+        clock.advance(Duration::hours(12));
+        app.check_day_rollover();
+        assert_eq!(app.session.marked_interruptions_today, 0);
+        let stats = app.db.get_daily_stats(app.session.last_date).unwrap();
+        assert_eq!(stats.marked_interruptions, 0);
+    }
+
+    #[test]
+    fn test_day_rollover_finalizes_score() {
+        let (mut app, clock) = create_test_app_at(at(17, 0));
+        app.settings.daily_webhook_url = Some("http://localhost/hook".to_string());
+        let yesterday = app.session.last_date;
+        app.session = Session {
+            pomodoros_completed_today: 4,
+            total_focus_mins_today: 100,
+            interruptions_today: 1,
+            last_date: yesterday,
+            ..Session::default()
+        };
+        app.db
+            .save_session(&app.session, &Session::default())
+            .unwrap();
+
+        clock.advance(Duration::days(1));
+        assert!(app.check_day_rollover());
+        let expected = DayScore {
+            date: yesterday,
+            score: Some(61),
+            formula_version: score::FORMULA_VERSION,
+        };
+        assert_eq!(app.db.get_day_score(yesterday).unwrap(), Some(expected));
+        assert_eq!(app.db.queued_summaries().unwrap()[0].focus_score, Some(61));
+        assert_eq!(app.focus_score(), None);
+
+        // A later goal change doesn't touch the finalized score
+        app.settings.daily_goal = 4;
+        app.finalize_scores();
+        assert_eq!(app.db.get_day_score(yesterday).unwrap(), Some(expected));
+    }
+
+    #[test]
+    fn test_startup_finalizes_missed_days() {
+        let db = Database::new_in_memory().unwrap();
+        let two_days_ago = Local::now().date_naive() - Duration::days(2);
+        db.save_session(
+            &Session {
+                pomodoros_completed_today: 8,
+                last_date: two_days_ago,
+                ..Session::default()
+            },
+            &Session::default(),
+        )
+        .unwrap();
+
+        let app = App::new_with_db(db).unwrap();
+        let score = app.db.get_day_score(two_days_ago).unwrap().unwrap();
+        assert_eq!(score.score, Some(100));
+    }
+
+    #[test]
+    fn test_merge_stray_db() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let stray_path = dir.path().join("pomobar.db");
+        let stray = Database::open(&stray_path).unwrap();
+        let mut stray_session = Session::default();
+        stray_session.complete_pomodoro(25);
+        stray_session.complete_pomodoro(25);
+        stray
+            .save_session(&stray_session, &Session::default())
+            .unwrap();
+
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.stray_db = Some(stray_path.clone());
+
+        // Still open elsewhere, so its rows are only in the -wal file
+        app.merge_stray_db().unwrap();
+        drop(stray);
+        assert_eq!(app.stray_db, None);
+        assert_eq!(app.session.pomodoros_completed_today, 3);
+        assert_eq!(app.session.total_focus_mins_today, 75);
+        assert_eq!(app.session.pomodoros_in_cycle, 1);
+        assert!(!stray_path.exists());
+        assert!(dir.path().join("pomobar.db.merged").exists());
+    }
+
+    fn create_waiting_app(reminder_mins: u32) -> (App, DateTime<Local>) {
+        let mut app = create_test_app();
+        app.settings.idle_reminder_mins = reminder_mins;
+        app.start_pomodoro();
+        app.complete_early();
+        app.skip_break();
+        let since = app.waiting_since.unwrap();
+        (app, since)
+    }
+
+    #[test]
+    fn test_reminder_fires_after_interval() {
+        let (mut app, since) = create_waiting_app(10);
+
+        assert!(!app.reminder_due(since + Duration::minutes(9)));
+        assert!(app.reminder_due(since + Duration::minutes(10)));
+    }
+
+    #[test]
+    fn test_reminder_repeats_at_interval() {
+        let (mut app, since) = create_waiting_app(10);
+
+        assert!(app.reminder_due(since + Duration::minutes(10)));
+        assert!(!app.reminder_due(since + Duration::minutes(15)));
+        assert!(app.reminder_due(since + Duration::minutes(20)));
+    }
+
+    #[test]
+    fn test_reminder_stops_on_start_or_stop() {
+        let (mut app, since) = create_waiting_app(10);
+        app.start_pomodoro();
+        app.stop();
+        assert!(!app.reminder_due(since + Duration::minutes(30)));
+
+        let (mut app, since) = create_waiting_app(10);
+        app.stop();
+        assert!(!app.reminder_due(since + Duration::minutes(30)));
+    }
+
+    /// Runs a pomodoro and its break out on the clock.
+    fn create_break_finished_app() -> App {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.advance(StdDuration::from_secs(5 * 60));
+        assert_eq!(app.state, TimerState::BreakFinished);
+        app
+    }
+
+    #[test]
+    fn test_blink_attention_alternates() {
+        let mut app = create_break_finished_app();
+        assert!(!app.attention_blink_on());
+
+        for on in [true, false, true, false] {
+            assert!(app.blink_attention());
+            assert_eq!(app.attention_blink_on(), on);
+        }
+    }
+
+    #[test]
+    fn test_blink_attention_stops_on_start() {
+        let mut app = create_break_finished_app();
+        app.blink_attention();
+        app.start_pomodoro();
+        assert!(!app.attention_blink_on());
+
+        // One more redraw puts the plain title back
+        assert!(app.blink_attention());
+        assert!(!app.blink_attention());
+        app.stop();
+        assert!(!app.blink_attention());
+    }
+
+    #[test]
+    fn test_blink_attention_stops_when_seen() {
+        let mut app = create_break_finished_app();
+        app.blink_attention();
+        app.mark_attention_seen();
+
+        assert!(app.blink_attention());
+        assert!(!app.attention_blink_on());
+        assert!(!app.blink_attention());
+    }
+
+    #[test]
+    fn test_blink_attention_disabled_or_skipped() {
+        let mut app = create_break_finished_app();
+        app.settings.attention_blink = false;
+        assert!(!app.blink_attention());
+
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.skip_break();
+        assert_eq!(app.state, TimerState::BreakFinished);
+        assert!(!app.blink_attention());
+    }
+
+    #[test]
+    fn test_reminder_disabled() {
+        let (mut app, since) = create_waiting_app(0);
+        assert!(!app.reminder_due(since + Duration::hours(2)));
+
+        let (mut app, since) = create_waiting_app(10);
+        app.settings.notifications_enabled = false;
+        assert!(!app.reminder_due(since + Duration::hours(2)));
+    }
+
+    #[test]
+    fn test_reminder_not_before_first_pomodoro() {
+        let mut app = create_test_app();
+        app.settings.idle_reminder_mins = 10;
+        assert!(!app.reminder_due(Local::now() + Duration::hours(2)));
+
+        // A day rollover resets the count
+        let (mut app, since) = create_waiting_app(10);
+        app.session.pomodoros_completed_today = 0;
+        assert!(!app.reminder_due(since + Duration::hours(2)));
+    }
+
+    #[test]
+    fn test_break_completion_event() {
+        let mut app = create_test_app();
+        app.settings.short_break_mins = 1; // 1 minute for faster test
+
+        // Start and complete a pomodoro to get to break
+        app.start_pomodoro();
+        app.complete_early();
+
+        assert!(app.state.is_break());
+
+        // Tick through the break
+        let mut break_event = None;
+        for _ in 0..61 {
+            let (_, event) = app.tick();
+            if event.is_some() {
+                break_event = event;
+                break;
+            }
+        }
+
+        assert!(matches!(app.state, TimerState::BreakFinished));
+        assert!(matches!(break_event, Some(CompletionEvent::BreakComplete)));
+    }
+
+    /// Fake Focus and media controls that record their calls and let tests
+    /// change the state behind the app's back.
+    #[derive(Clone, Default)]
+    struct FakeSystem {
+        /// Current Focus state (None = unknown).
+        focus_active: Arc<Mutex<Option<bool>>>,
+        focus_calls: Arc<Mutex<Vec<bool>>>,
+        /// The players that are playing.
+        playing: Arc<Mutex<Vec<Player>>>,
+        media_calls: Arc<Mutex<Vec<(&'static str, Player)>>>,
+    }
+
+    impl FakeSystem {
+        /// Returns the Focus calls made once the app's Focus thread has caught up.
+        fn focus_calls(&self, app: &App) -> Vec<bool> {
+            app.focus.wait();
+            self.focus_calls.lock().unwrap().clone()
+        }
+
+        /// Returns the media calls made once the app's media thread has caught up.
+        fn media_calls(&self, app: &App) -> Vec<(&'static str, Player)> {
+            app.media.wait();
+            self.media_calls.lock().unwrap().clone()
+        }
+    }
+
+    impl FocusControl for FakeSystem {
+        fn is_active(&self) -> Option<bool> {
+            *self.focus_active.lock().unwrap()
+        }
+
+        fn set_active(&self, active: bool) -> Result<(), FocusError> {
+            *self.focus_active.lock().unwrap() = Some(active);
+            self.focus_calls.lock().unwrap().push(active);
+            Ok(())
+        }
+    }
+
+    impl MediaControl for FakeSystem {
+        fn is_playing(&self, player: Player) -> bool {
+            self.playing.lock().unwrap().contains(&player)
+        }
+
+        fn pause(&self, player: Player) -> Result<(), MediaError> {
+            self.playing.lock().unwrap().retain(|&p| p != player);
+            self.media_calls.lock().unwrap().push(("pause", player));
+            Ok(())
+        }
+
+        fn play(&self, player: Player) -> Result<(), MediaError> {
+            self.playing.lock().unwrap().push(player);
+            self.media_calls.lock().unwrap().push(("play", player));
+            Ok(())
+        }
+    }
+
+    /// Creates a test app with Focus and media control on, switching fakes
+    /// that start out with `focus_active` and `playing`.
+    fn create_system_app(focus_active: Option<bool>, playing: &[Player]) -> (App, FakeSystem) {
+        let mut app = create_test_app();
+        app.settings.focus_mode_enabled = true;
+        app.settings.control_media = true;
+        let system = FakeSystem::default();
+        *system.focus_active.lock().unwrap() = focus_active;
+        *system.playing.lock().unwrap() = playing.to_vec();
+        app.focus = Background::spawn(FocusSwitch::new(Box::new(system.clone())));
+        app.media = Background::spawn(MediaPauser::new(Box::new(system.clone())));
+        (app, system)
+    }
+
+    #[test]
+    fn test_focus_on_for_pomodoro_and_off_after() {
+        let (mut app, system) = create_system_app(Some(false), &[]);
+        app.start_pomodoro();
+        assert_eq!(system.focus_calls(&app), vec![true]);
+
+        app.complete_early();
+        assert_eq!(system.focus_calls(&app), vec![true, false]);
+
+        // Breaks don't touch Focus
+        app.skip_break();
+        assert_eq!(system.focus_calls(&app).len(), 2);
+    }
+
+    #[test]
+    fn test_focus_off_on_stop_and_quit() {
+        let (mut app, system) = create_system_app(Some(false), &[]);
+        app.start_pomodoro();
+        app.stop();
+        assert_eq!(system.focus_calls(&app), vec![true, false]);
+
+        app.start_pomodoro();
+        app.release_focus();
+        app.release_focus();
+        assert_eq!(system.focus_calls(&app), vec![true, false, true, false]);
+    }
+
+    #[test]
+    fn test_focus_off_runs_before_the_app_is_dropped() {
+        let (mut app, system) = create_system_app(Some(false), &[]);
+        app.start_pomodoro();
+        app.release_focus();
+        drop(app);
+        assert_eq!(*system.focus_calls.lock().unwrap(), vec![true, false]);
+    }
+
+    #[test]
+    fn test_focus_disabled_by_default() {
+        let (mut app, system) = create_system_app(Some(false), &[]);
+        app.settings.focus_mode_enabled = false;
+        app.start_pomodoro();
+        app.stop();
+        assert!(system.focus_calls(&app).is_empty());
+    }
+
+    #[test]
+    fn test_focus_left_alone_when_already_on() {
+        let (mut app, system) = create_system_app(Some(true), &[]);
+        app.start_pomodoro();
+        app.complete_early();
+        assert!(system.focus_calls(&app).is_empty());
+        assert_eq!(*system.focus_active.lock().unwrap(), Some(true));
+    }
+
+    #[test]
+    fn test_focus_not_fought_when_turned_off_mid_pomodoro() {
+        let (mut app, system) = create_system_app(Some(false), &[]);
+        app.start_pomodoro();
+        app.focus.wait();
+
+        // The user turns Focus off manually
+        *system.focus_active.lock().unwrap() = Some(false);
+        app.stop();
+        assert_eq!(system.focus_calls(&app), vec![true]);
+    }
+
+    #[test]
+    fn test_focus_released_when_state_unknown() {
+        let (mut app, system) = create_system_app(None, &[]);
+        app.start_pomodoro();
+        app.focus.wait();
+        *system.focus_active.lock().unwrap() = None;
+        app.stop();
+        assert_eq!(system.focus_calls(&app), vec![true, false]);
+    }
+
+    #[test]
+    fn test_media_paused_for_break_and_resumed_on_start() {
+        let (mut app, system) = create_system_app(None, &[Player::Spotify]);
+        app.start_pomodoro();
+        assert!(system.media_calls(&app).is_empty());
+
+        app.complete_early();
+        assert_eq!(system.media_calls(&app), vec![("pause", Player::Spotify)]);
+
+        app.skip_break();
+        app.start_pomodoro();
+        assert_eq!(
+            system.media_calls(&app),
+            vec![("pause", Player::Spotify), ("play", Player::Spotify)]
+        );
+    }
+
+    #[test]
+    fn test_media_resumed_on_auto_start() {
+        let (mut app, system) = create_system_app(None, &[Player::Music]);
+        app.start_pomodoro();
+        app.complete_early();
+        app.auto_start_next = true;
+
+        app.advance(StdDuration::from_secs(5 * 60));
+        assert!(app.state.is_pomodoro());
+        assert_eq!(
+            system.media_calls(&app),
+            vec![("pause", Player::Music), ("play", Player::Music)]
+        );
+    }
+
+    #[test]
+    fn test_media_paused_for_manual_long_break() {
+        let (mut app, system) = create_system_app(None, &[Player::Spotify]);
+        assert!(app.start_long_break());
+        assert_eq!(system.media_calls(&app), vec![("pause", Player::Spotify)]);
+    }
+
+    #[test]
+    fn test_media_untouched_when_nothing_playing() {
+        let (mut app, system) = create_system_app(None, &[]);
+        app.start_pomodoro();
+        app.complete_early();
+        app.skip_break();
+        app.start_pomodoro();
+        assert!(system.media_calls(&app).is_empty());
+    }
+
+    #[test]
+    fn test_media_not_resumed_twice() {
+        let (mut app, system) = create_system_app(None, &[Player::Spotify]);
+        app.start_pomodoro();
+        app.complete_early();
+        app.skip_break();
+        app.start_pomodoro();
+
+        // The user pauses the music themselves; the next start must not resume it
+        app.media.wait();
+        system.playing.lock().unwrap().clear();
+        app.stop();
+        app.start_pomodoro();
+        assert_eq!(system.media_calls(&app).len(), 2);
+    }
+
+    #[test]
+    fn test_media_disabled_by_default() {
+        let (mut app, system) = create_system_app(None, &[Player::Spotify]);
+        app.settings.control_media = false;
+        app.start_pomodoro();
+        app.complete_early();
+        assert!(system.media_calls(&app).is_empty());
+    }
+
+    /// A user action or a run of timer ticks.
+    #[derive(Debug, Clone)]
+    enum Step {
+        Ticks(u32),
+        Start,
+        Pause,
+        Resume,
+        Stop,
+        CompleteEarly,
+        SkipBreak,
+    }
+
+    fn any_step() -> impl Strategy<Value = Step> {
+        prop_oneof![
+            4 => (1..=150u32).prop_map(Step::Ticks),
+            1 => Just(Step::Start),
+            1 => Just(Step::Pause),
+            1 => Just(Step::Resume),
+            1 => Just(Step::Stop),
+            1 => Just(Step::CompleteEarly),
+            1 => Just(Step::SkipBreak),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_completed_pomodoros_never_decrease(
+            steps in prop::collection::vec(any_step(), 1..40)
+        ) {
+            let mut app = create_test_app();
+            app.update_setting(|s| {
+                s.pomodoro_mins = 1;
+                s.short_break_mins = 1;
+                s.long_break_mins = 2;
+            }).unwrap();
+
+            let mut completed = app.session.pomodoros_completed_today;
+            let mut check = |app: &App| {
+                let now = app.session.pomodoros_completed_today;
+                let ok = now >= completed;
+                completed = now;
+                ok
+            };
+            for step in steps {
+                match step {
+                    Step::Ticks(n) => {
+                        for _ in 0..n {
+                            app.tick();
+                            prop_assert!(check(&app), "{:?}", app.state);
+                        }
+                    }
+                    Step::Start => app.start_pomodoro(),
+                    Step::Pause => app.pause(),
+                    Step::Resume => app.resume(),
+                    Step::Stop => app.stop(),
+                    Step::CompleteEarly => {
+                        app.complete_early();
+                    }
+                    Step::SkipBreak => app.skip_break(),
+                }
+                prop_assert!(check(&app), "{:?} after {:?}", app.state, step);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Slow system calls made around pomodoros, run off the timer actor.
+//!
+//! Switching Focus runs a shortcut through the `shortcuts` CLI, and pausing
+//! music runs `osascript` up to twice per player; either can take seconds.
+//! On the actor that would hold up every `AppHandle::call`, freezing the
+//! menu until it finished. A `Background` owns the state the calls need on a
+//! thread of its own and runs them there, one at a time and in the order
+//! they were sent: a Focus Off must never overtake the Focus On before it.
+
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+type Job<T> = Box<dyn FnOnce(&mut T) + Send>;
+
+/// A thread that owns a `T` and runs jobs on it in order.
+pub struct Background<T> {
+    jobs: Option<Sender<Job<T>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> Background<T> {
+    /// Moves `state` to a new thread that waits for jobs.
+    pub fn spawn(mut state: T) -> Self {
+        let (jobs, rx) = mpsc::channel::<Job<T>>();
+        let thread = thread::spawn(move || {
+            for job in rx {
+                job(&mut state);
+            }
+        });
+        Self {
+            jobs: Some(jobs),
+            thread: Some(thread),
+        }
+    }
+
+    /// Queues `job` and returns without waiting for it.
+    pub fn run(&self, job: impl FnOnce(&mut T) + Send + 'static) {
+        if let Some(jobs) = &self.jobs {
+            let _ = jobs.send(Box::new(job));
+        }
+    }
+
+    /// Waits until every job queued so far has run.
+    pub fn wait(&self) {
+        let (done_tx, done_rx) = mpsc::channel();
+        self.run(move |_| {
+            let _ = done_tx.send(());
+        });
+        let _ = done_rx.recv();
+    }
+}
+
+impl<T> Drop for Background<T> {
+    /// Runs the jobs still queued before returning, so turning Focus off on
+    /// quit isn't cut short.
+    fn drop(&mut self) {
+        drop(self.jobs.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_jobs_run_in_order() {
+        let background = Background::spawn(Vec::new());
+        for i in 0..100 {
+            background.run(move |seen: &mut Vec<u32>| seen.push(i));
+        }
+        let (tx, rx) = mpsc::channel();
+        background.run(move |seen| tx.send(seen.clone()).unwrap());
+        assert_eq!(rx.recv().unwrap(), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_drop_runs_queued_jobs() {
+        let ran = Arc::new(Mutex::new(0));
+        let background = Background::spawn(());
+        for _ in 0..10 {
+            let ran = Arc::clone(&ran);
+            background.run(move |_| *ran.lock().unwrap() += 1);
+        }
+        drop(background);
+        assert_eq!(*ran.lock().unwrap(), 10);
+    }
+}
@@ -0,0 +1,156 @@
+//! macOS Focus / Do Not Disturb control during pomodoros.
+//!
+//! Focus modes have no public API, so Pomobar runs two user-created shortcuts
+//! ("Pomobar Focus On" and "Pomobar Focus Off") through the `shortcuts` CLI.
+//! The current state is read from the Do Not Disturb assertions file when it
+//! is readable, so Pomobar can leave a Focus the user turned on alone.
+//! Shortcuts can take seconds to run, so the app drives a `FocusSwitch` on
+//! a `Background` thread.
+
+use directories::BaseDirs;
+use std::fs;
+use std::io;
+use std::process::Command;
+use thiserror::Error;
+
+/// Shortcut run when a pomodoro starts.
+pub const FOCUS_ON_SHORTCUT: &str = "Pomobar Focus On";
+
+/// Shortcut run when a pomodoro ends.
+pub const FOCUS_OFF_SHORTCUT: &str = "Pomobar Focus Off";
+
+/// Why switching the Focus mode failed.
+#[derive(Error, Debug)]
+pub enum FocusError {
+    #[error("Failed to run shortcut: {0}")]
+    Io(#[from] io::Error),
+    #[error("Shortcut \"{0}\" failed")]
+    ShortcutFailed(&'static str),
+}
+
+/// Switches the system Focus mode on and off.
+pub trait FocusControl: Send {
+    /// Returns whether a Focus mode is on, or None if it can't be determined.
+    fn is_active(&self) -> Option<bool>;
+    /// Turns Focus on or off.
+    fn set_active(&self, active: bool) -> Result<(), FocusError>;
+}
+
+/// Focus control through the Shortcuts app.
+pub struct ShortcutsFocus;
+
+impl FocusControl for ShortcutsFocus {
+    fn is_active(&self) -> Option<bool> {
+        system_focus_active()
+    }
+
+    fn set_active(&self, active: bool) -> Result<(), FocusError> {
+        let shortcut = if active {
+            FOCUS_ON_SHORTCUT
+        } else {
+            FOCUS_OFF_SHORTCUT
+        };
+        let status = Command::new("shortcuts").args(["run", shortcut]).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(FocusError::ShortcutFailed(shortcut))
+        }
+    }
+}
+
+/// Turns Focus on for a pomodoro and off after it, remembering whether the
+/// Focus on is ours to turn off.
+pub struct FocusSwitch {
+    control: Box<dyn FocusControl>,
+    /// Whether Focus was turned on by us and should be turned off again.
+    engaged: bool,
+}
+
+impl FocusSwitch {
+    pub fn new(control: Box<dyn FocusControl>) -> Self {
+        Self {
+            control,
+            engaged: false,
+        }
+    }
+
+    /// Turns on Focus, unless the user already has one on.
+    pub fn engage(&mut self) {
+        if self.engaged {
+            return;
+        }
+        // Leave a Focus the user turned on themselves alone
+        if self.control.is_active() == Some(true) {
+            return;
+        }
+        match self.control.set_active(true) {
+            Ok(()) => self.engaged = true,
+            Err(e) => eprintln!("Failed to turn on Focus: {}", e),
+        }
+    }
+
+    /// Turns off the Focus we turned on. Does nothing if the user already
+    /// turned Focus off mid-pomodoro.
+    pub fn release(&mut self) {
+        if !std::mem::take(&mut self.engaged) {
+            return;
+        }
+        if self.control.is_active() == Some(false) {
+            return;
+        }
+        if let Err(e) = self.control.set_active(false) {
+            eprintln!("Failed to turn off Focus: {}", e);
+        }
+    }
+}
+
+/// Returns whether a Focus mode (including Do Not Disturb) is on,
+/// or None if it can't be determined.
+pub fn system_focus_active() -> Option<bool> {
+    let base = BaseDirs::new()?;
+    let path = base
+        .home_dir()
+        .join("Library/DoNotDisturb/DB/Assertions.json");
+    // Reading this file needs Full Disk Access; without it the state is unknown
+    let json = fs::read_to_string(path).ok()?;
+    parse_assertions(&json)
+}
+
+/// Returns whether the assertions file lists an active Focus.
+fn parse_assertions(json: &str) -> Option<bool> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let records = value
+        .get("data")?
+        .get(0)?
+        .get("storeAssertionRecords")
+        .and_then(|records| records.as_array())
+        .map_or(0, |records| records.len());
+    Some(records > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assertions_active() {
+        let json = r#"{"data":[{"storeAssertionRecords":[{"assertionDetails":{}}]}]}"#;
+        assert_eq!(parse_assertions(json), Some(true));
+    }
+
+    #[test]
+    fn test_parse_assertions_inactive() {
+        assert_eq!(parse_assertions(r#"{"data":[{}]}"#), Some(false));
+        assert_eq!(
+            parse_assertions(r#"{"data":[{"storeAssertionRecords":[]}]}"#),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_parse_assertions_unreadable() {
+        assert_eq!(parse_assertions("not json"), None);
+        assert_eq!(parse_assertions(r#"{"data":[]}"#), None);
+    }
+}
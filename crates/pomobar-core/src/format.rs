@@ -0,0 +1,1194 @@
+//! Text shown in the menu and in notifications.
+//!
+//! Everything here is a pure function of the timer state, session and
+//! settings, so the menu, the tray and other front ends format alike.
+
+use crate::layout::{self, MAX_LINE_LEN};
+use crate::models::{
+    DailyStats, ProgressBarStyle, ProgressMode, Session, Settings, TaskStats, TimerState,
+    WeekTotals, PROGRESS_BAR_WIDTHS,
+};
+use crate::score;
+use crate::stats;
+use crate::timer::format_time;
+use chrono::{DateTime, Duration, Local};
+
+/// Formats the status line for the menu. During a pomodoro the marked
+/// interruptions follow, and during a pomodoro or stopwatch the active task,
+/// e.g. "⏱  14:02 remaining · 2 interruptions — Email". Where the task
+/// wouldn't fit beside it, the count shrinks to "· 2⚡".
+///
+/// `ends_at` is the wall-clock time the timer would end, shown after the
+/// countdown where it fits beside the rest ("· ends 14:47", or "~14:47"
+/// while paused, as the time only holds if resumed now).
+pub fn format_status(
+    state: &TimerState,
+    interruptions: u32,
+    task: Option<&str>,
+    ends_at: Option<&str>,
+) -> String {
+    let mut status = match state {
+        TimerState::Idle => "Ready to focus".to_string(),
+        TimerState::PomodoroActive { remaining_secs, .. } => {
+            format!("⏱  {} remaining", format_time(*remaining_secs))
+        }
+        TimerState::Overtime {
+            elapsed_overtime_secs,
+            ..
+        } => format!("⏱  +{} overtime", format_time(*elapsed_overtime_secs)),
+        TimerState::PomodoroPaused { remaining_secs, .. } => {
+            format!("⏸  {} (paused)", format_time(*remaining_secs))
+        }
+        TimerState::BreakActive {
+            is_long_break,
+            remaining_secs,
+            ..
+        } => {
+            let kind = if *is_long_break {
+                "Long break"
+            } else {
+                "Short break"
+            };
+            format!("☕  {} - {}", kind, format_time(*remaining_secs))
+        }
+        TimerState::BreakPaused {
+            is_long_break,
+            remaining_secs,
+            ..
+        } => {
+            let kind = if *is_long_break {
+                "Long break"
+            } else {
+                "Short break"
+            };
+            format!("⏸  {} - {} (paused)", kind, format_time(*remaining_secs))
+        }
+        TimerState::BreakFinished => "Break complete - ready for next".to_string(),
+        TimerState::StopwatchActive { elapsed_secs } => {
+            format!("⏱  Stopwatch - {}", format_time(*elapsed_secs))
+        }
+        TimerState::StopwatchPaused { elapsed_secs } => {
+            format!("⏸  Stopwatch - {} (paused)", format_time(*elapsed_secs))
+        }
+    };
+    let task = match task {
+        Some(task) if state.is_pomodoro() || state.is_stopwatch() => format!(" — {}", task),
+        _ => String::new(),
+    };
+    let mut extras = task.clone();
+    if state.is_pomodoro() && interruptions > 0 {
+        let noun = if interruptions == 1 {
+            "interruption"
+        } else {
+            "interruptions"
+        };
+        extras = format!(" · {} {}{}", interruptions, noun, task);
+        if layout::display_len(&status) + layout::display_len(&extras) > MAX_LINE_LEN {
+            extras = format!(" · {}⚡{}", interruptions, task);
+        }
+    }
+    if let Some(ends_at) = ends_at.filter(|_| state.remaining_secs().is_some()) {
+        let approx = if state.is_paused() { "~" } else { "" };
+        let with_end = format!("{} · ends {}{}", status, approx, ends_at);
+        if layout::display_len(&with_end) + layout::display_len(&extras) <= MAX_LINE_LEN {
+            status = with_end;
+        }
+    }
+    // The task comes last, so it is what gets cut at the menu width
+    layout::truncate(&(status + &extras), MAX_LINE_LEN)
+}
+
+/// Formats the status line, covering the daily limit and idle auto-pause
+/// cases. The end time is left out when the progress line already shows it.
+pub fn format_status_line(
+    state: &TimerState,
+    session: &Session,
+    settings: &Settings,
+    auto_paused: bool,
+    interruptions: u32,
+    task: Option<&str>,
+    now: DateTime<Local>,
+) -> String {
+    match state {
+        TimerState::PomodoroPaused { remaining_secs, .. } if auto_paused => {
+            format!("⏸  {} Auto-paused (idle)", format_time(*remaining_secs))
+        }
+        _ if state.is_idle() && session.daily_limit_reached(settings.max_daily_focus_mins) => {
+            "Daily limit reached — rest up".to_string()
+        }
+        _ => {
+            let ends_at = match state.remaining_secs() {
+                Some(secs) if settings.progress_mode != ProgressMode::EndTime => Some(
+                    format_clock_time(now + Duration::seconds(secs as i64), settings.clock_24h),
+                ),
+                _ => None,
+            };
+            format_status(state, interruptions, task, ends_at.as_deref())
+        }
+    }
+}
+
+/// Formats a wall-clock time, e.g. "14:47", or "2:47 PM" on a 12-hour clock.
+pub fn format_clock_time(time: DateTime<Local>, clock_24h: bool) -> String {
+    let format = if clock_24h { "%H:%M" } else { "%-I:%M %p" };
+    time.format(format).to_string()
+}
+
+/// Formats the status line of a pomodoro shortened to end with the day.
+pub fn format_wind_down_status(ends_at: DateTime<Local>, clock_24h: bool) -> String {
+    format!(
+        "⏱  Wind-down · ends {}",
+        format_clock_time(ends_at, clock_24h)
+    )
+}
+
+/// Formats the progress bar for the menu in the configured width and style.
+pub fn format_progress(state: &TimerState, settings: &Settings) -> String {
+    let pct = state.progress_percent().unwrap_or(0.0);
+    let number = format!("{}%", (pct * 100.0).round() as u32);
+    let width = usize::from(
+        settings
+            .progress_bar_width
+            .clamp(*PROGRESS_BAR_WIDTHS.start(), *PROGRESS_BAR_WIDTHS.end()),
+    );
+    let filled = ((pct * width as f32).round() as usize).min(width);
+    let empty = width - filled;
+    match settings.progress_bar_style {
+        ProgressBarStyle::BlockFill => {
+            format!("{}{}  {}", "█".repeat(filled), "░".repeat(empty), number)
+        }
+        ProgressBarStyle::BracketDots => {
+            format!("[{}{}]  {}", "•".repeat(filled), "·".repeat(empty), number)
+        }
+        ProgressBarStyle::NumberOnly => number,
+    }
+}
+
+/// Formats the progress line in the configured display mode.
+pub fn format_progress_line(
+    state: &TimerState,
+    settings: &Settings,
+    now: DateTime<Local>,
+) -> String {
+    let mode = settings.progress_mode;
+    if let Some(elapsed_secs) = state.stopwatch_secs() {
+        // A stopwatch has no end, so every mode shows the elapsed time
+        return format!("{} elapsed", format_time(elapsed_secs));
+    }
+
+    if let TimerState::Overtime {
+        elapsed_overtime_secs,
+        total_secs,
+    } = state
+    {
+        // Nothing is left to count down, so the time-based modes show the overtime
+        return match mode {
+            ProgressMode::Bar | ProgressMode::Percent => format_progress(state, settings),
+            ProgressMode::ElapsedRemaining | ProgressMode::EndTime => format!(
+                "{} elapsed · +{} over",
+                format_time(total_secs + elapsed_overtime_secs),
+                format_time(*elapsed_overtime_secs)
+            ),
+        };
+    }
+
+    match mode {
+        ProgressMode::Bar => format_progress(state, settings),
+        ProgressMode::Percent => {
+            let pct = state.progress_percent().unwrap_or(0.0);
+            format!("{}% complete", (pct * 100.0).round() as u32)
+        }
+        ProgressMode::ElapsedRemaining => match (state.remaining_secs(), state.total_secs()) {
+            (Some(remaining), Some(total)) => format!(
+                "{} elapsed · {} left",
+                format_time(total.saturating_sub(remaining)),
+                format_time(remaining)
+            ),
+            _ => "No timer running".to_string(),
+        },
+        ProgressMode::EndTime => match state.remaining_secs() {
+            Some(remaining) => {
+                let end = format_clock_time(
+                    now + Duration::seconds(remaining as i64),
+                    settings.clock_24h,
+                );
+                if state.is_paused() {
+                    format!("Ends at {} if resumed now", end)
+                } else {
+                    format!("Ends at {}", end)
+                }
+            }
+            None => "No timer running".to_string(),
+        },
+    }
+}
+
+/// Formats the daily stats for the menu, cut to fit the menu width.
+/// With `compact` set, long runs of 🍅 are shown as a count.
+pub fn format_stats(session: &Session, compact: bool) -> String {
+    let count = session.pomodoros_completed_today;
+    if count == 0 {
+        return "Today: —  0 (0 min)".to_string();
+    }
+
+    let line = format!(
+        "Today: {}  {} ({})",
+        layout::emoji_run("🍅", count, compact),
+        count,
+        format_duration(session.total_focus_mins_today)
+    );
+    layout::truncate(&line, MAX_LINE_LEN)
+}
+
+/// Formats the position in the long break cycle, e.g. "Cycle: ●●○○ (2 of 4)".
+/// A cycle already past the threshold (after lowering it mid-cycle) shows
+/// full, as its next pomodoro ends in a long break.
+pub fn format_cycle(session: &Session, settings: &Settings) -> String {
+    let threshold = settings.pomodoros_for_long_break.max(1);
+    let done = session.pomodoros_in_cycle.min(threshold);
+    let line = format!(
+        "Cycle: {}{} ({} of {})",
+        "●".repeat(done as usize),
+        "○".repeat((threshold - done) as usize),
+        done,
+        threshold
+    );
+    layout::truncate(&line, MAX_LINE_LEN)
+}
+
+/// Formats a number of minutes: "45 min" under an hour, "3h 20min" from an
+/// hour on.
+pub fn format_duration(mins: u32) -> String {
+    if mins >= 60 {
+        format!("{}h {}min", mins / 60, mins % 60)
+    } else {
+        format!("{} min", mins)
+    }
+}
+
+/// Formats yesterday's stats for the menu, with a dash for a day without pomodoros.
+pub fn format_yesterday_stats(stats: &DailyStats) -> String {
+    if stats.completed_pomodoros == 0 {
+        return "Yesterday: —".to_string();
+    }
+    format!(
+        "Yesterday: 🍅×{} ({})",
+        stats.completed_pomodoros,
+        format_duration(stats.total_focus_minutes)
+    )
+}
+
+/// Formats this week's totals for the menu.
+pub fn format_week_stats(week: &WeekTotals) -> String {
+    let line = format!(
+        "Week: {} 🍅 ({})",
+        week.completed_pomodoros,
+        format_duration(week.total_focus_minutes)
+    );
+    layout::truncate(&line, MAX_LINE_LEN)
+}
+
+/// Formats one task's pomodoros today for the Today by Task submenu, e.g.
+/// "Write report: 3 🍅 (1h 15min)".
+pub fn format_task_stats(stats: &TaskStats) -> String {
+    let line = format!(
+        "{}: {} 🍅 ({})",
+        stats.task.as_deref().unwrap_or("No task"),
+        stats.pomodoros,
+        format_duration(stats.focus_minutes)
+    );
+    layout::truncate(&line, MAX_LINE_LEN)
+}
+
+/// Formats the all-time stats line, e.g. "Best: 9 🍅 on Jan 1 · Avg: 4.2/day",
+/// where the average covers every recorded day. Dashes before the first
+/// completed pomodoro. The date leaves out the weekday so the line usually
+/// fits the menu; longer lines are cut.
+pub fn format_all_time_stats(stats: &[DailyStats]) -> String {
+    let Some(best) = stats::best_day(stats) else {
+        return "Best: — · Avg: —".to_string();
+    };
+    let (pomodoros, _) = stats::total_all_time(stats);
+    let line = format!(
+        "Best: {} 🍅 on {} · Avg: {:.1}/day",
+        best.completed_pomodoros,
+        best.date.format("%b %-d"),
+        pomodoros as f32 / stats.len() as f32
+    );
+    layout::truncate(&line, MAX_LINE_LEN)
+}
+
+/// Formats today's efficiency, with a dash before the first completed pomodoro.
+pub fn format_efficiency(session: &Session) -> String {
+    if session.pomodoros_completed_today == 0 {
+        return "Efficiency: —".to_string();
+    }
+    format!(
+        "Efficiency: {}%",
+        (session.efficiency_score() * 100.0).round() as u32
+    )
+}
+
+/// Formats today's focus score and its grade, with a dash before any activity.
+pub fn format_focus_score(score: Option<u32>) -> String {
+    match score {
+        Some(score) => format!("Focus score: {} ({})", score, score::letter_grade(score)),
+        None => "Focus score: —".to_string(),
+    }
+}
+
+/// Formats the event webhook item: the URL, cut to the menu width, or
+/// "Not configured".
+pub fn format_event_webhook(url: Option<&str>) -> String {
+    let line = format!("Event Webhook: {}", url.unwrap_or("Not configured"));
+    layout::truncate(&line, MAX_LINE_LEN)
+}
+
+/// Formats the hour a day starts at for the menu.
+pub fn format_day_start(hour: u32) -> String {
+    if hour == 0 {
+        "Midnight".to_string()
+    } else {
+        format!("{}:00", hour)
+    }
+}
+
+/// Placeholder names and their values for a notification template.
+pub type TemplateVars = Vec<(&'static str, String)>;
+
+/// Builds the placeholder values for notification templates.
+/// `{pomodoros}` is the count with its noun, e.g. "1 pomodoro" or "5 pomodoros";
+/// `{duration}` is the pomodoro length in minutes.
+pub fn template_vars(
+    count: u32,
+    duration_mins: u32,
+    streak_days: u32,
+    task: Option<&str>,
+) -> TemplateVars {
+    let pomodoros = if count == 1 {
+        "1 pomodoro".to_string()
+    } else {
+        format!("{} pomodoros", count)
+    };
+    vec![
+        ("count", count.to_string()),
+        ("pomodoros", pomodoros),
+        ("duration", duration_mins.to_string()),
+        ("streak", streak_days.to_string()),
+        ("task", task.unwrap_or_default().to_string()),
+    ]
+}
+
+/// Replaces `{name}` placeholders with their values from `vars`, reading
+/// the template once from left to right, so a value that looks like a
+/// placeholder (a task named "{count}") is kept as it is.
+/// Unknown placeholders are left as they are.
+pub fn render_template(template: &str, vars: &[(&str, String)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            let name = &after[..end];
+            let (_, value) = vars.iter().find(|(var, _)| *var == name)?;
+            Some((value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                text.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = after;
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_format_status_idle() {
+        let state = TimerState::Idle;
+        assert_eq!(format_status(&state, 0, None, None), "Ready to focus");
+    }
+
+    #[test]
+    fn test_format_status_pomodoro_active() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 1432,
+            total_secs: 1500,
+        };
+        assert_eq!(format_status(&state, 0, None, None), "⏱  23:52 remaining");
+    }
+
+    #[test]
+    fn test_format_status_shows_task() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 1432,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            format_status(&state, 0, Some("Write report"), None),
+            "⏱  23:52 remaining — Write report"
+        );
+
+        // Breaks aren't spent on the task
+        let state = TimerState::BreakActive {
+            is_long_break: false,
+            remaining_secs: 180,
+            total_secs: 300,
+        };
+        assert_eq!(
+            format_status(&state, 0, Some("Write report"), None),
+            "☕  Short break - 03:00"
+        );
+
+        // A long label is cut to the menu width
+        let state = TimerState::StopwatchActive { elapsed_secs: 60 };
+        let status = format_status(&state, 0, Some(&"x".repeat(100)), None);
+        assert!(status.starts_with("⏱  Stopwatch - 01:00 — x"));
+        assert!(layout::display_len(&status) <= MAX_LINE_LEN);
+    }
+
+    #[test]
+    fn test_format_all_time_stats() {
+        assert_eq!(format_all_time_stats(&[]), "Best: — · Avg: —");
+
+        let day = |d, count| DailyStats {
+            completed_pomodoros: count,
+            ..DailyStats::new(NaiveDate::from_ymd_opt(2024, 1, d).unwrap())
+        };
+        assert_eq!(format_all_time_stats(&[day(1, 0)]), "Best: — · Avg: —");
+        assert_eq!(
+            format_all_time_stats(&[day(1, 9), day(2, 0), day(3, 4)]),
+            "Best: 9 🍅 on Jan 1 · Avg: 4.3/day"
+        );
+        assert_eq!(
+            format_all_time_stats(&[day(11, 12), day(12, 2)]),
+            "Best: 12 🍅 on Jan 11 · Avg: 7.0/day"
+        );
+
+        // Longer lines are cut to the menu width
+        let line = format_all_time_stats(&[day(11, 120), day(12, 100)]);
+        assert_eq!(line, "Best: 120 🍅 on Jan 11 · Avg: 110.0/…");
+        assert_eq!(layout::display_len(&line), MAX_LINE_LEN);
+    }
+
+    #[test]
+    fn test_format_status_shows_interruptions() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 842,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            format_status(&state, 2, None, None),
+            "⏱  14:02 remaining · 2 interruptions"
+        );
+        // The count shrinks to keep the task in view
+        assert_eq!(
+            format_status(&state, 1, Some("Email"), None),
+            "⏱  14:02 remaining · 1⚡ — Email"
+        );
+        // The task comes last, so it is what gets cut at the menu width
+        assert_eq!(
+            format_status(&state, 12, Some("Write the quarterly report"), None),
+            "⏱  14:02 remaining · 12⚡ — Write th…"
+        );
+
+        // Only pomodoros are interrupted
+        let state = TimerState::StopwatchActive { elapsed_secs: 60 };
+        assert_eq!(format_status(&state, 2, None, None), "⏱  Stopwatch - 01:00");
+    }
+
+    #[test]
+    fn test_format_task_stats() {
+        let stats = TaskStats {
+            task: Some("Write report".to_string()),
+            pomodoros: 3,
+            focus_minutes: 75,
+        };
+        assert_eq!(format_task_stats(&stats), "Write report: 3 🍅 (1h 15min)");
+        let stats = TaskStats {
+            task: None,
+            pomodoros: 1,
+            focus_minutes: 25,
+        };
+        assert_eq!(format_task_stats(&stats), "No task: 1 🍅 (25 min)");
+    }
+
+    #[test]
+    fn test_format_status_break_paused() {
+        let state = TimerState::BreakPaused {
+            is_long_break: true,
+            remaining_secs: 600,
+            total_secs: 900,
+        };
+        assert_eq!(
+            format_status(&state, 0, None, None),
+            "⏸  Long break - 10:00 (paused)"
+        );
+    }
+
+    #[test]
+    fn test_format_status_paused() {
+        let state = TimerState::PomodoroPaused {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        assert_eq!(format_status(&state, 0, None, None), "⏸  10:00 (paused)");
+    }
+
+    #[test]
+    fn test_format_status_short_break() {
+        let state = TimerState::BreakActive {
+            is_long_break: false,
+            remaining_secs: 180,
+            total_secs: 300,
+        };
+        assert_eq!(
+            format_status(&state, 0, None, None),
+            "☕  Short break - 03:00"
+        );
+    }
+
+    #[test]
+    fn test_format_status_long_break() {
+        let state = TimerState::BreakActive {
+            is_long_break: true,
+            remaining_secs: 600,
+            total_secs: 900,
+        };
+        assert_eq!(
+            format_status(&state, 0, None, None),
+            "☕  Long break - 10:00"
+        );
+    }
+
+    #[test]
+    fn test_format_status_break_finished() {
+        let state = TimerState::BreakFinished;
+        assert_eq!(
+            format_status(&state, 0, None, None),
+            "Break complete - ready for next"
+        );
+    }
+
+    #[test]
+    fn test_format_status_hours() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 3900,
+            total_secs: 3900,
+        };
+        assert_eq!(format_status(&state, 0, None, None), "⏱  1:05:00 remaining");
+    }
+
+    #[test]
+    fn test_format_status_line_daily_limit() {
+        let session = Session {
+            pomodoros_completed_today: 12,
+            total_focus_mins_today: 360,
+            ..Session::default()
+        };
+        let settings = Settings {
+            max_daily_focus_mins: Some(360),
+            ..Settings::default()
+        };
+
+        assert_eq!(
+            format_status_line(
+                &TimerState::Idle,
+                &session,
+                &settings,
+                false,
+                0,
+                None,
+                at_nine()
+            ),
+            "Daily limit reached — rest up"
+        );
+
+        // Breaks keep their normal status line
+        let state = TimerState::BreakActive {
+            is_long_break: false,
+            remaining_secs: 180,
+            total_secs: 300,
+        };
+        assert_eq!(
+            format_status_line(&state, &session, &settings, false, 0, None, at_nine()),
+            "☕  Short break - 03:00 · ends 09:03"
+        );
+
+        // No limit configured
+        assert_eq!(
+            format_status_line(
+                &TimerState::Idle,
+                &session,
+                &Settings::default(),
+                false,
+                0,
+                None,
+                at_nine()
+            ),
+            "Ready to focus"
+        );
+    }
+
+    #[test]
+    fn test_format_status_line_auto_paused() {
+        let session = Session::default();
+        let settings = Settings::default();
+        let state = TimerState::PomodoroPaused {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+
+        assert_eq!(
+            format_status_line(&state, &session, &settings, true, 0, None, at_nine()),
+            "⏸  10:00 Auto-paused (idle)"
+        );
+        assert_eq!(
+            format_status_line(&state, &session, &settings, false, 0, None, at_nine()),
+            "⏸  10:00 (paused) · ends ~09:10"
+        );
+    }
+
+    #[test]
+    fn test_format_status_shows_end_time() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 1432,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            format_status(&state, 0, None, Some("14:47")),
+            "⏱  23:52 remaining · ends 14:47"
+        );
+        assert_eq!(
+            format_status(&state, 0, Some("Email"), Some("14:47")),
+            "⏱  23:52 remaining — Email"
+        );
+        let state = TimerState::PomodoroPaused {
+            remaining_secs: 1432,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            format_status(&state, 0, None, Some("14:47")),
+            "⏸  23:52 (paused) · ends ~14:47"
+        );
+
+        // Left out where it doesn't fit, and where nothing counts down
+        let state = TimerState::BreakPaused {
+            is_long_break: false,
+            remaining_secs: 180,
+            total_secs: 300,
+        };
+        assert_eq!(
+            format_status(&state, 0, None, Some("14:47")),
+            "⏸  Short break - 03:00 (paused)"
+        );
+        let state = TimerState::StopwatchActive { elapsed_secs: 60 };
+        assert_eq!(
+            format_status(&state, 0, None, Some("14:47")),
+            "⏱  Stopwatch - 01:00"
+        );
+    }
+
+    #[test]
+    fn test_format_status_line_end_time_across_midnight() {
+        use chrono::TimeZone;
+        let late = Local.with_ymd_and_hms(2024, 1, 15, 23, 50, 0).unwrap();
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 1500,
+            total_secs: 1500,
+        };
+        let line = |settings: &Settings| {
+            format_status_line(&state, &Session::default(), settings, false, 0, None, late)
+        };
+
+        assert_eq!(
+            line(&Settings::default()),
+            "⏱  25:00 remaining · ends 00:15"
+        );
+        let settings = Settings {
+            clock_24h: false,
+            ..Settings::default()
+        };
+        assert_eq!(line(&settings), "⏱  25:00 remaining · ends 12:15 AM");
+
+        // The progress line already shows it
+        assert_eq!(
+            line(&with_mode(ProgressMode::EndTime)),
+            "⏱  25:00 remaining"
+        );
+    }
+
+    #[test]
+    fn test_format_clock_time() {
+        use chrono::TimeZone;
+        let time = |h, m| Local.with_ymd_and_hms(2024, 1, 15, h, m, 0).unwrap();
+        assert_eq!(format_clock_time(time(14, 47), true), "14:47");
+        assert_eq!(format_clock_time(time(14, 47), false), "2:47 PM");
+        assert_eq!(format_clock_time(time(0, 5), true), "00:05");
+        assert_eq!(format_clock_time(time(0, 5), false), "12:05 AM");
+        assert_eq!(format_clock_time(time(12, 0), false), "12:00 PM");
+    }
+
+    #[test]
+    fn test_format_wind_down_status() {
+        use chrono::TimeZone;
+        let ends_at = Local.with_ymd_and_hms(2024, 1, 15, 18, 0, 0).unwrap();
+        assert_eq!(
+            format_wind_down_status(ends_at, true),
+            "⏱  Wind-down · ends 18:00"
+        );
+        assert_eq!(
+            format_wind_down_status(ends_at, false),
+            "⏱  Wind-down · ends 6:00 PM"
+        );
+    }
+
+    fn half_done() -> TimerState {
+        TimerState::PomodoroActive {
+            remaining_secs: 750,
+            total_secs: 1500,
+        }
+    }
+
+    fn at_nine() -> DateTime<Local> {
+        use chrono::TimeZone;
+        Local.with_ymd_and_hms(2024, 1, 15, 9, 0, 0).unwrap()
+    }
+
+    fn with_mode(progress_mode: ProgressMode) -> Settings {
+        Settings {
+            progress_mode,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn test_format_progress_line_bar() {
+        assert_eq!(
+            format_progress_line(&half_done(), &with_mode(ProgressMode::Bar), at_nine()),
+            format_progress(&half_done(), &Settings::default())
+        );
+    }
+
+    #[test]
+    fn test_format_progress_line_percent() {
+        assert_eq!(
+            format_progress_line(&half_done(), &with_mode(ProgressMode::Percent), at_nine()),
+            "50% complete"
+        );
+        assert_eq!(
+            format_progress_line(
+                &TimerState::Idle,
+                &with_mode(ProgressMode::Percent),
+                at_nine()
+            ),
+            "0% complete"
+        );
+    }
+
+    #[test]
+    fn test_format_progress_line_elapsed_remaining() {
+        assert_eq!(
+            format_progress_line(
+                &half_done(),
+                &with_mode(ProgressMode::ElapsedRemaining),
+                at_nine()
+            ),
+            "12:30 elapsed · 12:30 left"
+        );
+        assert_eq!(
+            format_progress_line(
+                &TimerState::Idle,
+                &with_mode(ProgressMode::ElapsedRemaining),
+                at_nine()
+            ),
+            "No timer running"
+        );
+    }
+
+    #[test]
+    fn test_format_progress_line_end_time() {
+        assert_eq!(
+            format_progress_line(&half_done(), &with_mode(ProgressMode::EndTime), at_nine()),
+            "Ends at 09:12"
+        );
+
+        let paused = TimerState::PomodoroPaused {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            format_progress_line(&paused, &with_mode(ProgressMode::EndTime), at_nine()),
+            "Ends at 09:10 if resumed now"
+        );
+    }
+
+    #[test]
+    fn test_format_progress_idle() {
+        let state = TimerState::Idle;
+        assert_eq!(
+            format_progress(&state, &Settings::default()),
+            "░░░░░░░░░░░░░░░░░░░░  0%"
+        );
+    }
+
+    #[test]
+    fn test_format_progress_half() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 750,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            format_progress(&state, &Settings::default()),
+            "██████████░░░░░░░░░░  50%"
+        );
+    }
+
+    #[test]
+    fn test_format_progress_complete() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 0,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            format_progress(&state, &Settings::default()),
+            "████████████████████  100%"
+        );
+    }
+
+    #[test]
+    fn test_format_progress_styles() {
+        let settings = |progress_bar_style, progress_bar_width| Settings {
+            progress_bar_style,
+            progress_bar_width,
+            ..Settings::default()
+        };
+        assert_eq!(
+            format_progress(&half_done(), &settings(ProgressBarStyle::BlockFill, 10)),
+            "█████░░░░░  50%"
+        );
+        assert_eq!(
+            format_progress(&half_done(), &settings(ProgressBarStyle::BracketDots, 10)),
+            "[•••••·····]  50%"
+        );
+        assert_eq!(
+            format_progress(&half_done(), &settings(ProgressBarStyle::NumberOnly, 10)),
+            "50%"
+        );
+
+        // A width outside the accepted range is drawn at the nearest limit
+        assert_eq!(
+            format_progress(
+                &TimerState::Idle,
+                &settings(ProgressBarStyle::BracketDots, 1)
+            ),
+            "[·····]  0%"
+        );
+    }
+
+    #[test]
+    fn test_format_stats_empty() {
+        let session = Session {
+            pomodoros_completed_today: 0,
+            total_focus_mins_today: 0,
+            ..Session::default()
+        };
+        assert_eq!(format_stats(&session, false), "Today: —  0 (0 min)");
+    }
+
+    #[test]
+    fn test_format_stats_with_pomodoros() {
+        let session = Session {
+            pomodoros_completed_today: 4,
+            total_focus_mins_today: 100,
+            ..Session::default()
+        };
+        assert_eq!(
+            format_stats(&session, false),
+            "Today: 🍅🍅🍅🍅  4 (1h 40min)"
+        );
+        // Four fit even in compact mode
+        assert_eq!(
+            format_stats(&session, true),
+            "Today: 🍅🍅🍅🍅  4 (1h 40min)"
+        );
+    }
+
+    #[test]
+    fn test_format_stats_many_pomodoros() {
+        let session = Session {
+            pomodoros_completed_today: 15,
+            total_focus_mins_today: 375,
+            ..Session::default()
+        };
+        let result = format_stats(&session, false);
+        assert!(result.contains("+5"));
+        assert!(result.contains("15"));
+        assert!(result.contains("6h 15min"));
+        assert!(layout::display_len(&result) <= MAX_LINE_LEN);
+    }
+
+    #[test]
+    fn test_format_stats_compact() {
+        let session = Session {
+            pomodoros_completed_today: 12,
+            total_focus_mins_today: 300,
+            ..Session::default()
+        };
+        assert_eq!(format_stats(&session, true), "Today: 🍅×12  12 (5h 0min)");
+    }
+
+    #[test]
+    fn test_format_stats_truncated_to_menu_width() {
+        let session = Session {
+            pomodoros_completed_today: 12345,
+            total_focus_mins_today: 308625,
+            ..Session::default()
+        };
+        let result = format_stats(&session, false);
+        assert_eq!(layout::display_len(&result), MAX_LINE_LEN);
+        assert!(result.ends_with('…'));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0 min");
+        assert_eq!(format_duration(59), "59 min");
+        assert_eq!(format_duration(60), "1h 0min");
+        assert_eq!(format_duration(125), "2h 5min");
+        assert_eq!(format_duration(200), "3h 20min");
+    }
+
+    #[test]
+    fn test_format_yesterday_stats() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        assert_eq!(
+            format_yesterday_stats(&DailyStats::new(date)),
+            "Yesterday: —"
+        );
+        let stats = DailyStats {
+            completed_pomodoros: 7,
+            total_focus_minutes: 175,
+            ..DailyStats::new(date)
+        };
+        assert_eq!(format_yesterday_stats(&stats), "Yesterday: 🍅×7 (2h 55min)");
+    }
+
+    #[test]
+    fn test_format_week_stats() {
+        let week = WeekTotals {
+            start: NaiveDate::from_ymd_opt(2024, 1, 8).unwrap(),
+            completed_pomodoros: 12,
+            total_focus_minutes: 300,
+        };
+        assert_eq!(format_week_stats(&week), "Week: 12 🍅 (5h 0min)");
+    }
+
+    #[test]
+    fn test_format_efficiency() {
+        let mut session = Session::default();
+        assert_eq!(format_efficiency(&session), "Efficiency: —");
+
+        // Stopped pomodoros alone don't show a score yet
+        session.abandon_pomodoro(10);
+        assert_eq!(format_efficiency(&session), "Efficiency: —");
+
+        session.complete_pomodoro(25);
+        session.complete_pomodoro(25);
+        session.complete_pomodoro(25);
+        session.complete_pomodoro(25);
+        assert_eq!(format_efficiency(&session), "Efficiency: 91%");
+    }
+
+    #[test]
+    fn test_format_cycle() {
+        let settings = Settings::default();
+        let cycle = |in_cycle| Session {
+            pomodoros_in_cycle: in_cycle,
+            ..Session::default()
+        };
+        assert_eq!(format_cycle(&cycle(0), &settings), "Cycle: ○○○○ (0 of 4)");
+        assert_eq!(format_cycle(&cycle(2), &settings), "Cycle: ●●○○ (2 of 4)");
+        assert_eq!(format_cycle(&cycle(4), &settings), "Cycle: ●●●● (4 of 4)");
+    }
+
+    #[test]
+    fn test_format_cycle_past_threshold() {
+        // The threshold was lowered from 4 to 2 after three pomodoros
+        let settings = Settings {
+            pomodoros_for_long_break: 2,
+            ..Settings::default()
+        };
+        let session = Session {
+            pomodoros_in_cycle: 3,
+            ..Session::default()
+        };
+        assert_eq!(format_cycle(&session, &settings), "Cycle: ●● (2 of 2)");
+    }
+
+    #[test]
+    fn test_format_cycle_edge_thresholds() {
+        let session = Session::default();
+        let every_break_is_long = Settings {
+            pomodoros_for_long_break: 1,
+            ..Settings::default()
+        };
+        assert_eq!(
+            format_cycle(&session, &every_break_is_long),
+            "Cycle: ○ (0 of 1)"
+        );
+        // A zero threshold behaves like one
+        let zero = Settings {
+            pomodoros_for_long_break: 0,
+            ..Settings::default()
+        };
+        assert_eq!(format_cycle(&session, &zero), "Cycle: ○ (0 of 1)");
+        // A long cycle is cut to the menu width
+        let long = Settings {
+            pomodoros_for_long_break: 40,
+            ..Settings::default()
+        };
+        let line = format_cycle(&session, &long);
+        assert!(line.starts_with("Cycle: ○○○"));
+        assert_eq!(layout::display_len(&line), MAX_LINE_LEN);
+    }
+
+    #[test]
+    fn test_format_focus_score() {
+        assert_eq!(format_focus_score(Some(82)), "Focus score: 82 (B)");
+        assert_eq!(format_focus_score(Some(0)), "Focus score: 0 (F)");
+        assert_eq!(format_focus_score(None), "Focus score: —");
+    }
+
+    #[test]
+    fn test_format_event_webhook() {
+        assert_eq!(format_event_webhook(None), "Event Webhook: Not configured");
+        assert_eq!(
+            format_event_webhook(Some("http://light.local/on")),
+            "Event Webhook: http://light.local/on"
+        );
+
+        let long = format_event_webhook(Some("https://hooks.example.com/services/T000/B000"));
+        assert_eq!(layout::display_len(&long), MAX_LINE_LEN);
+        assert!(long.ends_with('…'));
+    }
+
+    #[test]
+    fn test_default_templates_match_previous_text() {
+        let settings = Settings::default();
+        assert_eq!(
+            render_template(
+                &settings.notif_pomo_template,
+                &template_vars(1, 25, 1, None)
+            ),
+            "Great work! You've completed 1 pomodoro today.\nTime for a break."
+        );
+        assert_eq!(
+            render_template(
+                &settings.notif_pomo_template,
+                &template_vars(5, 25, 1, None)
+            ),
+            "Great work! You've completed 5 pomodoros today.\nTime for a break."
+        );
+        assert_eq!(
+            render_template(
+                &settings.notif_break_template,
+                &template_vars(5, 25, 1, None)
+            ),
+            "Ready to start another pomodoro?"
+        );
+    }
+
+    #[test]
+    fn test_render_template_placeholders() {
+        let vars = template_vars(3, 50, 7, Some("Write docs"));
+        assert_eq!(
+            render_template("{count} × {duration} min on {task}, day {streak}", &vars),
+            "3 × 50 min on Write docs, day 7"
+        );
+    }
+
+    #[test]
+    fn test_render_template_leaves_unknown_placeholders() {
+        let vars = template_vars(3, 25, 0, None);
+        assert_eq!(
+            render_template("{count} {unknown} [{task}]", &vars),
+            "3 {unknown} []"
+        );
+        assert_eq!(render_template("", &vars), "");
+        assert_eq!(render_template("{{count}} {count", &vars), "{3} {count");
+    }
+
+    #[test]
+    fn test_render_template_keeps_placeholders_in_values() {
+        let vars = template_vars(3, 25, 0, Some("{count} {streak}"));
+        assert_eq!(
+            render_template("{task} / {count}", &vars),
+            "{count} {streak} / 3"
+        );
+    }
+
+    #[test]
+    fn test_stopwatch_lines() {
+        let state = TimerState::StopwatchActive { elapsed_secs: 3723 };
+        assert_eq!(
+            format_status(&state, 0, None, None),
+            "⏱  Stopwatch - 1:02:03"
+        );
+        assert_eq!(
+            format_status(
+                &TimerState::StopwatchPaused { elapsed_secs: 754 },
+                0,
+                None,
+                None
+            ),
+            "⏸  Stopwatch - 12:34 (paused)"
+        );
+        for mode in [
+            ProgressMode::Bar,
+            ProgressMode::Percent,
+            ProgressMode::ElapsedRemaining,
+            ProgressMode::EndTime,
+        ] {
+            assert_eq!(
+                format_progress_line(&state, &with_mode(mode), Local::now()),
+                "1:02:03 elapsed"
+            );
+        }
+    }
+
+    #[test]
+    fn test_overtime_lines() {
+        let state = TimerState::Overtime {
+            elapsed_overtime_secs: 90,
+            total_secs: 1500,
+        };
+        assert_eq!(format_status(&state, 0, None, None), "⏱  +01:30 overtime");
+        assert_eq!(
+            format_progress_line(
+                &state,
+                &with_mode(ProgressMode::ElapsedRemaining),
+                Local::now()
+            ),
+            "26:30 elapsed · +01:30 over"
+        );
+
+        // Past an hour of overtime the hours show
+        let state = TimerState::Overtime {
+            elapsed_overtime_secs: 3661,
+            total_secs: 5400,
+        };
+        assert_eq!(format_status(&state, 0, None, None), "⏱  +1:01:01 overtime");
+    }
+}
@@ -0,0 +1,148 @@
+//! Text heatmap of completed pomodoros over the last four weeks.
+
+use crate::models::{DailyStats, WeekStart};
+use chrono::NaiveDate;
+use std::iter;
+
+/// Weeks shown in the heatmap, the current one included.
+pub const HEATMAP_WEEKS: u32 = 4;
+
+/// Days per heatmap row.
+const ROW_LEN: usize = 7;
+
+/// Returns the cell for a day with `pomodoros` completed pomodoros.
+fn cell(pomodoros: u32) -> char {
+    match pomodoros {
+        0 => '·',
+        1..=2 => '░',
+        3..=4 => '▒',
+        5..=6 => '▓',
+        _ => '█',
+    }
+}
+
+/// Returns the number of days from the start of the first heatmap week up
+/// to and including `today`: the current week so far and the full weeks
+/// before it.
+pub fn heatmap_days(today: NaiveDate, week_starts_on: WeekStart) -> u32 {
+    let into_week = (today - week_starts_on.week_start(today)).num_days() as u32;
+    (HEATMAP_WEEKS - 1) * ROW_LEN as u32 + into_week + 1
+}
+
+/// Renders a line of weekday initials, then one line per week, oldest
+/// first, with one cell per day. Weeks begin on `week_starts_on`, so each
+/// column is one weekday; the first week is padded with blanks before its
+/// first day. `stats` is expected in date order without gaps.
+pub fn render_heatmap(stats: &[DailyStats], week_starts_on: WeekStart) -> String {
+    let Some(first) = stats.first() else {
+        return String::new();
+    };
+    let padding = (first.date - week_starts_on.week_start(first.date)).num_days() as usize;
+    let cells: Vec<char> = iter::repeat_n(' ', padding)
+        .chain(stats.iter().map(|day| cell(day.completed_pomodoros)))
+        .collect();
+    let mut weekday = week_starts_on.weekday();
+    let header: Vec<String> = (0..ROW_LEN)
+        .map(|_| {
+            let initial = weekday.to_string()[..1].to_string();
+            weekday = weekday.succ();
+            initial
+        })
+        .collect();
+    iter::once(header.join(" "))
+        .chain(cells.chunks(ROW_LEN).map(|week| {
+            week.iter()
+                .map(char::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        }))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Days;
+
+    fn days(counts: &[u32]) -> Vec<DailyStats> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| DailyStats {
+                completed_pomodoros: count,
+                ..DailyStats::new(start + Days::new(i as u64))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cell_levels() {
+        // 2024-01-01 is a Monday
+        assert_eq!(
+            render_heatmap(&days(&[0, 1, 2, 3, 4, 5, 6]), WeekStart::Monday),
+            "M T W T F S S\n\
+             · ░ ░ ▒ ▒ ▓ ▓"
+        );
+        assert_eq!(
+            render_heatmap(&days(&[7, 12, 0]), WeekStart::Monday),
+            "M T W T F S S\n\
+             █ █ ·"
+        );
+    }
+
+    #[test]
+    fn test_four_weeks() {
+        let mut counts = vec![0; 28];
+        counts[6] = 8;
+        counts[27] = 3;
+        assert_eq!(
+            render_heatmap(&days(&counts), WeekStart::Monday),
+            "M T W T F S S\n\
+             · · · · · · █\n\
+             · · · · · · ·\n\
+             · · · · · · ·\n\
+             · · · · · · ▒"
+        );
+    }
+
+    #[test]
+    fn test_rows_follow_week_start() {
+        // Monday to Wednesday: the Sunday week pads one day, the Saturday
+        // week two
+        let stats = days(&[1, 3, 5]);
+        assert_eq!(
+            render_heatmap(&stats, WeekStart::Sunday),
+            "S M T W T F S\n  ░ ▒ ▓"
+        );
+        assert_eq!(
+            render_heatmap(&stats, WeekStart::Saturday),
+            "S S M T W T F\n    ░ ▒ ▓"
+        );
+
+        // A week wraps after its last day
+        let stats = days(&[1, 1, 1, 1, 1, 1, 8]);
+        assert_eq!(
+            render_heatmap(&stats, WeekStart::Sunday),
+            "S M T W T F S\n  ░ ░ ░ ░ ░ ░\n█"
+        );
+    }
+
+    #[test]
+    fn test_heatmap_days() {
+        // Wednesday 2024-01-03
+        let today = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+        assert_eq!(heatmap_days(today, WeekStart::Monday), 21 + 3);
+        assert_eq!(heatmap_days(today, WeekStart::Sunday), 21 + 4);
+        assert_eq!(heatmap_days(today, WeekStart::Saturday), 21 + 5);
+        // On the week's first day, three full weeks and today
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert_eq!(heatmap_days(monday, WeekStart::Monday), 22);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(render_heatmap(&[], WeekStart::Monday), "");
+    }
+}
@@ -0,0 +1,31 @@
+//! System idle time detection for auto-pausing pomodoros.
+
+/// Returns the number of seconds since the last keyboard or mouse input.
+#[cfg(target_os = "macos")]
+pub fn seconds_since_last_input() -> f64 {
+    use core_graphics::event_source::CGEventSourceStateID;
+
+    /// Matches any input event type (`kCGAnyInputEventType`).
+    const ANY_INPUT_EVENT_TYPE: u32 = !0;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(
+            source: CGEventSourceStateID,
+            event_type: u32,
+        ) -> f64;
+    }
+
+    unsafe {
+        CGEventSourceSecondsSinceLastEventType(
+            CGEventSourceStateID::CombinedSessionState,
+            ANY_INPUT_EVENT_TYPE,
+        )
+    }
+}
+
+/// Idle detection is only supported on macOS; elsewhere the user is never idle.
+#[cfg(not(target_os = "macos"))]
+pub fn seconds_since_last_input() -> f64 {
+    0.0
+}
@@ -0,0 +1,107 @@
+//! Text layout helpers for menu lines.
+//!
+//! Menu fonts are proportional and emoji render at different widths across
+//! macOS versions, so lines are measured in grapheme clusters (what the user
+//! sees as one character) rather than bytes or chars, and long lines are cut
+//! before the menu wraps or clips them.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Maximum number of grapheme clusters in a menu line.
+pub const MAX_LINE_LEN: usize = 36;
+
+/// Emoji shown individually before a run is cut off with "+N".
+const MAX_EMOJI_RUN: u32 = 10;
+
+/// Emoji shown individually before a compact run switches to "🍅×N".
+const COMPACT_EMOJI_RUN: u32 = 4;
+
+/// Returns the display length of a string in grapheme clusters.
+pub fn display_len(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Cuts a string to at most `max_len` grapheme clusters, ending with "…"
+/// if anything was removed. Never splits an emoji or combined character.
+pub fn truncate(text: &str, max_len: usize) -> String {
+    if display_len(text) <= max_len {
+        return text.to_string();
+    }
+    if max_len == 0 {
+        return String::new();
+    }
+
+    let mut cut: String = text.graphemes(true).take(max_len - 1).collect();
+    cut.truncate(cut.trim_end().len());
+    cut.push('…');
+    cut
+}
+
+/// Renders `count` copies of an emoji, e.g. for completed pomodoros.
+/// Long runs show the first ten followed by "+N". With `compact` set, runs
+/// above four are shown as a count instead, e.g. "🍅×12".
+pub fn emoji_run(emoji: &str, count: u32, compact: bool) -> String {
+    if compact && count > COMPACT_EMOJI_RUN {
+        return format!("{}×{}", emoji, count);
+    }
+
+    let shown = emoji.repeat(count.min(MAX_EMOJI_RUN) as usize);
+    if count > MAX_EMOJI_RUN {
+        format!("{}+{}", shown, count - MAX_EMOJI_RUN)
+    } else {
+        shown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_len_counts_graphemes() {
+        assert_eq!(display_len("abc"), 3);
+        assert_eq!(display_len("🍅🍅"), 2);
+        // Flag and skin-tone sequences are one grapheme each
+        assert_eq!(display_len("🇩🇪👍🏽"), 2);
+        assert_eq!(display_len("e\u{301}"), 1);
+    }
+
+    #[test]
+    fn test_truncate_short_text_unchanged() {
+        assert_eq!(truncate("Today: 🍅 1", 20), "Today: 🍅 1");
+        assert_eq!(truncate("exact", 5), "exact");
+    }
+
+    #[test]
+    fn test_truncate_adds_ellipsis() {
+        assert_eq!(truncate("abcdefgh", 5), "abcd…");
+        assert_eq!(display_len(&truncate("abcdefgh", 5)), 5);
+        assert_eq!(truncate("abc", 0), "");
+    }
+
+    #[test]
+    fn test_truncate_keeps_emoji_whole() {
+        let line = "🍅".repeat(8);
+        assert_eq!(truncate(&line, 4), "🍅🍅🍅…");
+        assert_eq!(truncate("👍🏽👍🏽👍🏽", 2), "👍🏽…");
+    }
+
+    #[test]
+    fn test_truncate_drops_trailing_space() {
+        assert_eq!(truncate("ab  cdef", 4), "ab…");
+    }
+
+    #[test]
+    fn test_emoji_run_full() {
+        assert_eq!(emoji_run("🍅", 0, false), "");
+        assert_eq!(emoji_run("🍅", 3, false), "🍅🍅🍅");
+        assert_eq!(emoji_run("🍅", 12, false), format!("{}+2", "🍅".repeat(10)));
+    }
+
+    #[test]
+    fn test_emoji_run_compact_switch() {
+        assert_eq!(emoji_run("🍅", 4, true), "🍅🍅🍅🍅");
+        assert_eq!(emoji_run("🍅", 5, true), "🍅×5");
+        assert_eq!(emoji_run("🍅", 12, true), "🍅×12");
+    }
+}
@@ -0,0 +1,30 @@
+//! The parts of Pomobar that don't need a menubar: the timer state machine,
+//! the SQLite store and the text the menu shows.
+//!
+//! The `pomobar` binary adds the tray icon, menu, sounds, notifications and
+//! hotkeys on top. Nothing in this crate links a GUI or audio library, so
+//! other front ends and tests can use it on its own.
+
+pub mod actor;
+pub mod app;
+pub mod background;
+pub mod focus;
+pub mod format;
+pub mod heatmap;
+pub mod idle;
+pub mod layout;
+pub mod media;
+pub mod metrics;
+pub mod models;
+pub mod persistence;
+pub mod presets;
+pub mod score;
+pub mod settings_file;
+pub mod snapshot;
+pub mod stats;
+pub mod stats_export;
+pub mod status_file;
+#[cfg(test)]
+mod test_utils;
+pub mod timer;
+pub mod webhook;
@@ -0,0 +1,145 @@
+//! Pausing and resuming music around breaks.
+//!
+//! Music and Spotify are controlled through AppleScript. The media keys are
+//! not used because they can only toggle playback: they can't tell whether
+//! anything is playing, so they could start music the user had stopped.
+//! Each script launches `osascript`, so the app drives a `MediaPauser` on a
+//! `Background` thread.
+
+use std::io;
+use std::process::Command;
+use thiserror::Error;
+
+/// Why pausing or resuming a player failed.
+#[derive(Error, Debug)]
+pub enum MediaError {
+    #[error("Failed to run osascript: {0}")]
+    Io(#[from] io::Error),
+    #[error("AppleScript for {0} failed")]
+    ScriptFailed(&'static str),
+}
+
+/// A music player that can be paused and resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Player {
+    Music,
+    Spotify,
+}
+
+impl Player {
+    /// Every supported player, in the order they are tried.
+    pub const ALL: [Player; 2] = [Player::Music, Player::Spotify];
+
+    /// Returns the application name used in AppleScript.
+    pub fn app_name(self) -> &'static str {
+        match self {
+            Player::Music => "Music",
+            Player::Spotify => "Spotify",
+        }
+    }
+}
+
+/// Pauses and resumes music players.
+pub trait MediaControl: Send {
+    /// Returns whether the player is currently playing.
+    fn is_playing(&self, player: Player) -> bool;
+    /// Pauses the player.
+    fn pause(&self, player: Player) -> Result<(), MediaError>;
+    /// Resumes the player.
+    fn play(&self, player: Player) -> Result<(), MediaError>;
+}
+
+/// Media control through `osascript`.
+pub struct AppleScriptMedia;
+
+impl AppleScriptMedia {
+    fn run(player: Player, script: &str) -> Result<String, MediaError> {
+        let output = Command::new("osascript").args(["-e", script]).output()?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(MediaError::ScriptFailed(player.app_name()))
+        }
+    }
+}
+
+impl MediaControl for AppleScriptMedia {
+    fn is_playing(&self, player: Player) -> bool {
+        // Checking `is running` first keeps AppleScript from launching the app
+        let name = player.app_name();
+        let script = format!(
+            "if application \"{name}\" is running then\n\
+             tell application \"{name}\" to return player state as string\n\
+             end if\n\
+             return \"stopped\""
+        );
+        Self::run(player, &script).is_ok_and(|output| is_playing_state(&output))
+    }
+
+    fn pause(&self, player: Player) -> Result<(), MediaError> {
+        let script = format!("tell application \"{}\" to pause", player.app_name());
+        Self::run(player, &script).map(|_| ())
+    }
+
+    fn play(&self, player: Player) -> Result<(), MediaError> {
+        let script = format!("tell application \"{}\" to play", player.app_name());
+        Self::run(player, &script).map(|_| ())
+    }
+}
+
+/// Pauses music for a break and resumes it after, remembering which players
+/// it paused.
+pub struct MediaPauser {
+    control: Box<dyn MediaControl>,
+    /// Players we paused for the current break, to resume with the next pomodoro.
+    paused: Vec<Player>,
+}
+
+impl MediaPauser {
+    pub fn new(control: Box<dyn MediaControl>) -> Self {
+        Self {
+            control,
+            paused: Vec::new(),
+        }
+    }
+
+    /// Pauses any player that is playing.
+    pub fn pause(&mut self) {
+        for player in Player::ALL {
+            if self.paused.contains(&player) || !self.control.is_playing(player) {
+                continue;
+            }
+            match self.control.pause(player) {
+                Ok(()) => self.paused.push(player),
+                Err(e) => eprintln!("Failed to pause {}: {}", player.app_name(), e),
+            }
+        }
+    }
+
+    /// Resumes the players we paused. Players nobody paused are never touched.
+    pub fn resume(&mut self) {
+        for player in std::mem::take(&mut self.paused) {
+            if let Err(e) = self.control.play(player) {
+                eprintln!("Failed to resume {}: {}", player.app_name(), e);
+            }
+        }
+    }
+}
+
+/// Returns whether the `player state` printed by AppleScript means playing.
+fn is_playing_state(output: &str) -> bool {
+    output.trim() == "playing"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_playing_state() {
+        assert!(is_playing_state("playing\n"));
+        assert!(!is_playing_state("paused\n"));
+        assert!(!is_playing_state("stopped"));
+        assert!(!is_playing_state(""));
+    }
+}
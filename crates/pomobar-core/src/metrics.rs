@@ -0,0 +1,269 @@
+//! Prometheus metrics in a textfile for node_exporter's textfile collector.
+//!
+//! The file is written after every completion and every five minutes, and
+//! replaced atomically so the collector never scrapes a half-written file.
+
+use crate::models::{Session, TimerState};
+use crate::status_file;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Time between two writes while nothing completes.
+const WRITE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Values of the `state` label, one per timer state.
+const STATES: [&str; 9] = [
+    "idle",
+    "pomodoro_active",
+    "pomodoro_paused",
+    "overtime",
+    "break_active",
+    "break_paused",
+    "break_finished",
+    "stopwatch_active",
+    "stopwatch_paused",
+];
+
+/// Returns the `state` label value for a timer state.
+fn state_label(state: &TimerState) -> &'static str {
+    match state {
+        TimerState::Idle => "idle",
+        TimerState::PomodoroActive { .. } => "pomodoro_active",
+        TimerState::PomodoroPaused { .. } => "pomodoro_paused",
+        TimerState::Overtime { .. } => "overtime",
+        TimerState::BreakActive { .. } => "break_active",
+        TimerState::BreakPaused { .. } => "break_paused",
+        TimerState::BreakFinished => "break_finished",
+        TimerState::StopwatchActive { .. } => "stopwatch_active",
+        TimerState::StopwatchPaused { .. } => "stopwatch_paused",
+    }
+}
+
+/// Renders the metrics in the Prometheus text format. Every state is listed,
+/// with 1 for the current one and 0 for the rest. The focus score is left
+/// out until the day has one.
+pub fn render(state: &TimerState, session: &Session, focus_score: Option<u32>) -> String {
+    let mut out = String::new();
+    let current = state_label(state);
+
+    let _ = writeln!(
+        out,
+        "# HELP pomobar_pomodoros_completed_today Pomodoros completed today.\n\
+         # TYPE pomobar_pomodoros_completed_today gauge\n\
+         pomobar_pomodoros_completed_today {}",
+        session.pomodoros_completed_today
+    );
+    let _ = writeln!(
+        out,
+        "# HELP pomobar_focus_minutes_today Minutes spent in completed pomodoros today.\n\
+         # TYPE pomobar_focus_minutes_today gauge\n\
+         pomobar_focus_minutes_today {}",
+        session.total_focus_mins_today
+    );
+    if let Some(score) = focus_score {
+        let _ = writeln!(
+            out,
+            "# HELP pomobar_focus_score Today's focus score from 0 to 100.\n\
+             # TYPE pomobar_focus_score gauge\n\
+             pomobar_focus_score {}",
+            score
+        );
+    }
+    out.push_str(
+        "# HELP pomobar_timer_state Current timer state.\n\
+         # TYPE pomobar_timer_state gauge\n",
+    );
+    for label in STATES {
+        let value = u8::from(label == current);
+        let _ = writeln!(out, "pomobar_timer_state{{state=\"{}\"}} {}", label, value);
+    }
+    let _ = writeln!(
+        out,
+        "# HELP pomobar_remaining_seconds Seconds left on the running timer.\n\
+         # TYPE pomobar_remaining_seconds gauge\n\
+         pomobar_remaining_seconds {}",
+        state.remaining_secs().unwrap_or(0)
+    );
+    out
+}
+
+/// Writes the metrics file after completions and every five minutes.
+pub struct MetricsWriter {
+    path: PathBuf,
+    last_write_at: Option<Instant>,
+}
+
+impl MetricsWriter {
+    /// Creates a writer for the given file.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_write_at: None,
+        }
+    }
+
+    /// The file this writer writes to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the metrics if a timer just completed, nothing has been written
+    /// yet, or the interval has passed. Returns true if the file was written.
+    pub fn update(
+        &mut self,
+        state: &TimerState,
+        session: &Session,
+        focus_score: Option<u32>,
+        completed: bool,
+        now: Instant,
+    ) -> bool {
+        let due = match self.last_write_at {
+            Some(at) => completed || now.duration_since(at) >= WRITE_INTERVAL,
+            None => true,
+        };
+        if !due {
+            return false;
+        }
+
+        if let Err(e) =
+            status_file::write_atomic(&self.path, render(state, session, focus_score).as_bytes())
+        {
+            eprintln!("Failed to write metrics file: {}", e);
+            return false;
+        }
+        self.last_write_at = Some(now);
+        true
+    }
+}
+
+/// Removes the metrics file so the collector doesn't keep reporting a
+/// stopped app's last values. A missing file is not an error.
+pub fn remove(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn session(count: u32, mins: u32) -> Session {
+        Session {
+            pomodoros_completed_today: count,
+            total_focus_mins_today: mins,
+            ..Session::default()
+        }
+    }
+
+    #[test]
+    fn test_render_pomodoro_active() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 754,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            render(&state, &session(3, 75), Some(65)),
+            "# HELP pomobar_pomodoros_completed_today Pomodoros completed today.\n\
+             # TYPE pomobar_pomodoros_completed_today gauge\n\
+             pomobar_pomodoros_completed_today 3\n\
+             # HELP pomobar_focus_minutes_today Minutes spent in completed pomodoros today.\n\
+             # TYPE pomobar_focus_minutes_today gauge\n\
+             pomobar_focus_minutes_today 75\n\
+             # HELP pomobar_focus_score Today's focus score from 0 to 100.\n\
+             # TYPE pomobar_focus_score gauge\n\
+             pomobar_focus_score 65\n\
+             # HELP pomobar_timer_state Current timer state.\n\
+             # TYPE pomobar_timer_state gauge\n\
+             pomobar_timer_state{state=\"idle\"} 0\n\
+             pomobar_timer_state{state=\"pomodoro_active\"} 1\n\
+             pomobar_timer_state{state=\"pomodoro_paused\"} 0\n\
+             pomobar_timer_state{state=\"overtime\"} 0\n\
+             pomobar_timer_state{state=\"break_active\"} 0\n\
+             pomobar_timer_state{state=\"break_paused\"} 0\n\
+             pomobar_timer_state{state=\"break_finished\"} 0\n\
+             pomobar_timer_state{state=\"stopwatch_active\"} 0\n\
+             pomobar_timer_state{state=\"stopwatch_paused\"} 0\n\
+             # HELP pomobar_remaining_seconds Seconds left on the running timer.\n\
+             # TYPE pomobar_remaining_seconds gauge\n\
+             pomobar_remaining_seconds 754\n"
+        );
+    }
+
+    #[test]
+    fn test_render_idle() {
+        let text = render(&TimerState::Idle, &session(0, 0), None);
+        assert!(text.contains("pomobar_timer_state{state=\"idle\"} 1\n"));
+        assert!(text.contains("pomobar_timer_state{state=\"pomodoro_active\"} 0\n"));
+        assert!(text.ends_with("pomobar_remaining_seconds 0\n"));
+        assert!(!text.contains("pomobar_focus_score"));
+    }
+
+    #[test]
+    fn test_render_paused_break() {
+        let state = TimerState::BreakPaused {
+            is_long_break: true,
+            remaining_secs: 600,
+            total_secs: 900,
+        };
+        let text = render(&state, &session(4, 100), Some(90));
+        assert!(text.contains("pomobar_timer_state{state=\"break_paused\"} 1\n"));
+        assert_eq!(text.matches("} 1\n").count(), 1);
+        assert!(text.ends_with("pomobar_remaining_seconds 600\n"));
+    }
+
+    #[test]
+    fn test_writer_writes_on_completion_and_interval() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.prom");
+        let mut writer = MetricsWriter::new(path.clone());
+        let start = Instant::now();
+
+        assert!(writer.update(&TimerState::Idle, &session(0, 0), None, false, start));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            render(&TimerState::Idle, &session(0, 0), None)
+        );
+
+        // Ticks in between don't write, completions do
+        assert!(!writer.update(&TimerState::Idle, &session(0, 0), None, false, start));
+        assert!(writer.update(
+            &TimerState::BreakFinished,
+            &session(1, 25),
+            Some(53),
+            true,
+            start + Duration::from_secs(10)
+        ));
+        assert!(!writer.update(
+            &TimerState::BreakFinished,
+            &session(1, 25),
+            Some(53),
+            false,
+            start + Duration::from_secs(60)
+        ));
+        assert!(writer.update(
+            &TimerState::BreakFinished,
+            &session(1, 25),
+            Some(53),
+            false,
+            start + Duration::from_secs(10) + WRITE_INTERVAL
+        ));
+    }
+
+    #[test]
+    fn test_remove() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.prom");
+        fs::write(&path, "stale").unwrap();
+
+        remove(&path).unwrap();
+        assert!(!path.exists());
+        // Removing again is fine
+        remove(&path).unwrap();
+    }
+}
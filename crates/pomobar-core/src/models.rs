@@ -0,0 +1,2154 @@
+//! Data models for the Pomobar application.
+
+use chrono::{DateTime, Datelike, Days, Duration, Local, NaiveDate, NaiveTime, Weekday};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Timer state machine representing all possible states of the pomodoro timer.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum TimerState {
+    /// No active timer, ready to start a pomodoro.
+    #[default]
+    Idle,
+    /// Pomodoro work session in progress.
+    PomodoroActive {
+        remaining_secs: u32,
+        total_secs: u32,
+    },
+    /// Pomodoro paused by user.
+    PomodoroPaused {
+        remaining_secs: u32,
+        total_secs: u32,
+    },
+    /// Break in progress (short or long).
+    BreakActive {
+        is_long_break: bool,
+        remaining_secs: u32,
+        total_secs: u32,
+    },
+    /// Pomodoro past its end, counting up until the user completes it.
+    Overtime {
+        elapsed_overtime_secs: u32,
+        total_secs: u32,
+    },
+    /// Break paused by user.
+    BreakPaused {
+        is_long_break: bool,
+        remaining_secs: u32,
+        total_secs: u32,
+    },
+    /// Break finished, waiting for user to start next pomodoro.
+    BreakFinished,
+    /// Stopwatch counting up, with no end set.
+    StopwatchActive { elapsed_secs: u32 },
+    /// Stopwatch paused by user.
+    StopwatchPaused { elapsed_secs: u32 },
+}
+
+impl TimerState {
+    /// Returns the variant name, e.g. "PomodoroActive".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Idle => "Idle",
+            Self::PomodoroActive { .. } => "PomodoroActive",
+            Self::PomodoroPaused { .. } => "PomodoroPaused",
+            Self::Overtime { .. } => "Overtime",
+            Self::BreakActive { .. } => "BreakActive",
+            Self::BreakPaused { .. } => "BreakPaused",
+            Self::BreakFinished => "BreakFinished",
+            Self::StopwatchActive { .. } => "StopwatchActive",
+            Self::StopwatchPaused { .. } => "StopwatchPaused",
+        }
+    }
+
+    /// Returns true if the timer is in an idle state (Idle or BreakFinished).
+    pub fn is_idle(&self) -> bool {
+        matches!(self, Self::Idle | Self::BreakFinished)
+    }
+
+    /// Returns true if the timer is actively counting down, i.e. for
+    /// `PomodoroActive` and `BreakActive`. Overtime and the stopwatch count
+    /// up, so they are not active in this sense.
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::PomodoroActive { .. } | Self::BreakActive { .. })
+    }
+
+    /// Returns true if the timer is paused (pomodoro, break or stopwatch).
+    pub fn is_paused(&self) -> bool {
+        matches!(
+            self,
+            Self::PomodoroPaused { .. } | Self::BreakPaused { .. } | Self::StopwatchPaused { .. }
+        )
+    }
+
+    /// Returns true if currently in a pomodoro session (active, paused or in overtime).
+    pub fn is_pomodoro(&self) -> bool {
+        matches!(
+            self,
+            Self::PomodoroActive { .. } | Self::PomodoroPaused { .. } | Self::Overtime { .. }
+        )
+    }
+
+    /// Returns true if the stopwatch is running or paused.
+    pub fn is_stopwatch(&self) -> bool {
+        matches!(
+            self,
+            Self::StopwatchActive { .. } | Self::StopwatchPaused { .. }
+        )
+    }
+
+    /// Returns the seconds the stopwatch has counted, if it is running or paused.
+    pub fn stopwatch_secs(&self) -> Option<u32> {
+        match self {
+            Self::StopwatchActive { elapsed_secs } | Self::StopwatchPaused { elapsed_secs } => {
+                Some(*elapsed_secs)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the seconds a pomodoro or break has run so far, overtime included.
+    pub fn elapsed_secs(&self) -> Option<u32> {
+        match self {
+            Self::Overtime {
+                elapsed_overtime_secs,
+                total_secs,
+            } => Some(total_secs + elapsed_overtime_secs),
+            _ => Some(self.total_secs()?.saturating_sub(self.remaining_secs()?)),
+        }
+    }
+
+    /// Returns true if currently on a break (active or paused).
+    pub fn is_break(&self) -> bool {
+        matches!(self, Self::BreakActive { .. } | Self::BreakPaused { .. })
+    }
+
+    /// Returns the progress percentage (0.0 to 1.0) if a timer is active.
+    pub fn progress_percent(&self) -> Option<f32> {
+        match self {
+            Self::PomodoroActive {
+                remaining_secs,
+                total_secs,
+            }
+            | Self::PomodoroPaused {
+                remaining_secs,
+                total_secs,
+            }
+            | Self::BreakActive {
+                remaining_secs,
+                total_secs,
+                ..
+            }
+            | Self::BreakPaused {
+                remaining_secs,
+                total_secs,
+                ..
+            } => {
+                if *total_secs == 0 {
+                    return Some(1.0);
+                }
+                Some(1.0 - (*remaining_secs as f32 / *total_secs as f32))
+            }
+            Self::Overtime { .. } => Some(1.0),
+            _ => None,
+        }
+    }
+
+    /// Returns the remaining seconds if a timer is active.
+    pub fn remaining_secs(&self) -> Option<u32> {
+        match self {
+            Self::PomodoroActive { remaining_secs, .. }
+            | Self::PomodoroPaused { remaining_secs, .. }
+            | Self::BreakActive { remaining_secs, .. }
+            | Self::BreakPaused { remaining_secs, .. } => Some(*remaining_secs),
+            _ => None,
+        }
+    }
+
+    /// Returns the total seconds if a timer is active.
+    pub fn total_secs(&self) -> Option<u32> {
+        match self {
+            Self::PomodoroActive { total_secs, .. }
+            | Self::PomodoroPaused { total_secs, .. }
+            | Self::BreakActive { total_secs, .. }
+            | Self::BreakPaused { total_secs, .. }
+            | Self::Overtime { total_secs, .. } => Some(*total_secs),
+            _ => None,
+        }
+    }
+
+    /// Returns true if strict mode keeps this timer from being paused or
+    /// stopped, i.e. a pomodoro is running or in overtime.
+    pub fn is_locked(&self, strict_mode: bool) -> bool {
+        strict_mode && matches!(self, Self::PomodoroActive { .. } | Self::Overtime { .. })
+    }
+}
+
+/// How the progress line in the menu is displayed. Clicking it cycles modes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum ProgressMode {
+    /// ASCII progress bar with percentage.
+    #[default]
+    Bar,
+    /// Percentage only.
+    Percent,
+    /// Elapsed and remaining time.
+    ElapsedRemaining,
+    /// Clock time the timer ends at.
+    EndTime,
+}
+
+impl ProgressMode {
+    /// Returns the mode shown after this one.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Bar => Self::Percent,
+            Self::Percent => Self::ElapsedRemaining,
+            Self::ElapsedRemaining => Self::EndTime,
+            Self::EndTime => Self::Bar,
+        }
+    }
+}
+
+/// How the progress bar in the menu is drawn.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum ProgressBarStyle {
+    /// Filled and empty blocks: `██████████░░░░░░░░░░  50%`.
+    #[default]
+    BlockFill,
+    /// Dots in brackets: `[••••••••••··········]  50%`.
+    BracketDots,
+    /// Only the percentage, for a compact menu: `50%`.
+    NumberOnly,
+}
+
+impl ProgressBarStyle {
+    /// Every style, in menu order.
+    pub const ALL: [ProgressBarStyle; 3] = [Self::BlockFill, Self::BracketDots, Self::NumberOnly];
+
+    /// Returns the style name shown in the menu.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::BlockFill => "Blocks",
+            Self::BracketDots => "Dots",
+            Self::NumberOnly => "Number Only",
+        }
+    }
+
+    /// Returns the stable identifier used in menu item ids.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::BlockFill => "block_fill",
+            Self::BracketDots => "bracket_dots",
+            Self::NumberOnly => "number_only",
+        }
+    }
+}
+
+/// What the menu bar shows for a running timer.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum TrayDisplay {
+    /// The title text next to a dot icon: `● 🍅 24:59`.
+    #[default]
+    Text,
+    /// Only the icon, drawn as a ring that fills up as the timer runs.
+    Icon,
+    /// The progress ring and the title text.
+    Both,
+}
+
+impl TrayDisplay {
+    /// Every choice, in menu order.
+    pub const ALL: [TrayDisplay; 3] = [Self::Text, Self::Icon, Self::Both];
+
+    /// Returns the choice name shown in the menu.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Text => "Text",
+            Self::Icon => "Progress Ring",
+            Self::Both => "Text and Ring",
+        }
+    }
+
+    /// Returns the stable identifier used in menu item ids.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Icon => "icon",
+            Self::Both => "both",
+        }
+    }
+
+    /// Whether the title text is shown.
+    pub fn shows_text(self) -> bool {
+        self != Self::Icon
+    }
+
+    /// Whether the icon is drawn as a progress ring.
+    pub fn shows_ring(self) -> bool {
+        self != Self::Text
+    }
+}
+
+/// First day of the week for weekly stats.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+    Saturday,
+}
+
+impl WeekStart {
+    /// Every choice, in menu order.
+    pub const ALL: [WeekStart; 3] = [Self::Monday, Self::Sunday, Self::Saturday];
+
+    /// Returns the day name shown in the menu.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Monday => "Monday",
+            Self::Sunday => "Sunday",
+            Self::Saturday => "Saturday",
+        }
+    }
+
+    /// Returns the weekday the week starts on.
+    pub fn weekday(self) -> Weekday {
+        match self {
+            Self::Monday => Weekday::Mon,
+            Self::Sunday => Weekday::Sun,
+            Self::Saturday => Weekday::Sat,
+        }
+    }
+
+    /// Returns the first day of the week containing `date`.
+    pub fn week_start(self, date: NaiveDate) -> NaiveDate {
+        let first = self.weekday();
+        let offset = (date.weekday().num_days_from_monday() + 7 - first.num_days_from_monday()) % 7;
+        date - Days::new(offset as u64)
+    }
+}
+
+/// What happens to a pomodoro that would run past the end of the day.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum WindDownPolicy {
+    /// Shorten it to end exactly at the end of the day.
+    #[default]
+    Shorten,
+    /// Run the full pomodoro anyway.
+    AllowOverrun,
+    /// Don't start it.
+    Block,
+}
+
+/// What drives the timer ticks.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum TimerMode {
+    /// A background thread that sleeps one second between ticks.
+    #[default]
+    Thread,
+    /// The event loop, which sleeps until the next tick is due.
+    EventLoop,
+}
+
+/// A duration setting that can also be set to a custom number of minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationSetting {
+    Pomodoro,
+    ShortBreak,
+    LongBreak,
+}
+
+impl DurationSetting {
+    /// Minutes allowed for a custom duration.
+    pub const CUSTOM_RANGE: RangeInclusive<u32> = 1..=180;
+
+    /// Returns the name shown in prompts.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pomodoro => "Pomodoro",
+            Self::ShortBreak => "Short break",
+            Self::LongBreak => "Long break",
+        }
+    }
+
+    /// Returns the current value in minutes.
+    pub fn get(self, settings: &Settings) -> u32 {
+        match self {
+            Self::Pomodoro => settings.pomodoro_mins,
+            Self::ShortBreak => settings.short_break_mins,
+            Self::LongBreak => settings.long_break_mins,
+        }
+    }
+
+    /// Sets the value in minutes.
+    pub fn set(self, settings: &mut Settings, mins: u32) {
+        match self {
+            Self::Pomodoro => settings.pomodoro_mins = mins,
+            Self::ShortBreak => settings.short_break_mins = mins,
+            Self::LongBreak => settings.long_break_mins = mins,
+        }
+    }
+
+    /// Parses a custom duration typed by the user.
+    /// Returns None unless it is a whole number of minutes in `CUSTOM_RANGE`.
+    pub fn parse_custom(input: &str) -> Option<u32> {
+        input
+            .trim()
+            .parse()
+            .ok()
+            .filter(|mins| Self::CUSTOM_RANGE.contains(mins))
+    }
+}
+
+/// Sound played when a timer completes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub enum SoundChoice {
+    /// The custom sound file if one is set, otherwise the chime.
+    #[default]
+    Default,
+    /// The embedded chime: two soft bell strikes.
+    Chime,
+    /// The embedded soft, low bell.
+    Soft,
+    /// Three descending tones.
+    Bell,
+    /// A single short tone.
+    Ping,
+    /// No sound.
+    None,
+    /// A sound file (MP3, WAV or OGG).
+    Custom(String),
+}
+
+impl SoundChoice {
+    /// The choices offered in the sound submenus, in menu order.
+    pub const MENU_CHOICES: [SoundChoice; 6] = [
+        SoundChoice::Default,
+        SoundChoice::Chime,
+        SoundChoice::Soft,
+        SoundChoice::Bell,
+        SoundChoice::Ping,
+        SoundChoice::None,
+    ];
+
+    /// Short name used in menu item ids.
+    pub fn key(&self) -> &'static str {
+        match self {
+            SoundChoice::Default => "default",
+            SoundChoice::Chime => "chime",
+            SoundChoice::Soft => "soft",
+            SoundChoice::Bell => "bell",
+            SoundChoice::Ping => "ping",
+            SoundChoice::None => "none",
+            SoundChoice::Custom(_) => "custom",
+        }
+    }
+
+    /// Returns the menu choice with the given key. Custom files have no key.
+    pub fn from_key(key: &str) -> Option<SoundChoice> {
+        Self::MENU_CHOICES
+            .into_iter()
+            .find(|choice| choice.key() == key)
+    }
+
+    /// Label shown in the sound submenus.
+    pub fn label(&self) -> &'static str {
+        match self {
+            SoundChoice::Default => "Default",
+            SoundChoice::Chime => "Chime",
+            SoundChoice::Soft => "Soft Bell",
+            SoundChoice::Bell => "Bell",
+            SoundChoice::Ping => "Ping",
+            SoundChoice::None => "None",
+            SoundChoice::Custom(_) => "Custom File",
+        }
+    }
+}
+
+/// User-configurable settings for the pomodoro timer.
+/// Missing fields take their value from `Settings::default()`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Duration of a pomodoro work session in minutes.
+    pub pomodoro_mins: u32,
+    /// Duration of a short break in minutes.
+    pub short_break_mins: u32,
+    /// Duration of a long break in minutes.
+    pub long_break_mins: u32,
+    /// Number of pomodoros before a long break.
+    pub pomodoros_for_long_break: u32,
+    /// Whether to play a sound when a pomodoro completes.
+    pub sound_on_pomodoro_end: bool,
+    /// Whether to play a sound when a break completes.
+    pub sound_on_break_end: bool,
+    /// Whether to show system notifications.
+    pub notifications_enabled: bool,
+    /// Chime volume from 0.0 (mute) to 1.0, relative to the system volume.
+    /// Stored values outside that range are clamped on load.
+    #[serde(default = "default_volume", deserialize_with = "deserialize_volume")]
+    pub volume: f32,
+    /// Sound file played instead of the built-in chime (MP3, WAV or OGG).
+    #[serde(default)]
+    pub custom_sound_path: Option<String>,
+    /// Sound played when a pomodoro completes.
+    #[serde(default)]
+    pub pomodoro_sound: SoundChoice,
+    /// Sound played when a break completes.
+    #[serde(default)]
+    pub break_sound: SoundChoice,
+    /// Whether to launch the app at login.
+    #[serde(default)]
+    pub launch_at_login: bool,
+    /// Pomodoros per day that earn the full progress part of the focus score.
+    #[serde(default = "default_daily_goal")]
+    pub daily_goal: u32,
+    /// Minutes a stopwatch has to run to count as a pomodoro; shorter runs
+    /// only add their minutes to the focus time.
+    #[serde(default = "default_stopwatch_min_mins")]
+    pub stopwatch_min_mins: u32,
+    /// Maximum minutes of focus per day before new pomodoros are refused (None = no limit).
+    #[serde(default)]
+    pub max_daily_focus_mins: Option<u32>,
+    /// When the working day ends; pomodoros that would run past it follow
+    /// `wind_down_policy` (None = no end of day).
+    #[serde(default)]
+    pub end_of_day: Option<NaiveTime>,
+    /// What to do with a pomodoro that would run past `end_of_day`.
+    #[serde(default)]
+    pub wind_down_policy: WindDownPolicy,
+    /// Seconds without keyboard/mouse input before a pomodoro auto-pauses (0 = disabled).
+    #[serde(default)]
+    pub idle_pause_secs: u32,
+    /// Minutes after a break before reminding to start the next pomodoro (0 = disabled).
+    #[serde(default)]
+    pub idle_reminder_mins: u32,
+    /// Seconds before the end of a pomodoro to show a heads-up (0 = disabled).
+    #[serde(default = "default_warn_before_end_secs")]
+    pub warn_before_end_secs: u32,
+    /// Whether the heads-up is also shown before breaks end.
+    #[serde(default)]
+    pub warn_before_break_end: bool,
+    /// Whether a quiet click sounds every second in a pomodoro's last minute.
+    #[serde(default)]
+    pub ticking_enabled: bool,
+    /// URL that receives one summary POST per finished day (None = disabled).
+    #[serde(default)]
+    pub daily_webhook_url: Option<String>,
+    /// URL that receives a POST for every timer event without a URL of its
+    /// own (None = disabled).
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// URL that receives a POST when a pomodoro starts (None = disabled).
+    #[serde(default)]
+    pub on_pomodoro_start: Option<String>,
+    /// URL that receives a POST when a pomodoro completes (None = disabled).
+    #[serde(default)]
+    pub on_pomodoro_complete: Option<String>,
+    /// URL that receives a POST when a break starts (None = disabled).
+    #[serde(default)]
+    pub on_break_start: Option<String>,
+    /// URL that receives a POST when a break completes (None = disabled).
+    #[serde(default)]
+    pub on_break_complete: Option<String>,
+    /// Body of the pomodoro-complete notification. Supports `{count}`,
+    /// `{pomodoros}`, `{task}`, `{duration}` and `{streak}` placeholders.
+    #[serde(default = "default_notif_pomo_template")]
+    pub notif_pomo_template: String,
+    /// Body of the break-complete notification, with the same placeholders.
+    #[serde(default = "default_notif_break_template")]
+    pub notif_break_template: String,
+    /// Body of the notification when a long break starts. Also supports
+    /// `{length}`, the break length, e.g. "15 min".
+    #[serde(default = "default_notif_long_break_template")]
+    pub notif_long_break_template: String,
+    /// Body of the notification when the next pomodoro started on its own
+    /// after a break. Also supports `{length}`, the pomodoro length.
+    #[serde(default = "default_notif_auto_start_template")]
+    pub notif_auto_start_template: String,
+    /// Body of the notification when a pomodoro runs into overtime. Also
+    /// supports `{length}`, the pomodoro length.
+    #[serde(default = "default_notif_overtime_template")]
+    pub notif_overtime_template: String,
+    /// Body of the reminder that the break ended a while ago.
+    #[serde(default = "default_notif_reminder_template")]
+    pub notif_reminder_template: String,
+    /// Body of the heads-up before a timer ends. Also supports
+    /// `{time_left}`, e.g. "1 minute" or "30 seconds".
+    #[serde(default = "default_notif_warning_template")]
+    pub notif_warning_template: String,
+    /// Whether to hold back notifications while Do Not Disturb or a Focus is on.
+    #[serde(default = "default_respect_dnd")]
+    pub respect_dnd: bool,
+    /// Whether to turn on a macOS Focus mode while a pomodoro runs.
+    #[serde(default)]
+    pub focus_mode_enabled: bool,
+    /// Whether to pause music for breaks and resume it with the next pomodoro.
+    #[serde(default)]
+    pub control_media: bool,
+    /// Whether to write the current status to `status.json` in the data directory.
+    #[serde(default)]
+    pub status_file_enabled: bool,
+    /// Where to write Prometheus metrics for node_exporter's textfile collector.
+    #[serde(default)]
+    pub metrics_textfile_path: Option<PathBuf>,
+    /// Whether a running pomodoro can't be paused or stopped, only completed early.
+    #[serde(default)]
+    pub strict_mode: bool,
+    /// Whether a pomodoro keeps counting up past zero until it is completed,
+    /// instead of starting the break on its own.
+    #[serde(default)]
+    pub overtime_enabled: bool,
+    /// Whether long runs of 🍅 in the menu are shown as a count, e.g. "🍅×12".
+    #[serde(default)]
+    pub compact_emoji_counts: bool,
+    /// Whether clock times are shown on a 24-hour clock ("14:47") rather
+    /// than a 12-hour one ("2:47 PM").
+    #[serde(default = "default_clock_24h")]
+    pub clock_24h: bool,
+    /// Whether the menubar title shows the time next to the icon.
+    #[serde(default = "default_show_time_in_menubar")]
+    pub show_time_in_menubar: bool,
+    /// Whether the menubar title blinks after a break ends, until the next
+    /// pomodoro starts or the menu is opened.
+    #[serde(default = "default_attention_blink")]
+    pub attention_blink: bool,
+    /// Whether the menu bar shows the title text, a progress ring or both.
+    #[serde(default)]
+    pub tray_display: TrayDisplay,
+    /// How the progress line in the menu is displayed.
+    #[serde(default)]
+    pub progress_mode: ProgressMode,
+    /// Number of characters in the menu's progress bar.
+    #[serde(default = "default_progress_bar_width")]
+    pub progress_bar_width: u8,
+    /// How the menu's progress bar is drawn.
+    #[serde(default)]
+    pub progress_bar_style: ProgressBarStyle,
+    /// First day of the week for weekly stats.
+    #[serde(default)]
+    pub week_starts_on: WeekStart,
+    /// Hour at which a new day starts for the daily stats, so work past
+    /// midnight still counts towards the evening it began.
+    #[serde(default)]
+    pub day_starts_at_hour: u32,
+    /// What drives the timer ticks; takes effect on the next launch.
+    #[serde(default)]
+    pub timer_mode: TimerMode,
+    /// Tray title format with the `timer::TRAY_PLACEHOLDERS`, e.g. `{icon} {mm}:{ss}`.
+    #[serde(default = "default_tray_format")]
+    pub tray_format: String,
+    /// Whether the global hotkeys are registered.
+    #[serde(default = "default_hotkeys_enabled")]
+    pub hotkeys_enabled: bool,
+    /// Global hotkey that starts or pauses/resumes the timer.
+    #[serde(default = "default_hotkey_start_pause")]
+    pub hotkey_start_pause: String,
+    /// Global hotkey that stops the current pomodoro.
+    #[serde(default = "default_hotkey_stop")]
+    pub hotkey_stop: String,
+    /// Whether Pomobar hasn't been launched before; cleared at the first launch.
+    #[serde(default = "default_first_run")]
+    pub first_run: bool,
+}
+
+fn default_volume() -> f32 {
+    0.7
+}
+
+fn deserialize_volume<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    Ok(f32::deserialize(deserializer)?.clamp(0.0, 1.0))
+}
+
+fn default_daily_goal() -> u32 {
+    8
+}
+
+fn default_stopwatch_min_mins() -> u32 {
+    10
+}
+
+fn default_notif_pomo_template() -> String {
+    "Great work! You've completed {pomodoros} today.\nTime for a break.".to_string()
+}
+
+fn default_notif_break_template() -> String {
+    "Ready to start another pomodoro?".to_string()
+}
+
+fn default_notif_long_break_template() -> String {
+    "You've earned a {length} break. Great job staying focused!".to_string()
+}
+
+fn default_notif_auto_start_template() -> String {
+    "Your next {length} pomodoro has started.".to_string()
+}
+
+fn default_notif_overtime_template() -> String {
+    "{length} are up. Keep going, and click Complete when you're ready for a break.".to_string()
+}
+
+fn default_notif_reminder_template() -> String {
+    "Your break ended a while ago.".to_string()
+}
+
+fn default_notif_warning_template() -> String {
+    "{time_left} left".to_string()
+}
+
+fn default_respect_dnd() -> bool {
+    true
+}
+
+fn default_clock_24h() -> bool {
+    true
+}
+
+fn default_show_time_in_menubar() -> bool {
+    true
+}
+
+fn default_attention_blink() -> bool {
+    true
+}
+
+fn default_warn_before_end_secs() -> u32 {
+    60
+}
+
+fn default_tray_format() -> String {
+    DEFAULT_TRAY_FORMAT.to_string()
+}
+
+fn default_hotkeys_enabled() -> bool {
+    true
+}
+
+fn default_hotkey_start_pause() -> String {
+    "Ctrl+Option+P".to_string()
+}
+
+fn default_hotkey_stop() -> String {
+    "Ctrl+Option+S".to_string()
+}
+
+fn default_first_run() -> bool {
+    true
+}
+
+fn default_progress_bar_width() -> u8 {
+    20
+}
+
+/// Version of the stored settings format. Bump it whenever a change to
+/// `Settings` needs stored settings to be migrated, and add a step for the
+/// old version to `migrate_settings`. New fields with a default don't.
+pub const SETTINGS_VERSION: u32 = 2;
+
+/// Settings as stored in the database and in exported files, tagged with the
+/// format version they were written in. Settings from before versioning are
+/// stored bare and count as version 0.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionedSettings {
+    /// The `SETTINGS_VERSION` the settings were written in.
+    pub version: u32,
+    /// The settings themselves, not yet migrated.
+    pub data: serde_json::Value,
+}
+
+impl Settings {
+    /// Serializes the settings tagged with the current format version.
+    pub fn to_versioned_json(&self, pretty: bool) -> serde_json::Result<String> {
+        let versioned = VersionedSettings {
+            version: SETTINGS_VERSION,
+            data: serde_json::to_value(self)?,
+        };
+        if pretty {
+            serde_json::to_string_pretty(&versioned)
+        } else {
+            serde_json::to_string(&versioned)
+        }
+    }
+
+    /// Parses stored settings JSON of any version, bare or versioned.
+    pub fn from_json(json: &str) -> serde_json::Result<Settings> {
+        match serde_json::from_str::<VersionedSettings>(json) {
+            Ok(versioned) => migrate_settings(versioned.version, versioned.data),
+            Err(_) => migrate_settings(0, serde_json::from_str(json)?),
+        }
+    }
+
+    /// Rejects zero or absurdly long durations, and other values outside
+    /// what the menu offers.
+    pub fn validate(&self) -> Result<(), OutOfRange> {
+        for setting in [
+            DurationSetting::Pomodoro,
+            DurationSetting::ShortBreak,
+            DurationSetting::LongBreak,
+        ] {
+            check_range(
+                setting.name(),
+                setting.get(self),
+                DurationSetting::CUSTOM_RANGE,
+            )?;
+        }
+        check_range(
+            "Long break after",
+            self.pomodoros_for_long_break,
+            LONG_BREAK_AFTER_RANGE,
+        )?;
+        check_range(
+            "Day starts at hour",
+            self.day_starts_at_hour,
+            DAY_START_HOURS,
+        )?;
+        check_range(
+            "Progress bar width",
+            self.progress_bar_width,
+            PROGRESS_BAR_WIDTHS,
+        )
+    }
+
+    /// Moves every value `validate` checks into its range, for settings an
+    /// older version saved out of range.
+    pub fn clamp_to_range(&mut self) {
+        let clamp =
+            |value: u32, range: RangeInclusive<u32>| value.clamp(*range.start(), *range.end());
+        for setting in [
+            DurationSetting::Pomodoro,
+            DurationSetting::ShortBreak,
+            DurationSetting::LongBreak,
+        ] {
+            let mins = clamp(setting.get(self), DurationSetting::CUSTOM_RANGE);
+            setting.set(self, mins);
+        }
+        self.pomodoros_for_long_break =
+            clamp(self.pomodoros_for_long_break, LONG_BREAK_AFTER_RANGE);
+        self.day_starts_at_hour = clamp(self.day_starts_at_hour, DAY_START_HOURS);
+        self.progress_bar_width = self
+            .progress_bar_width
+            .clamp(*PROGRESS_BAR_WIDTHS.start(), *PROGRESS_BAR_WIDTHS.end());
+    }
+}
+
+/// A setting outside the range Pomobar accepts.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{name} must be between {min} and {max}")]
+pub struct OutOfRange {
+    pub name: &'static str,
+    pub min: u32,
+    pub max: u32,
+}
+
+fn check_range<T: PartialOrd + Copy + Into<u32>>(
+    name: &'static str,
+    value: T,
+    range: RangeInclusive<T>,
+) -> Result<(), OutOfRange> {
+    if range.contains(&value) {
+        Ok(())
+    } else {
+        Err(OutOfRange {
+            name,
+            min: (*range.start()).into(),
+            max: (*range.end()).into(),
+        })
+    }
+}
+
+/// Brings settings stored in format `version` up to the current one and
+/// deserializes them. Each step handles one version, so old settings pass
+/// through every step after their own. Fields that are still missing take
+/// their defaults.
+pub fn migrate_settings(version: u32, mut raw: serde_json::Value) -> serde_json::Result<Settings> {
+    if let Some(fields) = raw.as_object_mut() {
+        if version < 1 {
+            // `sound_enabled` was split into one flag per completion event
+            if let Some(enabled) = fields.remove("sound_enabled") {
+                for key in ["sound_on_pomodoro_end", "sound_on_break_end"] {
+                    fields.entry(key).or_insert_with(|| enabled.clone());
+                }
+            }
+        }
+        if version < 2 {
+            // Settings were saved, so this isn't the first launch
+            fields.insert("first_run".to_string(), false.into());
+        }
+    }
+    serde_json::from_value(raw)
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            pomodoro_mins: 25,
+            short_break_mins: 5,
+            long_break_mins: 15,
+            pomodoros_for_long_break: 4,
+            sound_on_pomodoro_end: true,
+            sound_on_break_end: true,
+            notifications_enabled: true,
+            volume: default_volume(),
+            custom_sound_path: None,
+            pomodoro_sound: SoundChoice::Default,
+            break_sound: SoundChoice::Default,
+            launch_at_login: false,
+            daily_goal: default_daily_goal(),
+            stopwatch_min_mins: default_stopwatch_min_mins(),
+            max_daily_focus_mins: None,
+            end_of_day: None,
+            wind_down_policy: WindDownPolicy::default(),
+            idle_pause_secs: 0,
+            idle_reminder_mins: 0,
+            warn_before_end_secs: default_warn_before_end_secs(),
+            warn_before_break_end: false,
+            ticking_enabled: false,
+            daily_webhook_url: None,
+            webhook_url: None,
+            on_pomodoro_start: None,
+            on_pomodoro_complete: None,
+            on_break_start: None,
+            on_break_complete: None,
+            notif_pomo_template: default_notif_pomo_template(),
+            notif_break_template: default_notif_break_template(),
+            notif_long_break_template: default_notif_long_break_template(),
+            notif_auto_start_template: default_notif_auto_start_template(),
+            notif_overtime_template: default_notif_overtime_template(),
+            notif_reminder_template: default_notif_reminder_template(),
+            notif_warning_template: default_notif_warning_template(),
+            respect_dnd: default_respect_dnd(),
+            focus_mode_enabled: false,
+            control_media: false,
+            status_file_enabled: false,
+            metrics_textfile_path: None,
+            strict_mode: false,
+            overtime_enabled: false,
+            compact_emoji_counts: false,
+            clock_24h: true,
+            show_time_in_menubar: true,
+            attention_blink: true,
+            tray_display: TrayDisplay::default(),
+            progress_mode: ProgressMode::default(),
+            progress_bar_width: default_progress_bar_width(),
+            progress_bar_style: ProgressBarStyle::default(),
+            week_starts_on: WeekStart::default(),
+            day_starts_at_hour: 0,
+            timer_mode: TimerMode::default(),
+            tray_format: default_tray_format(),
+            hotkeys_enabled: default_hotkeys_enabled(),
+            hotkey_start_pause: default_hotkey_start_pause(),
+            hotkey_stop: default_hotkey_stop(),
+            first_run: default_first_run(),
+        }
+    }
+}
+
+/// The default `Settings::tray_format`, e.g. "🍅 24:59".
+pub const DEFAULT_TRAY_FORMAT: &str = "{icon} {mm}:{ss}";
+
+/// Accepted widths for `Settings::progress_bar_width`.
+pub const PROGRESS_BAR_WIDTHS: RangeInclusive<u8> = 5..=40;
+
+/// Hours a day can start at, for `Settings::day_starts_at_hour`.
+pub const DAY_START_HOURS: RangeInclusive<u32> = 0..=6;
+
+/// Accepted pomodoros per long break, for `Settings::pomodoros_for_long_break`.
+pub const LONG_BREAK_AFTER_RANGE: RangeInclusive<u32> = 1..=20;
+
+/// Parses a custom number of pomodoros per long break typed by the user.
+/// Returns None unless it is a whole number in `LONG_BREAK_AFTER_RANGE`.
+pub fn parse_long_break_after(input: &str) -> Option<u32> {
+    input
+        .trim()
+        .parse()
+        .ok()
+        .filter(|count| LONG_BREAK_AFTER_RANGE.contains(count))
+}
+
+/// Returns the day `now` counts towards when days start at
+/// `day_starts_at_hour` o'clock: with 4, 03:59 still belongs to the day before.
+pub fn logical_date(now: DateTime<Local>, day_starts_at_hour: u32) -> NaiveDate {
+    (now - Duration::hours(i64::from(day_starts_at_hour))).date_naive()
+}
+
+/// A pomodoro or break saved on quit, to be picked up on the next launch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedTimer {
+    pub state: TimerState,
+    /// When the pomodoro or break began, before any pauses.
+    pub block_started_at: Option<DateTime<Local>>,
+}
+
+/// Session tracking for the current day.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Session {
+    /// Number of pomodoros completed today.
+    pub pomodoros_completed_today: u32,
+    /// Total minutes of focus time today.
+    pub total_focus_mins_today: u32,
+    /// Number of pomodoros in current cycle (resets after long break).
+    pub pomodoros_in_cycle: u32,
+    /// Number of times a running pomodoro was paused today.
+    pub interruptions_today: u32,
+    /// Number of interruptions marked during pomodoros today, without pausing.
+    pub marked_interruptions_today: u32,
+    /// Number of pomodoros stopped before completing today.
+    pub abandoned_today: u32,
+    /// Number of breaks skipped today.
+    pub skipped_breaks_today: u32,
+    /// Minutes spent on pomodoros that were stopped before completing today.
+    pub total_interrupted_mins: u32,
+    /// The date these stats are for.
+    pub last_date: NaiveDate,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            pomodoros_completed_today: 0,
+            total_focus_mins_today: 0,
+            pomodoros_in_cycle: 0,
+            interruptions_today: 0,
+            marked_interruptions_today: 0,
+            abandoned_today: 0,
+            skipped_breaks_today: 0,
+            total_interrupted_mins: 0,
+            last_date: Local::now().date_naive(),
+        }
+    }
+}
+
+impl Session {
+    /// Creates a new session for the given date.
+    pub fn new(date: NaiveDate) -> Self {
+        Self {
+            pomodoros_completed_today: 0,
+            total_focus_mins_today: 0,
+            pomodoros_in_cycle: 0,
+            interruptions_today: 0,
+            marked_interruptions_today: 0,
+            abandoned_today: 0,
+            skipped_breaks_today: 0,
+            total_interrupted_mins: 0,
+            last_date: date,
+        }
+    }
+
+    /// Moves the session on to `today`, resetting the daily counts, if that
+    /// is a later day. It never moves back, so starting days later doesn't
+    /// reset the counts of a day that has already begun.
+    pub fn check_day_rollover(&mut self, today: NaiveDate) {
+        if today > self.last_date {
+            self.reset_counts();
+            self.last_date = today;
+        }
+    }
+
+    /// Records completion of a pomodoro.
+    pub fn complete_pomodoro(&mut self, duration_mins: u32) {
+        self.pomodoros_completed_today += 1;
+        self.total_focus_mins_today += duration_mins;
+        self.pomodoros_in_cycle += 1;
+    }
+
+    /// Records a pomodoro done away from the timer. It counts towards
+    /// today's totals but not towards the long break cycle.
+    pub fn log_manual_pomodoro(&mut self, duration_mins: u32) {
+        self.pomodoros_completed_today += 1;
+        self.total_focus_mins_today += duration_mins;
+    }
+
+    /// Records a finished stopwatch run. Runs of at least `min_mins` count
+    /// as a pomodoro; shorter ones only add their minutes to the focus time.
+    /// Returns true if the run counted as a pomodoro.
+    pub fn complete_stopwatch(&mut self, elapsed_secs: u32, min_mins: u32) -> bool {
+        let mins = elapsed_secs / 60;
+        if elapsed_secs >= min_mins.saturating_mul(60) {
+            self.complete_pomodoro(mins);
+            true
+        } else {
+            self.total_focus_mins_today += mins;
+            false
+        }
+    }
+
+    /// Records a pomodoro stopped after `elapsed_mins`.
+    pub fn abandon_pomodoro(&mut self, elapsed_mins: u32) {
+        self.abandoned_today += 1;
+        self.total_interrupted_mins += elapsed_mins;
+    }
+
+    /// Returns the share of today's pomodoro time that went into focus
+    /// rather than pomodoros that were stopped, from 0.0 to 1.0.
+    pub fn efficiency_score(&self) -> f32 {
+        let total = self.total_focus_mins_today + self.total_interrupted_mins;
+        if total == 0 {
+            return 1.0;
+        }
+        self.total_focus_mins_today as f32 / total as f32
+    }
+
+    /// Returns true if a long break is due based on the threshold.
+    pub fn is_long_break_due(&self, threshold: u32) -> bool {
+        self.pomodoros_in_cycle >= threshold
+    }
+
+    /// Returns true if today's focus time has reached the given daily limit.
+    pub fn daily_limit_reached(&self, max_mins: Option<u32>) -> bool {
+        max_mins.is_some_and(|max| self.total_focus_mins_today >= max)
+    }
+
+    /// Resets the current cycle counter (called after a long break).
+    pub fn reset_cycle(&mut self) {
+        self.pomodoros_in_cycle = 0;
+    }
+
+    /// Resets today's counts, keeping the cycle.
+    pub fn reset_counts(&mut self) {
+        self.pomodoros_completed_today = 0;
+        self.total_focus_mins_today = 0;
+        self.interruptions_today = 0;
+        self.marked_interruptions_today = 0;
+        self.abandoned_today = 0;
+        self.skipped_breaks_today = 0;
+        self.total_interrupted_mins = 0;
+    }
+
+    /// Resets all counts for today.
+    pub fn reset_today(&mut self) {
+        self.reset_counts();
+        self.reset_cycle();
+    }
+}
+
+/// Daily statistics for persistence.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DailyStats {
+    pub date: NaiveDate,
+    pub completed_pomodoros: u32,
+    pub total_focus_minutes: u32,
+    pub interruptions: u32,
+    pub marked_interruptions: u32,
+    pub abandoned: u32,
+    pub skipped_breaks: u32,
+    pub interrupted_minutes: u32,
+}
+
+impl DailyStats {
+    /// Returns the stats of a day without any activity.
+    pub fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            completed_pomodoros: 0,
+            total_focus_minutes: 0,
+            interruptions: 0,
+            marked_interruptions: 0,
+            abandoned: 0,
+            skipped_breaks: 0,
+            interrupted_minutes: 0,
+        }
+    }
+}
+
+/// The focus score of a finished day, as computed when the day ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayScore {
+    pub date: NaiveDate,
+    /// None for a day without activity.
+    pub score: Option<u32>,
+    /// Version of the formula the score was computed with.
+    pub formula_version: u32,
+}
+
+/// The pomodoros of one day logged under one task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskStats {
+    /// None for pomodoros completed without a task.
+    pub task: Option<String>,
+    pub pomodoros: u32,
+    pub focus_minutes: u32,
+}
+
+/// What the Task submenus show.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskHistory {
+    /// The most recently used distinct labels, newest first.
+    pub recent: Vec<String>,
+    /// Today's pomodoros by task, most pomodoros first.
+    pub today: Vec<TaskStats>,
+}
+
+/// Pomodoro totals for one week.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeekTotals {
+    /// First day of the week.
+    pub start: NaiveDate,
+    pub completed_pomodoros: u32,
+    pub total_focus_minutes: u32,
+}
+
+impl WeekTotals {
+    /// Returns the totals of a week without any pomodoros.
+    pub fn new(start: NaiveDate) -> Self {
+        Self {
+            start,
+            completed_pomodoros: 0,
+            total_focus_minutes: 0,
+        }
+    }
+}
+
+/// Current timer status written to the status file for external bars.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatusSnapshot {
+    pub state: String,
+    pub remaining_secs: u32,
+    pub total_secs: u32,
+    pub pomodoros_today: u32,
+    pub is_break: bool,
+    #[serde(default)]
+    pub focus_score: Option<u32>,
+}
+
+impl StatusSnapshot {
+    /// Captures the status of a timer and session.
+    pub fn new(state: &TimerState, session: &Session, focus_score: Option<u32>) -> Self {
+        Self {
+            state: state.name().to_string(),
+            remaining_secs: state.remaining_secs().unwrap_or(0),
+            total_secs: state.total_secs().unwrap_or(0),
+            pomodoros_today: session.pomodoros_completed_today,
+            is_break: state.is_break(),
+            focus_score,
+        }
+    }
+}
+
+/// End-of-day summary posted to the daily webhook.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DailySummary {
+    pub date: NaiveDate,
+    pub completed_pomodoros: u32,
+    pub total_focus_minutes: u32,
+    /// Consecutive days with at least one pomodoro, ending on `date`.
+    pub streak_days: u32,
+    /// The day's finalized focus score (None for a day without activity).
+    #[serde(default)]
+    pub focus_score: Option<u32>,
+    /// Whether the day reached the daily goal (None in summaries queued
+    /// before the goal was part of them).
+    #[serde(default)]
+    pub goal: Option<GoalStatus>,
+}
+
+/// Whether a day reached the daily goal of pomodoros.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum GoalStatus {
+    Met,
+    /// Short of the goal by this many pomodoros.
+    Missed {
+        by: u32,
+    },
+}
+
+impl GoalStatus {
+    /// Returns the status of a day with `completed` pomodoros against a
+    /// goal of `goal`.
+    pub fn new(completed: u32, goal: u32) -> Self {
+        if completed >= goal {
+            GoalStatus::Met
+        } else {
+            GoalStatus::Missed {
+                by: goal - completed,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_is_active_for_every_state() {
+        let cases = [
+            (TimerState::Idle, false),
+            (
+                TimerState::PomodoroActive {
+                    remaining_secs: 60,
+                    total_secs: 1500,
+                },
+                true,
+            ),
+            (
+                TimerState::PomodoroPaused {
+                    remaining_secs: 60,
+                    total_secs: 1500,
+                },
+                false,
+            ),
+            (
+                TimerState::BreakActive {
+                    is_long_break: true,
+                    remaining_secs: 60,
+                    total_secs: 900,
+                },
+                true,
+            ),
+            (
+                TimerState::BreakPaused {
+                    is_long_break: false,
+                    remaining_secs: 60,
+                    total_secs: 300,
+                },
+                false,
+            ),
+            (
+                TimerState::Overtime {
+                    elapsed_overtime_secs: 5,
+                    total_secs: 1500,
+                },
+                false,
+            ),
+            (TimerState::BreakFinished, false),
+            (TimerState::StopwatchActive { elapsed_secs: 5 }, false),
+            (TimerState::StopwatchPaused { elapsed_secs: 5 }, false),
+        ];
+        for (state, active) in cases {
+            assert_eq!(state.is_active(), active, "{}", state.name());
+        }
+    }
+
+    #[test]
+    fn test_timer_state_idle() {
+        let state = TimerState::Idle;
+        assert!(state.is_idle());
+        assert!(!state.is_active());
+        assert!(!state.is_paused());
+        assert!(!state.is_pomodoro());
+        assert!(!state.is_break());
+        assert_eq!(state.progress_percent(), None);
+        assert_eq!(state.remaining_secs(), None);
+    }
+
+    #[test]
+    fn test_timer_state_pomodoro_active() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 1200,
+            total_secs: 1500,
+        };
+        assert!(!state.is_idle());
+        assert!(state.is_active());
+        assert!(!state.is_paused());
+        assert!(state.is_pomodoro());
+        assert!(!state.is_break());
+        assert_eq!(state.remaining_secs(), Some(1200));
+        assert_eq!(state.total_secs(), Some(1500));
+
+        // Progress should be 20% (300 seconds elapsed out of 1500)
+        let progress = state.progress_percent().unwrap();
+        assert!((progress - 0.2).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_timer_state_pomodoro_paused() {
+        let state = TimerState::PomodoroPaused {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        assert!(!state.is_idle());
+        assert!(!state.is_active());
+        assert!(state.is_paused());
+        assert!(state.is_pomodoro());
+        assert!(!state.is_break());
+        assert_eq!(state.remaining_secs(), Some(600));
+
+        // Progress should be 60%
+        let progress = state.progress_percent().unwrap();
+        assert!((progress - 0.6).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_timer_state_break_active() {
+        let state = TimerState::BreakActive {
+            is_long_break: false,
+            remaining_secs: 150,
+            total_secs: 300,
+        };
+        assert!(!state.is_idle());
+        assert!(state.is_active());
+        assert!(!state.is_paused());
+        assert!(!state.is_pomodoro());
+        assert!(state.is_break());
+        assert_eq!(state.remaining_secs(), Some(150));
+
+        // Progress should be 50%
+        let progress = state.progress_percent().unwrap();
+        assert!((progress - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_timer_state_break_paused() {
+        let state = TimerState::BreakPaused {
+            is_long_break: true,
+            remaining_secs: 600,
+            total_secs: 900,
+        };
+        assert_eq!(state.name(), "BreakPaused");
+        assert!(!state.is_idle());
+        assert!(!state.is_active());
+        assert!(state.is_paused());
+        assert!(!state.is_pomodoro());
+        assert!(state.is_break());
+        assert_eq!(state.remaining_secs(), Some(600));
+        assert_eq!(state.total_secs(), Some(900));
+
+        let progress = state.progress_percent().unwrap();
+        assert!((progress - 1.0 / 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_timer_state_break_finished() {
+        let state = TimerState::BreakFinished;
+        assert!(state.is_idle());
+        assert!(!state.is_active());
+        assert!(!state.is_paused());
+        assert!(!state.is_pomodoro());
+        assert!(!state.is_break());
+    }
+
+    #[test]
+    fn test_timer_state_progress_at_zero() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 0,
+            total_secs: 1500,
+        };
+        let progress = state.progress_percent().unwrap();
+        assert!((progress - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_timer_state_progress_division_by_zero() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 0,
+            total_secs: 0,
+        };
+        let progress = state.progress_percent().unwrap();
+        assert_eq!(progress, 1.0);
+    }
+
+    #[test]
+    fn test_settings_default() {
+        let settings = Settings::default();
+        assert_eq!(settings.pomodoro_mins, 25);
+        assert_eq!(settings.short_break_mins, 5);
+        assert_eq!(settings.long_break_mins, 15);
+        assert_eq!(settings.pomodoros_for_long_break, 4);
+        assert!(settings.sound_on_pomodoro_end);
+        assert!(settings.sound_on_break_end);
+        assert!(settings.notifications_enabled);
+        assert_eq!(settings.volume, 0.7);
+        assert_eq!(settings.max_daily_focus_mins, None);
+        assert_eq!(settings.warn_before_end_secs, 60);
+        assert!(!settings.warn_before_break_end);
+        assert!(!settings.ticking_enabled);
+        assert!(settings.clock_24h);
+        assert!(settings.show_time_in_menubar);
+        assert!(settings.attention_blink);
+        assert_eq!(settings.tray_display, TrayDisplay::Text);
+        assert_eq!(settings.tray_format, "{icon} {mm}:{ss}");
+        assert_eq!(settings.hotkey_start_pause, "Ctrl+Option+P");
+        assert_eq!(settings.hotkey_stop, "Ctrl+Option+S");
+    }
+
+    #[test]
+    fn test_settings_validate_boundaries() {
+        let validate = |change: fn(&mut Settings)| {
+            let mut settings = Settings::default();
+            change(&mut settings);
+            settings.validate().map_err(|e| e.name)
+        };
+
+        assert_eq!(Settings::default().validate(), Ok(()));
+        assert_eq!(validate(|s| s.pomodoro_mins = 1), Ok(()));
+        assert_eq!(validate(|s| s.pomodoro_mins = 180), Ok(()));
+        assert_eq!(validate(|s| s.short_break_mins = 1), Ok(()));
+        assert_eq!(validate(|s| s.long_break_mins = 180), Ok(()));
+        assert_eq!(validate(|s| s.pomodoros_for_long_break = 1), Ok(()));
+        assert_eq!(validate(|s| s.pomodoros_for_long_break = 16), Ok(()));
+        assert_eq!(validate(|s| s.pomodoros_for_long_break = 20), Ok(()));
+
+        assert_eq!(validate(|s| s.pomodoro_mins = 0), Err("Pomodoro"));
+        assert_eq!(validate(|s| s.pomodoro_mins = 181), Err("Pomodoro"));
+        assert_eq!(validate(|s| s.short_break_mins = 0), Err("Short break"));
+        assert_eq!(validate(|s| s.long_break_mins = 181), Err("Long break"));
+        assert_eq!(
+            validate(|s| s.pomodoros_for_long_break = 0),
+            Err("Long break after")
+        );
+        assert_eq!(
+            validate(|s| s.pomodoros_for_long_break = 21),
+            Err("Long break after")
+        );
+    }
+
+    #[test]
+    fn test_settings_clamp_to_range() {
+        let mut settings = Settings {
+            pomodoro_mins: 0,
+            long_break_mins: 500,
+            pomodoros_for_long_break: 99,
+            day_starts_at_hour: 12,
+            progress_bar_width: 2,
+            ..Settings::default()
+        };
+        settings.clamp_to_range();
+        assert_eq!(settings.validate(), Ok(()));
+        assert_eq!(settings.pomodoro_mins, 1);
+        assert_eq!(settings.long_break_mins, 180);
+        assert_eq!(settings.pomodoros_for_long_break, 20);
+        assert_eq!(settings.day_starts_at_hour, 6);
+        assert_eq!(settings.progress_bar_width, 5);
+
+        // Values in range are kept
+        let mut settings = Settings::default();
+        settings.clamp_to_range();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_settings_hotkeys_round_trip() {
+        let settings = Settings {
+            hotkey_start_pause: "Cmd+Shift+P".to_string(),
+            hotkey_stop: "Cmd+Shift+S".to_string(),
+            ..Settings::default()
+        };
+
+        let json = serde_json::to_string(&settings).unwrap();
+        let loaded: Settings = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.hotkey_start_pause, "Cmd+Shift+P");
+        assert_eq!(loaded.hotkey_stop, "Cmd+Shift+S");
+    }
+
+    #[test]
+    fn test_settings_hotkeys_default_when_missing() {
+        // Settings saved before hotkeys existed
+        let json = r#"{
+            "pomodoro_mins": 25,
+            "short_break_mins": 5,
+            "long_break_mins": 15,
+            "pomodoros_for_long_break": 4,
+            "sound_enabled": true,
+            "notifications_enabled": true
+        }"#;
+
+        let loaded: Settings = serde_json::from_str(json).unwrap();
+        assert!(loaded.hotkeys_enabled);
+        assert_eq!(loaded.hotkey_start_pause, "Ctrl+Option+P");
+        assert_eq!(loaded.hotkey_stop, "Ctrl+Option+S");
+        assert_eq!(loaded.pomodoro_sound, SoundChoice::Default);
+        assert_eq!(loaded.break_sound, SoundChoice::Default);
+        assert_eq!(loaded.week_starts_on, WeekStart::Monday);
+        assert_eq!(loaded.progress_bar_width, 20);
+        assert_eq!(loaded.progress_bar_style, ProgressBarStyle::BlockFill);
+        assert_eq!(loaded.tray_display, TrayDisplay::Text);
+        assert!(loaded.respect_dnd);
+        assert_eq!(loaded.metrics_textfile_path, None);
+    }
+
+    #[test]
+    fn test_settings_end_of_day_from_json() {
+        let json = r#"{
+            "pomodoro_mins": 25,
+            "short_break_mins": 5,
+            "long_break_mins": 15,
+            "pomodoros_for_long_break": 4,
+            "sound_enabled": true,
+            "notifications_enabled": true,
+            "end_of_day": "18:00",
+            "wind_down_policy": "Block"
+        }"#;
+
+        let loaded: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(loaded.end_of_day, NaiveTime::from_hms_opt(18, 0, 0));
+        assert_eq!(loaded.wind_down_policy, WindDownPolicy::Block);
+        assert_eq!(Settings::default().end_of_day, None);
+    }
+
+    #[test]
+    fn test_settings_sounds_round_trip() {
+        let settings = Settings {
+            pomodoro_sound: SoundChoice::Bell,
+            break_sound: SoundChoice::Custom("/tmp/gong.wav".to_string()),
+            ..Settings::default()
+        };
+
+        let json = serde_json::to_string(&settings).unwrap();
+        let loaded: Settings = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.pomodoro_sound, SoundChoice::Bell);
+        assert_eq!(
+            loaded.break_sound,
+            SoundChoice::Custom("/tmp/gong.wav".to_string())
+        );
+    }
+
+    #[test]
+    fn test_settings_sound_enabled_migration() {
+        let load = |json: &str| {
+            let s = Settings::from_json(json).unwrap();
+            (s.sound_on_pomodoro_end, s.sound_on_break_end)
+        };
+        assert_eq!(load(r#"{"sound_enabled": false}"#), (false, false));
+        assert_eq!(load(r#"{"sound_enabled": true}"#), (true, true));
+        assert_eq!(load("{}"), (true, true));
+        // The new flags win over the old one
+        assert_eq!(
+            load(r#"{"sound_enabled": false, "sound_on_break_end": true}"#),
+            (false, true)
+        );
+    }
+
+    #[test]
+    fn test_settings_versioned_round_trip() {
+        let settings = Settings {
+            pomodoro_mins: 50,
+            sound_on_break_end: false,
+            ..Settings::default()
+        };
+        let json = settings.to_versioned_json(false).unwrap();
+        let versioned: VersionedSettings = serde_json::from_str(&json).unwrap();
+        assert_eq!(versioned.version, SETTINGS_VERSION);
+        assert_eq!(Settings::from_json(&json).unwrap(), settings);
+        assert_eq!(
+            Settings::from_json(&settings.to_versioned_json(true).unwrap()).unwrap(),
+            settings
+        );
+    }
+
+    #[test]
+    fn test_migrate_settings_only_applies_older_steps() {
+        let raw = || serde_json::json!({"sound_enabled": false, "pomodoro_mins": 30});
+        let old = migrate_settings(0, raw()).unwrap();
+        assert_eq!(old.pomodoro_mins, 30);
+        assert!(!old.sound_on_pomodoro_end);
+
+        // A current-version file has no `sound_enabled` to carry over
+        let current = migrate_settings(SETTINGS_VERSION, raw()).unwrap();
+        assert!(current.sound_on_pomodoro_end);
+        assert!(current.sound_on_break_end);
+    }
+
+    #[test]
+    fn test_only_new_settings_are_first_run() {
+        assert!(Settings::default().first_run);
+        assert!(
+            !Settings::from_json(r#"{"pomodoro_mins": 30}"#)
+                .unwrap()
+                .first_run
+        );
+        assert!(
+            !migrate_settings(1, serde_json::json!({}))
+                .unwrap()
+                .first_run
+        );
+    }
+
+    #[test]
+    fn test_settings_from_json_rejects_malformed() {
+        assert!(Settings::from_json("{\"pomodoro_mins\": ").is_err());
+        assert!(Settings::from_json(r#"{"pomodoro_mins": "long"}"#).is_err());
+        assert!(
+            Settings::from_json(r#"{"version": 1, "data": {"pomodoro_mins": "long"}}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_session_default() {
+        let session = Session::default();
+        assert_eq!(session.pomodoros_completed_today, 0);
+        assert_eq!(session.total_focus_mins_today, 0);
+        assert_eq!(session.pomodoros_in_cycle, 0);
+        assert_eq!(session.last_date, Local::now().date_naive());
+    }
+
+    fn at(day: u32, hour: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 1, day, hour, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_logical_date_from_midnight() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        assert_eq!(logical_date(at(15, 23, 59), 0), day(15));
+        assert_eq!(logical_date(at(16, 0, 1), 0), day(16));
+        assert_eq!(logical_date(at(16, 3, 59), 0), day(16));
+        assert_eq!(logical_date(at(16, 0, 1), 0), at(16, 0, 1).date_naive());
+    }
+
+    #[test]
+    fn test_logical_date_from_four() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        assert_eq!(logical_date(at(15, 23, 59), 4), day(15));
+        assert_eq!(logical_date(at(16, 0, 1), 4), day(15));
+        assert_eq!(logical_date(at(16, 3, 59), 4), day(15));
+        assert_eq!(logical_date(at(16, 4, 0), 4), day(16));
+    }
+
+    #[test]
+    fn test_day_rollover_at_day_start() {
+        let mut session = Session::new(logical_date(at(15, 23, 59), 4));
+        session.complete_pomodoro(25);
+
+        // Past midnight, the evening goes on
+        session.check_day_rollover(logical_date(at(16, 3, 59), 4));
+        assert_eq!(session.pomodoros_completed_today, 1);
+
+        session.check_day_rollover(logical_date(at(16, 4, 0), 4));
+        assert_eq!(session.pomodoros_completed_today, 0);
+        assert_eq!(session.last_date, at(16, 4, 0).date_naive());
+    }
+
+    #[test]
+    fn test_day_rollover_never_moves_back() {
+        let mut session = Session::new(at(16, 0, 1).date_naive());
+        session.complete_pomodoro(25);
+        // Switching from midnight to 4 at 00:01 makes "today" the 15th
+        session.check_day_rollover(logical_date(at(16, 0, 1), 4));
+        assert_eq!(session.last_date, at(16, 0, 1).date_naive());
+        assert_eq!(session.pomodoros_completed_today, 1);
+    }
+
+    #[test]
+    fn test_session_complete_pomodoro() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let mut session = Session::new(date);
+
+        session.complete_pomodoro(25);
+        assert_eq!(session.pomodoros_completed_today, 1);
+        assert_eq!(session.total_focus_mins_today, 25);
+        assert_eq!(session.pomodoros_in_cycle, 1);
+
+        session.complete_pomodoro(25);
+        assert_eq!(session.pomodoros_completed_today, 2);
+        assert_eq!(session.total_focus_mins_today, 50);
+        assert_eq!(session.pomodoros_in_cycle, 2);
+    }
+
+    #[test]
+    fn test_session_long_break_due() {
+        let mut session = Session::default();
+        assert!(!session.is_long_break_due(4));
+
+        session.pomodoros_in_cycle = 3;
+        assert!(!session.is_long_break_due(4));
+
+        session.pomodoros_in_cycle = 4;
+        assert!(session.is_long_break_due(4));
+
+        session.pomodoros_in_cycle = 5;
+        assert!(session.is_long_break_due(4));
+    }
+
+    #[test]
+    fn test_session_daily_limit_reached() {
+        let mut session = Session::default();
+        assert!(!session.daily_limit_reached(None));
+
+        session.total_focus_mins_today = 359;
+        assert!(!session.daily_limit_reached(Some(360)));
+
+        session.total_focus_mins_today = 360;
+        assert!(session.daily_limit_reached(Some(360)));
+        assert!(!session.daily_limit_reached(None));
+    }
+
+    #[test]
+    fn test_session_reset_cycle() {
+        let mut session = Session {
+            pomodoros_in_cycle: 4,
+            ..Session::default()
+        };
+        session.reset_cycle();
+        assert_eq!(session.pomodoros_in_cycle, 0);
+    }
+
+    #[test]
+    fn test_session_reset_counts_keeps_cycle() {
+        let mut session = Session {
+            pomodoros_completed_today: 5,
+            total_focus_mins_today: 125,
+            pomodoros_in_cycle: 3,
+            interruptions_today: 2,
+            abandoned_today: 1,
+            skipped_breaks_today: 1,
+            total_interrupted_mins: 20,
+            ..Session::default()
+        };
+
+        session.reset_counts();
+
+        assert_eq!(session.pomodoros_completed_today, 0);
+        assert_eq!(session.total_focus_mins_today, 0);
+        assert_eq!(session.interruptions_today, 0);
+        assert_eq!(session.abandoned_today, 0);
+        assert_eq!(session.skipped_breaks_today, 0);
+        assert_eq!(session.total_interrupted_mins, 0);
+        assert_eq!(session.pomodoros_in_cycle, 3);
+    }
+
+    #[test]
+    fn test_session_efficiency_score() {
+        let mut session = Session::default();
+        assert_eq!(session.efficiency_score(), 1.0);
+
+        session.complete_pomodoro(25);
+        assert_eq!(session.efficiency_score(), 1.0);
+
+        session.abandon_pomodoro(25);
+        assert_eq!(session.efficiency_score(), 0.5);
+        assert_eq!(session.abandoned_today, 1);
+
+        // Only stopped pomodoros so far
+        let mut session = Session::default();
+        session.abandon_pomodoro(10);
+        assert_eq!(session.efficiency_score(), 0.0);
+    }
+
+    #[test]
+    fn test_stopwatch_counts_from_minimum() {
+        let mut session = Session::default();
+
+        // 9:59 is below a 10 minute minimum: minutes only
+        assert!(!session.complete_stopwatch(9 * 60 + 59, 10));
+        assert_eq!(session.pomodoros_completed_today, 0);
+        assert_eq!(session.total_focus_mins_today, 9);
+
+        // Exactly 10 minutes counts, with the real minutes
+        assert!(session.complete_stopwatch(10 * 60, 10));
+        assert_eq!(session.pomodoros_completed_today, 1);
+        assert_eq!(session.total_focus_mins_today, 19);
+
+        // A long run still counts as a single pomodoro
+        assert!(session.complete_stopwatch(95 * 60 + 30, 10));
+        assert_eq!(session.pomodoros_completed_today, 2);
+        assert_eq!(session.total_focus_mins_today, 114);
+        assert_eq!(session.pomodoros_in_cycle, 2);
+    }
+
+    #[test]
+    fn test_stopwatch_minimum_edge_cases() {
+        let mut session = Session::default();
+        assert!(session.complete_stopwatch(30, 0));
+        assert_eq!(session.total_focus_mins_today, 0);
+        // A huge minimum doesn't overflow
+        assert!(!session.complete_stopwatch(24 * 3600, u32::MAX));
+    }
+
+    #[test]
+    fn test_stopwatch_state_helpers() {
+        let running = TimerState::StopwatchActive { elapsed_secs: 42 };
+        let paused = TimerState::StopwatchPaused { elapsed_secs: 42 };
+        assert!(running.is_stopwatch() && paused.is_stopwatch());
+        assert!(!running.is_pomodoro() && !running.is_idle());
+        assert!(paused.is_paused() && !running.is_paused());
+        assert_eq!(running.stopwatch_secs(), Some(42));
+        assert_eq!(TimerState::Idle.stopwatch_secs(), None);
+        assert_eq!(running.remaining_secs(), None);
+        assert_eq!(running.progress_percent(), None);
+        assert!(!running.is_locked(true));
+    }
+
+    #[test]
+    fn test_log_manual_pomodoro_skips_cycle() {
+        let mut session = Session::default();
+        session.complete_pomodoro(25);
+        session.log_manual_pomodoro(40);
+
+        assert_eq!(session.pomodoros_completed_today, 2);
+        assert_eq!(session.total_focus_mins_today, 65);
+        assert_eq!(session.pomodoros_in_cycle, 1);
+    }
+
+    #[test]
+    fn test_volume_clamped_on_load() {
+        let load = |json: &str| serde_json::from_str::<Settings>(json).unwrap().volume;
+        assert_eq!(load(r#"{"volume": 3.5}"#), 1.0);
+        assert_eq!(load(r#"{"volume": -0.2}"#), 0.0);
+        assert_eq!(load(r#"{"volume": 0.25}"#), 0.25);
+        assert_eq!(load("{}"), 0.7);
+    }
+
+    #[test]
+    fn test_sound_choice_keys() {
+        for choice in SoundChoice::MENU_CHOICES {
+            assert_eq!(SoundChoice::from_key(choice.key()), Some(choice));
+        }
+        assert_eq!(SoundChoice::from_key("soft"), Some(SoundChoice::Soft));
+        assert_eq!(SoundChoice::from_key("custom"), None);
+        assert_eq!(SoundChoice::from_key("kazoo"), None);
+    }
+
+    #[test]
+    fn test_parse_custom_duration() {
+        assert_eq!(DurationSetting::parse_custom("1"), Some(1));
+        assert_eq!(DurationSetting::parse_custom(" 50\n"), Some(50));
+        assert_eq!(DurationSetting::parse_custom("180"), Some(180));
+        assert_eq!(DurationSetting::parse_custom("0"), None);
+        assert_eq!(DurationSetting::parse_custom("181"), None);
+        assert_eq!(DurationSetting::parse_custom("-5"), None);
+        assert_eq!(DurationSetting::parse_custom("12.5"), None);
+        assert_eq!(DurationSetting::parse_custom("ten"), None);
+        assert_eq!(DurationSetting::parse_custom(""), None);
+    }
+
+    #[test]
+    fn test_parse_long_break_after() {
+        assert_eq!(parse_long_break_after("1"), Some(1));
+        assert_eq!(parse_long_break_after(" 8\n"), Some(8));
+        assert_eq!(parse_long_break_after("16"), Some(16));
+        assert_eq!(parse_long_break_after("20"), Some(20));
+        assert_eq!(parse_long_break_after("0"), None);
+        assert_eq!(parse_long_break_after("21"), None);
+        assert_eq!(parse_long_break_after("four"), None);
+    }
+
+    #[test]
+    fn test_duration_setting_get_and_set() {
+        let mut settings = Settings::default();
+        DurationSetting::ShortBreak.set(&mut settings, 7);
+        assert_eq!(settings.short_break_mins, 7);
+        assert_eq!(DurationSetting::ShortBreak.get(&settings), 7);
+        assert_eq!(DurationSetting::Pomodoro.get(&settings), 25);
+        assert_eq!(DurationSetting::LongBreak.get(&settings), 15);
+    }
+
+    #[test]
+    fn test_session_reset_today() {
+        let mut session = Session {
+            pomodoros_completed_today: 5,
+            total_focus_mins_today: 125,
+            pomodoros_in_cycle: 3,
+            ..Session::default()
+        };
+
+        session.reset_today();
+
+        assert_eq!(session.pomodoros_completed_today, 0);
+        assert_eq!(session.total_focus_mins_today, 0);
+        assert_eq!(session.pomodoros_in_cycle, 0);
+    }
+
+    #[test]
+    fn test_goal_status() {
+        assert_eq!(GoalStatus::new(8, 8), GoalStatus::Met);
+        assert_eq!(GoalStatus::new(9, 8), GoalStatus::Met);
+        assert_eq!(GoalStatus::new(5, 8), GoalStatus::Missed { by: 3 });
+        assert_eq!(
+            serde_json::to_string(&GoalStatus::Missed { by: 3 }).unwrap(),
+            r#"{"status":"missed","by":3}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&GoalStatus::Met).unwrap(),
+            r#"{"status":"met"}"#
+        );
+    }
+
+    #[test]
+    fn test_progress_mode_cycles() {
+        let mut mode = ProgressMode::default();
+        assert_eq!(mode, ProgressMode::Bar);
+
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            mode = mode.next();
+            seen.push(mode);
+        }
+        assert_eq!(
+            seen,
+            vec![
+                ProgressMode::Percent,
+                ProgressMode::ElapsedRemaining,
+                ProgressMode::EndTime,
+                ProgressMode::Bar,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_status_snapshot_from_state() {
+        let state = TimerState::BreakActive {
+            is_long_break: false,
+            remaining_secs: 120,
+            total_secs: 300,
+        };
+        let session = Session {
+            pomodoros_completed_today: 3,
+            ..Session::default()
+        };
+
+        let snapshot = StatusSnapshot::new(&state, &session, Some(65));
+        assert_eq!(snapshot.state, "BreakActive");
+        assert_eq!(snapshot.remaining_secs, 120);
+        assert_eq!(snapshot.total_secs, 300);
+        assert_eq!(snapshot.pomodoros_today, 3);
+        assert!(snapshot.is_break);
+        assert_eq!(snapshot.focus_score, Some(65));
+
+        let idle = StatusSnapshot::new(&TimerState::Idle, &session, None);
+        assert_eq!(idle.remaining_secs, 0);
+        assert!(!idle.is_break);
+    }
+
+    #[test]
+    fn test_status_snapshot_round_trip() {
+        let snapshot = StatusSnapshot {
+            state: "PomodoroActive".to_string(),
+            remaining_secs: 1432,
+            total_secs: 1500,
+            pomodoros_today: 2,
+            is_break: false,
+            focus_score: Some(82),
+        };
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            json,
+            r#"{"state":"PomodoroActive","remaining_secs":1432,"total_secs":1500,"pomodoros_today":2,"is_break":false,"focus_score":82}"#
+        );
+        let loaded: StatusSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, snapshot);
+    }
+
+    #[test]
+    fn test_daily_stats_new() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let stats = DailyStats::new(date);
+        assert_eq!(stats.date, date);
+        assert_eq!(stats.completed_pomodoros, 0);
+        assert_eq!(stats.total_focus_minutes, 0);
+    }
+
+    #[test]
+    fn test_week_start_depends_on_setting() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+
+        // Sunday 2024-01-14 ends a Monday week but starts a Sunday one
+        assert_eq!(WeekStart::Monday.week_start(day(14)), day(8));
+        assert_eq!(WeekStart::Sunday.week_start(day(14)), day(14));
+        assert_eq!(WeekStart::Saturday.week_start(day(14)), day(13));
+
+        // Saturday 2024-01-13 starts a Saturday week
+        assert_eq!(WeekStart::Monday.week_start(day(13)), day(8));
+        assert_eq!(WeekStart::Sunday.week_start(day(13)), day(7));
+        assert_eq!(WeekStart::Saturday.week_start(day(13)), day(13));
+
+        // The first day of a week maps to itself
+        assert_eq!(WeekStart::Monday.week_start(day(8)), day(8));
+    }
+
+    #[test]
+    fn test_week_start_across_year_boundary() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        assert_eq!(
+            WeekStart::Saturday.week_start(date),
+            NaiveDate::from_ymd_opt(2023, 12, 30).unwrap()
+        );
+    }
+
+    /// Builds a timed state of the given kind (0–3) with these seconds.
+    fn timed_state(
+        kind: u8,
+        remaining_secs: u32,
+        total_secs: u32,
+        is_long_break: bool,
+    ) -> TimerState {
+        match kind {
+            0 => TimerState::PomodoroActive {
+                remaining_secs,
+                total_secs,
+            },
+            1 => TimerState::PomodoroPaused {
+                remaining_secs,
+                total_secs,
+            },
+            2 => TimerState::BreakActive {
+                is_long_break,
+                remaining_secs,
+                total_secs,
+            },
+            _ => TimerState::BreakPaused {
+                is_long_break,
+                remaining_secs,
+                total_secs,
+            },
+        }
+    }
+
+    /// Seconds with `remaining <= total`.
+    fn remaining_and_total() -> impl Strategy<Value = (u32, u32)> {
+        (any::<u32>(), any::<u32>()).prop_map(|(a, b)| (a.min(b), a.max(b)))
+    }
+
+    fn any_timer_state() -> impl Strategy<Value = TimerState> {
+        prop_oneof![
+            Just(TimerState::Idle),
+            Just(TimerState::BreakFinished),
+            (0..4u8, remaining_and_total(), any::<bool>()).prop_map(
+                |(kind, (remaining, total), is_long_break)| {
+                    timed_state(kind, remaining, total, is_long_break)
+                }
+            ),
+            (any::<u32>(), any::<u32>()).prop_map(|(elapsed_overtime_secs, total_secs)| {
+                TimerState::Overtime {
+                    elapsed_overtime_secs,
+                    total_secs,
+                }
+            }),
+            any::<u32>().prop_map(|elapsed_secs| TimerState::StopwatchActive { elapsed_secs }),
+            any::<u32>().prop_map(|elapsed_secs| TimerState::StopwatchPaused { elapsed_secs }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_progress_in_unit_range(state in any_timer_state()) {
+            if let Some(progress) = state.progress_percent() {
+                prop_assert!((0.0..=1.0).contains(&progress), "{:?}: {}", state, progress);
+            }
+        }
+
+        #[test]
+        fn prop_state_groups_exclusive(state in any_timer_state()) {
+            // Idle, counting down and paused exclude each other
+            let groups = [state.is_idle(), state.is_active(), state.is_paused()];
+            prop_assert!(groups.iter().filter(|&&g| g).count() <= 1, "{:?}", state);
+
+            // A state is at most one of pomodoro, break or stopwatch
+            let kinds = [state.is_pomodoro(), state.is_break(), state.is_stopwatch()];
+            prop_assert!(kinds.iter().filter(|&&k| k).count() <= 1, "{:?}", state);
+
+            if state.is_idle() {
+                prop_assert!(!kinds.contains(&true), "{:?}", state);
+            }
+            if state.is_active() || state.is_paused() {
+                prop_assert!(kinds.contains(&true), "{:?}", state);
+            }
+        }
+
+        #[test]
+        fn prop_timed_states_round_trip_secs(
+            kind in 0..4u8,
+            (remaining, total) in remaining_and_total(),
+            is_long_break in any::<bool>(),
+        ) {
+            let state = timed_state(kind, remaining, total, is_long_break);
+            prop_assert_eq!(state.remaining_secs(), Some(remaining));
+            prop_assert_eq!(state.total_secs(), Some(total));
+        }
+    }
+}
@@ -0,0 +1,1779 @@
+//! SQLite persistence layer for settings and session data.
+
+#[cfg(test)]
+use crate::models::SETTINGS_VERSION;
+use crate::models::{
+    DailyStats, DailySummary, DayScore, SavedTimer, Session, Settings, TaskStats, WeekStart,
+    WeekTotals,
+};
+use chrono::{DateTime, Days, Duration, Local, NaiveDate};
+use directories::{ProjectDirs, UserDirs};
+use rusqlite::{params, Connection, DatabaseName, ErrorCode, OptionalExtension};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration as StdDuration;
+use thiserror::Error;
+
+/// Why reading or writing the database failed.
+#[derive(Error, Debug)]
+pub enum DatabaseError {
+    #[error("SQLite error: {0}")]
+    Sqlite(rusqlite::Error),
+    #[error("Database is locked by another program")]
+    Busy,
+    #[error("Upgrading the database to version {version} failed: {source}")]
+    Migration {
+        version: u32,
+        source: rusqlite::Error,
+    },
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Failed to create database directory")]
+    DirectoryCreation,
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl From<rusqlite::Error> for DatabaseError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => DatabaseError::Busy,
+            _ => DatabaseError::Sqlite(e),
+        }
+    }
+}
+
+/// How long a query waits for another connection (e.g. an external tool
+/// reading the database) to release its lock before failing with `Busy`.
+const BUSY_TIMEOUT: StdDuration = StdDuration::from_secs(5);
+
+/// Where the database ends up when no data directory is available.
+const FALLBACK_DB_PATH: &str = "pomobar.db";
+
+/// How old the database file must be, and how long ago the last vacuum,
+/// before it is vacuumed at launch.
+const VACUUM_INTERVAL_DAYS: i64 = 30;
+
+/// Schema version after all migrations have run.
+const CURRENT_VERSION: u32 = 7;
+
+/// Schema migrations in order; the one at index `i` upgrades version `i` to
+/// `i + 1`. Never change a released migration, add a new one instead.
+const MIGRATIONS: [&str; CURRENT_VERSION as usize] = [
+    // v1: the original tables. Databases from before versioning already
+    // have them, hence IF NOT EXISTS.
+    r#"
+    CREATE TABLE IF NOT EXISTS settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS daily_stats (
+        date TEXT PRIMARY KEY,
+        completed_pomodoros INTEGER NOT NULL DEFAULT 0,
+        total_focus_minutes INTEGER NOT NULL DEFAULT 0
+    );
+
+    CREATE TABLE IF NOT EXISTS webhook_queue (
+        date TEXT PRIMARY KEY,
+        payload TEXT NOT NULL
+    );
+    "#,
+    // v2: pomodoros in the current long break cycle
+    r#"
+    ALTER TABLE daily_stats ADD COLUMN cycle_pomodoros INTEGER NOT NULL DEFAULT 0;
+    "#,
+    // v3: focus score inputs and finalized scores
+    r#"
+    ALTER TABLE daily_stats ADD COLUMN interruptions INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE daily_stats ADD COLUMN abandoned_pomodoros INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE daily_stats ADD COLUMN skipped_breaks INTEGER NOT NULL DEFAULT 0;
+
+    CREATE TABLE daily_scores (
+        date TEXT PRIMARY KEY,
+        score INTEGER,
+        formula_version INTEGER NOT NULL
+    );
+    "#,
+    // v4: minutes spent on stopped pomodoros
+    r#"
+    ALTER TABLE daily_stats ADD COLUMN interrupted_minutes INTEGER NOT NULL DEFAULT 0;
+    "#,
+    // v5: one row per completed pomodoro, with its task label
+    r#"
+    CREATE TABLE pomodoros (
+        id INTEGER PRIMARY KEY,
+        date TEXT NOT NULL,
+        minutes INTEGER NOT NULL,
+        task TEXT
+    );
+
+    CREATE INDEX pomodoros_by_date ON pomodoros (date);
+    "#,
+    // v6: interruptions marked without pausing, per day and per pomodoro
+    r#"
+    ALTER TABLE daily_stats ADD COLUMN marked_interruptions INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE pomodoros ADD COLUMN interruptions INTEGER NOT NULL DEFAULT 0;
+    "#,
+    // v7: the pomodoro or break under way when Pomobar quit: its state as
+    // JSON and when it began
+    r#"
+    ALTER TABLE daily_stats ADD COLUMN timer_state TEXT;
+    ALTER TABLE daily_stats ADD COLUMN block_started_at TEXT;
+    "#,
+];
+
+/// The SQLite database in the data directory, or an in-memory one in tests.
+pub struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    /// Creates a new database connection, initializing tables if needed.
+    pub fn new() -> Result<Self, DatabaseError> {
+        let db_path = Self::db_path();
+
+        // Ensure directory exists
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|_| DatabaseError::DirectoryCreation)?;
+        }
+
+        Self::open(&db_path)
+    }
+
+    /// Opens the database at the given path, initializing tables if needed.
+    /// The database is put in WAL mode so other programs can read it while
+    /// the app runs.
+    pub fn open(path: &Path) -> Result<Self, DatabaseError> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let _mode: String =
+            conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+        // Safe in WAL mode: a crash can lose the last commits, but never corrupts
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        Self::run_migrations(&conn)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Makes every write fail (or work again), for testing failed saves.
+    #[cfg(test)]
+    pub fn set_read_only(&self, read_only: bool) {
+        self.conn
+            .pragma_update(None, "query_only", read_only)
+            .unwrap();
+    }
+
+    /// Creates an in-memory database (for testing and the self-test).
+    pub fn new_in_memory() -> Result<Self, DatabaseError> {
+        let conn = Connection::open_in_memory()?;
+        Self::run_migrations(&conn)?;
+        Ok(Self { conn })
+    }
+
+    /// Brings the schema up to `CURRENT_VERSION`, running each migration the
+    /// database hasn't seen yet in its own transaction. The version is kept
+    /// in `PRAGMA user_version`.
+    fn run_migrations(conn: &Connection) -> Result<(), DatabaseError> {
+        Self::apply_migrations(conn, &MIGRATIONS)
+    }
+
+    /// Runs `migrations[user_version..]`. A migration that fails is rolled
+    /// back entirely, leaving the database at the last version that worked.
+    fn apply_migrations(conn: &Connection, migrations: &[&str]) -> Result<(), DatabaseError> {
+        let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (i, migration) in migrations.iter().enumerate().skip(version as usize) {
+            let version = i as u32 + 1;
+            let failed = |source| DatabaseError::Migration { version, source };
+            // Rolls back when dropped without committing
+            let tx = conn.unchecked_transaction().map_err(failed)?;
+            tx.execute_batch(migration).map_err(failed)?;
+            tx.pragma_update(None, "user_version", version)
+                .map_err(failed)?;
+            tx.commit().map_err(failed)?;
+        }
+        Ok(())
+    }
+
+    fn db_path() -> PathBuf {
+        ProjectDirs::from("com", "pomobar", "Pomobar")
+            .map(|dirs| dirs.data_dir().join("pomobar.db"))
+            .unwrap_or_else(|| PathBuf::from(FALLBACK_DB_PATH))
+    }
+
+    /// Adds the daily stats and pomodoro log of another database file into
+    /// this one. Days present in both get their counts summed. `other` must
+    /// be at the current schema with its WAL checkpointed, as opening it with
+    /// `open` and calling `checkpoint` leaves it.
+    /// Returns the number of days merged. Nothing is merged if any of it fails.
+    pub fn merge_daily_stats_from(&self, other: &Path) -> Result<usize, DatabaseError> {
+        self.conn
+            .execute("ATTACH DATABASE ? AS other", [other.to_string_lossy()])?;
+        let merged = self.merge_attached();
+        self.conn.execute("DETACH DATABASE other", [])?;
+        merged
+    }
+
+    /// Merges the attached database `other` in a single transaction.
+    fn merge_attached(&self) -> Result<usize, DatabaseError> {
+        // Rolls back when dropped without committing
+        let tx = self.conn.unchecked_transaction()?;
+        // `WHERE true` keeps SQLite from parsing ON CONFLICT as a join constraint
+        let merged = tx.execute(
+            "INSERT INTO daily_stats (date, completed_pomodoros, total_focus_minutes,
+                 interruptions, marked_interruptions, abandoned_pomodoros, skipped_breaks,
+                 interrupted_minutes)
+             SELECT date, completed_pomodoros, total_focus_minutes,
+                 interruptions, marked_interruptions, abandoned_pomodoros, skipped_breaks,
+                 interrupted_minutes
+             FROM other.daily_stats WHERE true
+             ON CONFLICT(date) DO UPDATE SET
+                 completed_pomodoros = completed_pomodoros + excluded.completed_pomodoros,
+                 total_focus_minutes = total_focus_minutes + excluded.total_focus_minutes,
+                 interruptions = interruptions + excluded.interruptions,
+                 marked_interruptions = marked_interruptions + excluded.marked_interruptions,
+                 abandoned_pomodoros = abandoned_pomodoros + excluded.abandoned_pomodoros,
+                 skipped_breaks = skipped_breaks + excluded.skipped_breaks,
+                 interrupted_minutes = interrupted_minutes + excluded.interrupted_minutes",
+            [],
+        )?;
+        tx.execute(
+            "INSERT INTO pomodoros (date, minutes, task, interruptions)
+             SELECT date, minutes, task, interruptions FROM other.pomodoros ORDER BY id",
+            [],
+        )?;
+        tx.commit()?;
+        Ok(merged)
+    }
+
+    /// Loads settings from the database, returning defaults if not found.
+    /// Values saved out of range are pulled back into it.
+    pub fn load_settings(&self) -> Result<Settings, DatabaseError> {
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'config'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match json {
+            Some(j) => {
+                let mut settings = Settings::from_json(&j)?;
+                settings.clamp_to_range();
+                Ok(settings)
+            }
+            None => Ok(Settings::default()),
+        }
+    }
+
+    /// Saves settings to the database.
+    pub fn save_settings(&self, settings: &Settings) -> Result<(), DatabaseError> {
+        let json = settings.to_versioned_json(false)?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('config', ?)",
+            [&json],
+        )?;
+        Ok(())
+    }
+
+    /// Loads the session for `today` from the database.
+    pub fn load_today_session(&self, today: NaiveDate) -> Result<Session, DatabaseError> {
+        let stats = self.get_daily_stats(today)?;
+        let cycle: Option<u32> = self
+            .conn
+            .query_row(
+                "SELECT cycle_pomodoros FROM daily_stats WHERE date = ?",
+                [today.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(Session {
+            pomodoros_completed_today: stats.completed_pomodoros,
+            total_focus_mins_today: stats.total_focus_minutes,
+            pomodoros_in_cycle: cycle.unwrap_or_default(),
+            interruptions_today: stats.interruptions,
+            marked_interruptions_today: stats.marked_interruptions,
+            abandoned_today: stats.abandoned,
+            skipped_breaks_today: stats.skipped_breaks,
+            total_interrupted_mins: stats.interrupted_minutes,
+            last_date: today,
+        })
+    }
+
+    /// Saves the current session by adding what changed since `saved`, the
+    /// session as it was last saved. Adding instead of replacing keeps the
+    /// counts another writer added to the same day in the meantime. A
+    /// `saved` session from another day counts as nothing saved yet.
+    pub fn save_session(&self, session: &Session, saved: &Session) -> Result<(), DatabaseError> {
+        let empty = Session::new(session.last_date);
+        let saved = if saved.last_date == session.last_date {
+            saved
+        } else {
+            &empty
+        };
+        let delta = |now: u32, before: u32| i64::from(now) - i64::from(before);
+        self.conn.execute(
+            "INSERT INTO daily_stats (date, completed_pomodoros, total_focus_minutes,
+                 cycle_pomodoros, interruptions, abandoned_pomodoros, skipped_breaks,
+                 interrupted_minutes, marked_interruptions)
+             VALUES (?1, MAX(0, ?2), MAX(0, ?3), ?4, MAX(0, ?5), MAX(0, ?6), MAX(0, ?7),
+                 MAX(0, ?8), MAX(0, ?9))
+             ON CONFLICT(date) DO UPDATE SET
+                 completed_pomodoros = MAX(0, completed_pomodoros + ?2),
+                 total_focus_minutes = MAX(0, total_focus_minutes + ?3),
+                 cycle_pomodoros = ?4,
+                 interruptions = MAX(0, interruptions + ?5),
+                 abandoned_pomodoros = MAX(0, abandoned_pomodoros + ?6),
+                 skipped_breaks = MAX(0, skipped_breaks + ?7),
+                 interrupted_minutes = MAX(0, interrupted_minutes + ?8),
+                 marked_interruptions = MAX(0, marked_interruptions + ?9)",
+            params![
+                session.last_date.to_string(),
+                delta(
+                    session.pomodoros_completed_today,
+                    saved.pomodoros_completed_today
+                ),
+                delta(session.total_focus_mins_today, saved.total_focus_mins_today),
+                session.pomodoros_in_cycle,
+                delta(session.interruptions_today, saved.interruptions_today),
+                delta(session.abandoned_today, saved.abandoned_today),
+                delta(session.skipped_breaks_today, saved.skipped_breaks_today),
+                delta(session.total_interrupted_mins, saved.total_interrupted_mins),
+                delta(
+                    session.marked_interruptions_today,
+                    saved.marked_interruptions_today
+                ),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Saves the timer under way when Pomobar quits with the session of
+    /// `date`, or clears it with None.
+    pub fn save_timer(
+        &self,
+        date: NaiveDate,
+        timer: Option<&SavedTimer>,
+    ) -> Result<(), DatabaseError> {
+        let state = timer
+            .map(|timer| serde_json::to_string(&timer.state))
+            .transpose()?;
+        let started_at = timer
+            .and_then(|timer| timer.block_started_at)
+            .map(|at| at.to_rfc3339());
+        self.conn.execute(
+            "INSERT INTO daily_stats (date, timer_state, block_started_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(date) DO UPDATE SET timer_state = ?2, block_started_at = ?3",
+            params![date.to_string(), state, started_at],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the timer saved with the session of `date` and clears it, so
+    /// it is restored only once. A timer that doesn't parse is dropped.
+    pub fn take_timer(&self, date: NaiveDate) -> Result<Option<SavedTimer>, DatabaseError> {
+        let row: Option<(Option<String>, Option<String>)> = self
+            .conn
+            .query_row(
+                "SELECT timer_state, block_started_at FROM daily_stats WHERE date = ?",
+                [date.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((Some(state), started_at)) = row else {
+            return Ok(None);
+        };
+        self.save_timer(date, None)?;
+        let Ok(state) = serde_json::from_str(&state) else {
+            return Ok(None);
+        };
+        let block_started_at = started_at
+            .and_then(|at| DateTime::parse_from_rfc3339(&at).ok())
+            .map(|at| at.with_timezone(&Local));
+        Ok(Some(SavedTimer {
+            state,
+            block_started_at,
+        }))
+    }
+
+    /// Adds a completed pomodoro to the log, under `task` if it has one,
+    /// with the interruptions marked during it.
+    pub fn log_pomodoro(
+        &self,
+        date: NaiveDate,
+        minutes: u32,
+        task: Option<&str>,
+        interruptions: u32,
+    ) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT INTO pomodoros (date, minutes, task, interruptions) VALUES (?, ?, ?, ?)",
+            params![date.to_string(), minutes, task, interruptions],
+        )?;
+        Ok(())
+    }
+
+    /// Returns up to `limit` distinct task labels, most recently used first.
+    pub fn recent_tasks(&self, limit: u32) -> Result<Vec<String>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT task FROM pomodoros WHERE task IS NOT NULL
+             GROUP BY task ORDER BY MAX(id) DESC LIMIT ?",
+        )?;
+        let tasks = stmt.query_map([limit], |row| row.get(0))?;
+        Ok(tasks.collect::<Result<_, _>>()?)
+    }
+
+    /// Returns the pomodoros of `date` grouped by task, most pomodoros
+    /// first. Pomodoros without a task are grouped under None.
+    pub fn stats_by_task(&self, date: NaiveDate) -> Result<Vec<TaskStats>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT task, COUNT(*), SUM(minutes) FROM pomodoros WHERE date = ?
+             GROUP BY task ORDER BY COUNT(*) DESC, MAX(id) DESC",
+        )?;
+        let stats = stmt.query_map([date.to_string()], |row| {
+            Ok(TaskStats {
+                task: row.get(0)?,
+                pomodoros: row.get(1)?,
+                focus_minutes: row.get(2)?,
+            })
+        })?;
+        Ok(stats.collect::<Result<_, _>>()?)
+    }
+
+    /// Gets daily statistics for a specific date.
+    pub fn get_daily_stats(&self, date: NaiveDate) -> Result<DailyStats, DatabaseError> {
+        let result = self.conn.query_row(
+            "SELECT completed_pomodoros, total_focus_minutes, interruptions,
+                    abandoned_pomodoros, skipped_breaks, interrupted_minutes,
+                    marked_interruptions
+             FROM daily_stats WHERE date = ?",
+            [date.to_string()],
+            |row| {
+                Ok(DailyStats {
+                    date,
+                    completed_pomodoros: row.get(0)?,
+                    total_focus_minutes: row.get(1)?,
+                    interruptions: row.get(2)?,
+                    abandoned: row.get(3)?,
+                    skipped_breaks: row.get(4)?,
+                    interrupted_minutes: row.get(5)?,
+                    marked_interruptions: row.get(6)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(stats) => Ok(stats),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(DailyStats::new(date)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the daily statistics of every day with a row, oldest first.
+    pub fn get_all_stats(&self) -> Result<Vec<DailyStats>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date, completed_pomodoros, total_focus_minutes, interruptions,
+                    abandoned_pomodoros, skipped_breaks, interrupted_minutes,
+                    marked_interruptions
+             FROM daily_stats ORDER BY date",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            // Skip rows whose date doesn't parse rather than failing on them
+            let Ok(date) = row.get::<_, String>(0)?.parse() else {
+                return Ok(None);
+            };
+            Ok(Some(DailyStats {
+                date,
+                completed_pomodoros: row.get(1)?,
+                total_focus_minutes: row.get(2)?,
+                interruptions: row.get(3)?,
+                abandoned: row.get(4)?,
+                skipped_breaks: row.get(5)?,
+                interrupted_minutes: row.get(6)?,
+                marked_interruptions: row.get(7)?,
+            }))
+        })?;
+
+        let mut days = Vec::new();
+        for row in rows {
+            days.extend(row?);
+        }
+        Ok(days)
+    }
+
+    /// Gets the statistics of the day before `today`, with zero counts if
+    /// there is no row.
+    pub fn get_yesterday_stats(&self, today: NaiveDate) -> Result<DailyStats, DatabaseError> {
+        self.get_daily_stats(today - Days::new(1))
+    }
+
+    /// Returns the daily statistics of the last `n` days up to `today`,
+    /// oldest first. Days without a row are included with zero counts.
+    pub fn get_last_n_days(
+        &self,
+        today: NaiveDate,
+        n: u32,
+    ) -> Result<Vec<DailyStats>, DatabaseError> {
+        self.get_days_ending(today, n)
+    }
+
+    fn get_days_ending(&self, end: NaiveDate, n: u32) -> Result<Vec<DailyStats>, DatabaseError> {
+        let Some(start) = end.checked_sub_days(Days::new(n.saturating_sub(1) as u64)) else {
+            return Ok(Vec::new());
+        };
+        start
+            .iter_days()
+            .take(n as usize)
+            .map(|date| self.get_daily_stats(date))
+            .collect()
+    }
+
+    /// Sums the daily statistics of the week containing `date`.
+    pub fn get_week_totals(
+        &self,
+        date: NaiveDate,
+        week_starts_on: WeekStart,
+    ) -> Result<WeekTotals, DatabaseError> {
+        let start = week_starts_on.week_start(date);
+        let end = start + Days::new(6);
+        let (completed_pomodoros, total_focus_minutes) = self.conn.query_row(
+            "SELECT COALESCE(SUM(completed_pomodoros), 0), COALESCE(SUM(total_focus_minutes), 0)
+             FROM daily_stats WHERE date BETWEEN ? AND ?",
+            [start.to_string(), end.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        Ok(WeekTotals {
+            start,
+            completed_pomodoros,
+            total_focus_minutes,
+        })
+    }
+
+    /// Returns the number of consecutive days with at least one completed
+    /// pomodoro, ending on `date`.
+    pub fn streak_ending(&self, date: NaiveDate) -> Result<u32, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date FROM daily_stats
+             WHERE completed_pomodoros > 0 AND date <= ?
+             ORDER BY date DESC",
+        )?;
+        let dates = stmt.query_map([date.to_string()], |row| row.get::<_, String>(0))?;
+
+        let mut streak = 0;
+        let mut expected = date;
+        for day in dates {
+            if day?.parse::<NaiveDate>().ok() != Some(expected) {
+                break;
+            }
+            streak += 1;
+            expected = match expected.pred_opt() {
+                Some(prev) => prev,
+                None => break,
+            };
+        }
+        Ok(streak)
+    }
+
+    /// Returns the days before `today` that have stats but no finalized score, oldest first.
+    pub fn unscored_days(&self, today: NaiveDate) -> Result<Vec<NaiveDate>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date FROM daily_stats
+             WHERE date < ? AND date NOT IN (SELECT date FROM daily_scores)
+             ORDER BY date",
+        )?;
+        let dates = stmt.query_map([today.to_string()], |row| row.get::<_, String>(0))?;
+
+        let mut days = Vec::new();
+        for date in dates {
+            if let Ok(date) = date?.parse() {
+                days.push(date);
+            }
+        }
+        Ok(days)
+    }
+
+    /// Stores the finalized score of a day. A day that already has a score
+    /// keeps it, so scores never change after the fact.
+    pub fn save_day_score(&self, score: &DayScore) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO daily_scores (date, score, formula_version) VALUES (?, ?, ?)",
+            params![score.date.to_string(), score.score, score.formula_version],
+        )?;
+        Ok(())
+    }
+
+    /// Gets the finalized score of a day, if it has one.
+    pub fn get_day_score(&self, date: NaiveDate) -> Result<Option<DayScore>, DatabaseError> {
+        let result = self.conn.query_row(
+            "SELECT score, formula_version FROM daily_scores WHERE date = ?",
+            [date.to_string()],
+            |row| {
+                Ok(DayScore {
+                    date,
+                    score: row.get(0)?,
+                    formula_version: row.get(1)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(score) => Ok(Some(score)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the last day a daily summary was queued for.
+    pub fn last_summary_date(&self) -> Result<Option<NaiveDate>, DatabaseError> {
+        let value: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'daily_summary_sent'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value.and_then(|v| v.parse().ok()))
+    }
+
+    /// Queues a daily summary for delivery and records its day as sent,
+    /// so a restart never produces a second summary for the same day.
+    pub fn queue_summary(&self, summary: &DailySummary) -> Result<(), DatabaseError> {
+        let json = serde_json::to_string(summary)?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO webhook_queue (date, payload) VALUES (?, ?)",
+            params![summary.date.to_string(), json],
+        )?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('daily_summary_sent', ?)",
+            [summary.date.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the summaries still waiting for delivery, oldest first.
+    pub fn queued_summaries(&self) -> Result<Vec<DailySummary>, DatabaseError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT payload FROM webhook_queue ORDER BY date")?;
+        let payloads = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut summaries = Vec::new();
+        for payload in payloads {
+            summaries.push(serde_json::from_str(&payload?)?);
+        }
+        Ok(summaries)
+    }
+
+    /// Removes a delivered summary from the queue.
+    pub fn remove_queued_summary(&self, date: NaiveDate) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "DELETE FROM webhook_queue WHERE date = ?",
+            [date.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Copies the whole database to `dest` with SQLite's online backup API,
+    /// so the copy is consistent even while Pomobar keeps writing.
+    pub fn backup(&self, dest: &Path) -> Result<(), DatabaseError> {
+        self.conn.backup(DatabaseName::Main, dest, None)?;
+        Ok(())
+    }
+
+    /// Rebuilds the database file to give back the space of deleted rows,
+    /// and records when it happened under the `last_vacuum` settings key.
+    pub fn vacuum(&self) -> Result<(), DatabaseError> {
+        self.conn.execute("VACUUM", [])?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('last_vacuum', ?)",
+            [Local::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Returns when the database was last vacuumed, if ever.
+    pub fn last_vacuum(&self) -> Result<Option<DateTime<Local>>, DatabaseError> {
+        let value: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'last_vacuum'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value
+            .and_then(|v| DateTime::parse_from_rfc3339(&v).ok())
+            .map(|t| t.with_timezone(&Local)))
+    }
+
+    /// Moves everything in the write-ahead log into the database file and
+    /// empties the log, so the `-wal` file doesn't keep growing. Run on quit.
+    pub fn checkpoint(&self) -> Result<(), DatabaseError> {
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Resets the statistics and pomodoro log of `today`.
+    pub fn reset_today(&self, today: NaiveDate) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "DELETE FROM daily_stats WHERE date = ?",
+            [today.to_string()],
+        )?;
+        self.conn
+            .execute("DELETE FROM pomodoros WHERE date = ?", [today.to_string()])?;
+        Ok(())
+    }
+
+    /// Resets the counts and pomodoro log of `date`, keeping its cycle.
+    pub fn reset_counts(&self, date: NaiveDate) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "UPDATE daily_stats SET completed_pomodoros = 0, total_focus_minutes = 0,
+                 interruptions = 0, abandoned_pomodoros = 0, skipped_breaks = 0,
+                 interrupted_minutes = 0, marked_interruptions = 0
+             WHERE date = ?",
+            [date.to_string()],
+        )?;
+        self.conn
+            .execute("DELETE FROM pomodoros WHERE date = ?", [date.to_string()])?;
+        Ok(())
+    }
+
+    /// Resets the long break cycle saved for `date`, keeping its counts.
+    pub fn reset_cycle_persisted(&self, date: NaiveDate) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "UPDATE daily_stats SET cycle_pomodoros = 0 WHERE date = ?",
+            [date.to_string()],
+        )?;
+        Ok(())
+    }
+}
+
+/// Returns the directory holding the database.
+/// Runtime files such as the control socket live next to it.
+pub fn data_dir() -> PathBuf {
+    Database::db_path()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Returns when the database file was created, if the file system knows.
+pub fn database_created() -> Option<DateTime<Local>> {
+    let created = fs::metadata(Database::db_path()).ok()?.created().ok()?;
+    Some(created.into())
+}
+
+/// Returns whether the database should be vacuumed: the file is at least
+/// 30 days old and was not vacuumed in the last 30 days. A file of unknown
+/// age is left alone.
+pub fn vacuum_due(
+    created: Option<DateTime<Local>>,
+    last_vacuum: Option<DateTime<Local>>,
+    now: DateTime<Local>,
+) -> bool {
+    let cutoff = now - Duration::days(VACUUM_INTERVAL_DAYS);
+    created.is_some_and(|created| created <= cutoff)
+        && last_vacuum.is_none_or(|last| last <= cutoff)
+}
+
+/// Suggested file for a backup made on `date`, e.g.
+/// `~/Desktop/pomobar_backup_20240115.db`.
+pub fn default_backup_path(date: NaiveDate) -> PathBuf {
+    let dir = UserDirs::new()
+        .and_then(|dirs| dirs.desktop_dir().map(Path::to_path_buf))
+        .unwrap_or_default();
+    dir.join(format!("pomobar_backup_{}.db", date.format("%Y%m%d")))
+}
+
+/// Returns the stray `pomobar.db` in the working directory if one exists
+/// alongside the database in the data directory.
+pub fn find_stray_database() -> Option<PathBuf> {
+    stray_database(&Database::db_path(), Path::new(FALLBACK_DB_PATH))
+}
+
+fn stray_database(primary: &Path, fallback: &Path) -> Option<PathBuf> {
+    if !primary.exists() || !fallback.exists() {
+        return None;
+    }
+
+    // Both paths may point at the same file, e.g. when run from the data directory
+    match (primary.canonicalize(), fallback.canonicalize()) {
+        (Ok(a), Ok(b)) if a != b => Some(fallback.to_path_buf()),
+        _ => None,
+    }
+}
+
+/// Renames a merged database out of the way so it is not detected again.
+/// Returns the new path.
+pub fn retire_database(path: &Path) -> Result<PathBuf, DatabaseError> {
+    let mut target = path.with_extension("db.merged");
+    let mut n = 1;
+    while target.exists() {
+        target = path.with_extension(format!("db.merged.{}", n));
+        n += 1;
+    }
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GoalStatus, TimerState};
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn insert_stats(db: &Database, date: &str, completed: u32, mins: u32) {
+        db.conn
+            .execute(
+                "INSERT INTO daily_stats (date, completed_pomodoros, total_focus_minutes)
+                 VALUES (?, ?, ?)",
+                params![date, completed, mins],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_get_all_stats_in_date_order() {
+        let db = Database::new_in_memory().unwrap();
+        assert!(db.get_all_stats().unwrap().is_empty());
+
+        insert_stats(&db, "2024-02-01", 3, 75);
+        insert_stats(&db, "2023-12-31", 1, 25);
+        insert_stats(&db, "2024-01-15", 0, 0);
+
+        let stats = db.get_all_stats().unwrap();
+        let days: Vec<_> = stats
+            .iter()
+            .map(|s| (s.date.to_string(), s.completed_pomodoros))
+            .collect();
+        assert_eq!(
+            days,
+            [
+                ("2023-12-31".to_string(), 1),
+                ("2024-01-15".to_string(), 0),
+                ("2024-02-01".to_string(), 3),
+            ]
+        );
+        assert_eq!(stats[2].total_focus_minutes, 75);
+    }
+
+    #[test]
+    fn test_week_totals_follow_week_start() {
+        let db = Database::new_in_memory().unwrap();
+        insert_stats(&db, "2024-01-06", 4, 100); // Saturday
+        insert_stats(&db, "2024-01-07", 1, 25); // Sunday
+        insert_stats(&db, "2024-01-08", 2, 50); // Monday
+        insert_stats(&db, "2024-01-13", 3, 75); // Saturday
+        insert_stats(&db, "2024-01-14", 5, 125); // Sunday
+
+        let sunday = NaiveDate::from_ymd_opt(2024, 1, 14).unwrap();
+
+        // Monday week: 8th to 14th
+        let totals = db.get_week_totals(sunday, WeekStart::Monday).unwrap();
+        assert_eq!(totals.start, NaiveDate::from_ymd_opt(2024, 1, 8).unwrap());
+        assert_eq!(totals.completed_pomodoros, 10);
+        assert_eq!(totals.total_focus_minutes, 250);
+
+        // Sunday week: the 14th starts a new week
+        let totals = db.get_week_totals(sunday, WeekStart::Sunday).unwrap();
+        assert_eq!(totals.completed_pomodoros, 5);
+
+        // Saturday week: 13th and 14th
+        let totals = db.get_week_totals(sunday, WeekStart::Saturday).unwrap();
+        assert_eq!(totals.completed_pomodoros, 8);
+    }
+
+    #[test]
+    fn test_week_totals_empty() {
+        let db = Database::new_in_memory().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 14).unwrap();
+        let totals = db.get_week_totals(date, WeekStart::Monday).unwrap();
+        assert_eq!(
+            totals,
+            WeekTotals::new(NaiveDate::from_ymd_opt(2024, 1, 8).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_streak_ending() {
+        let db = Database::new_in_memory().unwrap();
+        insert_stats(&db, "2024-01-10", 1, 25);
+        insert_stats(&db, "2024-01-12", 2, 50);
+        insert_stats(&db, "2024-01-13", 0, 0);
+        insert_stats(&db, "2024-01-14", 1, 25);
+        insert_stats(&db, "2024-01-15", 3, 75);
+
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        assert_eq!(db.streak_ending(day(15)).unwrap(), 2);
+        assert_eq!(db.streak_ending(day(12)).unwrap(), 1);
+        assert_eq!(db.streak_ending(day(13)).unwrap(), 0);
+        assert_eq!(db.streak_ending(day(11)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_summary_queued_without_goal_still_loads() {
+        let db = Database::new_in_memory().unwrap();
+        db.conn
+            .execute(
+                "INSERT INTO webhook_queue (date, payload) VALUES ('2024-01-15', ?)",
+                [r#"{"date":"2024-01-15","completed_pomodoros":6,"total_focus_minutes":150,"streak_days":3}"#],
+            )
+            .unwrap();
+        let queued = db.queued_summaries().unwrap();
+        assert_eq!(queued[0].goal, None);
+        assert_eq!(queued[0].focus_score, None);
+    }
+
+    #[test]
+    fn test_summary_queue_round_trip() {
+        let db = Database::new_in_memory().unwrap();
+        assert_eq!(db.last_summary_date().unwrap(), None);
+
+        let summary = DailySummary {
+            date: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            completed_pomodoros: 6,
+            total_focus_minutes: 150,
+            streak_days: 3,
+            focus_score: Some(72),
+            goal: Some(GoalStatus::Missed { by: 2 }),
+        };
+        db.queue_summary(&summary).unwrap();
+
+        assert_eq!(db.last_summary_date().unwrap(), Some(summary.date));
+        assert_eq!(db.queued_summaries().unwrap(), vec![summary.clone()]);
+
+        db.remove_queued_summary(summary.date).unwrap();
+        assert!(db.queued_summaries().unwrap().is_empty());
+        // Delivery does not clear the sent marker
+        assert_eq!(db.last_summary_date().unwrap(), Some(summary.date));
+    }
+
+    #[test]
+    fn test_stray_database_detection() {
+        let dir = TempDir::new().unwrap();
+        let primary = dir.path().join("data").join("pomobar.db");
+        let fallback = dir.path().join("pomobar.db");
+
+        // Neither exists
+        assert_eq!(stray_database(&primary, &fallback), None);
+
+        fs::create_dir_all(primary.parent().unwrap()).unwrap();
+        Database::open(&primary).unwrap();
+        assert_eq!(stray_database(&primary, &fallback), None);
+
+        Database::open(&fallback).unwrap();
+        assert_eq!(stray_database(&primary, &fallback), Some(fallback.clone()));
+
+        // Same file under two names is not a stray
+        assert_eq!(stray_database(&fallback, &fallback), None);
+    }
+
+    #[test]
+    fn test_backup_copies_stats_and_settings() {
+        let dir = TempDir::new().unwrap();
+        let dest = dir.path().join("backup.db");
+
+        let db = Database::new_in_memory().unwrap();
+        insert_stats(&db, "2024-01-15", 3, 75);
+        let settings = Settings {
+            pomodoro_mins: 50,
+            ..Settings::default()
+        };
+        db.save_settings(&settings).unwrap();
+        db.backup(&dest).unwrap();
+
+        let copy = Database::open(&dest).unwrap();
+        let stats = copy
+            .get_daily_stats(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap())
+            .unwrap();
+        assert_eq!(stats.completed_pomodoros, 3);
+        assert_eq!(copy.load_settings().unwrap().pomodoro_mins, 50);
+    }
+
+    #[test]
+    fn test_volume_round_trip_and_clamp() {
+        let db = Database::new_in_memory().unwrap();
+        for (saved, loaded) in [(0.25, 0.25), (1.0, 1.0), (4.0, 1.0), (-1.0, 0.0)] {
+            let settings = Settings {
+                volume: saved,
+                ..Settings::default()
+            };
+            db.save_settings(&settings).unwrap();
+            assert_eq!(db.load_settings().unwrap().volume, loaded);
+        }
+    }
+
+    #[test]
+    fn test_save_session_adds_changes_since_last_save() {
+        let db = Database::new_in_memory().unwrap();
+        let today = Local::now().date_naive();
+        let mut session = Session::new(today);
+        session.complete_pomodoro(25);
+        db.save_session(&session, &Session::new(today)).unwrap();
+
+        // Saving the same session again adds nothing
+        db.save_session(&session, &session).unwrap();
+        assert_eq!(db.get_daily_stats(today).unwrap().completed_pomodoros, 1);
+
+        let saved = session.clone();
+        session.complete_pomodoro(25);
+        session.interruptions_today += 1;
+        db.save_session(&session, &saved).unwrap();
+        let stats = db.get_daily_stats(today).unwrap();
+        assert_eq!(stats.completed_pomodoros, 2);
+        assert_eq!(stats.total_focus_minutes, 50);
+        assert_eq!(stats.interruptions, 1);
+
+        // A snapshot from yesterday counts as nothing saved for today
+        let yesterday = Session::new(today - Days::new(1));
+        db.save_session(&Session::new(today), &yesterday).unwrap();
+        assert_eq!(db.get_daily_stats(today).unwrap().completed_pomodoros, 2);
+    }
+
+    #[test]
+    fn test_two_instances_keep_each_others_completions() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let first = Database::open(&path).unwrap();
+        let second = Database::open(&path).unwrap();
+
+        let mut a = first.load_today_session(Local::now().date_naive()).unwrap();
+        let mut b = second
+            .load_today_session(Local::now().date_naive())
+            .unwrap();
+        let (a_saved, b_saved) = (a.clone(), b.clone());
+        a.complete_pomodoro(25);
+        a.complete_pomodoro(25);
+        b.complete_pomodoro(50);
+        first.save_session(&a, &a_saved).unwrap();
+        second.save_session(&b, &b_saved).unwrap();
+
+        // Replacing would have left only the second instance's pomodoro
+        let stats = first.get_daily_stats(a.last_date).unwrap();
+        assert_eq!(stats.completed_pomodoros, 3);
+        assert_eq!(stats.total_focus_minutes, 100);
+    }
+
+    #[test]
+    fn test_saved_timer_is_taken_once() {
+        let db = Database::new_in_memory().unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let started_at = Local.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap();
+        let timer = SavedTimer {
+            state: TimerState::BreakPaused {
+                is_long_break: true,
+                remaining_secs: 600,
+                total_secs: 900,
+            },
+            block_started_at: Some(started_at),
+        };
+        assert_eq!(db.take_timer(today).unwrap(), None);
+
+        db.save_timer(today, Some(&timer)).unwrap();
+        assert_eq!(db.take_timer(today).unwrap(), Some(timer.clone()));
+        assert_eq!(db.take_timer(today).unwrap(), None);
+
+        // Saving the counts afterwards leaves a saved timer alone
+        db.save_timer(today, Some(&timer)).unwrap();
+        let session = Session {
+            pomodoros_completed_today: 2,
+            ..Session::new(today)
+        };
+        db.save_session(&session, &Session::new(today)).unwrap();
+        assert_eq!(db.get_daily_stats(today).unwrap().completed_pomodoros, 2);
+        assert_eq!(db.take_timer(today).unwrap(), Some(timer));
+    }
+
+    #[test]
+    fn test_save_session_never_goes_negative() {
+        let db = Database::new_in_memory().unwrap();
+        let today = Local::now().date_naive();
+        let mut saved = Session::new(today);
+        saved.complete_pomodoro(25);
+        saved.complete_pomodoro(25);
+        // The row was reset elsewhere since our last save
+        db.save_session(&Session::new(today), &saved).unwrap();
+        let stats = db.get_daily_stats(today).unwrap();
+        assert_eq!(stats.completed_pomodoros, 0);
+        assert_eq!(stats.total_focus_minutes, 0);
+    }
+
+    #[test]
+    fn test_days_ending_fills_gaps() {
+        let db = Database::new_in_memory().unwrap();
+        insert_stats(&db, "2024-01-13", 9, 225); // Outside the range
+        insert_stats(&db, "2024-01-14", 2, 50);
+        insert_stats(&db, "2024-01-16", 5, 125);
+
+        let end = NaiveDate::from_ymd_opt(2024, 1, 16).unwrap();
+        let days = db.get_days_ending(end, 3).unwrap();
+        let counts: Vec<_> = days.iter().map(|d| d.completed_pomodoros).collect();
+        assert_eq!(counts, vec![2, 0, 5]);
+        assert_eq!(days[1].date, NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        assert_eq!(days[2].date, end);
+
+        assert!(db.get_days_ending(end, 0).unwrap().is_empty());
+        assert_eq!(
+            db.get_last_n_days(Local::now().date_naive(), 28)
+                .unwrap()
+                .len(),
+            28
+        );
+    }
+
+    #[test]
+    fn test_get_yesterday_stats() {
+        let db = Database::new_in_memory().unwrap();
+        let today = Local::now().date_naive();
+        let yesterday = today - Days::new(1);
+        assert_eq!(
+            db.get_yesterday_stats(today).unwrap(),
+            DailyStats::new(yesterday)
+        );
+
+        insert_stats(&db, &yesterday.to_string(), 6, 150);
+        insert_stats(&db, &today.to_string(), 2, 50);
+        let stats = db.get_yesterday_stats(today).unwrap();
+        assert_eq!(stats.date, yesterday);
+        assert_eq!(stats.completed_pomodoros, 6);
+        assert_eq!(stats.total_focus_minutes, 150);
+    }
+
+    #[test]
+    fn test_vacuum_after_many_deletes() {
+        let db = Database::new_in_memory().unwrap();
+        for day in 0..1000 {
+            let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap() + Days::new(day);
+            insert_stats(&db, &date.to_string(), 1, 25);
+        }
+        db.conn.execute("DELETE FROM daily_stats", []).unwrap();
+
+        assert_eq!(db.last_vacuum().unwrap(), None);
+        db.vacuum().unwrap();
+        assert!(db.last_vacuum().unwrap().is_some());
+        // The vacuum timestamp doesn't disturb the settings
+        assert_eq!(db.load_settings().unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn test_vacuum_due() {
+        let now = Local::now();
+        let days_ago = |days| Some(now - Duration::days(days));
+
+        assert!(vacuum_due(days_ago(31), None, now));
+        assert!(vacuum_due(days_ago(90), days_ago(30), now));
+        // Young file, recent vacuum, or unknown age
+        assert!(!vacuum_due(days_ago(10), None, now));
+        assert!(!vacuum_due(days_ago(90), days_ago(5), now));
+        assert!(!vacuum_due(None, None, now));
+    }
+
+    #[test]
+    fn test_default_backup_path_name() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let path = default_backup_path(date);
+        assert_eq!(path.file_name().unwrap(), "pomobar_backup_20240105.db");
+    }
+
+    #[test]
+    fn test_merge_daily_stats_is_additive() {
+        let dir = TempDir::new().unwrap();
+        let stray_path = dir.path().join("pomobar.db");
+        let stray = Database::open(&stray_path).unwrap();
+        insert_stats(&stray, "2024-01-15", 2, 50);
+        insert_stats(&stray, "2024-01-16", 1, 25);
+        let day = NaiveDate::from_ymd_opt(2024, 1, 16).unwrap();
+        stray.log_pomodoro(day, 25, Some("Email"), 0).unwrap();
+        drop(stray);
+
+        let db = Database::new_in_memory().unwrap();
+        insert_stats(&db, "2024-01-15", 3, 75);
+
+        let merged = db.merge_daily_stats_from(&stray_path).unwrap();
+        assert_eq!(merged, 2);
+
+        let day1 = db
+            .get_daily_stats(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap())
+            .unwrap();
+        assert_eq!(day1.completed_pomodoros, 5);
+        assert_eq!(day1.total_focus_minutes, 125);
+
+        let day2 = db
+            .get_daily_stats(NaiveDate::from_ymd_opt(2024, 1, 16).unwrap())
+            .unwrap();
+        assert_eq!(day2.completed_pomodoros, 1);
+        assert_eq!(day2.total_focus_minutes, 25);
+        assert_eq!(db.recent_tasks(5).unwrap(), ["Email"]);
+    }
+
+    #[test]
+    fn test_failed_merge_rolls_back() {
+        let dir = TempDir::new().unwrap();
+        let stray_path = dir.path().join("pomobar.db");
+        let stray = Database::open(&stray_path).unwrap();
+        insert_stats(&stray, "2024-01-15", 2, 50);
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        stray.log_pomodoro(day, 25, Some("Email"), 0).unwrap();
+        drop(stray);
+
+        let db = Database::new_in_memory().unwrap();
+        insert_stats(&db, "2024-01-15", 3, 75);
+        // Fail the second half of the merge, after the stats went in
+        db.conn
+            .execute_batch(
+                "CREATE TEMP TRIGGER no_log BEFORE INSERT ON pomodoros
+                 BEGIN SELECT RAISE(ABORT, 'log is read-only'); END",
+            )
+            .unwrap();
+
+        assert!(db.merge_daily_stats_from(&stray_path).is_err());
+        assert_eq!(db.get_daily_stats(day).unwrap().completed_pomodoros, 3);
+
+        // The source is detached again, so the merge can be retried
+        db.conn.execute_batch("DROP TRIGGER no_log").unwrap();
+        assert_eq!(db.merge_daily_stats_from(&stray_path).unwrap(), 1);
+        assert_eq!(db.get_daily_stats(day).unwrap().completed_pomodoros, 5);
+    }
+
+    #[test]
+    fn test_migrations_upgrade_unversioned_database() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE daily_stats (
+                 date TEXT PRIMARY KEY,
+                 completed_pomodoros INTEGER NOT NULL DEFAULT 0,
+                 total_focus_minutes INTEGER NOT NULL DEFAULT 0
+             );
+             INSERT INTO daily_stats VALUES ('2024-01-15', 4, 100);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let db = Database::open(&path).unwrap();
+        let stats = db
+            .get_daily_stats(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap())
+            .unwrap();
+        assert_eq!(stats.completed_pomodoros, 4);
+        assert_eq!(stats.interruptions, 0);
+        assert_eq!(stats.abandoned, 0);
+        assert_eq!(stats.skipped_breaks, 0);
+        assert_eq!(user_version(&db), CURRENT_VERSION);
+
+        // Opening again doesn't run the migrations twice
+        drop(db);
+        let db = Database::open(&path).unwrap();
+        assert_eq!(user_version(&db), CURRENT_VERSION);
+    }
+
+    fn user_version(db: &Database) -> u32 {
+        db.conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_new_database_is_current() {
+        let db = Database::new_in_memory().unwrap();
+        assert_eq!(user_version(&db), CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_migrations_continue_from_stored_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch("PRAGMA user_version = 1").unwrap();
+
+        Database::run_migrations(&conn).unwrap();
+        let version: u32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, CURRENT_VERSION);
+
+        // v2 added the cycle column, which sessions now save
+        let db = Database { conn };
+        let session = Session {
+            pomodoros_completed_today: 3,
+            pomodoros_in_cycle: 3,
+            ..Session::default()
+        };
+        db.save_session(&session, &Session::default()).unwrap();
+        let cycle: u32 = db
+            .conn
+            .query_row("SELECT cycle_pomodoros FROM daily_stats", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(cycle, 3);
+    }
+
+    #[test]
+    fn test_migrations_keep_rows_from_version_1() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch(
+            "PRAGMA user_version = 1;
+             INSERT INTO daily_stats VALUES ('2024-01-15', 4, 100);
+             INSERT INTO settings VALUES ('config', '{\"pomodoro_mins\": 40}');",
+        )
+        .unwrap();
+
+        Database::run_migrations(&conn).unwrap();
+        let db = Database { conn };
+        assert_eq!(user_version(&db), CURRENT_VERSION);
+        let stats = db
+            .get_daily_stats(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap())
+            .unwrap();
+        assert_eq!(stats.completed_pomodoros, 4);
+        assert_eq!(stats.total_focus_minutes, 100);
+        assert_eq!(stats.interruptions, 0);
+        assert_eq!(db.load_settings().unwrap().pomodoro_mins, 40);
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        Database::run_migrations(&conn).unwrap();
+
+        let mut migrations = MIGRATIONS.to_vec();
+        migrations.push("CREATE TABLE half_done (x); SELECT * FROM no_such_table;");
+        let err = Database::apply_migrations(&conn, &migrations).unwrap_err();
+        assert!(
+            matches!(err, DatabaseError::Migration { version, .. } if version == CURRENT_VERSION + 1)
+        );
+
+        let db = Database { conn };
+        assert_eq!(user_version(&db), CURRENT_VERSION);
+        let half_done: u32 = db
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'half_done'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(half_done, 0);
+        // The connection isn't left inside the failed transaction
+        db.save_settings(&Settings::default()).unwrap();
+    }
+
+    #[test]
+    fn test_day_scores_are_kept_once_saved() {
+        let db = Database::new_in_memory().unwrap();
+        insert_stats(&db, "2024-01-14", 0, 0);
+        insert_stats(&db, "2024-01-15", 2, 50);
+        insert_stats(&db, "2024-01-16", 1, 25);
+
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        assert_eq!(db.unscored_days(day(16)).unwrap(), vec![day(14), day(15)]);
+        assert_eq!(db.get_day_score(day(15)).unwrap(), None);
+
+        let first = DayScore {
+            date: day(15),
+            score: Some(48),
+            formula_version: 1,
+        };
+        db.save_day_score(&first).unwrap();
+        db.save_day_score(&DayScore {
+            score: Some(90),
+            formula_version: 2,
+            ..first
+        })
+        .unwrap();
+        db.save_day_score(&DayScore {
+            date: day(14),
+            score: None,
+            formula_version: 1,
+        })
+        .unwrap();
+
+        assert_eq!(db.get_day_score(day(15)).unwrap(), Some(first));
+        assert!(db.unscored_days(day(16)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_retire_database_avoids_overwriting() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+
+        fs::write(&path, "first").unwrap();
+        let first = retire_database(&path).unwrap();
+        assert_eq!(first, dir.path().join("pomobar.db.merged"));
+        assert!(!path.exists());
+
+        fs::write(&path, "second").unwrap();
+        let second = retire_database(&path).unwrap();
+        assert_eq!(second, dir.path().join("pomobar.db.merged.1"));
+        assert_eq!(fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "second");
+    }
+
+    #[test]
+    fn test_database_creation() {
+        let db = Database::new_in_memory();
+        assert!(db.is_ok());
+    }
+
+    #[test]
+    fn test_settings_save_and_load() {
+        let db = Database::new_in_memory().unwrap();
+
+        // Default settings should be returned when nothing is saved
+        let settings = db.load_settings().unwrap();
+        assert_eq!(settings, Settings::default());
+
+        // Save custom settings
+        let custom_settings = Settings {
+            pomodoro_mins: 30,
+            short_break_mins: 10,
+            long_break_mins: 20,
+            pomodoros_for_long_break: 3,
+            sound_on_break_end: false,
+            notifications_enabled: true,
+            launch_at_login: false,
+            max_daily_focus_mins: Some(360),
+            hotkey_start_pause: "Cmd+Shift+P".to_string(),
+            ..Settings::default()
+        };
+        db.save_settings(&custom_settings).unwrap();
+
+        // Load and verify
+        let loaded = db.load_settings().unwrap();
+        assert_eq!(loaded, custom_settings);
+    }
+
+    #[test]
+    fn test_session_save_and_load() {
+        let db = Database::new_in_memory().unwrap();
+
+        // Create and save a session
+        let today = Local::now().date_naive();
+        let session = Session {
+            pomodoros_completed_today: 5,
+            total_focus_mins_today: 125,
+            pomodoros_in_cycle: 2,
+            interruptions_today: 1,
+            marked_interruptions_today: 2,
+            abandoned_today: 1,
+            skipped_breaks_today: 0,
+            total_interrupted_mins: 12,
+            last_date: today,
+        };
+        db.save_session(&session, &Session::default()).unwrap();
+
+        // Load and verify
+        let loaded = db.load_today_session(Local::now().date_naive()).unwrap();
+        assert_eq!(loaded.pomodoros_completed_today, 5);
+        assert_eq!(loaded.total_focus_mins_today, 125);
+        assert_eq!(loaded.pomodoros_in_cycle, 2);
+        assert_eq!(loaded.interruptions_today, 1);
+        assert_eq!(loaded.marked_interruptions_today, 2);
+        assert_eq!(loaded.abandoned_today, 1);
+        assert_eq!(loaded.skipped_breaks_today, 0);
+        assert_eq!(loaded.total_interrupted_mins, 12);
+        assert_eq!(loaded.last_date, today);
+    }
+
+    #[test]
+    fn test_daily_stats_for_nonexistent_date() {
+        let db = Database::new_in_memory().unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let stats = db.get_daily_stats(date).unwrap();
+
+        assert_eq!(stats.date, date);
+        assert_eq!(stats.completed_pomodoros, 0);
+        assert_eq!(stats.total_focus_minutes, 0);
+    }
+
+    #[test]
+    fn test_reset_today() {
+        let db = Database::new_in_memory().unwrap();
+
+        // Save some data
+        let today = Local::now().date_naive();
+        let session = Session {
+            pomodoros_completed_today: 5,
+            total_focus_mins_today: 125,
+            pomodoros_in_cycle: 2,
+            interruptions_today: 1,
+            marked_interruptions_today: 2,
+            abandoned_today: 1,
+            skipped_breaks_today: 0,
+            total_interrupted_mins: 12,
+            last_date: today,
+        };
+        db.save_session(&session, &Session::default()).unwrap();
+
+        // Reset
+        db.reset_today(today).unwrap();
+
+        // Verify it's cleared
+        let loaded = db.load_today_session(Local::now().date_naive()).unwrap();
+        assert_eq!(loaded.pomodoros_completed_today, 0);
+        assert_eq!(loaded.total_focus_mins_today, 0);
+    }
+
+    #[test]
+    fn test_reset_counts_keeps_cycle() {
+        let db = Database::new_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let session = Session {
+            pomodoros_completed_today: 3,
+            total_focus_mins_today: 75,
+            pomodoros_in_cycle: 3,
+            interruptions_today: 1,
+            skipped_breaks_today: 1,
+            last_date: day,
+            ..Session::default()
+        };
+        db.save_session(&session, &Session::default()).unwrap();
+        db.log_pomodoro(day, 25, Some("Write report"), 0).unwrap();
+
+        db.reset_counts(day).unwrap();
+
+        let loaded = db.load_today_session(day).unwrap();
+        assert_eq!(loaded.pomodoros_completed_today, 0);
+        assert_eq!(loaded.total_focus_mins_today, 0);
+        assert_eq!(loaded.interruptions_today, 0);
+        assert_eq!(loaded.skipped_breaks_today, 0);
+        assert!(db.stats_by_task(day).unwrap().is_empty());
+        assert_eq!(loaded.pomodoros_in_cycle, 3);
+    }
+
+    #[test]
+    fn test_reset_cycle_persisted_keeps_counts() {
+        let db = Database::new_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let session = Session {
+            pomodoros_completed_today: 3,
+            total_focus_mins_today: 75,
+            pomodoros_in_cycle: 3,
+            last_date: day,
+            ..Session::default()
+        };
+        db.save_session(&session, &Session::default()).unwrap();
+
+        db.reset_cycle_persisted(day).unwrap();
+
+        let loaded = db.load_today_session(day).unwrap();
+        assert_eq!(loaded.pomodoros_completed_today, 3);
+        assert_eq!(loaded.total_focus_mins_today, 75);
+        assert_eq!(loaded.pomodoros_in_cycle, 0);
+    }
+
+    #[test]
+    fn test_recent_tasks_are_distinct_newest_first() {
+        let db = Database::new_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        for task in [
+            Some("Email"),
+            Some("Write report"),
+            None,
+            Some("Email"),
+            Some("Review"),
+            Some("Plan"),
+            Some("Triage"),
+            Some("Docs"),
+        ] {
+            db.log_pomodoro(day, 25, task, 0).unwrap();
+        }
+
+        assert_eq!(
+            db.recent_tasks(5).unwrap(),
+            ["Docs", "Triage", "Plan", "Review", "Email"]
+        );
+        assert_eq!(db.recent_tasks(10).unwrap().len(), 6);
+        assert!(Database::new_in_memory()
+            .unwrap()
+            .recent_tasks(5)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_log_pomodoro_keeps_interruptions() {
+        let db = Database::new_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        db.log_pomodoro(day, 25, None, 3).unwrap();
+
+        let interruptions: u32 = db
+            .conn
+            .query_row("SELECT interruptions FROM pomodoros", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(interruptions, 3);
+    }
+
+    #[test]
+    fn test_stats_by_task() {
+        let db = Database::new_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        db.log_pomodoro(day, 25, Some("Email"), 0).unwrap();
+        db.log_pomodoro(day, 25, Some("Write report"), 0).unwrap();
+        db.log_pomodoro(day, 50, Some("Write report"), 0).unwrap();
+        db.log_pomodoro(day, 25, None, 0).unwrap();
+        db.log_pomodoro(day.succ_opt().unwrap(), 25, Some("Email"), 0)
+            .unwrap();
+
+        let task = |task: Option<&str>, pomodoros, focus_minutes| TaskStats {
+            task: task.map(String::from),
+            pomodoros,
+            focus_minutes,
+        };
+        assert_eq!(
+            db.stats_by_task(day).unwrap(),
+            [
+                task(Some("Write report"), 2, 75),
+                task(None, 1, 25),
+                task(Some("Email"), 1, 25),
+            ]
+        );
+
+        db.reset_today(day).unwrap();
+        assert!(db.stats_by_task(day).unwrap().is_empty());
+        assert_eq!(db.recent_tasks(5).unwrap(), ["Email"]);
+    }
+
+    #[test]
+    fn test_settings_overwrite() {
+        let db = Database::new_in_memory().unwrap();
+
+        let settings1 = Settings {
+            pomodoro_mins: 30,
+            ..Settings::default()
+        };
+        db.save_settings(&settings1).unwrap();
+
+        let settings2 = Settings {
+            pomodoro_mins: 45,
+            ..Settings::default()
+        };
+        db.save_settings(&settings2).unwrap();
+
+        let loaded = db.load_settings().unwrap();
+        assert_eq!(loaded.pomodoro_mins, 45);
+    }
+
+    #[test]
+    fn test_settings_stored_with_version() {
+        let db = Database::new_in_memory().unwrap();
+        db.save_settings(&Settings::default()).unwrap();
+        let json: String = db
+            .conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = 'config'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let stored: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(stored["version"], SETTINGS_VERSION);
+    }
+
+    #[test]
+    fn test_load_unversioned_settings() {
+        let db = Database::new_in_memory().unwrap();
+        db.conn
+            .execute(
+                "INSERT INTO settings (key, value) VALUES ('config', ?)",
+                [r#"{"pomodoro_mins": 40, "sound_enabled": false}"#],
+            )
+            .unwrap();
+        let settings = db.load_settings().unwrap();
+        assert_eq!(settings.pomodoro_mins, 40);
+        assert!(!settings.sound_on_break_end);
+
+        // Saving again upgrades the stored format
+        db.save_settings(&settings).unwrap();
+        assert_eq!(db.load_settings().unwrap(), settings);
+    }
+
+    #[test]
+    fn test_open_uses_wal() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("pomobar.db")).unwrap();
+        let mode: String = db
+            .conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+    }
+
+    #[test]
+    fn test_second_connection_during_write() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let app_db = Database::open(&path).unwrap();
+        let tool_db = Database::open(&path).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+        // The app is in the middle of a write
+        app_db.conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+        insert_stats(&app_db, "2024-01-15", 3, 75);
+
+        // Readers aren't blocked and see the last commit
+        assert_eq!(
+            tool_db.get_daily_stats(date).unwrap().completed_pomodoros,
+            0
+        );
+
+        // A second writer waits for the lock instead of failing
+        let writer = std::thread::spawn(move || {
+            let settings = Settings {
+                daily_goal: 3,
+                ..Settings::default()
+            };
+            tool_db.save_settings(&settings)
+        });
+        std::thread::sleep(StdDuration::from_millis(200));
+        app_db.conn.execute_batch("COMMIT").unwrap();
+        writer.join().unwrap().unwrap();
+
+        assert_eq!(app_db.load_settings().unwrap().daily_goal, 3);
+        assert_eq!(app_db.get_daily_stats(date).unwrap().completed_pomodoros, 3);
+    }
+
+    #[test]
+    fn test_locked_database_is_busy() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let app_db = Database::open(&path).unwrap();
+        let tool_db = Database::open(&path).unwrap();
+        app_db.conn.busy_timeout(StdDuration::ZERO).unwrap();
+
+        tool_db.conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+        assert!(matches!(
+            app_db.save_settings(&Settings::default()),
+            Err(DatabaseError::Busy)
+        ));
+        tool_db.conn.execute_batch("COMMIT").unwrap();
+        app_db.save_settings(&Settings::default()).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_empties_wal() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.db");
+        let db = Database::open(&path).unwrap();
+        insert_stats(&db, "2024-01-15", 3, 75);
+        let wal = dir.path().join("pomobar.db-wal");
+        assert!(fs::metadata(&wal).unwrap().len() > 0);
+
+        db.checkpoint().unwrap();
+        assert_eq!(fs::metadata(&wal).unwrap().len(), 0);
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let reopened = Database::open(&path).unwrap();
+        assert_eq!(
+            reopened.get_daily_stats(date).unwrap().completed_pomodoros,
+            3
+        );
+    }
+}
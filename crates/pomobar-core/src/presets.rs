@@ -0,0 +1,136 @@
+//! Preset profiles that set all four timing settings at once.
+
+use crate::models::Settings;
+
+/// A named combination of pomodoro length, break lengths and long break interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    /// Stable identifier, used in menu item ids.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub pomodoro_mins: u32,
+    pub short_break_mins: u32,
+    pub long_break_mins: u32,
+    pub pomodoros_for_long_break: u32,
+}
+
+/// The built-in presets, in menu order.
+pub const PRESETS: [Preset; 3] = [
+    Preset {
+        id: "classic",
+        name: "Classic 25/5/15×4",
+        pomodoro_mins: 25,
+        short_break_mins: 5,
+        long_break_mins: 15,
+        pomodoros_for_long_break: 4,
+    },
+    Preset {
+        id: "long",
+        name: "50/10/20×3",
+        pomodoro_mins: 50,
+        short_break_mins: 10,
+        long_break_mins: 20,
+        pomodoros_for_long_break: 3,
+    },
+    Preset {
+        id: "deep_work",
+        name: "Deep Work 90/15/30×2",
+        pomodoro_mins: 90,
+        short_break_mins: 15,
+        long_break_mins: 30,
+        pomodoros_for_long_break: 2,
+    },
+];
+
+impl Preset {
+    /// Returns the built-in preset with the given id.
+    pub fn find(id: &str) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.id == id)
+    }
+
+    /// Returns the built-in preset the settings currently match, if any.
+    pub fn current(settings: &Settings) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.matches(settings))
+    }
+
+    /// Returns whether all four timing settings equal this preset.
+    pub fn matches(&self, settings: &Settings) -> bool {
+        settings.pomodoro_mins == self.pomodoro_mins
+            && settings.short_break_mins == self.short_break_mins
+            && settings.long_break_mins == self.long_break_mins
+            && settings.pomodoros_for_long_break == self.pomodoros_for_long_break
+    }
+
+    /// Sets all four timing settings from this preset.
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.pomodoro_mins = self.pomodoro_mins;
+        settings.short_break_mins = self.short_break_mins;
+        settings.long_break_mins = self.long_break_mins;
+        settings.pomodoros_for_long_break = self.pomodoros_for_long_break;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::Database;
+
+    #[test]
+    fn test_default_settings_are_classic() {
+        assert_eq!(Preset::current(&Settings::default()).unwrap().id, "classic");
+    }
+
+    #[test]
+    fn test_apply_round_trips_through_db() {
+        let db = Database::new_in_memory().unwrap();
+        for preset in &PRESETS {
+            let mut settings = db.load_settings().unwrap();
+            preset.apply(&mut settings);
+            db.save_settings(&settings).unwrap();
+
+            let loaded = db.load_settings().unwrap();
+            assert!(preset.matches(&loaded), "{}", preset.name);
+            assert_eq!(Preset::current(&loaded), Some(preset));
+        }
+    }
+
+    #[test]
+    fn test_any_deviation_is_custom() {
+        let preset = Preset::find("long").unwrap();
+        let mut base = Settings::default();
+        preset.apply(&mut base);
+
+        let deviations: [fn(&mut Settings); 4] = [
+            |s| s.pomodoro_mins += 1,
+            |s| s.short_break_mins += 1,
+            |s| s.long_break_mins += 1,
+            |s| s.pomodoros_for_long_break += 1,
+        ];
+        for deviate in deviations {
+            let mut settings = base.clone();
+            deviate(&mut settings);
+            assert!(!preset.matches(&settings));
+            assert_eq!(Preset::current(&settings), None);
+        }
+    }
+
+    #[test]
+    fn test_apply_leaves_other_settings() {
+        let mut settings = Settings {
+            sound_on_pomodoro_end: false,
+            daily_goal: 3,
+            ..Settings::default()
+        };
+        Preset::find("deep_work").unwrap().apply(&mut settings);
+        assert_eq!(settings.pomodoro_mins, 90);
+        assert!(!settings.sound_on_pomodoro_end);
+        assert_eq!(settings.daily_goal, 3);
+    }
+
+    #[test]
+    fn test_ids_are_unique() {
+        for (i, preset) in PRESETS.iter().enumerate() {
+            assert_eq!(Preset::find(preset.id), Some(&PRESETS[i]));
+        }
+    }
+}
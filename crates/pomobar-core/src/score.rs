@@ -0,0 +1,193 @@
+//! Daily focus score.
+//!
+//! One number from 0 to 100 for a day, made of two parts:
+//!
+//! - **Progress** (up to 70 points): `70 × min(completed, goal) / goal`.
+//!   A goal of 0 counts as 1.
+//! - **Discipline** (up to 30 points): 30, minus 4 per interruption (a paused
+//!   pomodoro), 10 per abandoned (stopped) pomodoro and 5 per skipped break,
+//!   never below 0.
+//!
+//! Interruptions are `Session::interruptions_today`: pauses the user makes.
+//! Idle auto-pauses don't count, and neither do interruptions marked with
+//! Mark Interruption (`marked_interruptions_today`), which only log an
+//! interruption without pausing.
+//!
+//! The sum is rounded and clamped to 0–100. A day with no activity at all
+//! has no score rather than a score of 30.
+//!
+//! The fastest way up is reaching the goal; after that, every pause,
+//! stopped pomodoro and skipped break costs points. [`letter_grade`] turns
+//! the score into a school grade: A from 90, B from 80, C from 70, D from 60
+//! and F below.
+//!
+//! Finished days store their score together with [`FORMULA_VERSION`], so
+//! changing the formula never rewrites history. Bump the version with any
+//! change to the numbers above.
+
+use crate::models::{DailyStats, Session};
+
+/// Version of the formula above, stored with every finalized score.
+pub const FORMULA_VERSION: u32 = 1;
+
+/// Points for reaching the daily goal.
+const PROGRESS_POINTS: f64 = 70.0;
+
+/// Points for a day without interruptions, abandoned pomodoros or skipped breaks.
+const DISCIPLINE_POINTS: u32 = 30;
+
+const INTERRUPTION_PENALTY: u32 = 4;
+const ABANDONED_PENALTY: u32 = 10;
+const SKIPPED_BREAK_PENALTY: u32 = 5;
+
+/// What happened on a day, as far as the score is concerned.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayActivity {
+    pub completed: u32,
+    pub interruptions: u32,
+    pub abandoned: u32,
+    pub skipped_breaks: u32,
+}
+
+impl From<&Session> for DayActivity {
+    fn from(session: &Session) -> Self {
+        Self {
+            completed: session.pomodoros_completed_today,
+            interruptions: session.interruptions_today,
+            abandoned: session.abandoned_today,
+            skipped_breaks: session.skipped_breaks_today,
+        }
+    }
+}
+
+impl From<&DailyStats> for DayActivity {
+    fn from(stats: &DailyStats) -> Self {
+        Self {
+            completed: stats.completed_pomodoros,
+            interruptions: stats.interruptions,
+            abandoned: stats.abandoned,
+            skipped_breaks: stats.skipped_breaks,
+        }
+    }
+}
+
+impl DayActivity {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Computes the focus score of a day against a goal of `goal` pomodoros.
+/// Returns None for a day without any activity.
+pub fn focus_score(day: &DayActivity, goal: u32) -> Option<u32> {
+    if day.is_empty() {
+        return None;
+    }
+
+    let goal = goal.max(1);
+    let progress = PROGRESS_POINTS * day.completed.min(goal) as f64 / goal as f64;
+
+    let penalty = day
+        .interruptions
+        .saturating_mul(INTERRUPTION_PENALTY)
+        .saturating_add(day.abandoned.saturating_mul(ABANDONED_PENALTY))
+        .saturating_add(day.skipped_breaks.saturating_mul(SKIPPED_BREAK_PENALTY));
+    let discipline = DISCIPLINE_POINTS.saturating_sub(penalty);
+
+    Some(((progress.round() as u32) + discipline).min(100))
+}
+
+/// Lowest score for each grade, best grade first.
+const GRADES: [(u32, &str); 4] = [(90, "A"), (80, "B"), (70, "C"), (60, "D")];
+
+/// Returns the letter grade of a focus score, from "A" to "F".
+pub fn letter_grade(score: u32) -> &'static str {
+    GRADES
+        .iter()
+        .find(|(min, _)| score >= *min)
+        .map_or("F", |(_, grade)| grade)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(completed: u32, interruptions: u32, abandoned: u32, skipped_breaks: u32) -> DayActivity {
+        DayActivity {
+            completed,
+            interruptions,
+            abandoned,
+            skipped_breaks,
+        }
+    }
+
+    #[test]
+    fn test_zero_activity_has_no_score() {
+        assert_eq!(focus_score(&DayActivity::default(), 8), None);
+    }
+
+    #[test]
+    fn test_perfect_day() {
+        assert_eq!(focus_score(&day(8, 0, 0, 0), 8), Some(100));
+    }
+
+    #[test]
+    fn test_progress_is_proportional_to_goal() {
+        // 4 of 8: 35 progress + 30 discipline
+        assert_eq!(focus_score(&day(4, 0, 0, 0), 8), Some(65));
+        // 1 of 3: 23.3 rounds to 23
+        assert_eq!(focus_score(&day(1, 0, 0, 0), 3), Some(53));
+    }
+
+    #[test]
+    fn test_progress_caps_at_goal() {
+        assert_eq!(focus_score(&day(12, 0, 0, 0), 8), Some(100));
+        assert_eq!(focus_score(&day(12, 1, 0, 0), 8), Some(96));
+    }
+
+    #[test]
+    fn test_penalties() {
+        assert_eq!(focus_score(&day(8, 2, 0, 0), 8), Some(92));
+        assert_eq!(focus_score(&day(8, 0, 1, 0), 8), Some(90));
+        assert_eq!(focus_score(&day(8, 0, 0, 1), 8), Some(95));
+        assert_eq!(focus_score(&day(8, 1, 1, 1), 8), Some(81));
+    }
+
+    #[test]
+    fn test_discipline_never_negative() {
+        assert_eq!(focus_score(&day(8, 20, 5, 5), 8), Some(70));
+        assert_eq!(focus_score(&day(0, 0, 3, 0), 8), Some(0));
+        // Huge counts don't overflow
+        assert_eq!(focus_score(&day(8, u32::MAX, u32::MAX, 0), 8), Some(70));
+    }
+
+    #[test]
+    fn test_only_abandoned_day_scores() {
+        // Activity without completions still gets a score
+        assert_eq!(focus_score(&day(0, 0, 1, 0), 8), Some(20));
+    }
+
+    #[test]
+    fn test_letter_grade_boundaries() {
+        for (score, grade) in [
+            (100, "A"),
+            (90, "A"),
+            (89, "B"),
+            (80, "B"),
+            (79, "C"),
+            (70, "C"),
+            (69, "D"),
+            (60, "D"),
+            (59, "F"),
+            (0, "F"),
+        ] {
+            assert_eq!(letter_grade(score), grade, "score {}", score);
+        }
+    }
+
+    #[test]
+    fn test_zero_goal_counts_as_one() {
+        assert_eq!(focus_score(&day(1, 0, 0, 0), 0), Some(100));
+        assert_eq!(focus_score(&day(0, 1, 0, 0), 0), Some(26));
+    }
+}
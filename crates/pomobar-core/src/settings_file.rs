@@ -0,0 +1,199 @@
+//! Exporting settings to a JSON file and importing them on another Mac.
+//!
+//! Import is lenient about the shape of the file: unknown fields are ignored
+//! and missing fields take their defaults, so files written by newer
+//! versions still import. The timings are checked before anything changes,
+//! and settings tied to this Mac keep their values.
+
+use crate::models::{OutOfRange, Settings, SoundChoice};
+use directories::UserDirs;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const SETTINGS_FILE_NAME: &str = "pomobar-settings.json";
+
+/// Why exporting or importing settings failed.
+#[derive(Error, Debug)]
+pub enum SettingsFileError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Not a valid settings file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    OutOfRange(#[from] OutOfRange),
+}
+
+/// Returns where settings are exported to and imported from:
+/// `~/Downloads/pomobar-settings.json`.
+pub fn default_path() -> PathBuf {
+    let dir = UserDirs::new()
+        .and_then(|dirs| dirs.download_dir().map(Path::to_path_buf))
+        .unwrap_or_default();
+    dir.join(SETTINGS_FILE_NAME)
+}
+
+/// Writes the settings to `path` as pretty-printed JSON.
+pub fn export(settings: &Settings, path: &Path) -> Result<(), SettingsFileError> {
+    let json = settings.to_versioned_json(true)?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+/// Reads and validates the settings in `path`.
+pub fn import(path: &Path) -> Result<Settings, SettingsFileError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// Parses and validates exported settings.
+pub fn parse(json: &str) -> Result<Settings, SettingsFileError> {
+    let settings = Settings::from_json(json)?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// Returns the imported settings with this Mac's values kept for those that
+/// belong to the machine rather than the user's routine: the webhook URLs,
+/// sound files and the metrics file. Their paths rarely exist on the other
+/// Mac, and the webhooks would post from both.
+pub fn keep_local(imported: Settings, current: &Settings) -> Settings {
+    let sound = |imported: SoundChoice, current: &SoundChoice| match imported {
+        SoundChoice::Custom(_) => current.clone(),
+        choice => choice,
+    };
+    Settings {
+        pomodoro_sound: sound(imported.pomodoro_sound, &current.pomodoro_sound),
+        break_sound: sound(imported.break_sound, &current.break_sound),
+        custom_sound_path: current.custom_sound_path.clone(),
+        metrics_textfile_path: current.metrics_textfile_path.clone(),
+        daily_webhook_url: current.daily_webhook_url.clone(),
+        webhook_url: current.webhook_url.clone(),
+        on_pomodoro_start: current.on_pomodoro_start.clone(),
+        on_pomodoro_complete: current.on_pomodoro_complete.clone(),
+        on_break_start: current.on_break_start.clone(),
+        on_break_complete: current.on_break_complete.clone(),
+        ..imported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(SETTINGS_FILE_NAME);
+        let settings = Settings {
+            pomodoro_mins: 50,
+            short_break_mins: 10,
+            sound_on_pomodoro_end: false,
+            tray_format: "{mm}:{ss}".to_string(),
+            ..Settings::default()
+        };
+
+        export(&settings, &path).unwrap();
+        assert_eq!(import(&path).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        let settings = parse(r#"{"pomodoro_mins": 45}"#).unwrap();
+        assert_eq!(settings.pomodoro_mins, 45);
+        assert_eq!(
+            settings.short_break_mins,
+            Settings::default().short_break_mins
+        );
+        // Except that an imported file never makes for a first launch
+        let imported = Settings {
+            first_run: false,
+            ..Settings::default()
+        };
+        assert_eq!(parse("{}").unwrap(), imported);
+    }
+
+    #[test]
+    fn test_imports_files_from_before_per_event_sounds() {
+        let settings = parse(r#"{"sound_enabled": false}"#).unwrap();
+        assert!(!settings.sound_on_pomodoro_end);
+        assert!(!settings.sound_on_break_end);
+    }
+
+    #[test]
+    fn test_keep_local_keeps_machine_settings() {
+        let current = Settings {
+            custom_sound_path: Some("/Users/me/chime.mp3".to_string()),
+            metrics_textfile_path: Some("/var/lib/node_exporter/pomobar.prom".into()),
+            daily_webhook_url: Some("http://localhost/daily".to_string()),
+            on_break_start: Some("http://localhost/break".to_string()),
+            break_sound: SoundChoice::Soft,
+            ..Settings::default()
+        };
+        let imported = Settings {
+            pomodoro_mins: 50,
+            custom_sound_path: Some("/Users/other/bell.wav".to_string()),
+            webhook_url: Some("http://other/events".to_string()),
+            pomodoro_sound: SoundChoice::Bell,
+            break_sound: SoundChoice::Custom("/Users/other/gong.mp3".to_string()),
+            ..Settings::default()
+        };
+
+        let merged = keep_local(imported, &current);
+        assert_eq!(merged.pomodoro_mins, 50);
+        assert_eq!(merged.pomodoro_sound, SoundChoice::Bell);
+        assert_eq!(merged.break_sound, SoundChoice::Soft);
+        assert_eq!(merged.custom_sound_path, current.custom_sound_path);
+        assert_eq!(merged.metrics_textfile_path, current.metrics_textfile_path);
+        assert_eq!(merged.daily_webhook_url, current.daily_webhook_url);
+        assert_eq!(merged.webhook_url, None);
+        assert_eq!(merged.on_break_start, current.on_break_start);
+    }
+
+    #[test]
+    fn test_unknown_fields_ignored() {
+        let settings = parse(r#"{"pomodoro_mins": 30, "from_the_future": [1, 2]}"#).unwrap();
+        assert_eq!(settings.pomodoro_mins, 30);
+    }
+
+    #[test]
+    fn test_rejects_bad_durations() {
+        for json in [
+            r#"{"pomodoro_mins": 0}"#,
+            r#"{"short_break_mins": 0}"#,
+            r#"{"long_break_mins": 10000}"#,
+            r#"{"pomodoros_for_long_break": 0}"#,
+            r#"{"day_starts_at_hour": 12}"#,
+            r#"{"progress_bar_width": 4}"#,
+            r#"{"progress_bar_width": 41}"#,
+        ] {
+            assert!(
+                matches!(parse(json), Err(SettingsFileError::OutOfRange(..))),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        assert!(matches!(
+            parse("{\"pomodoro_mins\": "),
+            Err(SettingsFileError::Json(_))
+        ));
+        assert!(matches!(
+            parse(r#"{"pomodoro_mins": "long"}"#),
+            Err(SettingsFileError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let dir = TempDir::new().unwrap();
+        assert!(matches!(
+            import(&dir.path().join("nope.json")),
+            Err(SettingsFileError::Io(_))
+        ));
+    }
+}
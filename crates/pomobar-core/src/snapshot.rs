@@ -0,0 +1,71 @@
+//! What the menu shows, copied out of the app.
+
+use crate::app::{App, StartRefused};
+use crate::focus;
+use crate::models::{DailyStats, Session, Settings, TaskHistory, TimerState, WeekTotals};
+use chrono::{DateTime, Local};
+
+/// Everything the menu shows, copied from the app on the timer actor so the
+/// main thread draws the menu without touching `App`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuSnapshot {
+    pub state: TimerState,
+    pub session: Session,
+    pub settings: Settings,
+    pub getting_started: bool,
+    pub auto_paused: bool,
+    pub start_anyway_armed: bool,
+    /// Why Start would be refused right now, offering Start Anyway instead.
+    pub start_refused: Option<StartRefused>,
+    pub wind_down_ends_at: Option<DateTime<Local>>,
+    pub persist_failed: bool,
+    /// Whether a second database is waiting to be merged.
+    pub stray_db: bool,
+    pub yesterday: DailyStats,
+    pub week: WeekTotals,
+    pub focus_score: Option<u32>,
+    pub heatmap: String,
+    pub all_time: String,
+    /// Interruptions marked during the current pomodoro.
+    pub interruptions: u32,
+    /// The task completed pomodoros are logged under.
+    pub task: Option<String>,
+    pub tasks: TaskHistory,
+    /// Whether the tray title shows its blink frame after a break ran out.
+    pub attention_blink: bool,
+    /// Whether Do Not Disturb or another Focus is on, None if it can't be
+    /// read. Only read while notifications respect it.
+    pub dnd_active: Option<bool>,
+}
+
+impl MenuSnapshot {
+    /// Copies what the menu shows from `app`.
+    pub fn new(app: &App) -> Self {
+        Self {
+            state: app.state.clone(),
+            session: app.session.clone(),
+            settings: app.settings.clone(),
+            getting_started: app.getting_started,
+            auto_paused: app.auto_paused,
+            start_anyway_armed: app.start_anyway_armed,
+            start_refused: app.start_refused(),
+            wind_down_ends_at: app.wind_down_ends_at,
+            persist_failed: app.last_persist_error.is_some(),
+            stray_db: app.stray_db.is_some(),
+            yesterday: app.yesterday_stats(),
+            week: app.week_totals(),
+            focus_score: app.focus_score(),
+            heatmap: app.history_heatmap(),
+            all_time: app.all_time_stats(),
+            interruptions: app.pomodoro_interruptions,
+            task: app.task.clone(),
+            tasks: app.task_history(),
+            attention_blink: app.attention_blink_on(),
+            dnd_active: if app.settings.respect_dnd {
+                focus::system_focus_active()
+            } else {
+                None
+            },
+        }
+    }
+}
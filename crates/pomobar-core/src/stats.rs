@@ -0,0 +1,113 @@
+//! All-time comparisons over the stored daily stats.
+//!
+//! These only see days that have a row in the database; days Pomobar never
+//! recorded anything on don't count towards the averages.
+
+use crate::models::DailyStats;
+use chrono::Datelike;
+
+/// Returns the day with the most completed pomodoros, the earliest of them
+/// on a tie. None if no day has a completed pomodoro.
+pub fn best_day(stats: &[DailyStats]) -> Option<&DailyStats> {
+    stats
+        .iter()
+        .filter(|day| day.completed_pomodoros > 0)
+        .rev()
+        .max_by_key(|day| day.completed_pomodoros)
+}
+
+/// Returns the average pomodoros per recorded day for each weekday,
+/// indexed by `Weekday::num_days_from_monday`. Weekdays without a recorded
+/// day average zero.
+pub fn avg_per_weekday(stats: &[DailyStats]) -> [f32; 7] {
+    let mut totals = [0u32; 7];
+    let mut days = [0u32; 7];
+    for day in stats {
+        let i = day.date.weekday().num_days_from_monday() as usize;
+        totals[i] += day.completed_pomodoros;
+        days[i] += 1;
+    }
+    std::array::from_fn(|i| {
+        if days[i] == 0 {
+            0.0
+        } else {
+            totals[i] as f32 / days[i] as f32
+        }
+    })
+}
+
+/// Returns the completed pomodoros and focus minutes of all days together.
+pub fn total_all_time(stats: &[DailyStats]) -> (u32, u32) {
+    stats.iter().fold((0, 0), |(pomodoros, mins), day| {
+        (
+            pomodoros + day.completed_pomodoros,
+            mins + day.total_focus_minutes,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Days, NaiveDate, Weekday};
+
+    /// Consecutive days from Monday 2024-01-01 with the given counts.
+    fn days(counts: &[u32]) -> Vec<DailyStats> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| DailyStats {
+                completed_pomodoros: count,
+                total_focus_minutes: count * 25,
+                ..DailyStats::new(start + Days::new(i as u64))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(best_day(&[]), None);
+        assert_eq!(avg_per_weekday(&[]), [0.0; 7]);
+        assert_eq!(total_all_time(&[]), (0, 0));
+    }
+
+    #[test]
+    fn test_single_entry() {
+        let stats = days(&[3]);
+        assert_eq!(best_day(&stats), Some(&stats[0]));
+        assert_eq!(avg_per_weekday(&stats), [3.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(total_all_time(&stats), (3, 75));
+    }
+
+    #[test]
+    fn test_all_zero_entries() {
+        let stats = days(&[0, 0, 0]);
+        assert_eq!(best_day(&stats), None);
+        assert_eq!(avg_per_weekday(&stats), [0.0; 7]);
+        assert_eq!(total_all_time(&stats), (0, 0));
+    }
+
+    #[test]
+    fn test_multiple_weeks() {
+        // Three Mondays (4, 2, 6) and two Sundays (1, 0)
+        let mut counts = vec![4, 1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 0, 6];
+        let stats = days(&counts);
+
+        let best = best_day(&stats).unwrap();
+        assert_eq!(best.date, NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        assert_eq!(best.date.weekday(), Weekday::Mon);
+
+        let avg = avg_per_weekday(&stats);
+        assert_eq!(avg[Weekday::Mon.num_days_from_monday() as usize], 4.0);
+        assert_eq!(avg[Weekday::Sun.num_days_from_monday() as usize], 0.5);
+        assert_eq!(avg[Weekday::Wed.num_days_from_monday() as usize], 1.0);
+
+        assert_eq!(total_all_time(&stats), (23, 23 * 25));
+
+        // On a tie the earlier day wins
+        counts[14] = 4;
+        let stats = days(&counts);
+        assert_eq!(best_day(&stats).unwrap().date, stats[0].date);
+    }
+}
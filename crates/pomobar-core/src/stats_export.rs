@@ -0,0 +1,132 @@
+//! Exporting the daily stats to CSV and JSON files for spreadsheets and
+//! scripts.
+//!
+//! Finished days carry the focus score they were finalized with; today
+//! carries its score so far.
+
+use crate::models::DailyStats;
+use crate::persistence::DatabaseError;
+use directories::UserDirs;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const CSV_FILE_NAME: &str = "pomobar-stats.csv";
+const JSON_FILE_NAME: &str = "pomobar-stats.json";
+
+/// Columns of the CSV file, in order.
+const CSV_HEADER: &str = "date,completed_pomodoros,total_focus_minutes,interruptions,\
+marked_interruptions,abandoned,skipped_breaks,interrupted_minutes,focus_score";
+
+/// Why exporting the stats failed.
+#[derive(Error, Debug)]
+pub enum StatsExportError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] DatabaseError),
+}
+
+/// One day of the export.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ExportedDay {
+    #[serde(flatten)]
+    pub stats: DailyStats,
+    /// None for a day without activity.
+    pub focus_score: Option<u32>,
+}
+
+/// Returns the folder stats are exported to: `~/Downloads`.
+pub fn default_dir() -> PathBuf {
+    UserDirs::new()
+        .and_then(|dirs| dirs.download_dir().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
+
+/// Writes the days to `pomobar-stats.csv` and `pomobar-stats.json` in `dir`.
+/// Returns the paths of the CSV and the JSON file.
+pub fn export(days: &[ExportedDay], dir: &Path) -> Result<(PathBuf, PathBuf), StatsExportError> {
+    let csv_path = dir.join(CSV_FILE_NAME);
+    let json_path = dir.join(JSON_FILE_NAME);
+    fs::write(&csv_path, to_csv(days))?;
+    fs::write(&json_path, serde_json::to_string_pretty(days)?)?;
+    Ok((csv_path, json_path))
+}
+
+/// Formats the days as CSV, one row per day under a header row. A day
+/// without a score has an empty last column.
+pub fn to_csv(days: &[ExportedDay]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for day in days {
+        let stats = &day.stats;
+        let score = day.focus_score.map(|s| s.to_string()).unwrap_or_default();
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{}",
+            stats.date,
+            stats.completed_pomodoros,
+            stats.total_focus_minutes,
+            stats.interruptions,
+            stats.marked_interruptions,
+            stats.abandoned,
+            stats.skipped_breaks,
+            stats.interrupted_minutes,
+            score
+        );
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use tempfile::TempDir;
+
+    fn exported(day: u32, pomodoros: u32, focus_score: Option<u32>) -> ExportedDay {
+        ExportedDay {
+            stats: DailyStats {
+                completed_pomodoros: pomodoros,
+                total_focus_minutes: pomodoros * 25,
+                ..DailyStats::new(NaiveDate::from_ymd_opt(2024, 1, day).unwrap())
+            },
+            focus_score,
+        }
+    }
+
+    #[test]
+    fn test_csv_has_the_focus_score() {
+        let csv = to_csv(&[exported(14, 0, None), exported(15, 4, Some(82))]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with(",focus_score"));
+        assert_eq!(lines[1], "2024-01-14,0,0,0,0,0,0,0,");
+        assert_eq!(lines[2], "2024-01-15,4,100,0,0,0,0,0,82");
+    }
+
+    #[test]
+    fn test_export_writes_both_files() {
+        let dir = TempDir::new().unwrap();
+        let (csv_path, json_path) = export(&[exported(15, 4, Some(82))], dir.path()).unwrap();
+
+        let csv = fs::read_to_string(csv_path).unwrap();
+        assert!(csv.ends_with("2024-01-15,4,100,0,0,0,0,0,82\n"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(json_path).unwrap()).unwrap();
+        assert_eq!(json[0]["date"], "2024-01-15");
+        assert_eq!(json[0]["completed_pomodoros"], 4);
+        assert_eq!(json[0]["focus_score"], 82);
+    }
+
+    #[test]
+    fn test_json_day_without_score() {
+        let json = serde_json::to_value([exported(14, 0, None)]).unwrap();
+        assert!(json[0]["focus_score"].is_null());
+    }
+}
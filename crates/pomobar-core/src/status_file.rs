@@ -0,0 +1,139 @@
+//! JSON status file for external status bars such as SketchyBar or xbar.
+//!
+//! The file is replaced atomically (write to a temp file, then rename) so
+//! readers never see partial JSON.
+
+use crate::models::StatusSnapshot;
+use crate::persistence;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const STATUS_FILE_NAME: &str = "status.json";
+
+/// Minimum time between two writes.
+const MIN_WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Returns the status file path in the data directory.
+pub fn status_path() -> PathBuf {
+    persistence::data_dir().join(STATUS_FILE_NAME)
+}
+
+/// Writes `contents` to `path` by writing a sibling temp file and renaming it
+/// over the target, which is atomic on the same filesystem.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Writes status snapshots to a file, skipping unchanged snapshots and
+/// writing at most once per second.
+pub struct StatusWriter {
+    path: PathBuf,
+    last_written: Option<StatusSnapshot>,
+    last_write_at: Option<Instant>,
+}
+
+impl StatusWriter {
+    /// Creates a writer for the given file.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_written: None,
+            last_write_at: None,
+        }
+    }
+
+    /// Writes the snapshot if it differs from the last one written and the
+    /// throttle interval has passed. Returns true if the file was written.
+    /// A throttled snapshot is picked up by the next call.
+    pub fn update(&mut self, snapshot: &StatusSnapshot, now: Instant) -> bool {
+        if self.last_written.as_ref() == Some(snapshot) {
+            return false;
+        }
+        if let Some(at) = self.last_write_at {
+            if now.duration_since(at) < MIN_WRITE_INTERVAL {
+                return false;
+            }
+        }
+
+        let json = match serde_json::to_vec(snapshot) {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("Failed to serialize status: {}", e);
+                return false;
+            }
+        };
+        if let Err(e) = write_atomic(&self.path, &json) {
+            eprintln!("Failed to write status file: {}", e);
+            return false;
+        }
+
+        self.last_written = Some(snapshot.clone());
+        self.last_write_at = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Session, TimerState};
+    use tempfile::TempDir;
+
+    fn snapshot(remaining_secs: u32) -> StatusSnapshot {
+        StatusSnapshot::new(
+            &TimerState::PomodoroActive {
+                remaining_secs,
+                total_secs: 1500,
+            },
+            &Session::default(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("status.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        // The temp file is renamed away, not left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_writer_writes_parseable_json() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("status.json");
+        let mut writer = StatusWriter::new(path.clone());
+
+        assert!(writer.update(&snapshot(1200), Instant::now()));
+
+        let loaded: StatusSnapshot =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(loaded, snapshot(1200));
+    }
+
+    #[test]
+    fn test_writer_skips_unchanged_and_throttles() {
+        let dir = TempDir::new().unwrap();
+        let mut writer = StatusWriter::new(dir.path().join("status.json"));
+        let start = Instant::now();
+
+        assert!(writer.update(&snapshot(1200), start));
+        assert!(!writer.update(&snapshot(1200), start + Duration::from_secs(5)));
+
+        // A change within the interval waits for the next call
+        assert!(!writer.update(&snapshot(1199), start + Duration::from_millis(500)));
+        assert!(writer.update(&snapshot(1199), start + Duration::from_secs(1)));
+    }
+}
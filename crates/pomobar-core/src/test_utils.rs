@@ -0,0 +1,134 @@
+//! Helpers shared by tests across modules.
+
+use crate::actor::{Command, Wake};
+use crate::timer::{Clock, TICK_INTERVAL};
+use chrono::{DateTime, Duration, Local};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration as StdDuration, Instant, SystemTime};
+
+/// How often a `FakeClock` waiting for ticks checks for commands.
+const COMMAND_POLL_INTERVAL: StdDuration = StdDuration::from_millis(5);
+
+/// A clock for running the timer actor deterministically. The actor blocks
+/// in `wait_for_tick` until the test hands out ticks with `advance`, and
+/// each tick moves the clock forward by exactly one second. Commands sent
+/// before `advance` run before its ticks.
+pub struct FakeClock {
+    state: Mutex<FakeClockState>,
+    changed: Condvar,
+}
+
+struct FakeClockState {
+    now: SystemTime,
+    /// Ticks handed out but not yet started by the loop.
+    pending: u32,
+    /// Whether the actor is waiting in `wait_for_tick`, i.e. done with
+    /// every tick and command it started.
+    waiting: bool,
+}
+
+impl FakeClock {
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            state: Mutex::new(FakeClockState {
+                now: start,
+                pending: 0,
+                waiting: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Lets the actor run `ticks` ticks and returns once it has finished them
+    /// and sent their messages.
+    pub fn advance(&self, ticks: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.pending += ticks;
+        self.changed.notify_all();
+        while state.pending > 0 || !state.waiting {
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+
+    /// Moves the clock forward by `secs` without any ticks, like a Mac
+    /// that is asleep.
+    pub fn sleep(&self, secs: u64) {
+        self.state.lock().unwrap().now += StdDuration::from_secs(secs);
+    }
+}
+
+impl Clock for FakeClock {
+    fn wait_for_tick(&self, _deadline: Instant, inbox: &Receiver<Command>) -> Wake {
+        let mut state = self.state.lock().unwrap();
+        state.waiting = true;
+        self.changed.notify_all();
+        loop {
+            match inbox.try_recv() {
+                Ok(command) => {
+                    state.waiting = false;
+                    return Wake::Command(command);
+                }
+                Err(TryRecvError::Disconnected) => return Wake::Disconnected,
+                Err(TryRecvError::Empty) => {}
+            }
+            if state.pending > 0 {
+                break;
+            }
+            state = self
+                .changed
+                .wait_timeout(state, COMMAND_POLL_INTERVAL)
+                .unwrap()
+                .0;
+        }
+        state.pending -= 1;
+        state.waiting = false;
+        state.now += TICK_INTERVAL;
+        Wake::Tick
+    }
+
+    fn now(&self) -> SystemTime {
+        self.state.lock().unwrap().now
+    }
+}
+
+/// A clock for `App` that stands still until the test moves it. Clones
+/// share the same time, so a test keeps one to move the clock after handing
+/// another to the app.
+#[derive(Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Local>>>,
+}
+
+impl MockClock {
+    pub fn new(now: DateTime<Local>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Jumps to `now`.
+    pub fn set(&self, now: DateTime<Local>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn wait_for_tick(&self, _deadline: Instant, _inbox: &Receiver<Command>) -> Wake {
+        self.advance(Duration::seconds(1));
+        Wake::Tick
+    }
+
+    fn now(&self) -> SystemTime {
+        self.now_local().into()
+    }
+
+    fn now_local(&self) -> DateTime<Local> {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,1040 @@
+//! Timer ticks for updating pomodoro state.
+
+use crate::actor::{Command, Wake};
+use crate::app::{App, CompletionEvent, StartRefused, WarningEvent};
+use crate::format::{format_clock_time, format_duration};
+use crate::metrics::MetricsWriter;
+use crate::models::{
+    DailySummary, Session, Settings, StatusSnapshot, TimerState, DEFAULT_TRAY_FORMAT,
+};
+use crate::snapshot::MenuSnapshot;
+use crate::status_file::{self, StatusWriter};
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Tray tooltip shown with no timer running and nothing done yet today.
+pub const DEFAULT_TOOLTIP: &str = "Pomobar - Pomodoro Timer";
+
+/// Time between two ticks.
+pub const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tray title shown every other second while a break that ran out waits
+/// for the next pomodoro, see `App::blink_attention`.
+pub const ATTENTION_TITLE: &str = "🍅!";
+
+/// Minimum time between tooltip updates while a timer counts down.
+const TOOLTIP_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Message sent from the timer actor to the main thread.
+#[derive(Debug, Clone)]
+pub enum TimerMessage {
+    /// Timer state has changed; the menu and tray show the snapshot.
+    StateChanged(Box<MenuSnapshot>),
+    /// A timer completed, trigger notification/sound.
+    Completed(CompletionEvent),
+    /// The running timer is about to end.
+    Warning(WarningEvent),
+    /// A pomodoro is in its last minute; play the ticking sound.
+    TickSound,
+    /// Saving to the database failed; tell the user (rate-limited by `App`).
+    PersistFailed { error: String },
+    /// The timer has sat idle after a break; remind to start the next pomodoro.
+    Reminder { count: u32 },
+    /// The tray tooltip should change.
+    TooltipChanged { tooltip: String },
+    /// A day with activity has ended; show its summary.
+    DayFinished(DailySummary),
+    /// A start from `pomobar-ctl` or AppleScript was refused; tell the user
+    /// and offer Start Anyway.
+    StartRefused(StartRefused),
+}
+
+/// Where the timer actor and `App` get their time from, so tests can run
+/// them on a fake clock.
+pub trait Clock {
+    /// Waits until `deadline` for the next tick, returning early when a
+    /// command arrives in `inbox`.
+    fn wait_for_tick(&self, deadline: Instant, inbox: &Receiver<Command>) -> Wake;
+    /// Returns the current wall-clock time.
+    fn now(&self) -> SystemTime;
+    /// Returns the current time in the local time zone.
+    fn now_local(&self) -> DateTime<Local> {
+        self.now().into()
+    }
+}
+
+/// The real clock: sleeps the thread until the tick is due.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn wait_for_tick(&self, deadline: Instant, inbox: &Receiver<Command>) -> Wake {
+        match inbox.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(command) => Wake::Command(command),
+            Err(RecvTimeoutError::Timeout) => Wake::Tick,
+            Err(RecvTimeoutError::Disconnected) => Wake::Disconnected,
+        }
+    }
+
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// What one tick produced.
+pub struct TickOutput {
+    /// Messages for the main thread, in the order they should be handled.
+    pub messages: Vec<TimerMessage>,
+    /// Whether yesterday's summary was queued and the webhook queue should be flushed.
+    pub summary_queued: bool,
+}
+
+/// The work done on every tick, shared by the timer actor and the
+/// event-loop timer so both modes behave the same.
+pub struct Ticker {
+    last_tick: SystemTime,
+    status_writer: StatusWriter,
+    metrics_writer: Option<MetricsWriter>,
+    tooltip_throttle: TooltipThrottle,
+}
+
+impl Ticker {
+    /// Creates a ticker that measures the first tick from `now`.
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            last_tick: now,
+            status_writer: StatusWriter::new(status_file::status_path()),
+            metrics_writer: None,
+            tooltip_throttle: TooltipThrottle::default(),
+        }
+    }
+
+    /// Advances the app to `now` and collects the resulting messages.
+    pub fn step(&mut self, app: &mut App, now: SystemTime, idle_secs: f64) -> TickOutput {
+        // A sleeping Mac pauses the tick schedule, but not the wall clock
+        let elapsed = now.duration_since(self.last_tick).unwrap_or_default();
+        self.last_tick = now;
+        let local_now: DateTime<Local> = now.into();
+        let mut messages = Vec::new();
+
+        // Check for day rollover, queueing yesterday's summary
+        let summary_queued = app.check_day_rollover();
+
+        // Retry database writes that failed earlier
+        let saved = app.retry_pending_writes();
+
+        // Advance by the measured time, which also covers time spent asleep
+        let (ticked, completion) = app.advance(elapsed);
+
+        // Auto-pause/resume based on system idle time
+        let idle_changed = app.apply_idle(idle_secs);
+        // Blinks the tray title while waiting after a break
+        let blinked = app.blink_attention();
+        let changed = ticked || idle_changed || saved || blinked;
+        let completed = completion.is_some();
+
+        if let Some(event) = completion {
+            messages.push(TimerMessage::Completed(event));
+        }
+
+        if let Some(warning) = app.take_warning() {
+            messages.push(TimerMessage::Warning(warning));
+        }
+
+        if app.take_tick_sound() {
+            messages.push(TimerMessage::TickSound);
+        }
+
+        if let Some(error) = app.take_persist_error_notice(local_now) {
+            messages.push(TimerMessage::PersistFailed { error });
+        }
+
+        if let Some(summary) = app.take_finished_day() {
+            messages.push(TimerMessage::DayFinished(summary));
+        }
+
+        if app.reminder_due(local_now) {
+            let count = app.session.pomodoros_completed_today;
+            messages.push(TimerMessage::Reminder { count });
+        }
+
+        // Countdown in the tooltip, refreshed at most every 15 seconds
+        let tooltip = format_tooltip(&app.state, &app.session, local_now, app.settings.clock_24h);
+        if let Some(tooltip) = self.tooltip_throttle.update(tooltip, Instant::now()) {
+            messages.push(TimerMessage::TooltipChanged { tooltip });
+        }
+
+        // Checked every tick so changes made from the menu are picked up too
+        if app.settings.status_file_enabled {
+            let snapshot = StatusSnapshot::new(&app.state, &app.session, app.focus_score());
+            self.status_writer.update(&snapshot, Instant::now());
+        }
+        self.update_metrics(app, completed);
+
+        if changed {
+            let snapshot = MenuSnapshot::new(app);
+            messages.push(TimerMessage::StateChanged(Box::new(snapshot)));
+        }
+
+        TickOutput {
+            messages,
+            summary_queued,
+        }
+    }
+
+    /// Writes the metrics file if one is configured, starting over with a
+    /// fresh writer when the path changes.
+    fn update_metrics(&mut self, app: &App, completed: bool) {
+        let Some(path) = &app.settings.metrics_textfile_path else {
+            self.metrics_writer = None;
+            return;
+        };
+        if self.metrics_writer.as_ref().map(|w| w.path()) != Some(path.as_path()) {
+            self.metrics_writer = Some(MetricsWriter::new(path.clone()));
+        }
+        if let Some(writer) = &mut self.metrics_writer {
+            writer.update(
+                &app.state,
+                &app.session,
+                app.focus_score(),
+                completed,
+                Instant::now(),
+            );
+        }
+    }
+}
+
+/// Drives the ticker from the event loop instead of a thread: the loop
+/// sleeps until `next_tick` and calls `poll` whenever it wakes.
+pub struct EventLoopTimer {
+    ticker: Ticker,
+    next_tick: Instant,
+}
+
+impl EventLoopTimer {
+    /// Creates a timer whose first tick is one second after `now`.
+    pub fn new(now: Instant, wall_now: SystemTime) -> Self {
+        Self {
+            ticker: Ticker::new(wall_now),
+            next_tick: now + TICK_INTERVAL,
+        }
+    }
+
+    /// When the event loop should wake up for the next tick.
+    pub fn next_tick(&self) -> Instant {
+        self.next_tick
+    }
+
+    /// Runs a tick if one is due. Wake-ups for other events return None.
+    /// Like the timer actor, the next tick is scheduled one second after
+    /// this one rather than on a fixed grid. `idle_secs` is only called
+    /// when a tick runs.
+    pub fn poll(
+        &mut self,
+        app: &mut App,
+        now: Instant,
+        wall_now: SystemTime,
+        idle_secs: impl FnOnce() -> f64,
+    ) -> Option<TickOutput> {
+        if now < self.next_tick {
+            return None;
+        }
+        self.next_tick = now + TICK_INTERVAL;
+        Some(self.ticker.step(app, wall_now, idle_secs()))
+    }
+}
+
+/// Placeholders the tray format can use.
+pub const TRAY_PLACEHOLDERS: [&str; 6] = ["icon", "mm", "ss", "remaining", "pct", "task"];
+
+/// Ready-made tray formats offered in the menu, each with an example title.
+pub const TRAY_FORMAT_CHOICES: [(&str, &str); 4] = [
+    (DEFAULT_TRAY_FORMAT, "🍅 24:59"),
+    ("{icon} {remaining}", "🍅 25m"),
+    ("{icon} {pct}%", "🍅 83%"),
+    ("{icon} {task}", "🍅 Write report"),
+];
+
+/// Returns the first `{name}` in `format` that isn't one of
+/// `TRAY_PLACEHOLDERS`, or None if all of them are.
+pub fn unknown_tray_placeholder(format: &str) -> Option<&str> {
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let end = after.find('}')?;
+        let name = &after[..end];
+        if name.contains('{') {
+            // A stray brace before the placeholder, as in "{{mm}"
+            rest = after;
+            continue;
+        }
+        if !TRAY_PLACEHOLDERS.contains(&name) {
+            return Some(name);
+        }
+        rest = &after[end + 1..];
+    }
+    None
+}
+
+/// Renders the tray title from the `tray_format` setting.
+/// Supports the `TRAY_PLACEHOLDERS`; a format with any other placeholder
+/// (only possible by editing the settings by hand) falls back to the
+/// default. When no timer is running, or `show_time_in_menubar` is off,
+/// the title is just the icon; a running timer shown only as the progress
+/// ring (`TrayDisplay::Icon`) has no title at all. From one hour on
+/// `{mm}` includes the hours, e.g. "🍅 1:05:00". In overtime the time counts
+/// up and `{mm}` gets a "+" sign, e.g. "🍅 +01:23"; the stopwatch counts up
+/// with a ⏱ icon. `{remaining}` is the time in whole minutes, e.g. "25m" or
+/// "1h 5m", rounded up while counting down.
+pub fn render_tray_title(state: &TimerState, settings: &Settings, task: Option<&str>) -> String {
+    let (icon, sign, secs) = match state {
+        TimerState::Idle | TimerState::BreakFinished => return "🍅".to_string(),
+        TimerState::StopwatchActive { elapsed_secs } => ("⏱", "", *elapsed_secs),
+        TimerState::StopwatchPaused { elapsed_secs } => ("⏸", "", *elapsed_secs),
+        TimerState::PomodoroActive { remaining_secs, .. } => ("🍅", "", *remaining_secs),
+        TimerState::Overtime {
+            elapsed_overtime_secs,
+            ..
+        } => ("🍅", "+", *elapsed_overtime_secs),
+        TimerState::PomodoroPaused { remaining_secs, .. }
+        | TimerState::BreakPaused { remaining_secs, .. } => ("⏸", "", *remaining_secs),
+        TimerState::BreakActive { remaining_secs, .. } => ("☕", "", *remaining_secs),
+    };
+    if !settings.tray_display.shows_text() {
+        return String::new();
+    }
+    if !settings.show_time_in_menubar {
+        return icon.to_string();
+    }
+    let pct = (state.progress_percent().unwrap_or(0.0) * 100.0).round() as u32;
+    let mins = if state.remaining_secs().is_some() {
+        secs.div_ceil(60)
+    } else {
+        secs / 60
+    };
+    let remaining = if mins >= 60 {
+        format!("{}{}h {}m", sign, mins / 60, mins % 60)
+    } else {
+        format!("{}{}m", sign, mins)
+    };
+    let format = match unknown_tray_placeholder(&settings.tray_format) {
+        Some(_) => DEFAULT_TRAY_FORMAT,
+        None => &settings.tray_format,
+    };
+
+    format
+        .replace("{icon}", icon)
+        .replace("{remaining}", &remaining)
+        .replace("{mm}", &format!("{}{}", sign, format_minutes(secs)))
+        .replace("{ss}", &format!("{:02}", secs % 60))
+        .replace("{pct}", &pct.to_string())
+        .replace("{task}", task.unwrap_or(""))
+}
+
+/// Formats the tray tooltip: the running timer and today's pomodoros, e.g.
+/// "Pomodoro · 14:02 left · 3 done today (1h 15min)". A break adds when it
+/// ends: "back at 14:35", or "back ~14:35 if resumed now" while paused.
+/// With no timer running and nothing done yet, this is `DEFAULT_TOOLTIP`.
+pub fn format_tooltip(
+    state: &TimerState,
+    session: &Session,
+    now: DateTime<Local>,
+    clock_24h: bool,
+) -> String {
+    let paused = if state.is_paused() { " (paused)" } else { "" };
+    let timer = match state {
+        TimerState::Idle | TimerState::BreakFinished => None,
+        TimerState::PomodoroActive { remaining_secs, .. }
+        | TimerState::PomodoroPaused { remaining_secs, .. } => Some(format!(
+            "Pomodoro{} · {} left",
+            paused,
+            format_time(*remaining_secs)
+        )),
+        TimerState::Overtime {
+            elapsed_overtime_secs,
+            ..
+        } => Some(format!(
+            "Pomodoro · +{} overtime",
+            format_time(*elapsed_overtime_secs)
+        )),
+        TimerState::BreakActive {
+            is_long_break,
+            remaining_secs,
+            ..
+        }
+        | TimerState::BreakPaused {
+            is_long_break,
+            remaining_secs,
+            ..
+        } => {
+            let kind = if *is_long_break {
+                "Long break"
+            } else {
+                "Short break"
+            };
+            let back_at = format_clock_time(
+                now + ChronoDuration::seconds(*remaining_secs as i64),
+                clock_24h,
+            );
+            let back = if state.is_paused() {
+                format!("back ~{} if resumed now", back_at)
+            } else {
+                format!("back at {}", back_at)
+            };
+            Some(format!(
+                "{}{} · {} left · {}",
+                kind,
+                paused,
+                format_time(*remaining_secs),
+                back
+            ))
+        }
+        TimerState::StopwatchActive { elapsed_secs }
+        | TimerState::StopwatchPaused { elapsed_secs } => Some(format!(
+            "Stopwatch{} · {} elapsed",
+            paused,
+            format_time(*elapsed_secs)
+        )),
+    };
+
+    let count = session.pomodoros_completed_today;
+    let done = (count > 0).then(|| {
+        format!(
+            "{} done today ({})",
+            count,
+            format_duration(session.total_focus_mins_today)
+        )
+    });
+    match (timer, done) {
+        (Some(timer), Some(done)) => format!("{} · {}", timer, done),
+        (Some(timer), None) => timer,
+        (None, Some(done)) => format!("Pomobar · {}", done),
+        (None, None) => DEFAULT_TOOLTIP.to_string(),
+    }
+}
+
+/// Limits how often the tooltip is updated while a timer counts down.
+/// A change of label (the part before the first " · "), such as starting,
+/// pausing or finishing a timer, is passed through immediately.
+#[derive(Default)]
+struct TooltipThrottle {
+    /// The last tooltip sent, and when it was sent.
+    last: Option<(String, Instant)>,
+}
+
+impl TooltipThrottle {
+    /// Returns the tooltip to show now, or None if it should stay as it is.
+    fn update(&mut self, tooltip: String, now: Instant) -> Option<String> {
+        if let Some((last, sent_at)) = &self.last {
+            if *last == tooltip {
+                return None;
+            }
+            if tooltip_label(last) == tooltip_label(&tooltip) && now - *sent_at < TOOLTIP_INTERVAL {
+                return None;
+            }
+        }
+
+        self.last = Some((tooltip.clone(), now));
+        Some(tooltip)
+    }
+}
+
+/// Returns the label part of a tooltip, e.g. "Short break".
+fn tooltip_label(tooltip: &str) -> &str {
+    tooltip.split(" · ").next().unwrap_or(tooltip)
+}
+
+/// Formats time as MM:SS, or H:MM:SS from one hour on.
+pub fn format_time(secs: u32) -> String {
+    format!("{}:{:02}", format_minutes(secs), secs % 60)
+}
+
+/// Formats the minutes part of a time: MM, or H:MM from one hour on.
+fn format_minutes(secs: u32) -> String {
+    if secs < 3600 {
+        format!("{:02}", secs / 60)
+    } else {
+        format!("{}:{:02}", secs / 3600, secs / 60 % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TrayDisplay;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render_tray_title_idle() {
+        let state = TimerState::Idle;
+        assert_eq!(render_tray_title(&state, &Settings::default(), None), "🍅");
+    }
+
+    #[test]
+    fn test_render_tray_title_break_finished() {
+        let state = TimerState::BreakFinished;
+        assert_eq!(render_tray_title(&state, &Settings::default(), None), "🍅");
+    }
+
+    #[test]
+    fn test_render_tray_title_pomodoro_active() {
+        let state = TimerState::PomodoroActive {
+            remaining_secs: 1432,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            render_tray_title(&state, &Settings::default(), None),
+            "🍅 23:52"
+        );
+    }
+
+    #[test]
+    fn test_render_tray_title_stopwatch() {
+        let settings = Settings::default();
+        assert_eq!(
+            render_tray_title(
+                &TimerState::StopwatchActive { elapsed_secs: 754 },
+                &settings,
+                None
+            ),
+            "⏱ 12:34"
+        );
+        assert_eq!(
+            render_tray_title(
+                &TimerState::StopwatchPaused { elapsed_secs: 3723 },
+                &settings,
+                None
+            ),
+            "⏸ 1:02:03"
+        );
+    }
+
+    #[test]
+    fn test_render_tray_title_overtime() {
+        let state = TimerState::Overtime {
+            elapsed_overtime_secs: 83,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            render_tray_title(&state, &Settings::default(), None),
+            "🍅 +01:23"
+        );
+    }
+
+    #[test]
+    fn test_render_tray_title_pomodoro_paused() {
+        let state = TimerState::PomodoroPaused {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            render_tray_title(&state, &Settings::default(), None),
+            "⏸ 10:00"
+        );
+    }
+
+    #[test]
+    fn test_render_tray_title_break_active() {
+        let state = TimerState::BreakActive {
+            is_long_break: false,
+            remaining_secs: 272,
+            total_secs: 300,
+        };
+        assert_eq!(
+            render_tray_title(&state, &Settings::default(), None),
+            "☕ 04:32"
+        );
+    }
+
+    #[test]
+    fn test_render_tray_title_break_paused() {
+        let state = TimerState::BreakPaused {
+            is_long_break: false,
+            remaining_secs: 272,
+            total_secs: 300,
+        };
+        assert_eq!(
+            render_tray_title(&state, &Settings::default(), None),
+            "⏸ 04:32"
+        );
+    }
+
+    fn active_state() -> TimerState {
+        TimerState::PomodoroActive {
+            remaining_secs: 1125,
+            total_secs: 1500,
+        }
+    }
+
+    fn settings_with_format(format: &str) -> Settings {
+        Settings {
+            tray_format: format.to_string(),
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn test_render_tray_title_icon() {
+        let settings = settings_with_format("{icon}");
+        assert_eq!(render_tray_title(&active_state(), &settings, None), "🍅");
+    }
+
+    #[test]
+    fn test_render_tray_title_minutes_and_seconds() {
+        let settings = settings_with_format("{mm}m {ss}s");
+        assert_eq!(
+            render_tray_title(&active_state(), &settings, None),
+            "18m 45s"
+        );
+    }
+
+    #[test]
+    fn test_render_tray_title_percent() {
+        let settings = settings_with_format("{pct}%");
+        assert_eq!(render_tray_title(&active_state(), &settings, None), "25%");
+    }
+
+    #[test]
+    fn test_render_tray_title_task() {
+        let settings = settings_with_format("{icon} {task}");
+        assert_eq!(
+            render_tray_title(&active_state(), &settings, Some("Write docs")),
+            "🍅 Write docs"
+        );
+        assert_eq!(render_tray_title(&active_state(), &settings, None), "🍅 ");
+    }
+
+    #[test]
+    fn test_render_tray_title_without_time() {
+        let settings = Settings {
+            show_time_in_menubar: false,
+            ..settings_with_format("{icon} {mm}:{ss} {task}")
+        };
+        let title = |state: &TimerState| render_tray_title(state, &settings, Some("Docs"));
+        assert_eq!(title(&active_state()), "🍅");
+        assert_eq!(title(&TimerState::Idle), "🍅");
+        assert_eq!(title(&short_break(190)), "☕");
+        assert_eq!(
+            title(&TimerState::PomodoroPaused {
+                remaining_secs: 600,
+                total_secs: 1500,
+            }),
+            "⏸"
+        );
+        assert_eq!(
+            title(&TimerState::BreakPaused {
+                is_long_break: false,
+                remaining_secs: 190,
+                total_secs: 300,
+            }),
+            "⏸"
+        );
+        assert_eq!(
+            title(&TimerState::StopwatchActive { elapsed_secs: 60 }),
+            "⏱"
+        );
+    }
+
+    #[test]
+    fn test_render_tray_title_ring_only() {
+        let settings = Settings {
+            tray_display: TrayDisplay::Icon,
+            ..Settings::default()
+        };
+        assert_eq!(render_tray_title(&active_state(), &settings, None), "");
+        assert_eq!(render_tray_title(&TimerState::Idle, &settings, None), "🍅");
+
+        let settings = Settings {
+            tray_display: TrayDisplay::Both,
+            ..Settings::default()
+        };
+        assert_eq!(
+            render_tray_title(&active_state(), &settings, None),
+            "🍅 18:45"
+        );
+    }
+
+    #[test]
+    fn test_render_tray_title_idle_ignores_format() {
+        let settings = settings_with_format("{pct}% {task}");
+        assert_eq!(render_tray_title(&TimerState::Idle, &settings, None), "🍅");
+    }
+
+    #[test]
+    fn test_render_tray_title_remaining() {
+        let settings = settings_with_format("{icon} {remaining}");
+        let title = |state: &TimerState| render_tray_title(state, &settings, None);
+        // Counting down rounds up, so the last seconds still show "1m"
+        assert_eq!(title(&active_state()), "🍅 19m");
+        assert_eq!(title(&short_break(1)), "☕ 1m");
+        assert_eq!(title(&short_break(300)), "☕ 5m");
+        assert_eq!(
+            title(&TimerState::PomodoroPaused {
+                remaining_secs: 600,
+                total_secs: 1500,
+            }),
+            "⏸ 10m"
+        );
+        assert_eq!(
+            title(&TimerState::BreakPaused {
+                is_long_break: true,
+                remaining_secs: 899,
+                total_secs: 900,
+            }),
+            "⏸ 15m"
+        );
+        assert_eq!(
+            title(&TimerState::PomodoroActive {
+                remaining_secs: 3901,
+                total_secs: 7200,
+            }),
+            "🍅 1h 6m"
+        );
+        // Counting up rounds down
+        assert_eq!(
+            title(&TimerState::Overtime {
+                elapsed_overtime_secs: 119,
+                total_secs: 1500,
+            }),
+            "🍅 +1m"
+        );
+        assert_eq!(
+            title(&TimerState::StopwatchActive { elapsed_secs: 59 }),
+            "⏱ 0m"
+        );
+        assert_eq!(
+            title(&TimerState::StopwatchPaused { elapsed_secs: 3660 }),
+            "⏸ 1h 1m"
+        );
+        assert_eq!(title(&TimerState::Idle), "🍅");
+        assert_eq!(title(&TimerState::BreakFinished), "🍅");
+    }
+
+    #[test]
+    fn test_render_tray_title_empty_format() {
+        let settings = settings_with_format("");
+        assert_eq!(render_tray_title(&active_state(), &settings, None), "");
+        assert_eq!(render_tray_title(&TimerState::Idle, &settings, None), "🍅");
+    }
+
+    #[test]
+    fn test_render_tray_title_unknown_placeholder() {
+        let settings = settings_with_format("{icon} {emoji} {mm}");
+        assert_eq!(
+            render_tray_title(&active_state(), &settings, None),
+            "🍅 18:45"
+        );
+        // Braces that aren't a placeholder stay as they are
+        let settings = settings_with_format("{ {mm}:{ss} }");
+        assert_eq!(
+            render_tray_title(&active_state(), &settings, None),
+            "{ 18:45 }"
+        );
+    }
+
+    #[test]
+    fn test_unknown_tray_placeholder() {
+        assert_eq!(unknown_tray_placeholder(""), None);
+        assert_eq!(unknown_tray_placeholder("focus"), None);
+        assert_eq!(unknown_tray_placeholder(DEFAULT_TRAY_FORMAT), None);
+        assert_eq!(
+            unknown_tray_placeholder("{icon} {remaining} {pct}% {task}"),
+            None
+        );
+        assert_eq!(unknown_tray_placeholder("{icon} {emoji}"), Some("emoji"));
+        assert_eq!(unknown_tray_placeholder("{mm}:{SS}"), Some("SS"));
+        assert_eq!(unknown_tray_placeholder("{}"), Some(""));
+        assert_eq!(unknown_tray_placeholder("{{mm}"), None);
+        assert_eq!(unknown_tray_placeholder("{mm"), None);
+        assert_eq!(unknown_tray_placeholder("{ {mm} }"), None);
+    }
+
+    #[test]
+    fn test_tray_format_choices() {
+        // The examples are what a pomodoro shows one second (or 83%) in
+        let title = |format: &str, remaining_secs| {
+            let state = TimerState::PomodoroActive {
+                remaining_secs,
+                total_secs: 1500,
+            };
+            render_tray_title(&state, &settings_with_format(format), Some("Write report"))
+        };
+        let [clock, remaining, pct, task] = TRAY_FORMAT_CHOICES;
+        assert_eq!(title(clock.0, 1499), clock.1);
+        assert_eq!(title(remaining.0, 1499), remaining.1);
+        assert_eq!(title(pct.0, 255), pct.1);
+        assert_eq!(title(task.0, 1499), task.1);
+        assert_eq!(clock.0, Settings::default().tray_format);
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "00:00");
+        assert_eq!(format_time(59), "00:59");
+        assert_eq!(format_time(60), "01:00");
+        assert_eq!(format_time(125), "02:05");
+        assert_eq!(format_time(1500), "25:00");
+        assert_eq!(format_time(3599), "59:59");
+    }
+
+    #[test]
+    fn test_format_time_hours() {
+        assert_eq!(format_time(3600), "1:00:00");
+        assert_eq!(format_time(3661), "1:01:01");
+        assert_eq!(format_time(3900), "1:05:00");
+        assert_eq!(format_time(7325), "2:02:05");
+        assert_eq!(format_time(12 * 3600 + 59 * 60), "12:59:00");
+    }
+
+    #[test]
+    fn test_render_tray_title_hours() {
+        let state = |remaining_secs| TimerState::PomodoroActive {
+            remaining_secs,
+            total_secs: 7800,
+        };
+        let settings = Settings::default();
+        assert_eq!(render_tray_title(&state(3599), &settings, None), "🍅 59:59");
+        assert_eq!(
+            render_tray_title(&state(3600), &settings, None),
+            "🍅 1:00:00"
+        );
+        assert_eq!(
+            render_tray_title(&state(3900), &settings, None),
+            "🍅 1:05:00"
+        );
+        assert_eq!(
+            render_tray_title(&state(7325), &settings, None),
+            "🍅 2:02:05"
+        );
+
+        let overtime = TimerState::Overtime {
+            elapsed_overtime_secs: 3725,
+            total_secs: 1500,
+        };
+        assert_eq!(render_tray_title(&overtime, &settings, None), "🍅 +1:02:05");
+    }
+
+    fn short_break(remaining_secs: u32) -> TimerState {
+        TimerState::BreakActive {
+            is_long_break: false,
+            remaining_secs,
+            total_secs: 300,
+        }
+    }
+
+    fn at(hour: u32, min: u32, sec: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 1, 15, hour, min, sec).unwrap()
+    }
+
+    fn tooltip(state: &TimerState, now: DateTime<Local>) -> String {
+        format_tooltip(state, &Session::default(), now, true)
+    }
+
+    #[test]
+    fn test_tooltip_short_break() {
+        assert_eq!(
+            tooltip(&short_break(190), at(14, 31, 50)),
+            "Short break · 03:10 left · back at 14:35"
+        );
+    }
+
+    #[test]
+    fn test_tooltip_long_break() {
+        let state = TimerState::BreakActive {
+            is_long_break: true,
+            remaining_secs: 900,
+            total_secs: 900,
+        };
+        assert_eq!(
+            tooltip(&state, at(9, 0, 0)),
+            "Long break · 15:00 left · back at 09:15"
+        );
+    }
+
+    #[test]
+    fn test_tooltip_12_hour_clock() {
+        assert_eq!(
+            format_tooltip(&short_break(190), &Session::default(), at(23, 58, 0), false),
+            "Short break · 03:10 left · back at 12:01 AM"
+        );
+    }
+
+    #[test]
+    fn test_tooltip_break_paused() {
+        let state = TimerState::BreakPaused {
+            is_long_break: false,
+            remaining_secs: 190,
+            total_secs: 300,
+        };
+        assert_eq!(
+            tooltip(&state, at(14, 31, 50)),
+            "Short break (paused) · 03:10 left · back ~14:35 if resumed now"
+        );
+    }
+
+    #[test]
+    fn test_tooltip_outside_breaks() {
+        assert_eq!(tooltip(&TimerState::Idle, at(9, 0, 0)), DEFAULT_TOOLTIP);
+        assert_eq!(
+            tooltip(&active_state(), at(9, 0, 0)),
+            "Pomodoro · 18:45 left"
+        );
+        let paused = TimerState::PomodoroPaused {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            tooltip(&paused, at(9, 0, 0)),
+            "Pomodoro (paused) · 10:00 left"
+        );
+        let overtime = TimerState::Overtime {
+            elapsed_overtime_secs: 83,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            tooltip(&overtime, at(9, 0, 0)),
+            "Pomodoro · +01:23 overtime"
+        );
+        let stopwatch = TimerState::StopwatchPaused { elapsed_secs: 754 };
+        assert_eq!(
+            tooltip(&stopwatch, at(9, 0, 0)),
+            "Stopwatch (paused) · 12:34 elapsed"
+        );
+    }
+
+    #[test]
+    fn test_tooltip_shows_pomodoros_done_today() {
+        let session = Session {
+            pomodoros_completed_today: 3,
+            total_focus_mins_today: 75,
+            ..Session::default()
+        };
+        assert_eq!(
+            format_tooltip(&active_state(), &session, at(9, 0, 0), true),
+            "Pomodoro · 18:45 left · 3 done today (1h 15min)"
+        );
+        assert_eq!(
+            format_tooltip(&TimerState::BreakFinished, &session, at(9, 0, 0), true),
+            "Pomobar · 3 done today (1h 15min)"
+        );
+    }
+
+    #[test]
+    fn test_tooltip_throttle() {
+        let mut throttle = TooltipThrottle::default();
+        let start = Instant::now();
+        let countdown = |secs: u32| tooltip(&short_break(secs), at(9, 0, 0));
+        let idle = || DEFAULT_TOOLTIP.to_string();
+
+        // With nothing running the default is sent once
+        assert_eq!(throttle.update(idle(), start), Some(idle()));
+        assert_eq!(
+            throttle.update(idle(), start + Duration::from_secs(1)),
+            None
+        );
+
+        // Starting a timer updates right away, then at most every 15 seconds
+        let t = start + Duration::from_secs(2);
+        assert!(throttle.update(countdown(300), t).is_some());
+        assert!(throttle
+            .update(countdown(290), t + Duration::from_secs(10))
+            .is_none());
+        assert!(throttle
+            .update(countdown(285), t + Duration::from_secs(15))
+            .is_some());
+
+        // Finishing it restores the default immediately
+        assert_eq!(
+            throttle.update(idle(), t + Duration::from_secs(16)),
+            Some(idle())
+        );
+    }
+
+    #[test]
+    fn test_tooltip_throttle_pausing_break() {
+        let mut throttle = TooltipThrottle::default();
+        let t = Instant::now();
+        let paused = TimerState::BreakPaused {
+            is_long_break: false,
+            remaining_secs: 290,
+            total_secs: 300,
+        };
+
+        assert!(throttle
+            .update(tooltip(&short_break(290), at(9, 0, 0)), t)
+            .is_some());
+
+        // Pausing shows right away, and the frozen tooltip isn't resent
+        let paused_tooltip = tooltip(&paused, at(9, 0, 1));
+        let t = t + Duration::from_secs(1);
+        assert!(throttle.update(paused_tooltip.clone(), t).is_some());
+        assert!(throttle
+            .update(paused_tooltip, t + Duration::from_secs(30))
+            .is_none());
+
+        // Resuming shows right away too
+        let resumed = tooltip(&short_break(290), at(9, 0, 32));
+        assert!(throttle
+            .update(resumed, t + Duration::from_secs(31))
+            .is_some());
+    }
+
+    fn test_app() -> App {
+        let db = crate::persistence::Database::new_in_memory().unwrap();
+        let mut app = App::new_with_db(db).unwrap();
+        app.start_pomodoro();
+        app
+    }
+
+    #[test]
+    fn test_ticker_step_reports_state_change() {
+        let mut app = test_app();
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut ticker = Ticker::new(start);
+
+        let output = ticker.step(&mut app, start + Duration::from_secs(3), 0.0);
+        assert!(matches!(
+            output.messages.last(),
+            Some(TimerMessage::StateChanged(_))
+        ));
+        assert_eq!(app.state.remaining_secs(), Some(25 * 60 - 3));
+    }
+
+    #[test]
+    fn test_ticker_writes_metrics_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("pomobar.prom");
+        let mut app = test_app();
+        app.settings.metrics_textfile_path = Some(path.clone());
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut ticker = Ticker::new(start);
+
+        ticker.step(&mut app, start + Duration::from_secs(1), 0.0);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("pomobar_timer_state{state=\"pomodoro_active\"} 1\n"));
+        assert!(text.contains("pomobar_remaining_seconds 1499\n"));
+    }
+
+    #[test]
+    fn test_event_loop_timer_waits_for_tick() {
+        let mut app = test_app();
+        let now = Instant::now();
+        let wall = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut timer = EventLoopTimer::new(now, wall);
+        assert_eq!(timer.next_tick(), now + TICK_INTERVAL);
+
+        // Waking early, e.g. for a menu click, doesn't tick
+        let early = Duration::from_millis(400);
+        assert!(timer
+            .poll(&mut app, now + early, wall + early, || 0.0)
+            .is_none());
+        assert_eq!(app.state.remaining_secs(), Some(25 * 60));
+
+        // A late wake-up ticks and schedules the next tick a second later
+        let late = Duration::from_millis(1300);
+        assert!(timer
+            .poll(&mut app, now + late, wall + late, || 0.0)
+            .is_some());
+        assert_eq!(timer.next_tick(), now + late + TICK_INTERVAL);
+    }
+}
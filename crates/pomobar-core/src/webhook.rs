@@ -0,0 +1,389 @@
+//! Delivery of the daily summary and timer event webhooks.
+//!
+//! Summaries are queued in the database at day rollover and posted from a
+//! background thread. Anything that fails to send stays queued and is retried
+//! on the next rollover or launch.
+//!
+//! Event webhooks (pomodoro start/complete, break start/complete) are fire-and-forget:
+//! each is posted from its own thread, retried once, and dropped on failure.
+
+use crate::actor::AppHandle;
+use crate::models::Settings;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::env;
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Event webhooks should arrive promptly or not at all.
+const EVENT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Why delivering a webhook failed.
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("HTTP request failed: {0}")]
+    Http(String),
+}
+
+/// Minimal HTTP client interface so delivery can be tested without a network.
+pub trait HttpClient {
+    /// POSTs a JSON body to the given URL.
+    fn post_json(&self, url: &str, body: &str) -> Result<(), WebhookError>;
+}
+
+/// HTTP client backed by ureq.
+pub struct UreqClient {
+    timeout: Duration,
+}
+
+impl UreqClient {
+    /// Creates a client whose requests give up after `timeout`.
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl HttpClient for UreqClient {
+    fn post_json(&self, url: &str, body: &str) -> Result<(), WebhookError> {
+        ureq::post(url)
+            .timeout(self.timeout)
+            .set("Content-Type", "application/json")
+            .send_string(body)
+            .map(|_| ())
+            .map_err(|e| WebhookError::Http(e.to_string()))
+    }
+}
+
+/// Posts every queued summary, oldest first, and removes the ones that were
+/// delivered. Stops at the first failure so the order is preserved.
+/// Returns the number of summaries delivered.
+pub fn flush_queue(app: &AppHandle, client: &dyn HttpClient) -> usize {
+    // Wait on the network here rather than on the timer actor
+    let Some(Some((url, queued))) = app.try_call(|app| {
+        let url = app.settings.daily_webhook_url.clone()?;
+        Some((url, app.db.queued_summaries().unwrap_or_default()))
+    }) else {
+        return 0;
+    };
+
+    let mut delivered = 0;
+    for summary in queued {
+        let Ok(body) = serde_json::to_string(&summary) else {
+            continue;
+        };
+        if let Err(e) = client.post_json(&url, &body) {
+            eprintln!("Failed to send daily summary for {}: {}", summary.date, e);
+            break;
+        }
+        app.send(move |app| {
+            let _ = app.db.remove_queued_summary(summary.date);
+        });
+        delivered += 1;
+    }
+    delivered
+}
+
+/// Flushes the queue on a background thread.
+pub fn spawn_flush(app: AppHandle) {
+    thread::spawn(move || {
+        flush_queue(&app, &UreqClient::new(REQUEST_TIMEOUT));
+    });
+}
+
+/// A timer event that can trigger a webhook.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimerEvent {
+    PomodoroStart,
+    PomodoroComplete,
+    BreakStart,
+    BreakComplete,
+}
+
+impl TimerEvent {
+    /// Name sent in the payload's `event` field.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PomodoroStart => "pomodoro_start",
+            Self::PomodoroComplete => "pomodoro_complete",
+            Self::BreakStart => "break_start",
+            Self::BreakComplete => "break_complete",
+        }
+    }
+
+    /// Environment variable that overrides the URL from the settings.
+    fn env_var(self) -> &'static str {
+        match self {
+            Self::PomodoroStart => "POMOBAR_ON_POMODORO_START",
+            Self::PomodoroComplete => "POMOBAR_ON_POMODORO_COMPLETE",
+            Self::BreakStart => "POMOBAR_ON_BREAK_START",
+            Self::BreakComplete => "POMOBAR_ON_BREAK_COMPLETE",
+        }
+    }
+}
+
+/// JSON body posted for a timer event.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventPayload {
+    /// The event name, e.g. `pomodoro_start`.
+    pub event: &'static str,
+    /// When the event happened.
+    pub timestamp: DateTime<Local>,
+    /// Pomodoros completed today so far.
+    pub count_today: u32,
+    /// Length of the pomodoro or break the event belongs to.
+    pub duration_secs: u32,
+    /// Whether the pomodoro was shortened to end with the day.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub wind_down: bool,
+}
+
+/// Returns the URL for an event: the environment variable if set, else the
+/// event's own setting, else `webhook_url`.
+pub fn event_url(settings: &Settings, event: TimerEvent) -> Option<String> {
+    if let Ok(url) = env::var(event.env_var()) {
+        return (!url.is_empty()).then_some(url);
+    }
+    let own_url = match event {
+        TimerEvent::PomodoroStart => &settings.on_pomodoro_start,
+        TimerEvent::PomodoroComplete => &settings.on_pomodoro_complete,
+        TimerEvent::BreakStart => &settings.on_break_start,
+        TimerEvent::BreakComplete => &settings.on_break_complete,
+    };
+    own_url.as_ref().or(settings.webhook_url.as_ref()).cloned()
+}
+
+/// POSTs the body, retrying once if the first attempt fails.
+pub fn post_with_retry(client: &dyn HttpClient, url: &str, body: &str) -> Result<(), WebhookError> {
+    client.post_json(url, body).or_else(|e| {
+        eprintln!("Webhook to {} failed: {}; retrying", url, e);
+        client.post_json(url, body)
+    })
+}
+
+/// Posts an event on a background thread so the UI never waits on the network.
+pub fn spawn_event(url: String, payload: EventPayload) {
+    thread::spawn(move || {
+        let Ok(body) = serde_json::to_string(&payload) else {
+            return;
+        };
+        if let Err(e) = post_with_retry(&UreqClient::new(EVENT_TIMEOUT), &url, &body) {
+            eprintln!("Failed to send {} webhook: {}", payload.event, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::TimerActor;
+    use crate::app::App;
+    use crate::models::{DailySummary, TimerMode};
+    use crate::persistence::Database;
+    use chrono::NaiveDate;
+    use std::cell::RefCell;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{self, Receiver};
+
+    /// Records posted bodies and fails once `fail_after` posts succeeded.
+    struct FakeClient {
+        posted: RefCell<Vec<String>>,
+        fail_after: usize,
+    }
+
+    impl HttpClient for FakeClient {
+        fn post_json(&self, _url: &str, body: &str) -> Result<(), WebhookError> {
+            let mut posted = self.posted.borrow_mut();
+            if posted.len() >= self.fail_after {
+                return Err(WebhookError::Http("connection refused".to_string()));
+            }
+            posted.push(body.to_string());
+            Ok(())
+        }
+    }
+
+    fn create_app_with_queue(days: &[u32]) -> TimerActor {
+        let db = Database::new_in_memory().unwrap();
+        for &day in days {
+            db.queue_summary(&DailySummary {
+                date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+                completed_pomodoros: day,
+                total_focus_minutes: day * 25,
+                streak_days: 1,
+                focus_score: None,
+                goal: None,
+            })
+            .unwrap();
+        }
+        let mut app = App::new_with_db(db).unwrap();
+        app.settings.daily_webhook_url = Some("http://localhost/hook".to_string());
+        TimerActor::spawn(app, mpsc::channel().0, TimerMode::EventLoop)
+    }
+
+    #[test]
+    fn test_flush_delivers_in_order() {
+        let app = create_app_with_queue(&[16, 15]).handle();
+        let client = FakeClient {
+            posted: RefCell::new(Vec::new()),
+            fail_after: usize::MAX,
+        };
+
+        assert_eq!(flush_queue(&app, &client), 2);
+        let posted = client.posted.borrow();
+        assert!(posted[0].contains(r#""date":"2024-01-15""#));
+        assert!(posted[1].contains(r#""date":"2024-01-16""#));
+        assert!(app.call(|app| app.db.queued_summaries().unwrap().is_empty()));
+    }
+
+    #[test]
+    fn test_flush_keeps_failed_summaries_queued() {
+        let app = create_app_with_queue(&[15, 16, 17]).handle();
+        let client = FakeClient {
+            posted: RefCell::new(Vec::new()),
+            fail_after: 1,
+        };
+
+        assert_eq!(flush_queue(&app, &client), 1);
+        let remaining = app.call(|app| app.db.queued_summaries().unwrap());
+        assert_eq!(remaining.len(), 2);
+        assert_eq!(
+            remaining[0].date,
+            NaiveDate::from_ymd_opt(2024, 1, 16).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_flush_without_url_sends_nothing() {
+        let app = create_app_with_queue(&[15]).handle();
+        app.send(|app| app.settings.daily_webhook_url = None);
+        let client = FakeClient {
+            posted: RefCell::new(Vec::new()),
+            fail_after: usize::MAX,
+        };
+
+        assert_eq!(flush_queue(&app, &client), 0);
+        let queued = app.call(|app| app.db.queued_summaries().unwrap());
+        assert_eq!(queued.len(), 1);
+    }
+
+    /// Serves one HTTP request per given status code on a local port.
+    /// Returns the base URL and a receiver for the request bodies.
+    fn start_mock_server(statuses: Vec<u16>) -> (String, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for status in statuses {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let _ = tx.send(String::from_utf8(body).unwrap());
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    fn sample_payload() -> EventPayload {
+        EventPayload {
+            event: TimerEvent::PomodoroComplete.name(),
+            timestamp: Local::now(),
+            count_today: 3,
+            duration_secs: 1500,
+            wind_down: false,
+        }
+    }
+
+    #[test]
+    fn test_post_event_to_mock_server() {
+        let (url, rx) = start_mock_server(vec![200]);
+        let body = serde_json::to_string(&sample_payload()).unwrap();
+
+        let client = UreqClient::new(EVENT_TIMEOUT);
+        assert!(post_with_retry(&client, &url, &body).is_ok());
+
+        let received: serde_json::Value = serde_json::from_str(&rx.recv().unwrap()).unwrap();
+        assert_eq!(received["event"], "pomodoro_complete");
+        assert_eq!(received["count_today"], 3);
+        assert_eq!(received["duration_secs"], 1500);
+        // Only wind-down pomodoros are marked
+        assert!(received.get("wind_down").is_none());
+        assert!(received["timestamp"].is_string());
+    }
+
+    #[test]
+    fn test_post_retries_once_after_failure() {
+        let (url, rx) = start_mock_server(vec![500, 200]);
+        let client = UreqClient::new(EVENT_TIMEOUT);
+
+        assert!(post_with_retry(&client, &url, "{}").is_ok());
+        assert_eq!(rx.iter().take(2).count(), 2);
+    }
+
+    #[test]
+    fn test_post_gives_up_after_retry() {
+        let (url, rx) = start_mock_server(vec![500, 500, 200]);
+        let client = UreqClient::new(EVENT_TIMEOUT);
+
+        assert!(post_with_retry(&client, &url, "{}").is_err());
+        assert_eq!(rx.iter().take(2).count(), 2);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_event_url_from_settings() {
+        let settings = Settings {
+            on_pomodoro_start: Some("http://localhost/start".to_string()),
+            ..Settings::default()
+        };
+
+        assert_eq!(
+            event_url(&settings, TimerEvent::PomodoroStart),
+            Some("http://localhost/start".to_string())
+        );
+        assert_eq!(event_url(&settings, TimerEvent::BreakComplete), None);
+    }
+
+    #[test]
+    fn test_event_url_falls_back_to_webhook_url() {
+        let settings = Settings {
+            webhook_url: Some("http://localhost/all".to_string()),
+            on_break_start: Some("http://localhost/break".to_string()),
+            ..Settings::default()
+        };
+
+        assert_eq!(
+            event_url(&settings, TimerEvent::BreakStart),
+            Some("http://localhost/break".to_string())
+        );
+        assert_eq!(
+            event_url(&settings, TimerEvent::PomodoroComplete),
+            Some("http://localhost/all".to_string())
+        );
+    }
+}
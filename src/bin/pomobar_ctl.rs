@@ -0,0 +1,89 @@
+//! pomobar-ctl - Control a running Pomobar from the terminal.
+//!
+//! Usage: `pomobar-ctl start|pause|resume|stop|skip|status`
+
+use pomobar_core::persistence;
+use serde::Deserialize;
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const COMMANDS: [&str; 6] = ["start", "pause", "resume", "stop", "skip", "status"];
+
+/// The part of the app's reply that decides the exit code.
+#[derive(Deserialize)]
+struct Reply {
+    ok: bool,
+}
+
+/// Returns the socket path (must match `ipc::socket_path` in the app).
+fn socket_path() -> PathBuf {
+    persistence::data_dir().join("pomobar.sock")
+}
+
+/// Returns whether a reply reports success. A reply that isn't valid JSON
+/// doesn't.
+fn succeeded(response: &str) -> bool {
+    serde_json::from_str::<Reply>(response).is_ok_and(|reply| reply.ok)
+}
+
+fn main() -> ExitCode {
+    let cmd = match env::args().nth(1) {
+        Some(cmd) if COMMANDS.contains(&cmd.as_str()) => cmd,
+        _ => {
+            eprintln!("Usage: pomobar-ctl {}", COMMANDS.join("|"));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match send(&cmd) {
+        Ok(response) => {
+            println!("{}", response);
+            if succeeded(&response) {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!(
+                "Could not reach Pomobar at {}: {}",
+                socket_path().display(),
+                e
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn send(cmd: &str) -> std::io::Result<String> {
+    let mut stream = UnixStream::connect(socket_path())?;
+    let request = serde_json::json!({ "cmd": cmd });
+    writeln!(stream, "{}", request)?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+    Ok(response.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_succeeded() {
+        assert!(succeeded(
+            r#"{"ok":true,"state":"PomodoroActive","remaining":1500}"#
+        ));
+        assert!(!succeeded(
+            r#"{"ok":false,"state":"Idle","error":"No running timer to pause"}"#
+        ));
+        // Only the reply's own field counts
+        assert!(!succeeded(
+            r#"{"ok":false,"state":"Idle","note":{"ok":true}}"#
+        ));
+        assert!(!succeeded("not json"));
+    }
+}
@@ -0,0 +1,130 @@
+//! Modal prompts for settings that need free-form input or a file.
+
+use std::path::{Path, PathBuf};
+
+/// Asks the user for a line of text, pre-filled with `default`.
+/// Returns None if the dialog was cancelled or could not be shown.
+/// Blocks until the user answers.
+#[cfg(target_os = "macos")]
+pub fn prompt_text(message: &str, default: &str) -> Option<String> {
+    run_osascript(&format!(
+        "text returned of (display dialog \"{}\" default answer \"{}\" with title \"Pomobar\")",
+        escape_applescript(message),
+        escape_applescript(default)
+    ))
+}
+
+/// Asks the user to pick a sound file (MP3, WAV or OGG).
+/// Returns None if the dialog was cancelled or could not be shown.
+/// Blocks until the user answers.
+#[cfg(target_os = "macos")]
+pub fn choose_sound_file() -> Option<String> {
+    run_osascript(
+        "POSIX path of (choose file with prompt \"Choose a sound\" \
+         of type {\"mp3\", \"wav\", \"ogg\"})",
+    )
+}
+
+/// Asks the user where to save a file, suggesting `default`.
+/// Returns None if the dialog was cancelled or could not be shown.
+/// Blocks until the user answers.
+#[cfg(target_os = "macos")]
+pub fn choose_save_path(prompt: &str, default: &Path) -> Option<PathBuf> {
+    run_osascript(&save_dialog_script(prompt, default)).map(PathBuf::from)
+}
+
+/// Runs an AppleScript snippet and returns its output.
+#[cfg(target_os = "macos")]
+fn run_osascript(script: &str) -> Option<String> {
+    use std::process::Command;
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .output()
+        .ok()?;
+
+    // osascript exits with an error when the user clicks Cancel
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    Some(text.trim_end_matches('\n').to_string())
+}
+
+/// Text prompts are only implemented on macOS.
+#[cfg(not(target_os = "macos"))]
+pub fn prompt_text(_message: &str, _default: &str) -> Option<String> {
+    None
+}
+
+/// File dialogs are only implemented on macOS.
+#[cfg(not(target_os = "macos"))]
+pub fn choose_sound_file() -> Option<String> {
+    None
+}
+
+/// Save dialogs are only implemented on macOS.
+#[cfg(not(target_os = "macos"))]
+pub fn choose_save_path(_prompt: &str, _default: &Path) -> Option<PathBuf> {
+    None
+}
+
+/// Builds the AppleScript for a save dialog. The default location is only
+/// passed when `default` has a parent directory.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn save_dialog_script(prompt: &str, default: &Path) -> String {
+    let name = default
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let mut script = format!(
+        "POSIX path of (choose file name with prompt \"{}\" default name \"{}\"",
+        escape_applescript(prompt),
+        escape_applescript(&name)
+    );
+    if let Some(dir) = default.parent().filter(|dir| dir.is_dir()) {
+        script.push_str(&format!(
+            " default location (POSIX file \"{}\")",
+            escape_applescript(&dir.to_string_lossy())
+        ));
+    }
+    script.push(')');
+    script
+}
+
+/// Escapes a string for use inside an AppleScript string literal.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn escape_applescript(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_applescript() {
+        assert_eq!(escape_applescript("{icon} {mm}:{ss}"), "{icon} {mm}:{ss}");
+        assert_eq!(escape_applescript(r#"say "hi""#), r#"say \"hi\""#);
+        assert_eq!(escape_applescript(r"a\b"), r"a\\b");
+    }
+
+    #[test]
+    fn test_save_dialog_script() {
+        let dir = std::env::temp_dir();
+        let script = save_dialog_script("Save backup as", &dir.join("backup.db"));
+        assert!(script.starts_with(
+            "POSIX path of (choose file name with prompt \"Save backup as\" \
+             default name \"backup.db\" default location (POSIX file \""
+        ));
+        assert!(script.ends_with("\"))"));
+
+        // A missing directory falls back to the dialog's own default location
+        let script = save_dialog_script("Save", Path::new("/does/not/exist/backup.db"));
+        assert_eq!(
+            script,
+            "POSIX path of (choose file name with prompt \"Save\" default name \"backup.db\")"
+        );
+    }
+}
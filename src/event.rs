@@ -1,12 +1,34 @@
 //! Menu event handling.
 
-use crate::app::{App, CompletionEvent};
+use crate::hotkeys::{HotkeyAction, START_PAUSE_CHOICES};
 use crate::launch_agent;
 use crate::menu::{
-    MenuItems, ID_COMPLETE, ID_LOGIN_TOGGLE, ID_NOTIF_TOGGLE, ID_PAUSE, ID_QUIT, ID_RESET_COUNT,
-    ID_RESUME, ID_SKIP_BREAK, ID_SOUND_TOGGLE, ID_START, ID_STOP,
+    BREAK_SOUND_ID_PREFIX, DAY_START_ID_PREFIX, EXTEND_MINS, HOTKEY_ID_PREFIX, ID_BACKUP,
+    ID_BLINK_TOGGLE, ID_BREAK_END_SOUND_TOGGLE, ID_CLOCK_TOGGLE, ID_COMPACT_TOGGLE, ID_COMPLETE,
+    ID_CUSTOM_SOUND, ID_DAILY_WEBHOOK, ID_DND_TOGGLE, ID_EVENT_WEBHOOK, ID_EXPORT_SETTINGS,
+    ID_EXPORT_STATS, ID_EXTEND, ID_FOCUS_TOGGLE, ID_HOTKEYS_TOGGLE, ID_IMPORT_SETTINGS,
+    ID_INTERRUPTION, ID_LOGIN_TOGGLE, ID_LOG_PAST, ID_LONG_BREAK_NOW, ID_LONG_CUSTOM,
+    ID_MEDIA_TOGGLE, ID_MENUBAR_TIME_TOGGLE, ID_MERGE_STORES, ID_NOTIF_TOGGLE, ID_OPTIMIZE_DB,
+    ID_PAUSE_RESUME, ID_POMO_CUSTOM, ID_POMO_END_SOUND_TOGGLE, ID_PREVIEW_BREAK_SOUND,
+    ID_PREVIEW_POMO_SOUND, ID_PREVIEW_SOUND, ID_PROGRESS, ID_QUIT, ID_RESET_COUNT, ID_RESET_COUNTS,
+    ID_RESET_CYCLE, ID_SHORT_CUSTOM, ID_SKIP_BREAK, ID_START, ID_START_ANYWAY, ID_START_STOPWATCH,
+    ID_STATUS_FILE_TOGGLE, ID_STOP, ID_STRICT_TOGGLE, ID_TASK_NEW, ID_TASK_NONE, ID_THRESH_CUSTOM,
+    ID_TRAY_FORMAT, ID_WARN_BREAKS, POMO_SOUND_ID_PREFIX, PRESET_ID_PREFIX,
+    PROGRESS_STYLE_ID_PREFIX, TASK_ID_PREFIX, TRAY_DISPLAY_ID_PREFIX, TRAY_FORMAT_ID_PREFIX,
 };
+use crate::notifications::{self, NotificationAction};
+use chrono::Local;
 use muda::MenuEvent;
+use pomobar_core::app::{App, CompletionEvent, StartRefused};
+use pomobar_core::models::{
+    DurationSetting, ProgressBarStyle, Settings, SoundChoice, TimerState, TrayDisplay, WeekStart,
+};
+use pomobar_core::persistence;
+use pomobar_core::presets::Preset;
+use pomobar_core::settings_file;
+use pomobar_core::stats_export;
+use pomobar_core::timer::TRAY_FORMAT_CHOICES;
+use std::path::PathBuf;
 
 /// Result of handling a menu event.
 #[derive(Debug, Clone, PartialEq)]
@@ -19,27 +41,62 @@ pub enum EventResult {
     StateChanged,
     /// Settings changed, menu needs rebuild.
     SettingsChanged,
+    /// Settings changed to a value the existing menu items can't show,
+    /// such as a duration that isn't one of the presets.
+    RebuildMenu,
     /// State changed with a completion event.
     StateChangedWithCompletion(CompletionEvent),
+    /// A pomodoro wasn't started; tell the user and offer Start Anyway.
+    StartRefused(StartRefused),
+    /// User asked to edit the tray title format (needs a dialog on the main thread).
+    EditTrayFormat,
+    /// User asked to edit the daily webhook URL (needs a dialog on the main thread).
+    EditDailyWebhook,
+    /// User asked to edit the URL that receives every timer event (needs a
+    /// dialog on the main thread).
+    EditEventWebhook,
+    /// User asked to enter a duration not in the presets (needs a dialog on the main thread).
+    EditCustomDuration(DurationSetting),
+    /// User asked to enter a number of pomodoros per long break not in the
+    /// presets (needs a dialog on the main thread).
+    EditLongBreakAfter,
+    /// Hotkey settings changed: the hotkeys need registering again on the
+    /// main thread, and the menu rebuilding for its shortcut labels.
+    HotkeysChanged,
+    /// User asked to log a pomodoro done away from the timer (needs a dialog
+    /// on the main thread).
+    LogPastPomodoro,
+    /// User asked to enter a new task label (needs a dialog on the main thread).
+    EditTask,
+    /// User asked to pick a custom chime file (needs a dialog on the main thread).
+    ChooseCustomSound,
+    /// User asked to hear a sound.
+    PreviewSound(SoundChoice),
+    /// User asked to hear the sound chosen for a kind of completion event.
+    PreviewCompletionSound(CompletionEvent),
+    /// User asked to back up the database; carries the suggested file
+    /// (needs a save dialog on the main thread).
+    BackupRequested(PathBuf),
 }
 
 /// Handles a menu event and updates the app state accordingly.
-pub fn handle_menu_event(app: &mut App, items: &MenuItems, event: MenuEvent) -> EventResult {
+pub fn handle_menu_event(app: &mut App, event: MenuEvent) -> EventResult {
     let id = event.id().as_ref();
 
     match id {
-        ID_START => {
-            app.start_pomodoro();
-            EventResult::StateChanged
-        }
-        ID_PAUSE => {
-            app.pause();
-            EventResult::StateChanged
+        ID_START => start_pomodoro(app),
+        ID_START_ANYWAY => {
+            if app.start_anyway() {
+                EventResult::StateChanged
+            } else {
+                EventResult::Continue
+            }
         }
-        ID_RESUME => {
-            app.resume();
+        ID_START_STOPWATCH => {
+            app.start_stopwatch();
             EventResult::StateChanged
         }
+        ID_PAUSE_RESUME => handle_pause_resume(app),
         ID_STOP => {
             app.stop();
             EventResult::StateChanged
@@ -47,6 +104,18 @@ pub fn handle_menu_event(app: &mut App, items: &MenuItems, event: MenuEvent) ->
         ID_COMPLETE => {
             if let Some(event) = app.complete_early() {
                 EventResult::StateChangedWithCompletion(event)
+            } else {
+                // A stopwatch completes without an event
+                EventResult::StateChanged
+            }
+        }
+        ID_EXTEND => {
+            app.extend_pomodoro(EXTEND_MINS);
+            EventResult::StateChanged
+        }
+        ID_INTERRUPTION => {
+            if app.mark_interruption() {
+                EventResult::StateChanged
             } else {
                 EventResult::Continue
             }
@@ -55,26 +124,79 @@ pub fn handle_menu_event(app: &mut App, items: &MenuItems, event: MenuEvent) ->
             app.skip_break();
             EventResult::StateChanged
         }
-        ID_SOUND_TOGGLE => {
-            app.update_setting(|s| s.sound_enabled = !s.sound_enabled);
-            items.sound_toggle.set_checked(app.settings.sound_enabled);
+        ID_LONG_BREAK_NOW => {
+            if app.start_long_break() {
+                EventResult::StateChanged
+            } else {
+                EventResult::Continue
+            }
+        }
+        ID_PROGRESS => {
+            update_setting(app, |s| s.progress_mode = s.progress_mode.next());
+            EventResult::Continue
+        }
+        ID_POMO_END_SOUND_TOGGLE => {
+            update_setting(app, |s| s.sound_on_pomodoro_end = !s.sound_on_pomodoro_end);
+            EventResult::Continue
+        }
+        ID_BREAK_END_SOUND_TOGGLE => {
+            update_setting(app, |s| s.sound_on_break_end = !s.sound_on_break_end);
             EventResult::Continue
         }
         ID_NOTIF_TOGGLE => {
-            app.update_setting(|s| s.notifications_enabled = !s.notifications_enabled);
-            items
-                .notif_toggle
-                .set_checked(app.settings.notifications_enabled);
+            update_setting(app, |s| s.notifications_enabled = !s.notifications_enabled);
+            EventResult::Continue
+        }
+        ID_DND_TOGGLE => {
+            update_setting(app, |s| s.respect_dnd = !s.respect_dnd);
+            EventResult::Continue
+        }
+        ID_STATUS_FILE_TOGGLE => {
+            update_setting(app, |s| s.status_file_enabled = !s.status_file_enabled);
+            EventResult::Continue
+        }
+        ID_FOCUS_TOGGLE => {
+            update_setting(app, |s| s.focus_mode_enabled = !s.focus_mode_enabled);
+            if !app.settings.focus_mode_enabled {
+                app.release_focus();
+            }
+            EventResult::Continue
+        }
+        ID_COMPACT_TOGGLE => {
+            update_setting(app, |s| s.compact_emoji_counts = !s.compact_emoji_counts);
+            EventResult::StateChanged
+        }
+        ID_CLOCK_TOGGLE => {
+            update_setting(app, |s| s.clock_24h = !s.clock_24h);
+            EventResult::StateChanged
+        }
+        ID_MENUBAR_TIME_TOGGLE => {
+            update_setting(app, |s| s.show_time_in_menubar = !s.show_time_in_menubar);
+            // Redraws the tray title right away
+            EventResult::SettingsChanged
+        }
+        ID_BLINK_TOGGLE => {
+            // The next tick starts or stops the blinking
+            update_setting(app, |s| s.attention_blink = !s.attention_blink);
+            EventResult::SettingsChanged
+        }
+        ID_STRICT_TOGGLE => {
+            update_setting(app, |s| s.strict_mode = !s.strict_mode);
+            // Enables or disables Pause and Stop right away
+            EventResult::StateChanged
+        }
+        ID_MEDIA_TOGGLE => {
+            update_setting(app, |s| s.control_media = !s.control_media);
             EventResult::Continue
         }
         ID_LOGIN_TOGGLE => {
             let new_state = !app.settings.launch_at_login;
-            if launch_agent::set_enabled(new_state).is_ok() {
-                app.update_setting(|s| s.launch_at_login = new_state);
-                items.login_toggle.set_checked(new_state);
-            } else {
-                // Revert the checkbox if the operation failed
-                items.login_toggle.set_checked(app.settings.launch_at_login);
+            match launch_agent::set_enabled(new_state) {
+                Ok(()) => {
+                    update_setting(app, |s| s.launch_at_login = new_state);
+                }
+                // The checkbox follows the unchanged setting
+                Err(e) => eprintln!("Failed to change Start at Login: {}", e),
             }
             EventResult::Continue
         }
@@ -82,10 +204,110 @@ pub fn handle_menu_event(app: &mut App, items: &MenuItems, event: MenuEvent) ->
             app.reset_today();
             EventResult::StateChanged
         }
+        ID_RESET_COUNTS => {
+            app.reset_counts();
+            EventResult::StateChanged
+        }
+        ID_RESET_CYCLE => {
+            app.reset_cycle();
+            EventResult::StateChanged
+        }
+        ID_MERGE_STORES => {
+            if let Err(e) = app.merge_stray_db() {
+                eprintln!("Failed to merge data stores: {}", e);
+                return EventResult::Continue;
+            }
+            EventResult::StateChanged
+        }
+        ID_EXPORT_SETTINGS => {
+            let path = settings_file::default_path();
+            match settings_file::export(&app.settings, &path) {
+                Ok(()) => notifications::notify_settings_exported(&path),
+                Err(e) => notifications::notify_settings_file_failed(&path, e.to_string()),
+            }
+            EventResult::Continue
+        }
+        ID_EXPORT_STATS => {
+            match app.export_stats(&stats_export::default_dir()) {
+                Ok((csv_path, _)) => notifications::notify_stats_exported(&csv_path),
+                Err(e) => notifications::notify_stats_export_failed(e.to_string()),
+            }
+            EventResult::Continue
+        }
+        ID_IMPORT_SETTINGS => {
+            let path = settings_file::default_path();
+            let launch_at_login = app.settings.launch_at_login;
+            match app.import_settings(&path) {
+                Ok(()) => {
+                    apply_launch_at_login(app, launch_at_login);
+                    notifications::notify_settings_imported();
+                    // Re-registers the imported hotkeys and rebuilds the menu
+                    EventResult::HotkeysChanged
+                }
+                Err(e) => {
+                    notifications::notify_settings_file_failed(&path, e.to_string());
+                    EventResult::Continue
+                }
+            }
+        }
+        ID_HOTKEYS_TOGGLE => {
+            update_setting(app, |s| s.hotkeys_enabled = !s.hotkeys_enabled);
+            EventResult::HotkeysChanged
+        }
+        ID_OPTIMIZE_DB => {
+            app.optimize_database();
+            EventResult::Continue
+        }
+        ID_WARN_BREAKS => {
+            update_setting(app, |s| s.warn_before_break_end = !s.warn_before_break_end);
+            EventResult::Continue
+        }
+        ID_LOG_PAST => EventResult::LogPastPomodoro,
+        ID_TASK_NEW => EventResult::EditTask,
+        ID_TASK_NONE => {
+            app.set_task(None);
+            EventResult::StateChanged
+        }
+        ID_TRAY_FORMAT => EventResult::EditTrayFormat,
+        ID_DAILY_WEBHOOK => EventResult::EditDailyWebhook,
+        ID_EVENT_WEBHOOK => EventResult::EditEventWebhook,
+        ID_POMO_CUSTOM => EventResult::EditCustomDuration(DurationSetting::Pomodoro),
+        ID_SHORT_CUSTOM => EventResult::EditCustomDuration(DurationSetting::ShortBreak),
+        ID_LONG_CUSTOM => EventResult::EditCustomDuration(DurationSetting::LongBreak),
+        ID_THRESH_CUSTOM => EventResult::EditLongBreakAfter,
+        ID_CUSTOM_SOUND => EventResult::ChooseCustomSound,
+        ID_PREVIEW_SOUND => EventResult::PreviewSound(SoundChoice::Default),
+        ID_PREVIEW_POMO_SOUND => {
+            EventResult::PreviewCompletionSound(CompletionEvent::PomodoroComplete {
+                count: app.session.pomodoros_completed_today,
+                is_long_break: false,
+            })
+        }
+        ID_PREVIEW_BREAK_SOUND => {
+            EventResult::PreviewCompletionSound(CompletionEvent::BreakComplete)
+        }
+        ID_BACKUP => EventResult::BackupRequested(persistence::default_backup_path(
+            Local::now().date_naive(),
+        )),
         ID_QUIT => EventResult::Quit,
         _ => {
+            if let Some(result) = handle_sound_change(app, id) {
+                return result;
+            }
+            if let Some(result) = handle_hotkey_change(app, id) {
+                return result;
+            }
+            if let Some(result) = handle_preset_change(app, id) {
+                return result;
+            }
+            if let Some(result) = handle_tray_format_change(app, id) {
+                return result;
+            }
+            if let Some(result) = handle_task_change(app, id) {
+                return result;
+            }
             // Check for settings duration changes
-            if let Some(result) = handle_duration_change(app, items, id) {
+            if let Some(result) = handle_duration_change(app, id) {
                 return result;
             }
             EventResult::Continue
@@ -93,50 +315,256 @@ pub fn handle_menu_event(app: &mut App, items: &MenuItems, event: MenuEvent) ->
     }
 }
 
+/// Handles a global hotkey press using the same App methods as the menu items.
+pub fn handle_hotkey_action(app: &mut App, action: HotkeyAction) -> EventResult {
+    match action {
+        HotkeyAction::StartPause if app.state.is_idle() => return start_pomodoro(app),
+        HotkeyAction::StartPause => return handle_pause_resume(app),
+        HotkeyAction::Stop => {
+            if !app.state.is_pomodoro() {
+                return EventResult::Continue;
+            }
+            app.stop();
+        }
+    }
+    EventResult::StateChanged
+}
+
+/// Starts a pomodoro, or reports why it was refused.
+fn start_pomodoro(app: &mut App) -> EventResult {
+    match app.try_start_pomodoro() {
+        Ok(()) => EventResult::StateChanged,
+        Err(reason) => EventResult::StartRefused(reason),
+    }
+}
+
+/// Pauses a running pomodoro or break, or resumes a paused one, depending on the state.
+fn handle_pause_resume(app: &mut App) -> EventResult {
+    match app.state {
+        TimerState::PomodoroActive { .. }
+        | TimerState::BreakActive { .. }
+        | TimerState::StopwatchActive { .. } => app.pause(),
+        TimerState::PomodoroPaused { .. }
+        | TimerState::BreakPaused { .. }
+        | TimerState::StopwatchPaused { .. } => app.resume(),
+        _ => return EventResult::Continue,
+    }
+    EventResult::StateChanged
+}
+
+/// Handles an action button clicked on a notification.
+/// Ignores the click if the state has moved on since the notification was shown.
+pub fn handle_notification_action(app: &mut App, action: NotificationAction) -> EventResult {
+    let completed = app.session.pomodoros_completed_today;
+    match action {
+        NotificationAction::SkipBreak { count } if count == completed && app.state.is_break() => {
+            app.skip_break();
+            EventResult::StateChanged
+        }
+        NotificationAction::StartBreak { count } if count == completed && app.state.is_break() => {
+            app.auto_start_next = true;
+            EventResult::Continue
+        }
+        NotificationAction::StartPomodoro { count }
+            if count == completed && app.state.is_idle() =>
+        {
+            start_pomodoro(app)
+        }
+        NotificationAction::StartAnyway { count }
+            if count == completed && app.start_confirmed_anyway() =>
+        {
+            EventResult::StateChanged
+        }
+        _ => EventResult::Continue,
+    }
+}
+
+/// Applies a settings change from the menu, logging and skipping one that
+/// would put a setting out of range. Returns true if it was applied.
+fn update_setting(app: &mut App, updater: impl FnOnce(&mut Settings)) -> bool {
+    match app.update_setting(updater) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Ignoring settings change: {}", e);
+            false
+        }
+    }
+}
+
+/// Applies a value picked from one of the settings submenus.
+fn change_setting(app: &mut App, updater: impl FnOnce(&mut Settings)) -> EventResult {
+    if update_setting(app, updater) {
+        EventResult::SettingsChanged
+    } else {
+        EventResult::Continue
+    }
+}
+
+/// Handles a click on one of the pomodoro or break sound choices.
+fn handle_sound_change(app: &mut App, id: &str) -> Option<EventResult> {
+    let (key, is_break) = if let Some(key) = id.strip_prefix(POMO_SOUND_ID_PREFIX) {
+        (key, false)
+    } else {
+        (id.strip_prefix(BREAK_SOUND_ID_PREFIX)?, true)
+    };
+    let choice = SoundChoice::from_key(key)?;
+    update_setting(app, |s| {
+        if is_break {
+            s.break_sound = choice;
+        } else {
+            s.pomodoro_sound = choice;
+        }
+    });
+    Some(EventResult::Continue)
+}
+
+/// Handles a click on one of the start/pause hotkey choices.
+fn handle_hotkey_change(app: &mut App, id: &str) -> Option<EventResult> {
+    let index: usize = id.strip_prefix(HOTKEY_ID_PREFIX)?.parse().ok()?;
+    let binding = *START_PAUSE_CHOICES.get(index)?;
+    update_setting(app, |s| s.hotkey_start_pause = binding.to_string());
+    Some(EventResult::HotkeysChanged)
+}
+
+/// Installs or removes the LaunchAgent when an import changed Start at
+/// Login from `previous`, putting the setting back if that fails.
+fn apply_launch_at_login(app: &mut App, previous: bool) {
+    let wanted = app.settings.launch_at_login;
+    if wanted == previous {
+        return;
+    }
+    if let Err(e) = launch_agent::set_enabled(wanted) {
+        eprintln!("Failed to change Start at Login: {}", e);
+        update_setting(app, |s| s.launch_at_login = previous);
+    }
+}
+
+/// Handles a click on one of the Presets items: sets all four timings at
+/// once. The menu is rebuilt, since a preset's timings needn't be among the
+/// choices the duration submenus were built with.
+fn handle_preset_change(app: &mut App, id: &str) -> Option<EventResult> {
+    let preset = Preset::find(id.strip_prefix(PRESET_ID_PREFIX)?)?;
+    update_setting(app, |s| preset.apply(s));
+    Some(EventResult::RebuildMenu)
+}
+
+/// Handles a click on one of the ready-made Tray Format items. The menu is
+/// rebuilt to move the check off a custom format, which has no item.
+fn handle_tray_format_change(app: &mut App, id: &str) -> Option<EventResult> {
+    let index: usize = id.strip_prefix(TRAY_FORMAT_ID_PREFIX)?.parse().ok()?;
+    let (format, _) = *TRAY_FORMAT_CHOICES.get(index)?;
+    update_setting(app, |s| s.tray_format = format.to_string());
+    Some(EventResult::RebuildMenu)
+}
+
+/// Handles a click on one of the recent tasks in the Task submenu.
+fn handle_task_change(app: &mut App, id: &str) -> Option<EventResult> {
+    let label = id.strip_prefix(TASK_ID_PREFIX)?;
+    app.set_task(Some(label.to_string()));
+    Some(EventResult::StateChanged)
+}
+
 /// Handles duration setting changes from submenus.
-fn handle_duration_change(app: &mut App, items: &MenuItems, id: &str) -> Option<EventResult> {
+fn handle_duration_change(app: &mut App, id: &str) -> Option<EventResult> {
     // Pomodoro duration
     if let Some(mins_str) = id.strip_prefix("pomo_") {
         if let Ok(mins) = mins_str.parse::<u32>() {
-            // Update checkmarks
-            for (&m, check) in &items.pomo_checks {
-                check.set_checked(m == mins);
-            }
-            app.update_setting(|s| s.pomodoro_mins = mins);
-            return Some(EventResult::SettingsChanged);
+            return Some(change_setting(app, |s| s.pomodoro_mins = mins));
         }
     }
 
     // Short break duration
     if let Some(mins_str) = id.strip_prefix("short_") {
         if let Ok(mins) = mins_str.parse::<u32>() {
-            for (&m, check) in &items.short_checks {
-                check.set_checked(m == mins);
-            }
-            app.update_setting(|s| s.short_break_mins = mins);
-            return Some(EventResult::SettingsChanged);
+            return Some(change_setting(app, |s| s.short_break_mins = mins));
         }
     }
 
     // Long break duration
     if let Some(mins_str) = id.strip_prefix("long_") {
         if let Ok(mins) = mins_str.parse::<u32>() {
-            for (&m, check) in &items.long_checks {
-                check.set_checked(m == mins);
-            }
-            app.update_setting(|s| s.long_break_mins = mins);
-            return Some(EventResult::SettingsChanged);
+            return Some(change_setting(app, |s| s.long_break_mins = mins));
+        }
+    }
+
+    // Daily focus limit (0 = no limit)
+    if let Some(mins_str) = id.strip_prefix("limit_") {
+        if let Ok(mins) = mins_str.parse::<u32>() {
+            return Some(change_setting(app, |s| {
+                s.max_daily_focus_mins = (mins > 0).then_some(mins)
+            }));
+        }
+    }
+
+    // Next-pomodoro reminder interval (0 = disabled)
+    if let Some(mins_str) = id.strip_prefix("remind_") {
+        if let Ok(mins) = mins_str.parse::<u32>() {
+            return Some(change_setting(app, |s| s.idle_reminder_mins = mins));
+        }
+    }
+
+    // Chime volume in percent (0 = mute)
+    if let Some(pct_str) = id.strip_prefix("vol_") {
+        if let Ok(pct) = pct_str.parse::<u32>() {
+            return Some(change_setting(app, |s| s.volume = pct as f32 / 100.0));
+        }
+    }
+
+    // End-of-timer warning lead time (0 = disabled)
+    if let Some(secs_str) = id.strip_prefix("warn_") {
+        if let Ok(secs) = secs_str.parse::<u32>() {
+            return Some(change_setting(app, |s| s.warn_before_end_secs = secs));
+        }
+    }
+
+    // Idle auto-pause threshold (0 = disabled)
+    if let Some(secs_str) = id.strip_prefix("idle_") {
+        if let Ok(secs) = secs_str.parse::<u32>() {
+            return Some(change_setting(app, |s| s.idle_pause_secs = secs));
+        }
+    }
+
+    // First day of the week; refreshes the weekly stats line
+    if let Some(name) = id.strip_prefix("week_start_") {
+        if let Some(start) = WeekStart::ALL
+            .into_iter()
+            .find(|start| start.name().eq_ignore_ascii_case(name))
+        {
+            return Some(change_setting(app, |s| s.week_starts_on = start));
+        }
+    }
+
+    // Progress bar style; redraws the progress line right away
+    if let Some(style_id) = id.strip_prefix(PROGRESS_STYLE_ID_PREFIX) {
+        if let Some(style) = ProgressBarStyle::ALL
+            .into_iter()
+            .find(|style| style.id() == style_id)
+        {
+            return Some(change_setting(app, |s| s.progress_bar_style = style));
+        }
+    }
+
+    // What the menu bar shows; redraws the title and icon right away
+    if let Some(display_id) = id.strip_prefix(TRAY_DISPLAY_ID_PREFIX) {
+        if let Some(display) = TrayDisplay::ALL
+            .into_iter()
+            .find(|display| display.id() == display_id)
+        {
+            return Some(change_setting(app, |s| s.tray_display = display));
+        }
+    }
+
+    // Hour the day starts at; never resets a day that has begun
+    if let Some(hour_str) = id.strip_prefix(DAY_START_ID_PREFIX) {
+        if let Ok(hour) = hour_str.parse::<u32>() {
+            return Some(change_setting(app, |s| s.day_starts_at_hour = hour));
         }
     }
 
     // Long break threshold
     if let Some(count_str) = id.strip_prefix("thresh_") {
         if let Ok(count) = count_str.parse::<u32>() {
-            for (&c, check) in &items.thresh_checks {
-                check.set_checked(c == count);
-            }
-            app.update_setting(|s| s.pomodoros_for_long_break = count);
-            return Some(EventResult::SettingsChanged);
+            return Some(change_setting(app, |s| s.pomodoros_for_long_break = count));
         }
     }
 
@@ -145,7 +573,365 @@ fn handle_duration_change(app: &mut App, items: &MenuItems, id: &str) -> Option<
 
 #[cfg(test)]
 mod tests {
-    // Event handling tests would require mocking the menu items
-    // which is complex. The logic is tested through integration tests
-    // and the app module tests.
+    use super::*;
+    use pomobar_core::persistence::Database;
+
+    fn create_test_app() -> App {
+        let db = Database::new_in_memory().unwrap();
+        App::new_with_db(db).unwrap()
+    }
+
+    #[test]
+    fn test_hotkey_start_pause_cycle() {
+        let mut app = create_test_app();
+
+        let result = handle_hotkey_action(&mut app, HotkeyAction::StartPause);
+        assert_eq!(result, EventResult::StateChanged);
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+
+        handle_hotkey_action(&mut app, HotkeyAction::StartPause);
+        assert!(app.state.is_paused());
+
+        handle_hotkey_action(&mut app, HotkeyAction::StartPause);
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+    }
+
+    #[test]
+    fn test_hotkey_start_pause_pauses_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        let result = handle_hotkey_action(&mut app, HotkeyAction::StartPause);
+        assert_eq!(result, EventResult::StateChanged);
+        assert!(matches!(app.state, TimerState::BreakPaused { .. }));
+    }
+
+    #[test]
+    fn test_pause_resume_follows_state() {
+        let mut app = create_test_app();
+        assert_eq!(handle_pause_resume(&mut app), EventResult::Continue);
+        assert!(app.state.is_idle());
+
+        app.start_pomodoro();
+        assert_eq!(handle_pause_resume(&mut app), EventResult::StateChanged);
+        assert!(app.state.is_paused());
+
+        assert_eq!(handle_pause_resume(&mut app), EventResult::StateChanged);
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+    }
+
+    #[test]
+    fn test_pause_resume_during_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        assert_eq!(handle_pause_resume(&mut app), EventResult::StateChanged);
+        assert!(matches!(app.state, TimerState::BreakPaused { .. }));
+
+        assert_eq!(handle_pause_resume(&mut app), EventResult::StateChanged);
+        assert!(matches!(app.state, TimerState::BreakActive { .. }));
+    }
+
+    #[test]
+    fn test_notification_skip_paused_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.pause();
+
+        let action = NotificationAction::SkipBreak { count: 1 };
+        let result = handle_notification_action(&mut app, action);
+        assert_eq!(result, EventResult::StateChanged);
+        assert!(matches!(app.state, TimerState::BreakFinished));
+    }
+
+    #[test]
+    fn test_notification_skip_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        let action = NotificationAction::SkipBreak { count: 1 };
+        let result = handle_notification_action(&mut app, action);
+        assert_eq!(result, EventResult::StateChanged);
+        assert!(matches!(app.state, TimerState::BreakFinished));
+    }
+
+    #[test]
+    fn test_notification_start_break_queues_next_pomodoro() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        let action = NotificationAction::StartBreak { count: 1 };
+        assert_eq!(
+            handle_notification_action(&mut app, action),
+            EventResult::Continue
+        );
+        assert!(app.state.is_break());
+        assert!(app.auto_start_next);
+
+        app.advance(std::time::Duration::from_secs(5 * 60));
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+    }
+
+    #[test]
+    fn test_notification_start_break_ignored_when_stale() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.skip_break();
+
+        let action = NotificationAction::StartBreak { count: 1 };
+        handle_notification_action(&mut app, action);
+        assert!(!app.auto_start_next);
+    }
+
+    #[test]
+    fn test_notification_start_pomodoro() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+        app.skip_break();
+
+        let action = NotificationAction::StartPomodoro { count: 1 };
+        let result = handle_notification_action(&mut app, action);
+        assert_eq!(result, EventResult::StateChanged);
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+    }
+
+    #[test]
+    fn test_notification_action_ignored_after_menu_change() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        // Break skipped via the menu before the notification is clicked
+        app.skip_break();
+        let action = NotificationAction::SkipBreak { count: 1 };
+        assert_eq!(
+            handle_notification_action(&mut app, action),
+            EventResult::Continue
+        );
+        assert!(matches!(app.state, TimerState::BreakFinished));
+
+        // Next pomodoro started and completed: the old notification is stale
+        app.start_pomodoro();
+        app.complete_early();
+        assert_eq!(
+            handle_notification_action(&mut app, action),
+            EventResult::Continue
+        );
+        assert!(app.state.is_break());
+    }
+
+    #[test]
+    fn test_notification_start_ignored_when_not_waiting() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+
+        let action = NotificationAction::StartPomodoro { count: 0 };
+        assert_eq!(
+            handle_notification_action(&mut app, action),
+            EventResult::Continue
+        );
+        assert!(matches!(app.state, TimerState::PomodoroActive { .. }));
+    }
+
+    #[test]
+    fn test_hotkey_stop() {
+        let mut app = create_test_app();
+
+        let result = handle_hotkey_action(&mut app, HotkeyAction::Stop);
+        assert_eq!(result, EventResult::Continue);
+
+        app.start_pomodoro();
+        let result = handle_hotkey_action(&mut app, HotkeyAction::Stop);
+        assert_eq!(result, EventResult::StateChanged);
+        assert!(app.state.is_idle());
+    }
+
+    fn click(app: &mut App, id: &str) -> EventResult {
+        handle_menu_event(
+            app,
+            MenuEvent {
+                id: muda::MenuId::new(id),
+            },
+        )
+    }
+
+    #[test]
+    fn test_start_anyway_needs_two_clicks() {
+        let mut app = create_test_app();
+
+        assert_eq!(click(&mut app, ID_START_ANYWAY), EventResult::Continue);
+        assert!(app.start_anyway_armed);
+        assert_eq!(click(&mut app, ID_START_ANYWAY), EventResult::StateChanged);
+        assert!(app.state.is_pomodoro());
+    }
+
+    #[test]
+    fn test_refused_start_offers_start_anyway() {
+        let mut app = create_test_app();
+        app.settings.max_daily_focus_mins = Some(25);
+        app.session.total_focus_mins_today = 25;
+
+        assert_eq!(
+            click(&mut app, ID_START),
+            EventResult::StartRefused(StartRefused::DailyLimit)
+        );
+        assert_eq!(
+            handle_hotkey_action(&mut app, HotkeyAction::StartPause),
+            EventResult::StartRefused(StartRefused::DailyLimit)
+        );
+        assert!(app.state.is_idle());
+
+        // The notification's button starts right away, unless the state moved on
+        let stale = NotificationAction::StartAnyway { count: 1 };
+        assert_eq!(
+            handle_notification_action(&mut app, stale),
+            EventResult::Continue
+        );
+        let action = NotificationAction::StartAnyway { count: 0 };
+        assert_eq!(
+            handle_notification_action(&mut app, action),
+            EventResult::StateChanged
+        );
+        assert!(app.state.is_pomodoro());
+        assert_eq!(
+            handle_notification_action(&mut app, action),
+            EventResult::Continue
+        );
+    }
+
+    #[test]
+    fn test_preset_click_rebuilds_menu() {
+        let mut app = create_test_app();
+
+        let result = click(&mut app, &format!("{}deep_work", PRESET_ID_PREFIX));
+        assert_eq!(result, EventResult::RebuildMenu);
+        assert_eq!(app.settings.pomodoro_mins, 90);
+        assert_eq!(app.settings.pomodoros_for_long_break, 2);
+    }
+
+    #[test]
+    fn test_long_break_now_click() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        assert_eq!(click(&mut app, ID_LONG_BREAK_NOW), EventResult::Continue);
+
+        app.stop();
+        assert_eq!(
+            click(&mut app, ID_LONG_BREAK_NOW),
+            EventResult::StateChanged
+        );
+        assert!(matches!(
+            app.state,
+            TimerState::BreakActive {
+                is_long_break: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_reset_cycle_click_keeps_counts() {
+        let mut app = create_test_app();
+        for _ in 0..2 {
+            app.start_pomodoro();
+            app.complete_early();
+            app.skip_break();
+        }
+
+        assert_eq!(click(&mut app, ID_RESET_CYCLE), EventResult::StateChanged);
+        assert_eq!(app.session.pomodoros_in_cycle, 0);
+        assert_eq!(app.session.pomodoros_completed_today, 2);
+
+        assert_eq!(click(&mut app, ID_RESET_COUNT), EventResult::StateChanged);
+        assert_eq!(app.session.pomodoros_completed_today, 0);
+    }
+
+    #[test]
+    fn test_tray_format_click_rebuilds_menu() {
+        let mut app = create_test_app();
+
+        let result = click(&mut app, &format!("{}1", TRAY_FORMAT_ID_PREFIX));
+        assert_eq!(result, EventResult::RebuildMenu);
+        assert_eq!(app.settings.tray_format, TRAY_FORMAT_CHOICES[1].0);
+
+        let unknown = format!("{}{}", TRAY_FORMAT_ID_PREFIX, TRAY_FORMAT_CHOICES.len());
+        assert_eq!(click(&mut app, &unknown), EventResult::Continue);
+        assert_eq!(click(&mut app, ID_TRAY_FORMAT), EventResult::EditTrayFormat);
+    }
+
+    #[test]
+    fn test_mark_interruption_click() {
+        let mut app = create_test_app();
+        assert_eq!(click(&mut app, ID_INTERRUPTION), EventResult::Continue);
+
+        app.start_pomodoro();
+        assert_eq!(click(&mut app, ID_INTERRUPTION), EventResult::StateChanged);
+        assert_eq!(app.pomodoro_interruptions, 1);
+    }
+
+    #[test]
+    fn test_task_clicks_set_task() {
+        let mut app = create_test_app();
+
+        let result = click(&mut app, &format!("{}Write report", TASK_ID_PREFIX));
+        assert_eq!(result, EventResult::StateChanged);
+        assert_eq!(app.task.as_deref(), Some("Write report"));
+        assert_eq!(click(&mut app, ID_TASK_NEW), EventResult::EditTask);
+        assert_eq!(click(&mut app, ID_TASK_NONE), EventResult::StateChanged);
+        assert_eq!(app.task, None);
+    }
+
+    #[test]
+    fn test_setting_clicks_change_settings() {
+        let mut app = create_test_app();
+
+        assert_eq!(click(&mut app, "pomo_45"), EventResult::SettingsChanged);
+        assert_eq!(click(&mut app, "limit_0"), EventResult::SettingsChanged);
+        assert_eq!(click(&mut app, ID_STRICT_TOGGLE), EventResult::StateChanged);
+        let id = format!("{}bell", BREAK_SOUND_ID_PREFIX);
+        assert_eq!(click(&mut app, &id), EventResult::Continue);
+
+        assert_eq!(app.settings.pomodoro_mins, 45);
+        assert_eq!(app.settings.max_daily_focus_mins, None);
+        assert!(app.settings.strict_mode);
+        assert_eq!(app.settings.break_sound, SoundChoice::Bell);
+        assert_eq!(click(&mut app, "pomo_soon"), EventResult::Continue);
+
+        assert_eq!(
+            click(&mut app, ID_THRESH_CUSTOM),
+            EventResult::EditLongBreakAfter
+        );
+        assert_eq!(click(&mut app, "thresh_8"), EventResult::SettingsChanged);
+        assert_eq!(app.settings.pomodoros_for_long_break, 8);
+        assert_eq!(click(&mut app, "thresh_4"), EventResult::SettingsChanged);
+
+        assert_eq!(
+            click(&mut app, ID_MENUBAR_TIME_TOGGLE),
+            EventResult::SettingsChanged
+        );
+        assert!(!app.settings.show_time_in_menubar);
+        assert_eq!(
+            click(&mut app, ID_BLINK_TOGGLE),
+            EventResult::SettingsChanged
+        );
+        assert!(!app.settings.attention_blink);
+        assert_eq!(
+            click(&mut app, &format!("{}both", TRAY_DISPLAY_ID_PREFIX)),
+            EventResult::SettingsChanged
+        );
+        assert_eq!(app.settings.tray_display, TrayDisplay::Both);
+
+        // Values out of range are skipped
+        assert_eq!(click(&mut app, "pomo_0"), EventResult::Continue);
+        assert_eq!(click(&mut app, "thresh_21"), EventResult::Continue);
+        assert_eq!(app.settings.pomodoro_mins, 45);
+        assert_eq!(app.settings.pomodoros_for_long_break, 4);
+    }
 }
@@ -0,0 +1,125 @@
+//! System-wide hotkeys for controlling the timer without opening the menu.
+
+use global_hotkey::hotkey::{HotKey, HotKeyParseError};
+use global_hotkey::GlobalHotKeyManager;
+use pomobar_core::models::Settings;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HotkeyError {
+    #[error("Invalid hotkey \"{0}\": {1}")]
+    Parse(String, HotKeyParseError),
+    #[error("Hotkey registration failed: {0}")]
+    Register(#[from] global_hotkey::Error),
+}
+
+/// Timer actions that can be bound to a global hotkey.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HotkeyAction {
+    /// Starts a pomodoro when idle, otherwise toggles pause/resume.
+    StartPause,
+    /// Stops the current pomodoro.
+    Stop,
+}
+
+/// Bindings offered for the start/pause hotkey in the Hotkey submenu.
+pub const START_PAUSE_CHOICES: [&str; 4] = [
+    "Ctrl+Option+P",
+    "Cmd+Shift+P",
+    "Ctrl+Shift+Space",
+    "Cmd+Option+Space",
+];
+
+/// Parses a hotkey binding such as "Ctrl+Option+P".
+pub fn parse_hotkey(binding: &str) -> Result<HotKey, HotkeyError> {
+    binding
+        .parse::<HotKey>()
+        .map_err(|e| HotkeyError::Parse(binding.to_string(), e))
+}
+
+/// The registered global hotkeys.
+/// Must be created on the main thread and kept alive for the hotkeys to work.
+pub struct Hotkeys {
+    _manager: GlobalHotKeyManager,
+    start_pause: HotKey,
+    stop: HotKey,
+}
+
+impl Hotkeys {
+    /// Registers the hotkeys configured in the settings.
+    /// Fails if a binding is invalid or already owned by another app.
+    pub fn register(settings: &Settings) -> Result<Self, HotkeyError> {
+        let start_pause = parse_hotkey(&settings.hotkey_start_pause)?;
+        let stop = parse_hotkey(&settings.hotkey_stop)?;
+
+        let manager = GlobalHotKeyManager::new()?;
+        manager.register_all(&[start_pause, stop])?;
+
+        Ok(Self {
+            _manager: manager,
+            start_pause,
+            stop,
+        })
+    }
+
+    /// Returns the action bound to the hotkey with the given id.
+    pub fn action_for(&self, id: u32) -> Option<HotkeyAction> {
+        if id == self.start_pause.id() {
+            Some(HotkeyAction::StartPause)
+        } else if id == self.stop.id() {
+            Some(HotkeyAction::Stop)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use global_hotkey::hotkey::{Code, Modifiers};
+
+    #[test]
+    fn test_parse_default_bindings() {
+        let settings = Settings::default();
+
+        let start_pause = parse_hotkey(&settings.hotkey_start_pause).unwrap();
+        assert_eq!(
+            start_pause,
+            HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyP)
+        );
+
+        let stop = parse_hotkey(&settings.hotkey_stop).unwrap();
+        assert_eq!(
+            stop,
+            HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyS)
+        );
+    }
+
+    #[test]
+    fn test_parse_is_case_insensitive() {
+        assert_eq!(
+            parse_hotkey("cmd+shift+p").unwrap(),
+            parse_hotkey("Command+Shift+P").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_start_pause_choices() {
+        let settings = Settings::default();
+        assert_eq!(START_PAUSE_CHOICES[0], settings.hotkey_start_pause);
+
+        let stop = parse_hotkey(&settings.hotkey_stop).unwrap();
+        for binding in START_PAUSE_CHOICES {
+            assert_ne!(parse_hotkey(binding).unwrap(), stop, "{}", binding);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_bindings() {
+        assert!(parse_hotkey("").is_err());
+        assert!(parse_hotkey("Ctrl+Option+").is_err());
+        assert!(parse_hotkey("Ctrl+P+Shift").is_err());
+        assert!(parse_hotkey("Ctrl+Banana").is_err());
+    }
+}
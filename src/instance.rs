@@ -0,0 +1,76 @@
+//! Keeps a second Pomobar from running on the same database.
+//!
+//! The running instance holds an exclusive lock on `pomobar.lock` in the
+//! data directory. The operating system drops the lock when the process
+//! exits, so a crash never leaves a stale lock behind.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::Path;
+use thiserror::Error;
+
+const LOCK_FILE_NAME: &str = "pomobar.lock";
+
+#[derive(Error, Debug)]
+pub enum InstanceError {
+    #[error("Pomobar is already running")]
+    AlreadyRunning,
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Proof that this process is the running instance. Keep it alive until
+/// the app quits.
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// Takes the instance lock in `dir` and writes our pid into the lock file.
+pub fn acquire(dir: &Path) -> Result<InstanceLock, InstanceError> {
+    fs::create_dir_all(dir)?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(dir.join(LOCK_FILE_NAME))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => return Err(InstanceError::AlreadyRunning),
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+    // Truncate only once locked, so the running instance's pid stays readable
+    file.set_len(0)?;
+    writeln!(file, "{}", std::process::id())?;
+    Ok(InstanceLock { _file: file })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_second_instance_is_refused() {
+        let dir = TempDir::new().unwrap();
+        let lock = acquire(dir.path()).unwrap();
+        assert!(matches!(
+            acquire(dir.path()),
+            Err(InstanceError::AlreadyRunning)
+        ));
+
+        let pid = fs::read_to_string(dir.path().join(LOCK_FILE_NAME)).unwrap();
+        assert_eq!(pid.trim(), std::process::id().to_string());
+
+        // Quitting releases the lock
+        drop(lock);
+        assert!(acquire(dir.path()).is_ok());
+    }
+
+    #[test]
+    fn test_creates_data_dir() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path().join("Pomobar");
+        acquire(&data_dir).unwrap();
+        assert!(data_dir.join(LOCK_FILE_NAME).exists());
+    }
+}
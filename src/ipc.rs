@@ -0,0 +1,379 @@
+//! Unix domain socket for controlling the timer from the terminal.
+//!
+//! Clients send newline-delimited JSON commands such as `{"cmd":"start"}`
+//! and receive one JSON response line per command. The socket lives in the
+//! data directory next to `pomobar.db`.
+
+use chrono::Local;
+use pomobar_core::actor::AppHandle;
+use pomobar_core::app::{App, StartRefused};
+use pomobar_core::models::{Session, Settings, TimerState};
+use pomobar_core::persistence;
+use pomobar_core::snapshot::MenuSnapshot;
+use pomobar_core::timer::TimerMessage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+const SOCKET_NAME: &str = "pomobar.sock";
+
+/// A command sent by a client.
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub cmd: String,
+}
+
+/// The reply to a command.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Response {
+    pub ok: bool,
+    pub state: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Why `start` was refused, so the menubar can offer Start Anyway.
+    #[serde(skip)]
+    pub refused: Option<StartRefused>,
+    /// Full snapshot, only included in `status` replies.
+    #[serde(flatten)]
+    pub snapshot: Option<Snapshot>,
+}
+
+/// Complete app state returned by `status`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Snapshot {
+    pub timer: TimerState,
+    pub session: Session,
+    pub settings: Settings,
+}
+
+/// Returns the socket path in the data directory.
+pub fn socket_path() -> PathBuf {
+    persistence::data_dir().join(SOCKET_NAME)
+}
+
+/// Removes the socket file. Called on quit.
+pub fn remove_socket() {
+    let _ = fs::remove_file(socket_path());
+}
+
+/// Binds the socket and serves clients until the process exits.
+/// Each state change is reported to the main thread through `tx`.
+pub fn run_listener(app: AppHandle, tx: Sender<TimerMessage>) -> io::Result<()> {
+    let path = socket_path();
+
+    if path.exists() {
+        // A socket that still accepts connections belongs to a running instance
+        if UnixStream::connect(&path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                "another Pomobar instance owns the control socket",
+            ));
+        }
+        // Remove a stale socket left behind by a previous run
+        fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Failed to accept IPC connection: {}", e);
+                continue;
+            }
+        };
+
+        let app = app.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            if let Err(e) = serve_client(stream, &app, &tx) {
+                eprintln!("IPC client error: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Answers every command line sent by a single client.
+fn serve_client(stream: UnixStream, app: &AppHandle, tx: &Sender<TimerMessage>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let reader = BufReader::new(stream);
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some((response, snapshot)) = app.try_call(move |app| {
+            let before = app.state.clone();
+            let response = handle_command(app, &line);
+            let snapshot = (app.state != before).then(|| Box::new(MenuSnapshot::new(app)));
+            (response, snapshot)
+        }) else {
+            // Pomobar is quitting
+            return Ok(());
+        };
+
+        if let Some(snapshot) = snapshot {
+            let _ = tx.send(TimerMessage::StateChanged(snapshot));
+        }
+        if let Some(reason) = response.refused {
+            let _ = tx.send(TimerMessage::StartRefused(reason));
+        }
+
+        let json = serde_json::to_string(&response)?;
+        writeln!(writer, "{}", json)?;
+    }
+
+    Ok(())
+}
+
+/// Parses and applies a single command line.
+pub fn handle_command(app: &mut App, line: &str) -> Response {
+    match serde_json::from_str::<Request>(line) {
+        Ok(request) => run_command(app, &request.cmd),
+        Err(e) => response(app, Some(format!("Invalid request: {}", e))),
+    }
+}
+
+/// Applies a single command, using the same App methods as the menu.
+/// AppleScript verbs go through here too, so both follow the same rules.
+pub fn run_command(app: &mut App, cmd: &str) -> Response {
+    let mut snapshot = None;
+    let mut refused = None;
+    let error = match cmd {
+        "start" if app.state.is_idle() => match app.try_start_pomodoro() {
+            Ok(()) => None,
+            Err(reason) => {
+                refused = Some(reason);
+                Some(reason.to_string())
+            }
+        },
+        "start" => Some("A timer is already running".to_string()),
+        "pause" | "stop" if app.state.is_locked(app.settings.strict_mode) => {
+            Some("Strict mode is on; the pomodoro can only be completed early".to_string())
+        }
+        "pause" if app.state.is_active() => {
+            app.pause();
+            None
+        }
+        "pause" => Some("No running timer to pause".to_string()),
+        "resume" if app.state.is_paused() => {
+            app.resume();
+            None
+        }
+        "resume" => Some("No paused timer to resume".to_string()),
+        "skip" if app.state.is_break() => {
+            app.skip_break();
+            None
+        }
+        "skip" => Some("No break to skip".to_string()),
+        "stop" if app.state.is_pomodoro() => {
+            app.stop();
+            None
+        }
+        "stop" => Some("No pomodoro to stop".to_string()),
+        "status" => {
+            snapshot = Some(Snapshot {
+                timer: app.state.clone(),
+                session: app.session.clone(),
+                settings: app.settings.clone(),
+            });
+            None
+        }
+        other => Some(format!("Unknown command: {}", other)),
+    };
+
+    Response {
+        refused,
+        snapshot,
+        ..response(app, error)
+    }
+}
+
+fn response(app: &App, error: Option<String>) -> Response {
+    Response {
+        ok: error.is_none(),
+        state: app.state.name(),
+        remaining: app.state.remaining_secs(),
+        started_at: app.current_block_started_at().map(|t| t.to_rfc3339()),
+        ends_at: app.projected_end_at(Local::now()).map(|t| t.to_rfc3339()),
+        error,
+        refused: None,
+        snapshot: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pomobar_core::actor::TimerActor;
+    use pomobar_core::models::TimerMode;
+    use pomobar_core::persistence::Database;
+    use std::sync::mpsc;
+
+    fn create_test_app() -> App {
+        let db = Database::new_in_memory().unwrap();
+        App::new_with_db(db).unwrap()
+    }
+
+    #[test]
+    fn test_status_when_idle() {
+        let mut app = create_test_app();
+        let response = handle_command(&mut app, r#"{"cmd":"status"}"#);
+
+        assert!(response.ok);
+        assert_eq!(response.state, "Idle");
+        assert_eq!(response.remaining, None);
+
+        let json: serde_json::Value = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["state"], "Idle");
+        assert_eq!(json["timer"], "Idle");
+        assert_eq!(json["session"]["pomodoros_completed_today"], 0);
+        assert_eq!(json["settings"]["pomodoro_mins"], 25);
+    }
+
+    #[test]
+    fn test_only_status_includes_snapshot() {
+        let mut app = create_test_app();
+        let response = handle_command(&mut app, r#"{"cmd":"start"}"#);
+        assert_eq!(response.snapshot, None);
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("settings"));
+    }
+
+    #[test]
+    fn test_resume_and_skip() {
+        let mut app = create_test_app();
+
+        let response = handle_command(&mut app, r#"{"cmd":"resume"}"#);
+        assert!(!response.ok);
+        let response = handle_command(&mut app, r#"{"cmd":"skip"}"#);
+        assert!(!response.ok);
+
+        app.start_pomodoro();
+        app.pause();
+        let response = handle_command(&mut app, r#"{"cmd":"resume"}"#);
+        assert!(response.ok);
+        assert_eq!(response.state, "PomodoroActive");
+
+        app.complete_early();
+        let response = handle_command(&mut app, r#"{"cmd":"skip"}"#);
+        assert!(response.ok);
+        assert_eq!(response.state, "BreakFinished");
+    }
+
+    #[test]
+    fn test_start_pause_stop() {
+        let mut app = create_test_app();
+
+        let response = handle_command(&mut app, r#"{"cmd":"start"}"#);
+        assert!(response.ok);
+        assert_eq!(response.state, "PomodoroActive");
+        assert_eq!(response.remaining, Some(25 * 60));
+        assert!(response.started_at.is_some());
+        assert!(response.ends_at.is_some());
+
+        let response = handle_command(&mut app, r#"{"cmd":"pause"}"#);
+        assert!(response.ok);
+        assert_eq!(response.state, "PomodoroPaused");
+
+        let response = handle_command(&mut app, r#"{"cmd":"stop"}"#);
+        assert!(response.ok);
+        assert_eq!(response.state, "Idle");
+    }
+
+    #[test]
+    fn test_pause_and_skip_break() {
+        let mut app = create_test_app();
+        app.start_pomodoro();
+        app.complete_early();
+
+        let response = handle_command(&mut app, r#"{"cmd":"pause"}"#);
+        assert!(response.ok);
+        assert_eq!(response.state, "BreakPaused");
+
+        let response = handle_command(&mut app, r#"{"cmd":"skip"}"#);
+        assert!(response.ok);
+        assert_eq!(response.state, "BreakFinished");
+    }
+
+    #[test]
+    fn test_commands_rejected_in_wrong_state() {
+        let mut app = create_test_app();
+
+        let response = handle_command(&mut app, r#"{"cmd":"pause"}"#);
+        assert!(!response.ok);
+        assert!(response.error.is_some());
+
+        app.start_pomodoro();
+        let response = handle_command(&mut app, r#"{"cmd":"start"}"#);
+        assert!(!response.ok);
+        assert_eq!(response.state, "PomodoroActive");
+        assert_eq!(response.refused, None);
+    }
+
+    #[test]
+    fn test_start_refused_past_daily_limit() {
+        let mut app = create_test_app();
+        app.settings.max_daily_focus_mins = Some(25);
+        app.session.total_focus_mins_today = 25;
+
+        let response = handle_command(&mut app, r#"{"cmd":"start"}"#);
+        assert!(!response.ok);
+        assert_eq!(response.error.as_deref(), Some("Daily focus limit reached"));
+        assert_eq!(response.refused, Some(StartRefused::DailyLimit));
+        assert_eq!(response.state, "Idle");
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let mut app = create_test_app();
+
+        let response = handle_command(&mut app, "not json");
+        assert!(!response.ok);
+
+        let response = handle_command(&mut app, r#"{"cmd":"explode"}"#);
+        assert!(!response.ok);
+        assert_eq!(response.error.as_deref(), Some("Unknown command: explode"));
+    }
+
+    #[test]
+    fn test_serve_client_round_trip() {
+        let actor = TimerActor::spawn(create_test_app(), mpsc::channel().0, TimerMode::EventLoop);
+        let (tx, rx) = mpsc::channel();
+        let (client, server) = UnixStream::pair().unwrap();
+
+        let mut writer = client.try_clone().unwrap();
+        for cmd in ["start", "pause", "resume", "status", "stop"] {
+            writeln!(writer, r#"{{"cmd":"{}"}}"#, cmd).unwrap();
+        }
+        writer.shutdown(std::net::Shutdown::Write).unwrap();
+
+        serve_client(server, &actor.handle(), &tx).unwrap();
+
+        let lines: Vec<String> = BufReader::new(client).lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with(r#"{"ok":true,"state":"PomodoroActive""#));
+        assert!(lines[1].starts_with(r#"{"ok":true,"state":"PomodoroPaused""#));
+        assert!(lines[2].starts_with(r#"{"ok":true,"state":"PomodoroActive""#));
+        assert!(lines[3].contains(r#""settings":"#));
+        assert!(lines[4].starts_with(r#"{"ok":true,"state":"Idle""#));
+
+        // Every command except status changed state
+        assert_eq!(rx.try_iter().count(), 4);
+    }
+}
@@ -1,15 +1,18 @@
 //! Launch agent management for macOS "Start at Login" functionality.
 //!
 //! Creates and removes a LaunchAgent plist file in ~/Library/LaunchAgents/
-//! to enable automatic startup at login.
+//! to enable automatic startup at login, and loads or unloads it with
+//! `launchctl` so the change takes effect without logging out.
 
 use directories::BaseDirs;
 use std::env;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
 use thiserror::Error;
 
+const LABEL: &str = "com.pomobar";
 const PLIST_NAME: &str = "com.pomobar.plist";
 
 #[derive(Error, Debug)]
@@ -20,6 +23,8 @@ pub enum LaunchAgentError {
     NoExePath(io::Error),
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
+    #[error("launchctl failed: {0}")]
+    LaunchctlFailed(String),
 }
 
 /// Returns the path to the LaunchAgents directory.
@@ -39,14 +44,14 @@ fn exe_path() -> Result<PathBuf, LaunchAgentError> {
 }
 
 /// Generates the plist content for the LaunchAgent.
-fn generate_plist(exe: &std::path::Path) -> String {
+fn generate_plist(exe: &Path) -> String {
     format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
 <!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
 <plist version="1.0">
 <dict>
     <key>Label</key>
-    <string>com.pomobar</string>
+    <string>{}</string>
     <key>ProgramArguments</key>
     <array>
         <string>{}</string>
@@ -58,10 +63,84 @@ fn generate_plist(exe: &std::path::Path) -> String {
 </dict>
 </plist>
 "#,
+        LABEL,
         exe.display()
     )
 }
 
+/// Returns the program path from plist content written by `generate_plist`:
+/// the first string in `ProgramArguments`.
+fn program_path(plist: &str) -> Option<&str> {
+    let (_, rest) = plist.split_once("<key>ProgramArguments</key>")?;
+    let (_, rest) = rest.split_once("<string>")?;
+    let (path, _) = rest.split_once("</string>")?;
+    Some(path.trim())
+}
+
+/// Returns whether `exe` runs from an installed app bundle, the only place
+/// worth pointing the LaunchAgent at. A build directory isn't, and neither
+/// is the temporary copy macOS runs a quarantined app from (App
+/// Translocation) until it has been moved out of Downloads.
+fn is_installed_app(exe: &Path) -> bool {
+    let path = exe.to_string_lossy();
+    path.contains(".app/Contents/MacOS/") && !path.contains("/AppTranslocation/")
+}
+
+/// Returns whether the plist at `plist` starts a program other than `exe`.
+/// A missing plist hasn't moved anywhere.
+fn exe_path_changed(plist: &Path, exe: &Path) -> Result<bool, LaunchAgentError> {
+    let content = match fs::read_to_string(plist) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(program_path(&content).map(Path::new) != Some(exe))
+}
+
+/// Runs `launchctl` and returns its output, or its stdout and stderr as
+/// the error if it exits unsuccessfully.
+fn launchctl(args: &[&str]) -> Result<String, LaunchAgentError> {
+    let output = Command::new("launchctl").args(args).output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() {
+        return Ok(stdout);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(LaunchAgentError::LaunchctlFailed(
+        format!("{} {}", stdout, stderr.trim()).trim().to_string(),
+    ))
+}
+
+/// Returns `launchctl list` output for our job, or None if it isn't loaded.
+fn list_job() -> Result<Option<String>, LaunchAgentError> {
+    match launchctl(&["list", LABEL]) {
+        Ok(output) => Ok(Some(output)),
+        // launchctl exits unsuccessfully for a job it doesn't know
+        Err(LaunchAgentError::LaunchctlFailed(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the pid from `launchctl list <label>` output, e.g. `"PID" = 123;`,
+/// or None if the job has no running process.
+fn job_pid(list_output: &str) -> Option<u32> {
+    list_output.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("\"PID\" = ")?;
+        value.strip_suffix(';')?.parse().ok()
+    })
+}
+
+/// Returns whether launchd started this process for our LaunchAgent, which
+/// it tells its jobs through `XPC_SERVICE_NAME`.
+pub fn started_by_launchd() -> bool {
+    env::var("XPC_SERVICE_NAME").is_ok_and(|name| name == LABEL)
+}
+
+/// Returns whether launchd has our LaunchAgent loaded.
+pub fn is_loaded() -> Result<bool, LaunchAgentError> {
+    Ok(list_job()?.is_some())
+}
+
 /// Enables launch at login by creating the LaunchAgent plist.
 pub fn enable() -> Result<(), LaunchAgentError> {
     let dir = launch_agents_dir()?;
@@ -75,6 +154,13 @@ pub fn enable() -> Result<(), LaunchAgentError> {
     let content = generate_plist(&exe);
     fs::write(&path, content)?;
 
+    // Register it now rather than at the next login. RunAtLoad starts a
+    // second Pomobar, which exits quietly as this one holds the instance
+    // lock, see `started_by_launchd`.
+    if !is_loaded()? {
+        launchctl(&["load", &path.to_string_lossy()])?;
+    }
+
     Ok(())
 }
 
@@ -82,6 +168,14 @@ pub fn enable() -> Result<(), LaunchAgentError> {
 pub fn disable() -> Result<(), LaunchAgentError> {
     let path = plist_path()?;
 
+    // Unloading stops the job's process, which is us if launchd started
+    // this Pomobar at login; then removing the plist has to be enough.
+    if let Some(job) = list_job()? {
+        if job_pid(&job) != Some(process::id()) {
+            launchctl(&["unload", &path.to_string_lossy()])?;
+        }
+    }
+
     if path.exists() {
         fs::remove_file(&path)?;
     }
@@ -89,6 +183,25 @@ pub fn disable() -> Result<(), LaunchAgentError> {
     Ok(())
 }
 
+/// Rewrites the LaunchAgent plist if it starts a Pomobar at another path,
+/// as after moving the app. Only an installed app bundle takes the plist
+/// over. Returns whether it was rewritten.
+pub fn verify_exe_path() -> Result<bool, LaunchAgentError> {
+    let exe = exe_path()?;
+    if !is_installed_app(&exe) || !exe_path_changed(&plist_path()?, &exe)? {
+        return Ok(false);
+    }
+    // launchd keeps a loaded job's old program until it is loaded again
+    let path = plist_path()?;
+    if let Some(job) = list_job()? {
+        if job_pid(&job) != Some(process::id()) {
+            launchctl(&["unload", &path.to_string_lossy()])?;
+        }
+    }
+    enable()?;
+    Ok(true)
+}
+
 /// Sets the launch at login state.
 pub fn set_enabled(enabled: bool) -> Result<(), LaunchAgentError> {
     if enabled {
@@ -101,6 +214,7 @@ pub fn set_enabled(enabled: bool) -> Result<(), LaunchAgentError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
 
     #[test]
     fn test_plist_generation() {
@@ -113,6 +227,70 @@ mod tests {
         assert!(plist.contains("<true/>"));
     }
 
+    #[test]
+    fn test_program_path() {
+        let exe = PathBuf::from("/Applications/Pomobar.app/Contents/MacOS/pomobar");
+        let plist = generate_plist(&exe);
+        assert_eq!(program_path(&plist), exe.to_str());
+        assert_eq!(program_path("<plist></plist>"), None);
+    }
+
+    #[test]
+    fn test_exe_path_changed() {
+        let dir = TempDir::new().unwrap();
+        let plist = dir.path().join(PLIST_NAME);
+        let exe = PathBuf::from("/Applications/Pomobar.app/Contents/MacOS/pomobar");
+
+        // Nothing to update without a plist
+        assert!(!exe_path_changed(&plist, &exe).unwrap());
+
+        fs::write(&plist, generate_plist(&exe)).unwrap();
+        assert!(!exe_path_changed(&plist, &exe).unwrap());
+
+        let moved = PathBuf::from("/Users/me/Applications/Pomobar.app/Contents/MacOS/pomobar");
+        assert!(exe_path_changed(&plist, &moved).unwrap());
+
+        // A plist without a program gets rewritten too
+        fs::write(&plist, "<plist></plist>").unwrap();
+        assert!(exe_path_changed(&plist, &exe).unwrap());
+    }
+
+    #[test]
+    fn test_is_installed_app() {
+        assert!(is_installed_app(Path::new(
+            "/Applications/Pomobar.app/Contents/MacOS/pomobar"
+        )));
+        assert!(is_installed_app(Path::new(
+            "/Users/me/Applications/Pomobar.app/Contents/MacOS/pomobar"
+        )));
+        assert!(!is_installed_app(Path::new(
+            "/Users/me/src/pomobar/target/debug/pomobar"
+        )));
+        assert!(!is_installed_app(Path::new("/usr/local/bin/pomobar")));
+        assert!(!is_installed_app(Path::new(
+            "/private/var/folders/xy/abc/T/AppTranslocation/1234-5678/d/Pomobar.app/Contents/MacOS/pomobar"
+        )));
+    }
+
+    #[test]
+    fn test_job_pid() {
+        let running = r#"{
+	"LimitLoadToSessionType" = "Aqua";
+	"Label" = "com.pomobar";
+	"PID" = 4242;
+	"Program" = "/Applications/Pomobar.app/Contents/MacOS/pomobar";
+};"#;
+        assert_eq!(job_pid(running), Some(4242));
+
+        // A loaded job whose process has exited
+        let exited = r#"{
+	"Label" = "com.pomobar";
+	"LastExitStatus" = 0;
+};"#;
+        assert_eq!(job_pid(exited), None);
+        assert_eq!(job_pid(""), None);
+    }
+
     #[test]
     fn test_launch_agents_dir() {
         let dir = launch_agents_dir();
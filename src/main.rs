@@ -3,53 +3,119 @@
 //! This application provides a simple, distraction-free pomodoro timer
 //! that lives in your menubar.
 
-use std::sync::mpsc::{self, Receiver};
-use std::sync::{Arc, Mutex};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
+use chrono::NaiveDate;
+use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
 use muda::MenuEvent;
-use tray_icon::{TrayIcon, TrayIconBuilder};
+use tray_icon::{TrayIcon, TrayIconBuilder, TrayIconEvent};
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::window::WindowId;
 
-mod app;
 mod audio;
+mod dialog;
 mod event;
+mod hotkeys;
+mod instance;
+mod ipc;
 mod launch_agent;
 mod menu;
-mod models;
 mod notifications;
-mod persistence;
-mod timer;
+mod power;
+mod scripting;
+mod self_test;
+mod tray;
 
-use app::{App, CompletionEvent};
 use audio::AudioPlayer;
 use event::EventResult;
+use hotkeys::Hotkeys;
+use instance::InstanceError;
 use menu::MenuItems;
-use timer::TimerMessage;
+use notifications::NotificationAction;
+use pomobar_core::actor::{AppHandle, TimerActor};
+use pomobar_core::app::{App, AppError, CompletionEvent, StartRefused, WarningEvent};
+use pomobar_core::models::{self, DurationSetting, Settings, SoundChoice, TimerMode, TimerState};
+use pomobar_core::persistence::{self, Database, DatabaseError};
+use pomobar_core::snapshot::MenuSnapshot;
+use pomobar_core::timer::{self, EventLoopTimer, TickOutput, TimerMessage};
+use pomobar_core::{idle, metrics, webhook};
+use tray::{TrayIconImage, TrayIcons};
+
+const TRAY_FORMAT_PROMPT: &str =
+    "Tray title format. Placeholders: {icon} {mm} {ss} {remaining} {pct} {task}";
+const DAILY_WEBHOOK_PROMPT: &str =
+    "URL to receive a summary of each finished day (leave empty to disable):";
+const EVENT_WEBHOOK_PROMPT: &str =
+    "URL to receive a POST for every timer event (leave empty to disable):";
+const LOG_PAST_PROMPT: &str = "Length of the pomodoro to log, in minutes:";
+const TASK_PROMPT: &str = "Task to log the next pomodoros under:";
+const BACKUP_PROMPT: &str = "Save a backup of your Pomobar data as:";
+
+/// Longest the event loop sleeps in event-loop timer mode, so menu and hotkey
+/// events (which don't wake the loop yet) are still picked up promptly.
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Application handler for the winit event loop.
 struct Pomobar {
-    app: Arc<Mutex<App>>,
+    /// Runs commands on the app, which lives on the timer actor.
+    app: AppHandle,
+    /// The timer actor, stopped on quit.
+    actor: Option<TimerActor>,
     tray: Option<TrayIcon>,
     menu_items: Option<MenuItems>,
     timer_rx: Receiver<TimerMessage>,
     audio: Option<AudioPlayer>,
+    hotkeys: Option<Hotkeys>,
+    action_tx: Sender<NotificationAction>,
+    action_rx: Receiver<NotificationAction>,
+    /// Schedules the ticks from the event loop; None when the actor ticks.
+    event_timer: Option<EventLoopTimer>,
+    /// The images the tray icon is made from.
+    icons: TrayIcons,
+    /// The icon currently shown, so it is only replaced when it changes.
+    icon: TrayIconImage,
+    /// The day the yesterday line was last loaded on.
+    date_last_refreshed: NaiveDate,
 }
 
 impl Pomobar {
-    fn new(app: Arc<Mutex<App>>, tray: TrayIcon, timer_rx: Receiver<TimerMessage>) -> Self {
+    fn new(
+        actor: TimerActor,
+        snapshot: &MenuSnapshot,
+        tray: TrayIcon,
+        icons: TrayIcons,
+        timer_rx: Receiver<TimerMessage>,
+        event_timer: Option<EventLoopTimer>,
+    ) -> Self {
         // Audio is created on the main thread to avoid Send issues
         let audio = AudioPlayer::new().ok();
 
+        // Hotkeys must be registered on the main thread as well
+        let hotkeys = register_hotkeys(&snapshot.settings);
+
+        // Channel for notification action buttons
+        let (action_tx, action_rx) = mpsc::channel();
+
         Self {
-            app,
+            app: actor.handle(),
+            actor: Some(actor),
             tray: Some(tray),
             menu_items: None,
             timer_rx,
             audio,
+            hotkeys,
+            action_tx,
+            action_rx,
+            event_timer,
+            icons,
+            icon: TrayIconImage::IDLE,
+            // The menu was built with yesterday's stats for this day
+            date_last_refreshed: snapshot.session.last_date,
         }
     }
 
@@ -57,96 +123,541 @@ impl Pomobar {
         self.menu_items = Some(items);
     }
 
-    fn update_menu(&self) {
+    /// Runs `f` on the app and returns its result along with a fresh
+    /// snapshot for the menu.
+    fn call_with_snapshot<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut App) -> R + Send + 'static,
+    ) -> (R, MenuSnapshot) {
+        self.app.call(|app| {
+            let result = f(app);
+            (result, MenuSnapshot::new(app))
+        })
+    }
+
+    /// Returns a copy of the current settings.
+    fn settings(&self) -> Settings {
+        self.app.call(|app| app.settings.clone())
+    }
+
+    fn update_menu(&mut self, snapshot: &MenuSnapshot) {
+        // Getting Started goes away once a timer has been started
+        if snapshot.getting_started && !snapshot.state.is_idle() {
+            let (hidden, snapshot) = self.call_with_snapshot(App::update_getting_started);
+            if hidden {
+                self.rebuild_menu(&snapshot);
+            }
+        }
+        // The Task submenus change with each completed pomodoro
+        if self
+            .menu_items
+            .as_ref()
+            .is_some_and(|items| menu::task_menus_stale(items, snapshot))
+        {
+            self.rebuild_menu(snapshot);
+        }
         if let Some(ref items) = self.menu_items {
-            let app = self.app.lock().unwrap();
-            menu::update_menu_items(items, &app.state, &app.session);
+            menu::update_menu_items(items, snapshot);
+            // Yesterday's stats only change when the day rolls over
+            if snapshot.session.last_date != self.date_last_refreshed {
+                menu::update_yesterday_stats(items, &snapshot.yesterday);
+                self.date_last_refreshed = snapshot.session.last_date;
+            }
+        }
+    }
+
+    /// Builds the menu from scratch, for changes the check items can't show
+    /// (such as a duration that isn't one of the presets).
+    fn rebuild_menu(&mut self, snapshot: &MenuSnapshot) {
+        match menu::build_menu(snapshot, dev_mode()) {
+            Ok((built_menu, items)) => {
+                if let Some(ref tray) = self.tray {
+                    tray.set_menu(Some(Box::new(built_menu)));
+                }
+                self.menu_items = Some(items);
+            }
+            Err(e) => eprintln!("Failed to rebuild menu: {}", e),
         }
     }
 
+    /// Shows a snapshot in the menu and the tray title and icon.
+    fn show_snapshot(&mut self, snapshot: &MenuSnapshot) {
+        self.update_menu(snapshot);
+        let display = self.icons.display(snapshot.settings.tray_display);
+        let title = if snapshot.attention_blink {
+            timer::ATTENTION_TITLE.to_string()
+        } else if display != snapshot.settings.tray_display {
+            let settings = Settings {
+                tray_display: display,
+                ..snapshot.settings.clone()
+            };
+            timer::render_tray_title(&snapshot.state, &settings, snapshot.task.as_deref())
+        } else {
+            timer::render_tray_title(
+                &snapshot.state,
+                &snapshot.settings,
+                snapshot.task.as_deref(),
+            )
+        };
+        self.update_tray_title(&title);
+        self.update_tray_icon(&snapshot.state, &snapshot.settings);
+    }
+
     fn update_tray_title(&self, title: &str) {
         if let Some(ref tray) = self.tray {
             tray.set_title(Some(title));
         }
     }
 
-    fn handle_completion(&self, event: CompletionEvent) {
-        let app = self.app.lock().unwrap();
+    fn update_tray_icon(&mut self, state: &TimerState, settings: &Settings) {
+        let icon = TrayIconImage::new(state, settings.tray_display);
+        if icon == self.icon || self.icons.has_user_icon() {
+            return;
+        }
+        if let Some(ref tray) = self.tray {
+            if let Err(e) = self.icons.set_icon(tray, icon) {
+                eprintln!("Failed to update tray icon: {}", e);
+                return;
+            }
+        }
+        self.icon = icon;
+    }
 
-        // Play sound if enabled
-        if app.settings.sound_enabled {
-            if let Some(ref audio) = self.audio {
-                audio.play_chime();
+    fn update_tray_tooltip(&self, tooltip: &str) {
+        if let Some(ref tray) = self.tray {
+            if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+                eprintln!("Failed to update tooltip: {}", e);
             }
         }
+    }
+
+    fn play_sound(&self, choice: &SoundChoice, settings: &Settings) {
+        if let Some(ref audio) = self.audio {
+            let default_sound = settings.custom_sound_path.as_deref().map(Path::new);
+            audio.play_sound(choice, settings.volume, default_sound);
+        }
+    }
+
+    fn handle_completion(&self, event: CompletionEvent) {
+        let (settings, state, completed, vars) = self.app.call(|app| {
+            let completed = app.session.pomodoros_completed_today;
+            (
+                app.settings.clone(),
+                app.state.clone(),
+                completed,
+                app.template_vars(),
+            )
+        });
+
+        // Play the sound for this kind of event if enabled
+        if let Some(ref audio) = self.audio {
+            audio::play_completion_sound(audio, event, &settings);
+        }
 
-        // Show notification if enabled
-        if app.settings.notifications_enabled {
+        // Show notification if enabled; the sound above still plays under Do Not Disturb
+        if notifications::allowed(&settings) {
             match event {
                 CompletionEvent::PomodoroComplete {
                     count,
                     is_long_break,
                 } => {
                     if is_long_break {
-                        notifications::notify_long_break_start(app.long_break_mins());
+                        notifications::notify_long_break_start(
+                            &settings,
+                            &vars,
+                            count,
+                            self.action_tx.clone(),
+                        );
                     } else {
-                        notifications::notify_pomodoro_complete(count);
+                        notifications::notify_pomodoro_complete(
+                            &settings,
+                            &vars,
+                            count,
+                            self.action_tx.clone(),
+                        );
                     }
                 }
+                CompletionEvent::OvertimeStarted => {
+                    let mins = state.total_secs().unwrap_or_default() / 60;
+                    notifications::notify_overtime_started(&settings, &vars, mins);
+                }
+                // "Start Break" was clicked earlier, so the next pomodoro is already running
+                CompletionEvent::BreakComplete if state.is_pomodoro() => {
+                    notifications::notify_next_pomodoro_started(&settings, &vars);
+                }
                 CompletionEvent::BreakComplete => {
-                    notifications::notify_break_complete();
+                    notifications::notify_break_complete(
+                        &settings,
+                        &vars,
+                        completed,
+                        self.action_tx.clone(),
+                    );
                 }
             }
         }
     }
 
+    fn handle_warning(&self, warning: WarningEvent) {
+        let (settings, vars) = self
+            .app
+            .call(|app| (app.settings.clone(), app.template_vars()));
+
+        // A softer tone than the completion chime
+        let sound_enabled = if warning.is_break {
+            settings.sound_on_break_end
+        } else {
+            settings.sound_on_pomodoro_end
+        };
+        if sound_enabled {
+            if let Some(ref audio) = self.audio {
+                audio.play_soft_tone(settings.volume);
+            }
+        }
+
+        if notifications::allowed(&settings) {
+            notifications::notify_ending_soon(
+                &settings,
+                &vars,
+                warning.remaining_secs,
+                warning.is_break,
+            );
+        }
+    }
+
     fn process_timer_messages(&mut self) {
         // Process all pending timer messages
         while let Ok(msg) = self.timer_rx.try_recv() {
-            match msg {
-                TimerMessage::StateChanged { title } => {
-                    self.update_tray_title(&title);
-                    self.update_menu();
+            self.handle_timer_message(msg);
+        }
+    }
+
+    fn handle_timer_message(&mut self, msg: TimerMessage) {
+        match msg {
+            TimerMessage::StateChanged(snapshot) => {
+                self.show_snapshot(&snapshot);
+            }
+            TimerMessage::Completed(event) => {
+                self.handle_completion(event);
+            }
+            TimerMessage::Warning(warning) => {
+                self.handle_warning(warning);
+            }
+            TimerMessage::PersistFailed { error } => {
+                if notifications::allowed(&self.settings()) {
+                    notifications::notify_persist_failed(&error);
+                }
+            }
+            TimerMessage::TickSound => {
+                let volume = self.app.call(|app| app.settings.volume);
+                if let Some(ref audio) = self.audio {
+                    audio.play_click(volume);
+                }
+            }
+            TimerMessage::Reminder { count } => {
+                let (settings, vars) = self
+                    .app
+                    .call(|app| (app.settings.clone(), app.template_vars()));
+                if notifications::allowed(&settings) {
+                    notifications::notify_next_pomodoro_reminder(
+                        &settings,
+                        &vars,
+                        count,
+                        self.action_tx.clone(),
+                    );
                 }
-                TimerMessage::Completed(event) => {
-                    self.handle_completion(event);
+            }
+            TimerMessage::TooltipChanged { tooltip } => {
+                self.update_tray_tooltip(&tooltip);
+            }
+            TimerMessage::DayFinished(summary) => {
+                if notifications::allowed(&self.settings()) {
+                    notifications::notify_daily_summary(&summary);
                 }
             }
+            TimerMessage::StartRefused(reason) => {
+                self.notify_start_refused(reason);
+            }
         }
     }
 
+    /// Says why a pomodoro didn't start, with a button to start it anyway.
+    fn notify_start_refused(&self, reason: StartRefused) {
+        let (settings, count) = self
+            .app
+            .call(|app| (app.settings.clone(), app.session.pomodoros_completed_today));
+        if notifications::allowed(&settings) {
+            notifications::notify_start_refused(reason, count, self.action_tx.clone());
+        }
+    }
+
+    /// Runs a timer tick on the actor if one is due (event-loop timer mode
+    /// only). Returns when the loop should wake up for the next one.
+    fn run_event_loop_tick(&mut self) -> Option<Instant> {
+        let next_tick = self.event_timer.as_ref()?.next_tick();
+        if Instant::now() < next_tick {
+            return Some(next_tick);
+        }
+        let mut timer = self.event_timer.take()?;
+        let (timer, output) = self.app.call(move |app| {
+            let output = timer.poll(
+                app,
+                Instant::now(),
+                SystemTime::now(),
+                idle::seconds_since_last_input,
+            );
+            (timer, output)
+        });
+        let next_tick = timer.next_tick();
+        self.event_timer = Some(timer);
+
+        if let Some(TickOutput {
+            messages,
+            summary_queued,
+        }) = output
+        {
+            for msg in messages {
+                self.handle_timer_message(msg);
+            }
+            if summary_queued {
+                webhook::spawn_flush(self.app.clone());
+            }
+        }
+        Some(next_tick)
+    }
+
     fn process_menu_events(&mut self, event_loop: &ActiveEventLoop) {
         if let Ok(event) = MenuEvent::receiver().try_recv() {
-            if let Some(ref items) = self.menu_items {
-                let result = {
-                    let mut app = self.app.lock().unwrap();
-                    event::handle_menu_event(&mut app, items, event)
-                };
-
-                match result {
-                    EventResult::Quit => {
-                        event_loop.exit();
+            let (result, snapshot) =
+                self.call_with_snapshot(move |app| event::handle_menu_event(app, event));
+            self.apply_event_result(event_loop, result, snapshot);
+        }
+    }
+
+    /// Stops the tray title blinking once the menu is opened; the next
+    /// tick puts the plain title back.
+    fn process_tray_events(&self) {
+        while let Ok(event) = TrayIconEvent::receiver().try_recv() {
+            if matches!(event, TrayIconEvent::Click { .. }) {
+                self.app.send(|app| app.mark_attention_seen());
+            }
+        }
+    }
+
+    fn process_hotkey_events(&mut self, event_loop: &ActiveEventLoop) {
+        if let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
+            if event.state != HotKeyState::Pressed {
+                return;
+            }
+            let action = self
+                .hotkeys
+                .as_ref()
+                .and_then(|hotkeys| hotkeys.action_for(event.id));
+            if let Some(action) = action {
+                let (result, snapshot) =
+                    self.call_with_snapshot(move |app| event::handle_hotkey_action(app, action));
+                self.apply_event_result(event_loop, result, snapshot);
+            }
+        }
+    }
+
+    fn process_notification_actions(&mut self, event_loop: &ActiveEventLoop) {
+        if let Ok(action) = self.action_rx.try_recv() {
+            let (result, snapshot) =
+                self.call_with_snapshot(move |app| event::handle_notification_action(app, action));
+            self.apply_event_result(event_loop, result, snapshot);
+        }
+    }
+
+    /// Stops the timer actor and saves what quitting would lose, however
+    /// the event loop exits. The actor is only stopped once.
+    fn shut_down(&mut self) {
+        // Stop the actor first, so nothing changes while we save
+        if let Some(mut app) = self.actor.take().and_then(TimerActor::shutdown) {
+            app.save_before_quit();
+            // Never leave Focus on after quitting
+            app.release_focus();
+            if let Some(path) = &app.settings.metrics_textfile_path {
+                if let Err(e) = metrics::remove(path) {
+                    eprintln!("Failed to remove metrics file: {}", e);
+                }
+            }
+            if let Err(e) = app.db.checkpoint() {
+                eprintln!("Failed to checkpoint database: {}", e);
+            }
+        }
+        ipc::remove_socket();
+    }
+
+    /// Acts on the result of an event. `snapshot` was taken right after the
+    /// event was handled.
+    fn apply_event_result(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        result: EventResult,
+        snapshot: MenuSnapshot,
+    ) {
+        match result {
+            EventResult::Quit => {
+                // `exiting` stops the actor and saves
+                event_loop.exit();
+            }
+            EventResult::StateChanged | EventResult::SettingsChanged => {
+                self.show_snapshot(&snapshot);
+            }
+            EventResult::RebuildMenu => {
+                self.rebuild_menu(&snapshot);
+                self.show_snapshot(&snapshot);
+            }
+            EventResult::HotkeysChanged => {
+                // Drop the old registration first so a binding can be reused
+                self.hotkeys = None;
+                self.hotkeys = register_hotkeys(&snapshot.settings);
+                self.apply_event_result(event_loop, EventResult::RebuildMenu, snapshot);
+            }
+            EventResult::StartRefused(reason) => {
+                self.show_snapshot(&snapshot);
+                self.notify_start_refused(reason);
+            }
+            EventResult::StateChangedWithCompletion(completion_event) => {
+                self.show_snapshot(&snapshot);
+                self.handle_completion(completion_event);
+            }
+            EventResult::EditTrayFormat => {
+                let mut message = TRAY_FORMAT_PROMPT.to_string();
+                let mut default = snapshot.settings.tray_format;
+                while let Some(format) = dialog::prompt_text(&message, &default) {
+                    if let Some(name) = timer::unknown_tray_placeholder(&format) {
+                        message =
+                            format!("Unknown placeholder {{{}}}. {}", name, TRAY_FORMAT_PROMPT);
+                        default = format;
+                        continue;
+                    }
+                    let (_, snapshot) = self
+                        .call_with_snapshot(|app| app.update_setting(|s| s.tray_format = format));
+                    // The submenu checks the format if it's one of the choices
+                    self.apply_event_result(event_loop, EventResult::RebuildMenu, snapshot);
+                    break;
+                }
+            }
+            EventResult::EditDailyWebhook => {
+                let current = snapshot.settings.daily_webhook_url.unwrap_or_default();
+                if let Some(url) = dialog::prompt_text(DAILY_WEBHOOK_PROMPT, &current) {
+                    let url = url.trim().to_string();
+                    let url = (!url.is_empty()).then_some(url);
+                    self.app.send(|app| {
+                        let _ = app.update_setting(|s| s.daily_webhook_url = url);
+                    });
+                }
+            }
+            EventResult::EditEventWebhook => {
+                let current = snapshot.settings.webhook_url.unwrap_or_default();
+                if let Some(url) = dialog::prompt_text(EVENT_WEBHOOK_PROMPT, &current) {
+                    let url = url.trim().to_string();
+                    let url = (!url.is_empty()).then_some(url);
+                    let (_, snapshot) =
+                        self.call_with_snapshot(|app| app.update_setting(|s| s.webhook_url = url));
+                    // The item shows the URL
+                    self.apply_event_result(event_loop, EventResult::RebuildMenu, snapshot);
+                }
+            }
+            EventResult::EditCustomDuration(setting) => {
+                let current = setting.get(&snapshot.settings);
+                let mut message = format!("{} length in minutes:", setting.name());
+                let mut default = current.to_string();
+                while let Some(input) = dialog::prompt_text(&message, &default) {
+                    if let Some(mins) = DurationSetting::parse_custom(&input) {
+                        // parse_custom only accepts minutes in range
+                        let (_, snapshot) = self.call_with_snapshot(move |app| {
+                            app.update_setting(|s| setting.set(s, mins))
+                        });
+                        self.apply_event_result(event_loop, EventResult::RebuildMenu, snapshot);
+                        break;
                     }
-                    EventResult::StateChanged | EventResult::SettingsChanged => {
-                        self.update_menu();
-                        // Update tray title
-                        let app = self.app.lock().unwrap();
-                        let title = timer::format_tray_title(&app.state);
-                        drop(app); // Release lock before updating tray
-                        self.update_tray_title(&title);
+                    message = invalid_minutes_message();
+                    default = input;
+                }
+            }
+            EventResult::EditLongBreakAfter => {
+                let mut message = format!(
+                    "Pomodoros before a long break, from {} to {}:",
+                    models::LONG_BREAK_AFTER_RANGE.start(),
+                    models::LONG_BREAK_AFTER_RANGE.end()
+                );
+                let mut default = snapshot.settings.pomodoros_for_long_break.to_string();
+                while let Some(input) = dialog::prompt_text(&message, &default) {
+                    if let Some(count) = models::parse_long_break_after(&input) {
+                        let (_, snapshot) = self.call_with_snapshot(move |app| {
+                            app.update_setting(|s| s.pomodoros_for_long_break = count)
+                        });
+                        self.apply_event_result(event_loop, EventResult::RebuildMenu, snapshot);
+                        break;
                     }
-                    EventResult::StateChangedWithCompletion(completion_event) => {
-                        self.update_menu();
-                        // Update tray title
-                        let app = self.app.lock().unwrap();
-                        let title = timer::format_tray_title(&app.state);
-                        drop(app); // Release lock before handling completion
-                        self.update_tray_title(&title);
-                        self.handle_completion(completion_event);
+                    message = format!(
+                        "Please enter a whole number from {} to {}:",
+                        models::LONG_BREAK_AFTER_RANGE.start(),
+                        models::LONG_BREAK_AFTER_RANGE.end()
+                    );
+                    default = input;
+                }
+            }
+            EventResult::LogPastPomodoro => {
+                let mut default = snapshot.settings.pomodoro_mins.to_string();
+                let mut message = LOG_PAST_PROMPT.to_string();
+                while let Some(input) = dialog::prompt_text(&message, &default) {
+                    if let Some(mins) = DurationSetting::parse_custom(&input) {
+                        let ((), snapshot) =
+                            self.call_with_snapshot(move |app| app.log_manual_pomodoro(mins));
+                        self.apply_event_result(event_loop, EventResult::StateChanged, snapshot);
+                        break;
                     }
-                    EventResult::Continue => {}
+                    message = invalid_minutes_message();
+                    default = input;
+                }
+            }
+            EventResult::EditTask => {
+                let current = snapshot.task.unwrap_or_default();
+                if let Some(task) = dialog::prompt_text(TASK_PROMPT, &current) {
+                    let ((), snapshot) =
+                        self.call_with_snapshot(move |app| app.set_task(Some(task)));
+                    self.apply_event_result(event_loop, EventResult::StateChanged, snapshot);
+                }
+            }
+            EventResult::ChooseCustomSound => {
+                if let Some(path) = dialog::choose_sound_file() {
+                    self.app.send(|app| {
+                        let _ = app.update_setting(|s| s.custom_sound_path = Some(path));
+                    });
+                }
+            }
+            EventResult::PreviewSound(choice) => {
+                self.play_sound(&choice, &snapshot.settings);
+            }
+            EventResult::PreviewCompletionSound(event) => {
+                if let Some(ref audio) = self.audio {
+                    audio.preview_for(event, &snapshot.settings);
                 }
             }
+            EventResult::BackupRequested(default) => {
+                // The dialog waits for the user, so it runs on its own thread
+                // and the copy is queued on the actor without waiting for it
+                let app = self.app.clone();
+                thread::spawn(move || {
+                    let Some(dest) = dialog::choose_save_path(BACKUP_PROMPT, &default) else {
+                        return;
+                    };
+                    app.send(move |app| match app.db.backup(&dest) {
+                        Ok(()) => notifications::notify_backup_complete(&dest),
+                        Err(e) => {
+                            eprintln!("Failed to back up database: {}", e);
+                            notifications::notify_backup_failed(&dest, e.to_string());
+                        }
+                    });
+                });
+            }
+            EventResult::Continue => {
+                // Checkmarks and labels follow the settings in the snapshot
+                self.update_menu(&snapshot);
+            }
         }
     }
 }
@@ -165,49 +676,174 @@ impl ApplicationHandler for Pomobar {
         // No window events for a tray-only app
     }
 
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        // Also covers the system quitting the app, e.g. on logout
+        self.shut_down();
+    }
+
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        // Set a short poll interval to check for events
-        event_loop.set_control_flow(ControlFlow::Poll);
+        match self.run_event_loop_tick() {
+            // Sleep until the next tick, waking early to check for input
+            Some(next_tick) => event_loop.set_control_flow(ControlFlow::WaitUntil(
+                next_tick.min(Instant::now() + INPUT_POLL_INTERVAL),
+            )),
+            // Set a short poll interval to check for events
+            None => event_loop.set_control_flow(ControlFlow::Poll),
+        }
 
         // Process timer messages from the background thread
         self.process_timer_messages();
 
         // Process menu events
         self.process_menu_events(event_loop);
+
+        // Process clicks on the tray icon
+        self.process_tray_events();
+
+        // Process global hotkey events
+        self.process_hotkey_events(event_loop);
+
+        // Process notification action buttons
+        self.process_notification_actions(event_loop);
+    }
+}
+
+/// Registers the global hotkeys unless they are turned off, notifying the
+/// user when that fails. Must run on the main thread.
+fn register_hotkeys(settings: &Settings) -> Option<Hotkeys> {
+    if !settings.hotkeys_enabled {
+        return None;
+    }
+    match Hotkeys::register(settings) {
+        Ok(hotkeys) => Some(hotkeys),
+        Err(e) => {
+            eprintln!("Failed to register hotkeys: {}", e);
+            if notifications::allowed(settings) {
+                notifications::notify_hotkeys_unavailable(e.to_string());
+            }
+            None
+        }
+    }
+}
+
+/// Prompt shown again after a number of minutes was rejected.
+fn invalid_minutes_message() -> String {
+    format!(
+        "Please enter a whole number of minutes from {} to {}:",
+        DurationSetting::CUSTOM_RANGE.start(),
+        DurationSetting::CUSTOM_RANGE.end()
+    )
+}
+
+/// Returns whether developer menu items are shown (`POMOBAR_DEV=1`).
+fn dev_mode() -> bool {
+    std::env::var("POMOBAR_DEV").is_ok_and(|value| value == "1")
+}
+
+/// Creates the app on the on-disk database. If that database can't be
+/// upgraded, the app runs on an in-memory one instead, so the timer still
+/// works and the file is left as it was.
+fn open_app() -> Result<App, AppError> {
+    match App::new() {
+        Err(AppError::Database(error @ DatabaseError::Migration { .. })) => {
+            eprintln!("{}; falling back to an in-memory database", error);
+            notifications::notify_database_unavailable(&error.to_string());
+            App::new_with_db(Database::new_in_memory()?)
+        }
+        result => result,
     }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Hidden mode that checks timer accuracy without starting the UI
+    if std::env::args().any(|arg| arg == "--self-test") {
+        self_test::run_cli()?;
+        return Ok(());
+    }
+
+    // A second instance would count its pomodoros into the same database
+    let _instance = match instance::acquire(&persistence::data_dir()) {
+        Ok(lock) => Some(lock),
+        Err(InstanceError::AlreadyRunning) => {
+            eprintln!("Pomobar is already running");
+            // Loading the LaunchAgent starts one more; nobody asked for it
+            if !launch_agent::started_by_launchd() {
+                notifications::notify_already_running();
+            }
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("Failed to check for a running instance: {}", e);
+            None
+        }
+    };
+
     // Initialize app state
-    let app = Arc::new(Mutex::new(App::new()?));
+    let mut app = open_app()?;
+    if app.getting_started {
+        notifications::notify_welcome();
+    }
+
+    // Start at Login keeps the path Pomobar had when it was turned on. A
+    // development build shares the settings but mustn't take it over.
+    if app.settings.launch_at_login && !dev_mode() {
+        match launch_agent::verify_exe_path() {
+            Ok(true) => notifications::notify_launch_agent_updated(),
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed to check the launch agent: {}", e),
+        }
+    }
+
+    // Queue the summaries of the days that ended while Pomobar wasn't running
+    if let Some(yesterday) = app.session.last_date.pred_opt() {
+        app.queue_summaries_through(yesterday);
+    }
+    let snapshot = MenuSnapshot::new(&app);
+
+    // Hand the app to the timer actor, which ticks it unless the event
+    // loop does
+    let (tx, rx) = mpsc::channel();
+    let timer_mode = app.settings.timer_mode;
+    let actor = TimerActor::spawn(app, tx.clone(), timer_mode);
+
+    // Deliver anything still waiting from earlier runs
+    webhook::spawn_flush(actor.handle());
 
     // Create event loop (required for tray on macOS)
     let event_loop = EventLoop::new()?;
 
     // Build menu
-    let (built_menu, menu_items) = {
-        let app_lock = app.lock().unwrap();
-        menu::build_menu(&app_lock.state, &app_lock.session, &app_lock.settings)?
-    };
+    let (built_menu, menu_items) = menu::build_menu(&snapshot, dev_mode())?;
 
-    // Create tray icon (no icon image, just use title text on macOS)
+    // Create tray icon: a dot colored by timer state, next to the title text
+    let icons = TrayIcons::load();
     let tray = TrayIconBuilder::new()
         .with_menu(Box::new(built_menu))
+        .with_icon(icons.icon(TrayIconImage::IDLE)?)
         .with_title("🍅")
-        .with_tooltip("Pomobar - Pomodoro Timer")
+        .with_tooltip(timer::DEFAULT_TOOLTIP)
         .build()?;
 
-    // Create channel for timer messages
-    let (tx, rx) = mpsc::channel();
+    // Answer AppleScript commands once the event loop runs
+    scripting::install(actor.handle(), tx.clone());
+
+    // Tick on sleep and wake so timers that ran out during sleep complete on wake
+    power::observe(actor.handle());
 
-    // Spawn timer tick thread
-    let app_clone = Arc::clone(&app);
+    // Spawn IPC listener thread for pomobar-ctl
+    let ipc_app = actor.handle();
     thread::spawn(move || {
-        timer::run_timer_loop(app_clone, tx);
+        if let Err(e) = ipc::run_listener(ipc_app, tx) {
+            eprintln!("IPC listener stopped: {}", e);
+        }
     });
 
+    // In event-loop timer mode the event loop schedules the ticks
+    let event_timer = (timer_mode == TimerMode::EventLoop)
+        .then(|| EventLoopTimer::new(Instant::now(), SystemTime::now()));
+
     // Create application handler
-    let mut pomobar = Pomobar::new(Arc::clone(&app), tray, rx);
+    let mut pomobar = Pomobar::new(actor, &snapshot, tray, icons, rx, event_timer);
     pomobar.set_menu_items(menu_items);
 
     // Run event loop
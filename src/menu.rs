@@ -1,27 +1,112 @@
 //! Menu building and updating for the tray dropdown.
 
-use crate::models::{Session, Settings, TimerState};
-use crate::timer::format_time;
+use crate::hotkeys::START_PAUSE_CHOICES;
+use chrono::Local;
 use muda::accelerator::Accelerator;
 use muda::{CheckMenuItem, Menu, MenuId, MenuItem, PredefinedMenuItem, Submenu};
+use pomobar_core::format::{
+    format_cycle, format_day_start, format_efficiency, format_event_webhook, format_focus_score,
+    format_progress_line, format_stats, format_status_line, format_task_stats, format_week_stats,
+    format_wind_down_status, format_yesterday_stats,
+};
+use pomobar_core::layout::{self, MAX_LINE_LEN};
+use pomobar_core::models::{
+    DailyStats, ProgressBarStyle, Session, Settings, SoundChoice, TaskHistory, TimerState,
+    TrayDisplay, WeekStart, DAY_START_HOURS,
+};
+use pomobar_core::presets::{Preset, PRESETS};
+use pomobar_core::score::{self, DayActivity};
+use pomobar_core::snapshot::MenuSnapshot;
+use pomobar_core::timer::TRAY_FORMAT_CHOICES;
 use std::collections::HashMap;
+use std::hash::Hash;
 use thiserror::Error;
 
 // Menu item IDs as constants
 pub const ID_STATUS: &str = "status";
 pub const ID_PROGRESS: &str = "progress";
 pub const ID_STATS: &str = "stats";
+pub const ID_CYCLE: &str = "stats_cycle";
+pub const ID_EFFICIENCY: &str = "stats_efficiency";
+pub const ID_YESTERDAY_STATS: &str = "stats_yesterday";
+pub const ID_WEEK_STATS: &str = "stats_week";
+pub const ID_ALL_TIME_STATS: &str = "stats_all_time";
+pub const ID_FOCUS_SCORE: &str = "stats_score";
+pub const ID_HEATMAP: &str = "stats_heatmap";
 pub const ID_START: &str = "start";
-pub const ID_PAUSE: &str = "pause";
-pub const ID_RESUME: &str = "resume";
+pub const ID_START_ANYWAY: &str = "start_anyway";
+pub const ID_START_STOPWATCH: &str = "start_stopwatch";
+pub const ID_POMO_CUSTOM: &str = "pomo_custom";
+pub const ID_SHORT_CUSTOM: &str = "short_custom";
+pub const ID_LONG_CUSTOM: &str = "long_custom";
+pub const ID_THRESH_CUSTOM: &str = "thresh_custom";
+pub const ID_PAUSE_RESUME: &str = "pause_resume";
 pub const ID_STOP: &str = "stop";
 pub const ID_COMPLETE: &str = "complete";
+pub const ID_EXTEND: &str = "extend_pomodoro";
+pub const ID_INTERRUPTION: &str = "interruption";
+pub const ID_LOG_PAST: &str = "log_past";
+pub const ID_TASK_NEW: &str = "task_new";
+pub const ID_TASK_NONE: &str = "task_none";
+/// Prefix of the recent task item ids, followed by the label itself.
+pub const TASK_ID_PREFIX: &str = "task_recent_";
 pub const ID_SKIP_BREAK: &str = "skip_break";
-pub const ID_SOUND_TOGGLE: &str = "sound_toggle";
+pub const ID_LONG_BREAK_NOW: &str = "long_break_now";
+pub const ID_POMO_END_SOUND_TOGGLE: &str = "sound_on_pomodoro_end";
+pub const ID_BREAK_END_SOUND_TOGGLE: &str = "sound_on_break_end";
 pub const ID_NOTIF_TOGGLE: &str = "notif_toggle";
+pub const ID_DND_TOGGLE: &str = "dnd_toggle";
 pub const ID_LOGIN_TOGGLE: &str = "login_toggle";
+pub const ID_STATUS_FILE_TOGGLE: &str = "status_file_toggle";
+pub const ID_FOCUS_TOGGLE: &str = "focus_toggle";
+pub const ID_MEDIA_TOGGLE: &str = "media_toggle";
+pub const ID_COMPACT_TOGGLE: &str = "compact_toggle";
+pub const ID_CLOCK_TOGGLE: &str = "clock_24h_toggle";
+pub const ID_MENUBAR_TIME_TOGGLE: &str = "menubar_time_toggle";
+pub const ID_BLINK_TOGGLE: &str = "blink_toggle";
+pub const ID_STRICT_TOGGLE: &str = "strict_toggle";
+pub const ID_HOTKEYS_TOGGLE: &str = "hotkeys_toggle";
+/// Prefix of the start/pause hotkey choices, followed by the choice's index.
+pub const HOTKEY_ID_PREFIX: &str = "hotkey_";
+pub const ID_TRAY_FORMAT: &str = "tray_format";
+/// Prefix of the ready-made tray format ids, followed by the choice's index.
+pub const TRAY_FORMAT_ID_PREFIX: &str = "tray_format_";
+pub const ID_DAILY_WEBHOOK: &str = "daily_webhook";
+pub const ID_EVENT_WEBHOOK: &str = "event_webhook";
+pub const ID_CUSTOM_SOUND: &str = "custom_sound";
+pub const ID_PREVIEW_SOUND: &str = "preview_sound";
+pub const ID_PREVIEW_POMO_SOUND: &str = "preview_pomo_sound";
+pub const ID_PREVIEW_BREAK_SOUND: &str = "preview_break_sound";
+/// Prefixes of the sound choice ids, followed by `SoundChoice::key`.
+pub const POMO_SOUND_ID_PREFIX: &str = "sound_pomo_";
+pub const BREAK_SOUND_ID_PREFIX: &str = "sound_break_";
+pub const ID_WARN_BREAKS: &str = "warn_breaks";
 pub const ID_RESET_COUNT: &str = "reset_count";
+pub const ID_RESET_COUNTS: &str = "reset_counts";
+pub const ID_RESET_CYCLE: &str = "reset_cycle";
+pub const ID_MERGE_STORES: &str = "merge_stores";
+pub const ID_BACKUP: &str = "backup";
+pub const ID_OPTIMIZE_DB: &str = "optimize_db";
+pub const ID_EXPORT_STATS: &str = "export_stats";
+pub const ID_EXPORT_SETTINGS: &str = "export_settings";
+pub const ID_IMPORT_SETTINGS: &str = "import_settings";
 pub const ID_QUIT: &str = "quit";
+/// Prefix of the day start item ids, followed by the hour.
+pub const DAY_START_ID_PREFIX: &str = "day_start_";
+/// Prefix of the progress bar style item ids, followed by the style id.
+pub const PROGRESS_STYLE_ID_PREFIX: &str = "progress_style_";
+/// Prefix of the menu bar display item ids, followed by the choice's id.
+pub const TRAY_DISPLAY_ID_PREFIX: &str = "tray_display_";
+/// Prefix of the preset item ids, followed by the preset id.
+pub const PRESET_ID_PREFIX: &str = "preset_";
+
+pub const START_ANYWAY_LABEL: &str = "⚠  Start Anyway";
+pub const START_ANYWAY_CONFIRM_LABEL: &str = "⚠  Click Again to Confirm";
+pub const PAUSE_LABEL: &str = "⏸  Pause";
+pub const RESUME_LABEL: &str = "▶  Resume";
+pub const CUSTOM_DURATION_LABEL: &str = "Custom…";
+/// Minutes the extend item adds to a pomodoro.
+pub const EXTEND_MINS: u32 = 5;
 
 #[derive(Error, Debug)]
 pub enum MenuError {
@@ -34,43 +119,122 @@ pub struct MenuItems {
     pub status: MenuItem,
     pub progress: MenuItem,
     pub stats: MenuItem,
+    /// Position in the long break cycle.
+    pub cycle: MenuItem,
+    pub efficiency: MenuItem,
+    /// Only refreshed when the day rolls over, see `update_yesterday_stats`.
+    pub yesterday_stats: MenuItem,
+    pub week_stats: MenuItem,
+    /// Best day and average per day over the whole history.
+    pub all_time_stats: MenuItem,
+    pub focus_score: MenuItem,
+    /// Four-week heatmap inside the History submenu.
+    pub heatmap: MenuItem,
+    /// The active task and history the Task submenus were built with; the
+    /// menu is rebuilt when they change, see `task_menus_stale`.
+    pub tasks_shown: (Option<String>, TaskHistory),
+    /// Offers to merge a second database; only present when one was found.
+    pub merge_stores: Option<MenuItem>,
     pub start: MenuItem,
-    pub pause: MenuItem,
-    pub resume: MenuItem,
+    pub start_anyway: MenuItem,
+    pub start_stopwatch: MenuItem,
+    /// Pauses the running pomodoro or break, or resumes it; the label follows the state.
+    pub pause_resume: MenuItem,
     pub stop: MenuItem,
     pub complete: MenuItem,
+    pub extend: MenuItem,
+    /// Counts an interruption without pausing; only enabled while a pomodoro runs.
+    pub interruption: MenuItem,
     pub skip_break: MenuItem,
-    pub sound_toggle: CheckMenuItem,
+    /// Starts a long break when idle, or turns a short break into one.
+    pub long_break_now: MenuItem,
+    pub pomo_end_sound_toggle: CheckMenuItem,
+    pub break_end_sound_toggle: CheckMenuItem,
     pub notif_toggle: CheckMenuItem,
+    pub dnd_toggle: CheckMenuItem,
+    /// Whether Do Not Disturb is on, or that it can't be read.
+    pub dnd_status: MenuItem,
     pub login_toggle: CheckMenuItem,
+    pub status_file_toggle: CheckMenuItem,
+    pub focus_toggle: CheckMenuItem,
+    pub media_toggle: CheckMenuItem,
+    pub compact_toggle: CheckMenuItem,
+    pub clock_toggle: CheckMenuItem,
+    pub menubar_time_toggle: CheckMenuItem,
+    pub blink_toggle: CheckMenuItem,
+    pub strict_toggle: CheckMenuItem,
     pub pomo_checks: HashMap<u32, CheckMenuItem>,
     pub short_checks: HashMap<u32, CheckMenuItem>,
     pub long_checks: HashMap<u32, CheckMenuItem>,
     pub thresh_checks: HashMap<u32, CheckMenuItem>,
+    pub limit_checks: HashMap<u32, CheckMenuItem>,
+    pub idle_checks: HashMap<u32, CheckMenuItem>,
+    pub remind_checks: HashMap<u32, CheckMenuItem>,
+    pub warn_checks: HashMap<u32, CheckMenuItem>,
+    pub warn_breaks_toggle: CheckMenuItem,
+    pub volume_checks: HashMap<u32, CheckMenuItem>,
+    /// The Week Starts On submenu, whose title shows the current choice.
+    pub week_menu: Submenu,
+    pub week_checks: HashMap<WeekStart, CheckMenuItem>,
+    /// Hours the day can start at, keyed by hour.
+    pub day_start_checks: HashMap<u32, CheckMenuItem>,
+    /// Progress bar styles, keyed by style.
+    pub progress_style_checks: HashMap<ProgressBarStyle, CheckMenuItem>,
+    /// Menu bar display choices, keyed by choice.
+    pub tray_display_checks: HashMap<TrayDisplay, CheckMenuItem>,
+    /// One item per built-in preset, keyed by preset id.
+    pub preset_checks: HashMap<&'static str, CheckMenuItem>,
+    /// Checked when the timings match no preset; never clickable.
+    pub preset_custom: CheckMenuItem,
+    /// Start/pause hotkey choices, keyed by binding.
+    pub hotkey_checks: HashMap<&'static str, CheckMenuItem>,
+    pub hotkeys_toggle: CheckMenuItem,
+    /// Sound choices for completed pomodoros and breaks, keyed by `SoundChoice::key`.
+    pub pomo_sound_checks: HashMap<&'static str, CheckMenuItem>,
+    pub break_sound_checks: HashMap<&'static str, CheckMenuItem>,
 }
 
 /// Builds the complete menu structure.
-pub fn build_menu(
-    state: &TimerState,
-    session: &Session,
-    settings: &Settings,
-) -> Result<(Menu, MenuItems), MenuError> {
+pub fn build_menu(snapshot: &MenuSnapshot, dev_mode: bool) -> Result<(Menu, MenuItems), MenuError> {
+    let (state, session, settings) = (&snapshot.state, &snapshot.session, &snapshot.settings);
     let menu = Menu::new();
 
+    // Walkthrough for the first launch (disabled, info only)
+    if snapshot.getting_started {
+        let getting_started = Submenu::new("👋 Getting Started", true);
+        for step in [
+            "1. Click Start Pomodoro".to_string(),
+            format!("2. Focus for {} min", settings.pomodoro_mins),
+            "3. Take a break".to_string(),
+        ] {
+            getting_started.append(&MenuItem::new(step, false, None::<Accelerator>))?;
+        }
+        menu.append(&getting_started)?;
+        menu.append(&PredefinedMenuItem::separator())?;
+    }
+
     // Status display (disabled, info only)
     let status = MenuItem::with_id(
         MenuId::new(ID_STATUS),
-        format_status(state),
+        format_status_line(
+            state,
+            session,
+            settings,
+            false,
+            snapshot.interruptions,
+            snapshot.task.as_deref(),
+            Local::now(),
+        ),
         false, // disabled
         None::<Accelerator>,
     );
     menu.append(&status)?;
 
-    // Progress bar (ASCII)
+    // Progress line (enabled so clicking it can cycle display modes)
     let progress = MenuItem::with_id(
         MenuId::new(ID_PROGRESS),
-        format_progress(state),
-        false,
+        format_progress_line(state, settings, Local::now()),
+        true,
         None::<Accelerator>,
     );
     menu.append(&progress)?;
@@ -80,43 +244,138 @@ pub fn build_menu(
     // Today's stats
     let stats = MenuItem::with_id(
         MenuId::new(ID_STATS),
-        format_stats(session),
+        format_stats(session, settings.compact_emoji_counts),
         false,
         None::<Accelerator>,
     );
     menu.append(&stats)?;
 
+    let cycle = MenuItem::with_id(
+        MenuId::new(ID_CYCLE),
+        cycle_line(state, session, settings),
+        false,
+        None::<Accelerator>,
+    );
+    menu.append(&cycle)?;
+
+    let efficiency = MenuItem::with_id(
+        MenuId::new(ID_EFFICIENCY),
+        format_efficiency(session),
+        false,
+        None::<Accelerator>,
+    );
+    menu.append(&efficiency)?;
+    menu.append(&build_task_breakdown_submenu(&snapshot.tasks)?)?;
+
+    let yesterday_stats = MenuItem::with_id(
+        MenuId::new(ID_YESTERDAY_STATS),
+        format_yesterday_stats(&snapshot.yesterday),
+        false,
+        None::<Accelerator>,
+    );
+    menu.append(&yesterday_stats)?;
+
+    let week_stats = MenuItem::with_id(
+        MenuId::new(ID_WEEK_STATS),
+        format_week_stats(&snapshot.week),
+        false,
+        None::<Accelerator>,
+    );
+    menu.append(&week_stats)?;
+
+    let all_time_stats = MenuItem::with_id(
+        MenuId::new(ID_ALL_TIME_STATS),
+        &snapshot.all_time,
+        false,
+        None::<Accelerator>,
+    );
+    menu.append(&all_time_stats)?;
+
+    let focus_score = MenuItem::with_id(
+        MenuId::new(ID_FOCUS_SCORE),
+        format_focus_score(score::focus_score(
+            &DayActivity::from(session),
+            settings.daily_goal,
+        )),
+        false,
+        None::<Accelerator>,
+    );
+    menu.append(&focus_score)?;
+
+    let history_sub = Submenu::new("📅 History", true);
+    let heatmap = MenuItem::with_id(
+        MenuId::new(ID_HEATMAP),
+        snapshot.heatmap.clone(),
+        false,
+        None::<Accelerator>,
+    );
+    history_sub.append(&heatmap)?;
+    menu.append(&history_sub)?;
+
+    // Warning about split stats across two databases
+    let merge_stores = if snapshot.stray_db {
+        let item = MenuItem::with_id(
+            MenuId::new(ID_MERGE_STORES),
+            "⚠  Stray pomobar.db found — Merge into Main Data",
+            true,
+            None::<Accelerator>,
+        );
+        menu.append(&item)?;
+        Some(item)
+    } else {
+        None
+    };
+
     menu.append(&PredefinedMenuItem::separator())?;
 
     // Control buttons
+    let limit_reached = session.daily_limit_reached(settings.max_daily_focus_mins);
     let start = MenuItem::with_id(
         MenuId::new(ID_START),
         "▶  Start Pomodoro",
-        state.is_idle(),
-        None::<Accelerator>,
+        state.is_idle() && !limit_reached,
+        hotkey_accelerator(settings, &settings.hotkey_start_pause),
     );
-    let pause = MenuItem::with_id(
-        MenuId::new(ID_PAUSE),
-        "⏸  Pause",
-        matches!(state, TimerState::PomodoroActive { .. }),
+    let start_anyway = MenuItem::with_id(
+        MenuId::new(ID_START_ANYWAY),
+        START_ANYWAY_LABEL,
+        state.is_idle() && snapshot.start_refused.is_some(),
         None::<Accelerator>,
     );
-    let resume = MenuItem::with_id(
-        MenuId::new(ID_RESUME),
-        "▶  Resume",
-        state.is_paused(),
+    let start_stopwatch = MenuItem::with_id(
+        MenuId::new(ID_START_STOPWATCH),
+        "⏱  Start Stopwatch",
+        state.is_idle() && !limit_reached,
         None::<Accelerator>,
     );
+    let pause_resume = MenuItem::with_id(
+        MenuId::new(ID_PAUSE_RESUME),
+        pause_resume_label(state),
+        can_pause_or_resume(state, settings.strict_mode),
+        hotkey_accelerator(settings, &settings.hotkey_start_pause),
+    );
     let stop = MenuItem::with_id(
         MenuId::new(ID_STOP),
         "⏹  Stop",
-        state.is_pomodoro(),
-        None::<Accelerator>,
+        can_stop(state, settings.strict_mode),
+        hotkey_accelerator(settings, &settings.hotkey_stop),
     );
     let complete = MenuItem::with_id(
         MenuId::new(ID_COMPLETE),
         "✓  Complete Early",
-        matches!(state, TimerState::PomodoroActive { .. }),
+        can_complete(state),
+        None::<Accelerator>,
+    );
+    let extend = MenuItem::with_id(
+        MenuId::new(ID_EXTEND),
+        format!("＋{} min", EXTEND_MINS),
+        can_extend(state),
+        None::<Accelerator>,
+    );
+    let interruption = MenuItem::with_id(
+        MenuId::new(ID_INTERRUPTION),
+        "⚡  Mark Interruption",
+        can_mark_interruption(state),
         None::<Accelerator>,
     );
     let skip_break = MenuItem::with_id(
@@ -125,20 +384,116 @@ pub fn build_menu(
         state.is_break(),
         None::<Accelerator>,
     );
+    let long_break_now = MenuItem::with_id(
+        MenuId::new(ID_LONG_BREAK_NOW),
+        "☕  Start Long Break",
+        can_start_long_break(state),
+        None::<Accelerator>,
+    );
 
     menu.append(&start)?;
-    menu.append(&pause)?;
-    menu.append(&resume)?;
+    menu.append(&start_anyway)?;
+    menu.append(&start_stopwatch)?;
+    menu.append(&pause_resume)?;
     menu.append(&stop)?;
     menu.append(&complete)?;
+    menu.append(&extend)?;
+    menu.append(&interruption)?;
     menu.append(&skip_break)?;
+    menu.append(&long_break_now)?;
+    menu.append(&MenuItem::with_id(
+        MenuId::new(ID_LOG_PAST),
+        "✎  Log Past Pomodoro…",
+        true,
+        None::<Accelerator>,
+    ))?;
+    menu.append(&build_task_submenu(
+        snapshot.task.as_deref(),
+        &snapshot.tasks.recent,
+    )?)?;
 
     menu.append(&PredefinedMenuItem::separator())?;
 
     // Settings submenu
-    let (settings_menu, pomo_checks, short_checks, long_checks, thresh_checks, sound_toggle, notif_toggle, login_toggle) =
-        build_settings_submenu(settings)?;
+    let (
+        settings_menu,
+        pomo_checks,
+        short_checks,
+        long_checks,
+        thresh_checks,
+        limit_checks,
+        idle_checks,
+        remind_checks,
+        warn_checks,
+        warn_breaks_toggle,
+        volume_checks,
+        pomo_end_sound_toggle,
+        break_end_sound_toggle,
+        notif_toggle,
+        dnd_toggle,
+        dnd_status,
+        login_toggle,
+        status_file_toggle,
+        focus_toggle,
+        media_toggle,
+        week_menu,
+        week_checks,
+        compact_toggle,
+        clock_toggle,
+        menubar_time_toggle,
+        blink_toggle,
+        strict_toggle,
+        pomo_sound_checks,
+        break_sound_checks,
+    ) = build_settings_submenu(settings, snapshot.dnd_active)?;
+    let (presets_menu, preset_checks, preset_custom) = build_presets_submenu(settings)?;
+    settings_menu.insert(&presets_menu, 0)?;
+    settings_menu.insert(&PredefinedMenuItem::separator(), 1)?;
+    let (day_start_menu, day_start_checks) = build_day_start_submenu(settings)?;
+    settings_menu.append(&day_start_menu)?;
+    let (progress_style_menu, progress_style_checks) = build_progress_style_submenu(settings)?;
+    settings_menu.append(&progress_style_menu)?;
+    let (tray_display_menu, tray_display_checks) = build_tray_display_submenu(settings)?;
+    settings_menu.append(&tray_display_menu)?;
+    let (hotkey_menu, hotkey_checks, hotkeys_toggle) = build_hotkey_submenu(settings)?;
+    settings_menu.append(&hotkey_menu)?;
     menu.append(&settings_menu)?;
+    menu.append(&MenuItem::with_id(
+        MenuId::new(ID_BACKUP),
+        "💾  Backup Data…",
+        true,
+        None::<Accelerator>,
+    ))?;
+    menu.append(&MenuItem::with_id(
+        MenuId::new(ID_EXPORT_STATS),
+        "Export Stats…",
+        true,
+        None::<Accelerator>,
+    ))?;
+    menu.append(&MenuItem::with_id(
+        MenuId::new(ID_EXPORT_SETTINGS),
+        "Export Settings…",
+        true,
+        None::<Accelerator>,
+    ))?;
+    menu.append(&MenuItem::with_id(
+        MenuId::new(ID_IMPORT_SETTINGS),
+        "Import Settings…",
+        true,
+        None::<Accelerator>,
+    ))?;
+
+    // Developer tools, only with POMOBAR_DEV=1
+    if dev_mode {
+        let dev_sub = Submenu::new("🛠  Developer", true);
+        dev_sub.append(&MenuItem::with_id(
+            MenuId::new(ID_OPTIMIZE_DB),
+            "Optimize Database",
+            true,
+            None::<Accelerator>,
+        ))?;
+        menu.append(&dev_sub)?;
+    }
 
     menu.append(&PredefinedMenuItem::separator())?;
 
@@ -150,24 +505,268 @@ pub fn build_menu(
         status,
         progress,
         stats,
+        cycle,
+        efficiency,
+        yesterday_stats,
+        week_stats,
+        all_time_stats,
+        focus_score,
+        heatmap,
+        tasks_shown: (snapshot.task.clone(), snapshot.tasks.clone()),
+        merge_stores,
         start,
-        pause,
-        resume,
+        start_anyway,
+        start_stopwatch,
+        pause_resume,
         stop,
         complete,
+        extend,
+        interruption,
         skip_break,
-        sound_toggle,
+        long_break_now,
+        pomo_end_sound_toggle,
+        break_end_sound_toggle,
         notif_toggle,
+        dnd_toggle,
+        dnd_status,
         login_toggle,
+        status_file_toggle,
+        focus_toggle,
+        media_toggle,
+        compact_toggle,
+        clock_toggle,
+        menubar_time_toggle,
+        blink_toggle,
+        strict_toggle,
         pomo_checks,
         short_checks,
         long_checks,
         thresh_checks,
+        limit_checks,
+        idle_checks,
+        remind_checks,
+        warn_checks,
+        warn_breaks_toggle,
+        volume_checks,
+        week_menu,
+        week_checks,
+        day_start_checks,
+        progress_style_checks,
+        tray_display_checks,
+        preset_checks,
+        preset_custom,
+        hotkey_checks,
+        hotkeys_toggle,
+        pomo_sound_checks,
+        break_sound_checks,
     };
 
     Ok((menu, items))
 }
 
+/// Builds the Task submenu: the recent labels, with the active one checked,
+/// then items to enter a new label or clear it.
+fn build_task_submenu(task: Option<&str>, recent: &[String]) -> Result<Submenu, MenuError> {
+    let title = match task {
+        Some(task) => layout::truncate(&format!("🏷  Task: {}", task), MAX_LINE_LEN),
+        None => "🏷  Task".to_string(),
+    };
+    let submenu = Submenu::new(title, true);
+
+    // A label typed in just now isn't in the log until a pomodoro completes
+    let unlogged = task.filter(|task| !recent.iter().any(|label| label == task));
+    let labels = unlogged
+        .into_iter()
+        .chain(recent.iter().map(String::as_str));
+    for label in labels {
+        submenu.append(&CheckMenuItem::with_id(
+            MenuId::new(format!("{}{}", TASK_ID_PREFIX, label)),
+            layout::truncate(label, MAX_LINE_LEN),
+            true,
+            task == Some(label),
+            None::<Accelerator>,
+        ))?;
+    }
+    if task.is_some() || !recent.is_empty() {
+        submenu.append(&PredefinedMenuItem::separator())?;
+    }
+    submenu.append(&MenuItem::with_id(
+        MenuId::new(ID_TASK_NEW),
+        "New Task…",
+        true,
+        None::<Accelerator>,
+    ))?;
+    submenu.append(&CheckMenuItem::with_id(
+        MenuId::new(ID_TASK_NONE),
+        "No Task",
+        true,
+        task.is_none(),
+        None::<Accelerator>,
+    ))?;
+    Ok(submenu)
+}
+
+/// Builds the Today by Task submenu (disabled, info only).
+fn build_task_breakdown_submenu(history: &TaskHistory) -> Result<Submenu, MenuError> {
+    let submenu = Submenu::new("📋 Today by Task", true);
+    for stats in &history.today {
+        submenu.append(&MenuItem::new(
+            format_task_stats(stats),
+            false,
+            None::<Accelerator>,
+        ))?;
+    }
+    if history.today.is_empty() {
+        submenu.append(&MenuItem::new(
+            "No pomodoros yet",
+            false,
+            None::<Accelerator>,
+        ))?;
+    }
+    Ok(submenu)
+}
+
+/// Returns true if the Task submenus no longer show the snapshot's task and
+/// history, such as after a pomodoro completes or a new task is picked.
+pub fn task_menus_stale(items: &MenuItems, snapshot: &MenuSnapshot) -> bool {
+    items.tasks_shown.0 != snapshot.task || items.tasks_shown.1 != snapshot.tasks
+}
+
+/// Builds the Presets submenu: one item per built-in preset, plus a
+/// disabled Custom item that is checked when none matches.
+fn build_presets_submenu(
+    settings: &Settings,
+) -> Result<(Submenu, HashMap<&'static str, CheckMenuItem>, CheckMenuItem), MenuError> {
+    let submenu = Submenu::new("Presets", true);
+    let current = Preset::current(settings);
+
+    let mut checks = HashMap::new();
+    for preset in &PRESETS {
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("{}{}", PRESET_ID_PREFIX, preset.id)),
+            preset.name,
+            true,
+            current == Some(preset),
+            None::<Accelerator>,
+        );
+        submenu.append(&item)?;
+        checks.insert(preset.id, item);
+    }
+
+    let custom = CheckMenuItem::new("Custom", false, current.is_none(), None::<Accelerator>);
+    submenu.append(&custom)?;
+
+    Ok((submenu, checks, custom))
+}
+
+fn build_day_start_submenu(
+    settings: &Settings,
+) -> Result<(Submenu, HashMap<u32, CheckMenuItem>), MenuError> {
+    let submenu = Submenu::new("Day Starts At", true);
+    let mut checks = HashMap::new();
+    for hour in DAY_START_HOURS {
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("{}{}", DAY_START_ID_PREFIX, hour)),
+            format_day_start(hour),
+            true,
+            hour == settings.day_starts_at_hour,
+            None::<Accelerator>,
+        );
+        submenu.append(&item)?;
+        checks.insert(hour, item);
+    }
+    Ok((submenu, checks))
+}
+
+fn build_progress_style_submenu(
+    settings: &Settings,
+) -> Result<(Submenu, HashMap<ProgressBarStyle, CheckMenuItem>), MenuError> {
+    let submenu = Submenu::new("Progress Bar", true);
+    let mut checks = HashMap::new();
+    for style in ProgressBarStyle::ALL {
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("{}{}", PROGRESS_STYLE_ID_PREFIX, style.id())),
+            style.name(),
+            true,
+            style == settings.progress_bar_style,
+            None::<Accelerator>,
+        );
+        submenu.append(&item)?;
+        checks.insert(style, item);
+    }
+    Ok((submenu, checks))
+}
+
+fn build_tray_display_submenu(
+    settings: &Settings,
+) -> Result<(Submenu, HashMap<TrayDisplay, CheckMenuItem>), MenuError> {
+    let submenu = Submenu::new("Menu Bar Shows", true);
+    let mut checks = HashMap::new();
+    for display in TrayDisplay::ALL {
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("{}{}", TRAY_DISPLAY_ID_PREFIX, display.id())),
+            display.name(),
+            true,
+            display == settings.tray_display,
+            None::<Accelerator>,
+        );
+        submenu.append(&item)?;
+        checks.insert(display, item);
+    }
+    Ok((submenu, checks))
+}
+
+/// Builds the Hotkey submenu: a choice of start/pause bindings and a toggle
+/// for all global hotkeys.
+fn build_hotkey_submenu(
+    settings: &Settings,
+) -> Result<(Submenu, HashMap<&'static str, CheckMenuItem>, CheckMenuItem), MenuError> {
+    let submenu = Submenu::new("⌨  Hotkey", true);
+
+    let mut checks = HashMap::new();
+    for (i, binding) in START_PAUSE_CHOICES.into_iter().enumerate() {
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("{}{}", HOTKEY_ID_PREFIX, i)),
+            format!("Start/Pause: {}", binding),
+            true,
+            binding == settings.hotkey_start_pause,
+            None::<Accelerator>,
+        );
+        submenu.append(&item)?;
+        checks.insert(binding, item);
+    }
+
+    submenu.append(&PredefinedMenuItem::separator())?;
+    let toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_HOTKEYS_TOGGLE),
+        "Hotkeys Enabled",
+        true,
+        settings.hotkeys_enabled,
+        None::<Accelerator>,
+    );
+    submenu.append(&toggle)?;
+
+    Ok((submenu, checks, toggle))
+}
+
+/// Returns the menu shortcut label for a hotkey binding, so the menu shows
+/// the same keys that work system-wide. None when hotkeys are off.
+fn hotkey_accelerator(settings: &Settings, binding: &str) -> Option<Accelerator> {
+    if !settings.hotkeys_enabled {
+        return None;
+    }
+    binding.parse().ok()
+}
+
+/// Checks the preset the settings match, or Custom if none does.
+pub fn sync_preset_checks(items: &MenuItems, settings: &Settings) {
+    let current = Preset::current(settings);
+    for (&id, check) in &items.preset_checks {
+        check.set_checked(current.is_some_and(|preset| preset.id == id));
+    }
+    items.preset_custom.set_checked(current.is_none());
+}
+
 /// Type alias for the settings submenu result to avoid clippy complexity warning.
 type SettingsSubmenuResult = (
     Submenu,
@@ -175,18 +774,42 @@ type SettingsSubmenuResult = (
     HashMap<u32, CheckMenuItem>,
     HashMap<u32, CheckMenuItem>,
     HashMap<u32, CheckMenuItem>,
+    HashMap<u32, CheckMenuItem>,
+    HashMap<u32, CheckMenuItem>,
+    HashMap<u32, CheckMenuItem>,
+    HashMap<u32, CheckMenuItem>,
+    CheckMenuItem,
+    HashMap<u32, CheckMenuItem>,
+    CheckMenuItem,
+    CheckMenuItem,
+    CheckMenuItem,
+    CheckMenuItem,
+    MenuItem,
+    CheckMenuItem,
     CheckMenuItem,
     CheckMenuItem,
     CheckMenuItem,
+    Submenu,
+    HashMap<WeekStart, CheckMenuItem>,
+    CheckMenuItem,
+    CheckMenuItem,
+    CheckMenuItem,
+    CheckMenuItem,
+    CheckMenuItem,
+    HashMap<&'static str, CheckMenuItem>,
+    HashMap<&'static str, CheckMenuItem>,
 );
 
-fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult, MenuError> {
+fn build_settings_submenu(
+    settings: &Settings,
+    dnd_active: Option<bool>,
+) -> Result<SettingsSubmenuResult, MenuError> {
     let submenu = Submenu::new("⚙  Settings", true);
 
     // Pomodoro duration submenu
     let pomo_sub = Submenu::new(format!("Pomodoro: {} min", settings.pomodoro_mins), true);
     let mut pomo_checks = HashMap::new();
-    for mins in [15, 20, 25, 30, 45, 60] {
+    for mins in duration_options(&[15, 20, 25, 30, 45, 60], settings.pomodoro_mins) {
         let checked = mins == settings.pomodoro_mins;
         let item = CheckMenuItem::with_id(
             MenuId::new(format!("pomo_{}", mins)),
@@ -198,6 +821,13 @@ fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult,
         pomo_sub.append(&item)?;
         pomo_checks.insert(mins, item);
     }
+    pomo_sub.append(&PredefinedMenuItem::separator())?;
+    pomo_sub.append(&MenuItem::with_id(
+        MenuId::new(ID_POMO_CUSTOM),
+        CUSTOM_DURATION_LABEL,
+        true,
+        None::<Accelerator>,
+    ))?;
     submenu.append(&pomo_sub)?;
 
     // Short break submenu
@@ -206,7 +836,7 @@ fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult,
         true,
     );
     let mut short_checks = HashMap::new();
-    for mins in [3, 5, 10, 15] {
+    for mins in duration_options(&[3, 5, 10, 15], settings.short_break_mins) {
         let checked = mins == settings.short_break_mins;
         let item = CheckMenuItem::with_id(
             MenuId::new(format!("short_{}", mins)),
@@ -218,12 +848,19 @@ fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult,
         short_sub.append(&item)?;
         short_checks.insert(mins, item);
     }
+    short_sub.append(&PredefinedMenuItem::separator())?;
+    short_sub.append(&MenuItem::with_id(
+        MenuId::new(ID_SHORT_CUSTOM),
+        CUSTOM_DURATION_LABEL,
+        true,
+        None::<Accelerator>,
+    ))?;
     submenu.append(&short_sub)?;
 
     // Long break submenu
     let long_sub = Submenu::new(format!("Long Break: {} min", settings.long_break_mins), true);
     let mut long_checks = HashMap::new();
-    for mins in [10, 15, 20, 30] {
+    for mins in duration_options(&[10, 15, 20, 30], settings.long_break_mins) {
         let checked = mins == settings.long_break_mins;
         let item = CheckMenuItem::with_id(
             MenuId::new(format!("long_{}", mins)),
@@ -235,6 +872,13 @@ fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult,
         long_sub.append(&item)?;
         long_checks.insert(mins, item);
     }
+    long_sub.append(&PredefinedMenuItem::separator())?;
+    long_sub.append(&MenuItem::with_id(
+        MenuId::new(ID_LONG_CUSTOM),
+        CUSTOM_DURATION_LABEL,
+        true,
+        None::<Accelerator>,
+    ))?;
     submenu.append(&long_sub)?;
 
     // Long break threshold submenu
@@ -246,7 +890,7 @@ fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult,
         true,
     );
     let mut thresh_checks = HashMap::new();
-    for count in [2, 3, 4, 5, 6] {
+    for count in duration_options(&[2, 3, 4, 5, 6], settings.pomodoros_for_long_break) {
         let checked = count == settings.pomodoros_for_long_break;
         let item = CheckMenuItem::with_id(
             MenuId::new(format!("thresh_{}", count)),
@@ -258,19 +902,258 @@ fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult,
         thresh_sub.append(&item)?;
         thresh_checks.insert(count, item);
     }
+    thresh_sub.append(&PredefinedMenuItem::separator())?;
+    thresh_sub.append(&MenuItem::with_id(
+        MenuId::new(ID_THRESH_CUSTOM),
+        CUSTOM_DURATION_LABEL,
+        true,
+        None::<Accelerator>,
+    ))?;
     submenu.append(&thresh_sub)?;
 
+    // Daily focus limit submenu (0 = no limit)
+    let current_limit = settings.max_daily_focus_mins.unwrap_or(0);
+    let limit_sub = Submenu::new(
+        format!("Daily Limit: {}", format_limit(current_limit)),
+        true,
+    );
+    let mut limit_checks = HashMap::new();
+    for mins in [0, 240, 300, 360, 480] {
+        let checked = mins == current_limit;
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("limit_{}", mins)),
+            format_limit(mins),
+            true,
+            checked,
+            None::<Accelerator>,
+        );
+        limit_sub.append(&item)?;
+        limit_checks.insert(mins, item);
+    }
+    submenu.append(&limit_sub)?;
+
+    // Idle auto-pause submenu (0 = disabled)
+    let idle_sub = Submenu::new(
+        format!("Pause When Idle: {}", format_idle(settings.idle_pause_secs)),
+        true,
+    );
+    let mut idle_checks = HashMap::new();
+    for secs in [0, 60, 120, 300, 600] {
+        let checked = secs == settings.idle_pause_secs;
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("idle_{}", secs)),
+            format_idle(secs),
+            true,
+            checked,
+            None::<Accelerator>,
+        );
+        idle_sub.append(&item)?;
+        idle_checks.insert(secs, item);
+    }
+    submenu.append(&idle_sub)?;
+
+    // Next-pomodoro reminder submenu (0 = disabled)
+    let remind_sub = Submenu::new(
+        format!(
+            "Remind After Break: {}",
+            format_reminder(settings.idle_reminder_mins)
+        ),
+        true,
+    );
+    let mut remind_checks = HashMap::new();
+    for mins in [0, 5, 10, 15, 30] {
+        let checked = mins == settings.idle_reminder_mins;
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("remind_{}", mins)),
+            format_reminder(mins),
+            true,
+            checked,
+            None::<Accelerator>,
+        );
+        remind_sub.append(&item)?;
+        remind_checks.insert(mins, item);
+    }
+    submenu.append(&remind_sub)?;
+
+    // End-of-timer warning submenu (0 = disabled)
+    let warn_sub = Submenu::new(
+        format!(
+            "Warn Before End: {}",
+            format_warning(settings.warn_before_end_secs)
+        ),
+        true,
+    );
+    let mut warn_checks = HashMap::new();
+    for secs in [0, 30, 60, 120, 300] {
+        let checked = secs == settings.warn_before_end_secs;
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("warn_{}", secs)),
+            format_warning(secs),
+            true,
+            checked,
+            None::<Accelerator>,
+        );
+        warn_sub.append(&item)?;
+        warn_checks.insert(secs, item);
+    }
+    warn_sub.append(&PredefinedMenuItem::separator())?;
+    let warn_breaks_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_WARN_BREAKS),
+        "Also Before Breaks End",
+        true,
+        settings.warn_before_break_end,
+        None::<Accelerator>,
+    );
+    warn_sub.append(&warn_breaks_toggle)?;
+    submenu.append(&warn_sub)?;
+
+    // First day of the week for weekly stats
+    let week_sub = Submenu::new(week_menu_title(settings.week_starts_on), true);
+    let mut week_checks = HashMap::new();
+    for start in WeekStart::ALL {
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("week_start_{}", start.name().to_lowercase())),
+            start.name(),
+            true,
+            start == settings.week_starts_on,
+            None::<Accelerator>,
+        );
+        week_sub.append(&item)?;
+        week_checks.insert(start, item);
+    }
+    submenu.append(&week_sub)?;
+
+    let menubar_time_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_MENUBAR_TIME_TOGGLE),
+        "Show Time in Menu Bar",
+        true,
+        settings.show_time_in_menubar,
+        None::<Accelerator>,
+    );
+    submenu.append(&menubar_time_toggle)?;
+
+    let blink_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_BLINK_TOGGLE),
+        "Blink When Break Ends",
+        true,
+        settings.attention_blink,
+        None::<Accelerator>,
+    );
+    submenu.append(&blink_toggle)?;
+
+    // Tray format submenu; the menu is rebuilt when the format changes
+    let tray_format_sub = Submenu::new("Tray Format", true);
+    for (i, (format, example)) in TRAY_FORMAT_CHOICES.into_iter().enumerate() {
+        tray_format_sub.append(&CheckMenuItem::with_id(
+            MenuId::new(format!("{}{}", TRAY_FORMAT_ID_PREFIX, i)),
+            example,
+            true,
+            format == settings.tray_format,
+            None::<Accelerator>,
+        ))?;
+    }
+    tray_format_sub.append(&PredefinedMenuItem::separator())?;
+    tray_format_sub.append(&MenuItem::with_id(
+        MenuId::new(ID_TRAY_FORMAT),
+        CUSTOM_DURATION_LABEL,
+        true,
+        None::<Accelerator>,
+    ))?;
+    submenu.append(&tray_format_sub)?;
+
+    let daily_webhook = MenuItem::with_id(
+        MenuId::new(ID_DAILY_WEBHOOK),
+        "Daily Summary Webhook…",
+        true,
+        None::<Accelerator>,
+    );
+    submenu.append(&daily_webhook)?;
+
+    let event_webhook = MenuItem::with_id(
+        MenuId::new(ID_EVENT_WEBHOOK),
+        format_event_webhook(settings.webhook_url.as_deref()),
+        true,
+        None::<Accelerator>,
+    );
+    submenu.append(&event_webhook)?;
+
     submenu.append(&PredefinedMenuItem::separator())?;
 
+    // Chime volume submenu, relative to the system volume
+    let volume_sub = Submenu::new(
+        format!("Volume: {}%", volume_percent(settings.volume)),
+        true,
+    );
+    // A volume between the steps, like the default 70%, checks none
+    let selected_volume = volume_percent(settings.volume);
+    let mut volume_checks = HashMap::new();
+    for pct in VOLUME_OPTIONS {
+        let label = if pct == 0 {
+            "0% (mute)".to_string()
+        } else {
+            format!("{}%", pct)
+        };
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("vol_{}", pct)),
+            label,
+            true,
+            pct == selected_volume,
+            None::<Accelerator>,
+        );
+        volume_sub.append(&item)?;
+        volume_checks.insert(pct, item);
+    }
+    submenu.append(&volume_sub)?;
+
+    let (pomo_sound_sub, pomo_sound_checks) = build_sound_submenu(
+        "Pomodoro Sound",
+        POMO_SOUND_ID_PREFIX,
+        ID_PREVIEW_POMO_SOUND,
+        &settings.pomodoro_sound,
+    )?;
+    submenu.append(&pomo_sound_sub)?;
+    let (break_sound_sub, break_sound_checks) = build_sound_submenu(
+        "Break Sound",
+        BREAK_SOUND_ID_PREFIX,
+        ID_PREVIEW_BREAK_SOUND,
+        &settings.break_sound,
+    )?;
+    submenu.append(&break_sound_sub)?;
+
+    let custom_sound = MenuItem::with_id(
+        MenuId::new(ID_CUSTOM_SOUND),
+        "Set Custom Sound…",
+        true,
+        None::<Accelerator>,
+    );
+    submenu.append(&custom_sound)?;
+
+    let preview_sound = MenuItem::with_id(
+        MenuId::new(ID_PREVIEW_SOUND),
+        "Preview Sound",
+        true,
+        None::<Accelerator>,
+    );
+    submenu.append(&preview_sound)?;
+
     // Toggle checkboxes
-    let sound_toggle = CheckMenuItem::with_id(
-        MenuId::new(ID_SOUND_TOGGLE),
-        "Sound Enabled",
+    let pomo_end_sound_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_POMO_END_SOUND_TOGGLE),
+        "Sound When Pomodoro Ends",
+        true,
+        settings.sound_on_pomodoro_end,
+        None::<Accelerator>,
+    );
+    submenu.append(&pomo_end_sound_toggle)?;
+
+    let break_end_sound_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_BREAK_END_SOUND_TOGGLE),
+        "Sound When Break Ends",
         true,
-        settings.sound_enabled,
+        settings.sound_on_break_end,
         None::<Accelerator>,
     );
-    submenu.append(&sound_toggle)?;
+    submenu.append(&break_end_sound_toggle)?;
 
     let notif_toggle = CheckMenuItem::with_id(
         MenuId::new(ID_NOTIF_TOGGLE),
@@ -281,6 +1164,21 @@ fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult,
     );
     submenu.append(&notif_toggle)?;
 
+    let dnd_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_DND_TOGGLE),
+        "Quiet During Do Not Disturb",
+        true,
+        settings.respect_dnd,
+        None::<Accelerator>,
+    );
+    submenu.append(&dnd_toggle)?;
+    let dnd_status = MenuItem::new(
+        dnd_status_line(settings.respect_dnd, dnd_active),
+        false,
+        None::<Accelerator>,
+    );
+    submenu.append(&dnd_status)?;
+
     let login_toggle = CheckMenuItem::with_id(
         MenuId::new(ID_LOGIN_TOGGLE),
         "Start at Login",
@@ -290,15 +1188,82 @@ fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult,
     );
     submenu.append(&login_toggle)?;
 
+    let status_file_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_STATUS_FILE_TOGGLE),
+        "Write Status File",
+        true,
+        settings.status_file_enabled,
+        None::<Accelerator>,
+    );
+    submenu.append(&status_file_toggle)?;
+
+    let focus_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_FOCUS_TOGGLE),
+        "Focus Mode During Pomodoros",
+        true,
+        settings.focus_mode_enabled,
+        None::<Accelerator>,
+    );
+    submenu.append(&focus_toggle)?;
+
+    let media_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_MEDIA_TOGGLE),
+        "Pause Music During Breaks",
+        true,
+        settings.control_media,
+        None::<Accelerator>,
+    );
+    submenu.append(&media_toggle)?;
+
+    let compact_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_COMPACT_TOGGLE),
+        "Compact Pomodoro Count",
+        true,
+        settings.compact_emoji_counts,
+        None::<Accelerator>,
+    );
+    submenu.append(&compact_toggle)?;
+
+    let clock_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_CLOCK_TOGGLE),
+        "24-Hour Clock",
+        true,
+        settings.clock_24h,
+        None::<Accelerator>,
+    );
+    submenu.append(&clock_toggle)?;
+
+    let strict_toggle = CheckMenuItem::with_id(
+        MenuId::new(ID_STRICT_TOGGLE),
+        "Strict Mode (No Pausing)",
+        true,
+        settings.strict_mode,
+        None::<Accelerator>,
+    );
+    submenu.append(&strict_toggle)?;
+
     submenu.append(&PredefinedMenuItem::separator())?;
 
-    let reset = MenuItem::with_id(
+    let reset_sub = Submenu::new("Reset…", true);
+    reset_sub.append(&MenuItem::with_id(
+        MenuId::new(ID_RESET_COUNTS),
+        "Reset counts",
+        true,
+        None::<Accelerator>,
+    ))?;
+    reset_sub.append(&MenuItem::with_id(
+        MenuId::new(ID_RESET_CYCLE),
+        "Reset cycle",
+        true,
+        None::<Accelerator>,
+    ))?;
+    reset_sub.append(&MenuItem::with_id(
         MenuId::new(ID_RESET_COUNT),
-        "Reset Today's Count",
+        "Reset everything",
         true,
         None::<Accelerator>,
-    );
-    submenu.append(&reset)?;
+    ))?;
+    submenu.append(&reset_sub)?;
 
     Ok((
         submenu,
@@ -306,206 +1271,594 @@ fn build_settings_submenu(settings: &Settings) -> Result<SettingsSubmenuResult,
         short_checks,
         long_checks,
         thresh_checks,
-        sound_toggle,
+        limit_checks,
+        idle_checks,
+        remind_checks,
+        warn_checks,
+        warn_breaks_toggle,
+        volume_checks,
+        pomo_end_sound_toggle,
+        break_end_sound_toggle,
         notif_toggle,
+        dnd_toggle,
+        dnd_status,
         login_toggle,
+        status_file_toggle,
+        focus_toggle,
+        media_toggle,
+        week_sub,
+        week_checks,
+        compact_toggle,
+        clock_toggle,
+        menubar_time_toggle,
+        blink_toggle,
+        strict_toggle,
+        pomo_sound_checks,
+        break_sound_checks,
     ))
 }
 
-/// Updates the menu items based on the current state.
-pub fn update_menu_items(items: &MenuItems, state: &TimerState, session: &Session) {
+/// Builds a submenu choosing the sound for one kind of event, with a
+/// Preview item that plays the current choice.
+fn build_sound_submenu(
+    title: &str,
+    id_prefix: &str,
+    preview_id: &str,
+    current: &SoundChoice,
+) -> Result<(Submenu, HashMap<&'static str, CheckMenuItem>), MenuError> {
+    let submenu = Submenu::new(title, true);
+    let mut checks = HashMap::new();
+    for choice in SoundChoice::MENU_CHOICES {
+        let item = CheckMenuItem::with_id(
+            MenuId::new(format!("{}{}", id_prefix, choice.key())),
+            choice.label(),
+            true,
+            choice == *current,
+            None::<Accelerator>,
+        );
+        submenu.append(&item)?;
+        checks.insert(choice.key(), item);
+    }
+    submenu.append(&PredefinedMenuItem::separator())?;
+    submenu.append(&MenuItem::with_id(
+        MenuId::new(preview_id),
+        "Preview",
+        true,
+        None::<Accelerator>,
+    ))?;
+    Ok((submenu, checks))
+}
+
+/// Updates the yesterday line. Called on day rollover rather than every
+/// tick, since yesterday's stats don't change in between.
+pub fn update_yesterday_stats(items: &MenuItems, yesterday: &DailyStats) {
+    items
+        .yesterday_stats
+        .set_text(format_yesterday_stats(yesterday));
+}
+
+/// Updates the menu items to show a snapshot of the app.
+pub fn update_menu_items(items: &MenuItems, snapshot: &MenuSnapshot) {
+    let (state, session, settings) = (&snapshot.state, &snapshot.session, &snapshot.settings);
+
     // Update text items
-    items.status.set_text(format_status(state));
-    items.progress.set_text(format_progress(state));
-    items.stats.set_text(format_stats(session));
+    let status = match snapshot.wind_down_ends_at {
+        Some(ends_at) if matches!(state, TimerState::PomodoroActive { .. }) => {
+            format_wind_down_status(ends_at, settings.clock_24h)
+        }
+        _ => format_status_line(
+            state,
+            session,
+            settings,
+            snapshot.auto_paused,
+            snapshot.interruptions,
+            snapshot.task.as_deref(),
+            Local::now(),
+        ),
+    };
+    items
+        .status
+        .set_text(mark_persist_error(status, snapshot.persist_failed));
+    items
+        .progress
+        .set_text(format_progress_line(state, settings, Local::now()));
+    items
+        .stats
+        .set_text(format_stats(session, settings.compact_emoji_counts));
+    items.cycle.set_text(cycle_line(state, session, settings));
+    items.efficiency.set_text(format_efficiency(session));
+    items.week_stats.set_text(format_week_stats(&snapshot.week));
+    items.all_time_stats.set_text(&snapshot.all_time);
+    items
+        .focus_score
+        .set_text(format_focus_score(snapshot.focus_score));
+    items.heatmap.set_text(&snapshot.heatmap);
 
     // Update enabled states
-    items.start.set_enabled(state.is_idle());
+    let limit_reached = session.daily_limit_reached(settings.max_daily_focus_mins);
+    items.start.set_enabled(state.is_idle() && !limit_reached);
     items
-        .pause
-        .set_enabled(matches!(state, TimerState::PomodoroActive { .. }));
-    items.resume.set_enabled(state.is_paused());
-    items.stop.set_enabled(state.is_pomodoro());
+        .start_anyway
+        .set_enabled(state.is_idle() && snapshot.start_refused.is_some());
+    items.start_anyway.set_text(if snapshot.start_anyway_armed {
+        START_ANYWAY_CONFIRM_LABEL
+    } else {
+        START_ANYWAY_LABEL
+    });
+    items
+        .start_stopwatch
+        .set_enabled(state.is_idle() && !limit_reached);
+    items.pause_resume.set_text(pause_resume_label(state));
     items
-        .complete
-        .set_enabled(matches!(state, TimerState::PomodoroActive { .. }));
+        .pause_resume
+        .set_enabled(can_pause_or_resume(state, settings.strict_mode));
+    items
+        .stop
+        .set_enabled(can_stop(state, settings.strict_mode));
+    items.complete.set_enabled(can_complete(state));
+    items.extend.set_enabled(can_extend(state));
+    items.interruption.set_enabled(can_mark_interruption(state));
     items.skip_break.set_enabled(state.is_break());
-}
+    items
+        .long_break_now
+        .set_enabled(can_start_long_break(state));
 
-/// Formats the status line for the menu.
-pub fn format_status(state: &TimerState) -> String {
-    match state {
-        TimerState::Idle => "Ready to focus".to_string(),
-        TimerState::PomodoroActive { remaining_secs, .. } => {
-            format!("⏱  {} remaining", format_time(*remaining_secs))
-        }
-        TimerState::PomodoroPaused { remaining_secs, .. } => {
-            format!("⏸  {} (paused)", format_time(*remaining_secs))
-        }
-        TimerState::BreakActive {
-            is_long_break,
-            remaining_secs,
-            ..
-        } => {
-            let kind = if *is_long_break {
-                "Long break"
-            } else {
-                "Short break"
-            };
-            format!("☕  {} - {}", kind, format_time(*remaining_secs))
-        }
-        TimerState::BreakFinished => "Break complete - ready for next".to_string(),
-    }
-}
-
-/// Formats the progress bar for the menu.
-pub fn format_progress(state: &TimerState) -> String {
-    match state.progress_percent() {
-        Some(pct) => {
-            let filled = (pct * 20.0).round() as usize;
-            let empty = 20 - filled;
-            format!(
-                "{}{}  {}%",
-                "█".repeat(filled),
-                "░".repeat(empty),
-                (pct * 100.0).round() as u32
-            )
+    sync_setting_checks(items, settings);
+    items
+        .dnd_status
+        .set_text(dnd_status_line(settings.respect_dnd, snapshot.dnd_active));
+
+    if let Some(ref merge_stores) = items.merge_stores {
+        if !snapshot.stray_db {
+            merge_stores.set_text("✓  Stray pomobar.db merged into main data");
+            merge_stores.set_enabled(false);
         }
-        None => "░░░░░░░░░░░░░░░░░░░░  0%".to_string(),
     }
 }
 
-/// Formats the daily stats for the menu.
-pub fn format_stats(session: &Session) -> String {
-    let tomatoes = "🍅".repeat(session.pomodoros_completed_today.min(10) as usize);
-    let extra = if session.pomodoros_completed_today > 10 {
-        format!("+{}", session.pomodoros_completed_today - 10)
-    } else {
-        String::new()
-    };
+/// Returns the line under the Do Not Disturb toggle. Reading the state
+/// needs Full Disk Access, so an unknown state says so instead of passing
+/// for "off".
+fn dnd_status_line(respect_dnd: bool, active: Option<bool>) -> &'static str {
+    match (respect_dnd, active) {
+        (false, _) => "Notifications ignore Do Not Disturb",
+        (true, Some(true)) => "Do Not Disturb is on",
+        (true, Some(false)) => "Do Not Disturb is off",
+        (true, None) => "DND unknown: grant Full Disk Access",
+    }
+}
 
-    if session.pomodoros_completed_today == 0 {
-        "Today: —  0 (0 min)".to_string()
+/// Moves every setting's checkmark to where the settings say, wherever the
+/// change came from.
+fn sync_setting_checks(items: &MenuItems, settings: &Settings) {
+    for (toggle, checked) in [
+        (&items.pomo_end_sound_toggle, settings.sound_on_pomodoro_end),
+        (&items.break_end_sound_toggle, settings.sound_on_break_end),
+        (&items.notif_toggle, settings.notifications_enabled),
+        (&items.dnd_toggle, settings.respect_dnd),
+        (&items.login_toggle, settings.launch_at_login),
+        (&items.status_file_toggle, settings.status_file_enabled),
+        (&items.focus_toggle, settings.focus_mode_enabled),
+        (&items.media_toggle, settings.control_media),
+        (&items.compact_toggle, settings.compact_emoji_counts),
+        (&items.clock_toggle, settings.clock_24h),
+        (&items.menubar_time_toggle, settings.show_time_in_menubar),
+        (&items.blink_toggle, settings.attention_blink),
+        (&items.strict_toggle, settings.strict_mode),
+        (&items.warn_breaks_toggle, settings.warn_before_break_end),
+        (&items.hotkeys_toggle, settings.hotkeys_enabled),
+    ] {
+        toggle.set_checked(checked);
+    }
+
+    // No limit is the "0" item
+    let limit = settings.max_daily_focus_mins.unwrap_or(0);
+    for (checks, selected) in [
+        (&items.pomo_checks, settings.pomodoro_mins),
+        (&items.short_checks, settings.short_break_mins),
+        (&items.long_checks, settings.long_break_mins),
+        (&items.thresh_checks, settings.pomodoros_for_long_break),
+        (&items.limit_checks, limit),
+        (&items.idle_checks, settings.idle_pause_secs),
+        (&items.remind_checks, settings.idle_reminder_mins),
+        (&items.warn_checks, settings.warn_before_end_secs),
+        (&items.volume_checks, volume_percent(settings.volume)),
+        (&items.day_start_checks, settings.day_starts_at_hour),
+    ] {
+        check_only(checks, &selected);
+    }
+    check_only(&items.week_checks, &settings.week_starts_on);
+    items
+        .week_menu
+        .set_text(week_menu_title(settings.week_starts_on));
+    check_only(&items.progress_style_checks, &settings.progress_bar_style);
+    check_only(&items.tray_display_checks, &settings.tray_display);
+    check_only(&items.hotkey_checks, &settings.hotkey_start_pause.as_str());
+    check_only(&items.pomo_sound_checks, &settings.pomodoro_sound.key());
+    check_only(&items.break_sound_checks, &settings.break_sound.key());
+    sync_preset_checks(items, settings);
+}
+
+/// Checks the item for `selected` and unchecks the rest.
+fn check_only<K: Eq + Hash>(checks: &HashMap<K, CheckMenuItem>, selected: &K) {
+    for (key, check) in checks {
+        check.set_checked(key == selected);
+    }
+}
+
+/// Returns the label of the combined pause/resume item.
+pub fn pause_resume_label(state: &TimerState) -> &'static str {
+    if state.is_paused() {
+        RESUME_LABEL
     } else {
-        format!(
-            "Today: {}{}  {} ({} min)",
-            tomatoes, extra, session.pomodoros_completed_today, session.total_focus_mins_today
+        PAUSE_LABEL
+    }
+}
+
+/// Returns whether the pause/resume item applies: a pomodoro, break or
+/// stopwatch is running or paused, and strict mode doesn't lock it.
+fn can_pause_or_resume(state: &TimerState, strict_mode: bool) -> bool {
+    (state.remaining_secs().is_some() || state.is_stopwatch()) && !state.is_locked(strict_mode)
+}
+
+/// Returns whether the complete item applies: a pomodoro is running or in
+/// overtime, or the stopwatch is running or paused.
+fn can_complete(state: &TimerState) -> bool {
+    matches!(
+        state,
+        TimerState::PomodoroActive { .. } | TimerState::Overtime { .. }
+    ) || state.is_stopwatch()
+}
+
+/// Returns whether the extend item applies: a pomodoro is running or paused.
+fn can_extend(state: &TimerState) -> bool {
+    matches!(
+        state,
+        TimerState::PomodoroActive { .. } | TimerState::PomodoroPaused { .. }
+    )
+}
+
+/// Returns the cycle line, or an empty one while idle before the cycle's
+/// first pomodoro, when there is nothing to track yet.
+fn cycle_line(state: &TimerState, session: &Session, settings: &Settings) -> String {
+    if state.is_idle() && session.pomodoros_in_cycle == 0 {
+        return String::new();
+    }
+    format_cycle(session, settings)
+}
+
+/// Returns whether the long break item applies: the timer is idle, or a
+/// short break is running or paused.
+fn can_start_long_break(state: &TimerState) -> bool {
+    state.is_idle()
+        || matches!(
+            state,
+            TimerState::BreakActive {
+                is_long_break: false,
+                ..
+            } | TimerState::BreakPaused {
+                is_long_break: false,
+                ..
+            }
         )
+}
+
+/// Returns whether an interruption can be marked: a pomodoro is running.
+fn can_mark_interruption(state: &TimerState) -> bool {
+    matches!(state, TimerState::PomodoroActive { .. })
+}
+
+/// Returns whether the stop item applies: a pomodoro that strict mode
+/// doesn't lock, or the stopwatch.
+fn can_stop(state: &TimerState, strict_mode: bool) -> bool {
+    (state.is_pomodoro() || state.is_stopwatch()) && !state.is_locked(strict_mode)
+}
+
+/// Prefixes the status line with a warning while saving to the database fails.
+fn mark_persist_error(status: String, failing: bool) -> String {
+    if failing {
+        format!("⚠ {}", status)
+    } else {
+        status
+    }
+}
+
+/// Returns the values offered in a duration or threshold submenu: the
+/// presets, plus the current value if it was set to something else.
+fn duration_options(presets: &[u32], current: u32) -> Vec<u32> {
+    let mut options = presets.to_vec();
+    if !options.contains(&current) {
+        options.push(current);
+        options.sort_unstable();
+    }
+    options
+}
+
+/// Formats a daily focus limit in minutes for the settings menu (0 = off).
+fn format_limit(mins: u32) -> String {
+    if mins == 0 {
+        "Off".to_string()
+    } else {
+        format!("{} hours", mins / 60)
+    }
+}
+
+/// Formats an idle auto-pause threshold in seconds for the settings menu (0 = off).
+fn format_idle(secs: u32) -> String {
+    if secs == 0 {
+        "Off".to_string()
+    } else {
+        format!("{} min", secs / 60)
+    }
+}
+
+/// Formats a next-pomodoro reminder interval in minutes for the settings menu (0 = off).
+fn format_reminder(mins: u32) -> String {
+    if mins == 0 {
+        "Off".to_string()
+    } else {
+        format!("{} min", mins)
+    }
+}
+
+/// Volume levels offered in the settings menu, in percent.
+const VOLUME_OPTIONS: [u32; 5] = [0, 25, 50, 75, 100];
+
+/// Returns the title of the Week Starts On submenu.
+fn week_menu_title(start: WeekStart) -> String {
+    format!("Week Starts On: {}", start.name())
+}
+
+/// Returns a volume (0.0–1.0) in whole percent, the key of its item in
+/// the Volume submenu if it has one.
+fn volume_percent(volume: f32) -> u32 {
+    (volume.clamp(0.0, 1.0) * 100.0).round() as u32
+}
+
+/// Formats an end-of-timer warning lead time in seconds for the settings menu (0 = off).
+fn format_warning(secs: u32) -> String {
+    match secs {
+        0 => "Off".to_string(),
+        s if s % 60 == 0 => format!("{} min", s / 60),
+        s => format!("{} sec", s),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Local;
 
     #[test]
-    fn test_format_status_idle() {
-        let state = TimerState::Idle;
-        assert_eq!(format_status(&state), "Ready to focus");
+    fn test_pause_resume_label_follows_state() {
+        let active = TimerState::PomodoroActive {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        let paused = TimerState::PomodoroPaused {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        assert_eq!(pause_resume_label(&active), PAUSE_LABEL);
+        assert_eq!(pause_resume_label(&paused), RESUME_LABEL);
+        assert!(can_pause_or_resume(&active, false));
+        assert!(can_pause_or_resume(&paused, false));
     }
 
     #[test]
-    fn test_format_status_pomodoro_active() {
-        let state = TimerState::PomodoroActive {
-            remaining_secs: 1432,
-            total_secs: 1500,
+    fn test_pause_resume_during_breaks() {
+        let on_break = TimerState::BreakActive {
+            is_long_break: false,
+            remaining_secs: 200,
+            total_secs: 300,
+        };
+        let break_paused = TimerState::BreakPaused {
+            is_long_break: false,
+            remaining_secs: 200,
+            total_secs: 300,
         };
-        assert_eq!(format_status(&state), "⏱  23:52 remaining");
+        assert_eq!(pause_resume_label(&on_break), PAUSE_LABEL);
+        assert_eq!(pause_resume_label(&break_paused), RESUME_LABEL);
+        assert!(can_pause_or_resume(&on_break, false));
+        assert!(can_pause_or_resume(&break_paused, false));
     }
 
     #[test]
-    fn test_format_status_paused() {
-        let state = TimerState::PomodoroPaused {
+    fn test_pause_resume_disabled_without_timer() {
+        assert!(!can_pause_or_resume(&TimerState::Idle, false));
+        assert!(!can_pause_or_resume(&TimerState::BreakFinished, false));
+    }
+
+    #[test]
+    fn test_strict_mode_disables_pause_and_stop() {
+        let active = TimerState::PomodoroActive {
             remaining_secs: 600,
             total_secs: 1500,
         };
-        assert_eq!(format_status(&state), "⏸  10:00 (paused)");
-    }
+        assert!(!can_pause_or_resume(&active, true));
+        assert!(!can_stop(&active, true));
+        assert!(can_pause_or_resume(&active, false));
+        assert!(can_stop(&active, false));
 
-    #[test]
-    fn test_format_status_short_break() {
-        let state = TimerState::BreakActive {
+        // Breaks stay pausable in strict mode
+        let on_break = TimerState::BreakActive {
             is_long_break: false,
-            remaining_secs: 180,
+            remaining_secs: 200,
             total_secs: 300,
         };
-        assert_eq!(format_status(&state), "☕  Short break - 03:00");
+        assert!(can_pause_or_resume(&on_break, true));
+    }
+
+    #[test]
+    fn test_stopwatch_menu_items() {
+        let state = TimerState::StopwatchActive { elapsed_secs: 3723 };
+
+        // Strict mode doesn't lock the stopwatch
+        assert!(can_pause_or_resume(&state, true));
+        assert!(can_stop(&state, true));
+        assert!(can_complete(&state));
+        assert!(!can_complete(&TimerState::Idle));
+    }
+
+    #[test]
+    fn test_overtime_menu_items() {
+        let state = TimerState::Overtime {
+            elapsed_overtime_secs: 90,
+            total_secs: 1500,
+        };
+
+        // Complete starts the break; there is nothing left to pause or extend
+        assert!(can_complete(&state));
+        assert!(!can_extend(&state));
+        assert!(can_stop(&state, false));
+        assert!(!can_stop(&state, true));
+        assert!(!can_pause_or_resume(&state, false));
+        assert!(!can_mark_interruption(&state));
     }
 
     #[test]
-    fn test_format_status_long_break() {
-        let state = TimerState::BreakActive {
+    fn test_mark_interruption_only_while_running() {
+        let active = TimerState::PomodoroActive {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        let paused = TimerState::PomodoroPaused {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        assert!(can_mark_interruption(&active));
+        assert!(!can_mark_interruption(&paused));
+        assert!(!can_mark_interruption(&TimerState::Idle));
+    }
+
+    #[test]
+    fn test_long_break_item_when_idle_or_on_short_break() {
+        let short_break = TimerState::BreakPaused {
+            is_long_break: false,
+            remaining_secs: 120,
+            total_secs: 300,
+        };
+        let long_break = TimerState::BreakActive {
             is_long_break: true,
             remaining_secs: 600,
             total_secs: 900,
         };
-        assert_eq!(format_status(&state), "☕  Long break - 10:00");
+        let running = TimerState::PomodoroActive {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        assert!(can_start_long_break(&TimerState::Idle));
+        assert!(can_start_long_break(&TimerState::BreakFinished));
+        assert!(can_start_long_break(&short_break));
+        assert!(!can_start_long_break(&long_break));
+        assert!(!can_start_long_break(&running));
     }
 
     #[test]
-    fn test_format_status_break_finished() {
-        let state = TimerState::BreakFinished;
-        assert_eq!(format_status(&state), "Break complete - ready for next");
+    fn test_format_limit() {
+        assert_eq!(format_limit(0), "Off");
+        assert_eq!(format_limit(360), "6 hours");
     }
 
     #[test]
-    fn test_format_progress_idle() {
-        let state = TimerState::Idle;
-        assert_eq!(format_progress(&state), "░░░░░░░░░░░░░░░░░░░░  0%");
+    fn test_format_idle() {
+        assert_eq!(format_idle(0), "Off");
+        assert_eq!(format_idle(300), "5 min");
     }
 
     #[test]
-    fn test_format_progress_half() {
-        let state = TimerState::PomodoroActive {
-            remaining_secs: 750,
-            total_secs: 1500,
-        };
-        assert_eq!(format_progress(&state), "██████████░░░░░░░░░░  50%");
+    fn test_week_menu_title() {
+        assert_eq!(week_menu_title(WeekStart::Sunday), "Week Starts On: Sunday");
     }
 
     #[test]
-    fn test_format_progress_complete() {
-        let state = TimerState::PomodoroActive {
-            remaining_secs: 0,
-            total_secs: 1500,
-        };
-        assert_eq!(format_progress(&state), "████████████████████  100%");
+    fn test_volume_percent() {
+        assert_eq!(volume_percent(0.0), 0);
+        assert_eq!(volume_percent(0.5), 50);
+        assert_eq!(volume_percent(0.75), 75);
+        assert_eq!(volume_percent(1.0), 100);
+        // The default sits between two items and checks neither
+        assert_eq!(volume_percent(0.7), 70);
+        assert!(!VOLUME_OPTIONS.contains(&volume_percent(0.7)));
     }
 
     #[test]
-    fn test_format_stats_empty() {
-        let session = Session {
-            pomodoros_completed_today: 0,
-            total_focus_mins_today: 0,
-            pomodoros_in_cycle: 0,
-            last_date: Local::now().date_naive(),
-        };
-        assert_eq!(format_stats(&session), "Today: —  0 (0 min)");
+    fn test_dnd_status_line() {
+        assert_eq!(dnd_status_line(true, Some(true)), "Do Not Disturb is on");
+        assert_eq!(dnd_status_line(true, Some(false)), "Do Not Disturb is off");
+        // Without Full Disk Access the state is unknown, not off
+        assert_eq!(
+            dnd_status_line(true, None),
+            "DND unknown: grant Full Disk Access"
+        );
+        assert_eq!(
+            dnd_status_line(false, None),
+            "Notifications ignore Do Not Disturb"
+        );
+        for line in [dnd_status_line(true, None), dnd_status_line(false, None)] {
+            assert!(layout::display_len(line) <= MAX_LINE_LEN);
+        }
     }
 
     #[test]
-    fn test_format_stats_with_pomodoros() {
-        let session = Session {
-            pomodoros_completed_today: 4,
-            total_focus_mins_today: 100,
-            pomodoros_in_cycle: 0,
-            last_date: Local::now().date_naive(),
-        };
-        assert_eq!(format_stats(&session), "Today: 🍅🍅🍅🍅  4 (100 min)");
+    fn test_format_warning() {
+        assert_eq!(format_warning(0), "Off");
+        assert_eq!(format_warning(30), "30 sec");
+        assert_eq!(format_warning(120), "2 min");
+    }
+
+    #[test]
+    fn test_format_reminder() {
+        assert_eq!(format_reminder(0), "Off");
+        assert_eq!(format_reminder(15), "15 min");
+    }
+
+    #[test]
+    fn test_duration_options_include_custom_value() {
+        let presets = [15, 20, 25, 30, 45, 60];
+        assert_eq!(duration_options(&presets, 25), presets);
+        assert_eq!(
+            duration_options(&presets, 50),
+            vec![15, 20, 25, 30, 45, 50, 60]
+        );
+        assert_eq!(
+            duration_options(&presets, 90),
+            vec![15, 20, 25, 30, 45, 60, 90]
+        );
+        assert_eq!(duration_options(&[3, 5, 10, 15], 1), vec![1, 3, 5, 10, 15]);
+    }
+
+    #[test]
+    fn test_mark_persist_error() {
+        assert_eq!(
+            mark_persist_error("🍅 Ready to focus".to_string(), true),
+            "⚠ 🍅 Ready to focus"
+        );
+        assert_eq!(
+            mark_persist_error("🍅 Ready to focus".to_string(), false),
+            "🍅 Ready to focus"
+        );
     }
 
     #[test]
-    fn test_format_stats_many_pomodoros() {
-        let session = Session {
-            pomodoros_completed_today: 15,
-            total_focus_mins_today: 375,
-            pomodoros_in_cycle: 0,
-            last_date: Local::now().date_naive(),
+    fn test_cycle_line_hidden_before_first_pomodoro() {
+        let settings = Settings::default();
+        let fresh = Session::default();
+        let two_done = Session {
+            pomodoros_in_cycle: 2,
+            ..Session::default()
         };
-        let result = format_stats(&session);
-        assert!(result.contains("+5"));
-        assert!(result.contains("15"));
-        assert!(result.contains("375 min"));
+        let running = TimerState::PomodoroActive {
+            remaining_secs: 600,
+            total_secs: 1500,
+        };
+        assert_eq!(cycle_line(&TimerState::Idle, &fresh, &settings), "");
+        assert_eq!(
+            cycle_line(&running, &fresh, &settings),
+            "Cycle: ○○○○ (0 of 4)"
+        );
+        assert_eq!(
+            cycle_line(&TimerState::Idle, &two_done, &settings),
+            "Cycle: ●●○○ (2 of 4)"
+        );
     }
 }
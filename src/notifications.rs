@@ -1,23 +1,178 @@
 //! macOS system notifications for timer events.
 
+use directories::UserDirs;
 use notify_rust::Notification;
+use pomobar_core::app::StartRefused;
+use pomobar_core::focus;
+use pomobar_core::format::{format_duration, render_template, TemplateVars};
+use pomobar_core::models::{DailySummary, GoalStatus, Settings};
+use std::path::Path;
+use std::sync::mpsc::Sender;
 use std::thread;
 
-/// Shows a notification when a pomodoro is completed.
+/// An action button clicked on a notification.
+/// Carries the completed pomodoro count at the time the notification was
+/// shown, so stale clicks can be recognized and ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationAction {
+    /// "Skip Break" clicked on a pomodoro-complete notification.
+    SkipBreak { count: u32 },
+    /// "Start Break" clicked on a pomodoro-complete notification: take the
+    /// break, then start the next pomodoro automatically.
+    StartBreak { count: u32 },
+    /// "Start Pomodoro" clicked on a break-complete notification.
+    StartPomodoro { count: u32 },
+    /// "Start Anyway" clicked on the notification of a refused start.
+    StartAnyway { count: u32 },
+}
+
+/// An action button on a notification: identifier and label.
+type Action = (&'static str, &'static str);
+
+const SKIP_BREAK: Action = ("skip_break", "Skip Break");
+const START_BREAK: Action = ("start_break", "Start Break");
+const START_POMODORO: Action = ("start_pomodoro", "Start Pomodoro");
+const START_ANYWAY: Action = ("start_anyway", "Start Anyway");
+
+/// Label of the button that closes a notification without acting on it.
+#[cfg(target_os = "macos")]
+const DISMISS_LABEL: &str = "Dismiss";
+
+/// Returns whether Do Not Disturb or another Focus mode is on.
+/// Returns false if the state can't be read (no Full Disk Access).
+pub fn is_dnd_active() -> bool {
+    focus::system_focus_active() == Some(true)
+}
+
+/// Returns whether notifications should be shown right now: they are
+/// enabled, and not silenced by an active Focus when `respect_dnd` is set.
+pub fn allowed(settings: &Settings) -> bool {
+    settings.notifications_enabled && !(settings.respect_dnd && is_dnd_active())
+}
+
+/// Shows a notification when a pomodoro is completed, offering to start the
+/// break (and the next pomodoro after it) or to skip the break.
+/// The body comes from the `notif_pomo_template` setting.
 /// Runs in a background thread to avoid blocking.
-pub fn notify_pomodoro_complete(count: u32) {
+pub fn notify_pomodoro_complete(
+    settings: &Settings,
+    vars: &[(&'static str, String)],
+    count: u32,
+    actions: Sender<NotificationAction>,
+) {
+    let body = render_template(&settings.notif_pomo_template, vars);
     thread::spawn(move || {
-        let body = if count == 1 {
-            "Great work! You've completed 1 pomodoro today.\nTime for a break.".to_string()
-        } else {
-            format!(
-                "Great work! You've completed {} pomodoros today.\nTime for a break.",
-                count
-            )
-        };
+        let action =
+            match show_with_actions("Pomodoro Complete! 🍅", &body, &[START_BREAK, SKIP_BREAK]) {
+                Some(id) if id == START_BREAK.0 => NotificationAction::StartBreak { count },
+                Some(id) if id == SKIP_BREAK.0 => NotificationAction::SkipBreak { count },
+                _ => return,
+            };
+        let _ = actions.send(action);
+    });
+}
+
+/// Shows a notification when a break is completed, offering to start the next pomodoro.
+/// The body comes from the `notif_break_template` setting.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_break_complete(
+    settings: &Settings,
+    vars: &[(&'static str, String)],
+    count: u32,
+    actions: Sender<NotificationAction>,
+) {
+    let body = render_template(&settings.notif_break_template, vars);
+    thread::spawn(move || {
+        if show_with_action("Break Over! ☕", &body, START_POMODORO) {
+            let _ = actions.send(NotificationAction::StartPomodoro { count });
+        }
+    });
+}
+
+/// Reminds the user that the break is long over, offering to start the next pomodoro.
+/// The body comes from the `notif_reminder_template` setting.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_next_pomodoro_reminder(
+    settings: &Settings,
+    vars: &[(&'static str, String)],
+    count: u32,
+    actions: Sender<NotificationAction>,
+) {
+    let body = render_template(&settings.notif_reminder_template, vars);
+    thread::spawn(move || {
+        if show_with_action("Ready for the next pomodoro? 🍅", &body, START_POMODORO) {
+            let _ = actions.send(NotificationAction::StartPomodoro { count });
+        }
+    });
+}
+
+/// Shows a notification when a long break starts, offering to skip the break.
+/// The body comes from the `notif_long_break_template` setting.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_long_break_start(
+    settings: &Settings,
+    vars: &[(&'static str, String)],
+    count: u32,
+    actions: Sender<NotificationAction>,
+) {
+    let vars = with_length(vars, settings.long_break_mins);
+    let body = render_template(&settings.notif_long_break_template, &vars);
+    thread::spawn(move || {
+        if show_with_action("Long Break Time! 🎉", &body, SKIP_BREAK) {
+            let _ = actions.send(NotificationAction::SkipBreak { count });
+        }
+    });
+}
+
+/// Says why a pomodoro didn't start, offering to start it anyway.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_start_refused(reason: StartRefused, count: u32, actions: Sender<NotificationAction>) {
+    let body = format!("{}.", reason);
+    thread::spawn(move || {
+        if show_with_action("Pomodoro Not Started ⛔", &body, START_ANYWAY) {
+            let _ = actions.send(NotificationAction::StartAnyway { count });
+        }
+    });
+}
+
+/// Shows a notification when a break ends and the next pomodoro was started
+/// automatically. The body comes from the `notif_auto_start_template` setting.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_next_pomodoro_started(settings: &Settings, vars: &[(&'static str, String)]) {
+    let vars = with_length(vars, settings.pomodoro_mins);
+    show_with_sound(
+        "Break Over! 🍅",
+        render_template(&settings.notif_auto_start_template, &vars),
+    );
+}
+
+/// Shows a notification when a pomodoro of `duration_mins` runs into
+/// overtime. The body comes from the `notif_overtime_template` setting.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_overtime_started(
+    settings: &Settings,
+    vars: &[(&'static str, String)],
+    duration_mins: u32,
+) {
+    let vars = with_length(vars, duration_mins);
+    show_with_sound(
+        "Pomodoro Complete! 🍅",
+        render_template(&settings.notif_overtime_template, &vars),
+    );
+}
+
+/// Adds the `{length}` placeholder, e.g. "15 min", to the template values.
+fn with_length(vars: &[(&'static str, String)], mins: u32) -> TemplateVars {
+    let mut vars = vars.to_vec();
+    vars.push(("length", format_duration(mins)));
+    vars
+}
 
+/// Shows a notification with the default sound in a background thread.
+fn show_with_sound(summary: &'static str, body: String) {
+    thread::spawn(move || {
         if let Err(e) = Notification::new()
-            .summary("Pomodoro Complete! 🍅")
+            .summary(summary)
             .body(&body)
             .sound_name("default")
             .show()
@@ -27,32 +182,133 @@ pub fn notify_pomodoro_complete(count: u32) {
     });
 }
 
-/// Shows a notification when a break is completed.
+/// Shows a notification with a single action button and waits for the user.
+/// Returns true if the action button was clicked.
+fn show_with_action(summary: &str, body: &str, action: Action) -> bool {
+    show_with_actions(summary, body, &[action]).is_some()
+}
+
+/// Shows a notification with action buttons and waits for the user.
+/// Returns the identifier of the clicked action, if any.
+#[cfg(target_os = "macos")]
+fn show_with_actions(summary: &str, body: &str, actions: &[Action]) -> Option<&'static str> {
+    use mac_notification_sys::{MainButton, NotificationResponse, Sound};
+
+    let labels: Vec<&str> = actions.iter().map(|(_, label)| *label).collect();
+    let main_button = match labels.as_slice() {
+        [label] => MainButton::SingleAction(label),
+        labels => MainButton::DropdownActions("Options", labels),
+    };
+
+    match mac_notification_sys::Notification::new()
+        .title(summary)
+        .message(body)
+        .sound(Sound::Default)
+        .main_button(main_button)
+        .close_button(DISMISS_LABEL)
+        .send()
+    {
+        Ok(NotificationResponse::ActionButton(clicked)) => actions
+            .iter()
+            .find(|(_, label)| *label == clicked)
+            .map(|(id, _)| *id),
+        Ok(_) => None,
+        Err(e) => {
+            eprintln!("Failed to show notification: {}", e);
+            None
+        }
+    }
+}
+
+/// On Linux and BSD, action buttons go through the notification server.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show_with_actions(summary: &str, body: &str, actions: &[Action]) -> Option<&'static str> {
+    let mut notification = Notification::new();
+    notification
+        .summary(summary)
+        .body(body)
+        .sound_name("default");
+    for (id, label) in actions {
+        notification.action(id, label);
+    }
+    notification.action("dismiss", "Dismiss");
+
+    match notification.show() {
+        Ok(handle) => {
+            let mut clicked = None;
+            handle.wait_for_action(|action| {
+                clicked = actions
+                    .iter()
+                    .find(|(id, _)| *id == action)
+                    .map(|(id, _)| *id);
+            });
+            clicked
+        }
+        Err(e) => {
+            eprintln!("Failed to show notification: {}", e);
+            None
+        }
+    }
+}
+
+/// Action buttons aren't supported elsewhere; a plain notification is shown.
+#[cfg(not(unix))]
+fn show_with_actions(summary: &str, body: &str, _actions: &[Action]) -> Option<&'static str> {
+    if let Err(e) = Notification::new()
+        .summary(summary)
+        .body(body)
+        .sound_name("default")
+        .show()
+    {
+        eprintln!("Failed to show notification: {}", e);
+    }
+    None
+}
+
+/// Shows a quiet heads-up (no sound) that the current timer is about to end.
+/// The body comes from the `notif_warning_template` setting.
 /// Runs in a background thread to avoid blocking.
-pub fn notify_break_complete() {
-    thread::spawn(|| {
-        if let Err(e) = Notification::new()
-            .summary("Break Over! ☕")
-            .body("Ready to start another pomodoro?")
-            .sound_name("default")
-            .show()
-        {
+pub fn notify_ending_soon(
+    settings: &Settings,
+    vars: &[(&'static str, String)],
+    remaining_secs: u32,
+    is_break: bool,
+) {
+    let mut vars = vars.to_vec();
+    vars.push(("time_left", format_time_left(remaining_secs)));
+    let body = render_template(&settings.notif_warning_template, &vars);
+    thread::spawn(move || {
+        let summary = if is_break {
+            "Break Ending Soon ☕"
+        } else {
+            "Almost Done 🍅"
+        };
+
+        if let Err(e) = Notification::new().summary(summary).body(&body).show() {
             eprintln!("Failed to show notification: {}", e);
         }
     });
 }
 
-/// Shows a notification when a long break starts.
+/// Formats the remaining time for the warning notification, e.g. "1 minute".
+fn format_time_left(secs: u32) -> String {
+    match secs {
+        60 => "1 minute".to_string(),
+        s if s % 60 == 0 => format!("{} minutes", s / 60),
+        s => format!("{} seconds", s),
+    }
+}
+
+/// Shows a notification when the global hotkeys could not be registered.
 /// Runs in a background thread to avoid blocking.
-pub fn notify_long_break_start(duration_mins: u32) {
+pub fn notify_hotkeys_unavailable(reason: String) {
     thread::spawn(move || {
         if let Err(e) = Notification::new()
-            .summary("Long Break Time! 🎉")
+            .summary("Hotkeys Disabled ⌨️")
             .body(&format!(
-                "You've earned a {} minute break. Great job staying focused!",
-                duration_mins
+                "Pomobar couldn't register its keyboard shortcuts: {}",
+                reason
             ))
-            .sound_name("default")
             .show()
         {
             eprintln!("Failed to show notification: {}", e);
@@ -60,6 +316,167 @@ pub fn notify_long_break_start(duration_mins: u32) {
     });
 }
 
+/// Shows a notification that a backup of the data was saved to `path`.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_backup_complete(path: &Path) {
+    show_plain(
+        "Backup Complete 💾",
+        format!("Backup saved to {}", display_path(path)),
+    );
+}
+
+/// Shows a notification that backing up the data to `path` failed.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_backup_failed(path: &Path, reason: String) {
+    show_plain(
+        "Backup Failed ⚠️",
+        format!("{}: {}", display_path(path), reason),
+    );
+}
+
+/// Shows a notification that the settings were exported to `path`.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_settings_exported(path: &Path) {
+    show_plain(
+        "Settings Exported ⚙️",
+        format!("Saved to {}", display_path(path)),
+    );
+}
+
+/// Shows a notification that the stats were exported next to `csv_path`,
+/// as CSV and JSON. Runs in a background thread to avoid blocking.
+pub fn notify_stats_exported(csv_path: &Path) {
+    show_plain(
+        "Stats Exported 📊",
+        format!("Saved to {} and pomobar-stats.json", display_path(csv_path)),
+    );
+}
+
+/// Shows a notification that exporting the stats failed.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_stats_export_failed(reason: String) {
+    show_plain("Stats Not Exported ⚠️", reason);
+}
+
+/// Shows the summary of a day that just ended, with its focus score.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_daily_summary(summary: &DailySummary) {
+    show_plain("Daily Summary 📊", format_daily_summary(summary));
+}
+
+/// Formats the body of the daily summary notification.
+fn format_daily_summary(summary: &DailySummary) -> String {
+    let mut body = format!(
+        "{}: {} pomodoro{} · {}",
+        summary.date.format("%a %b %-d"),
+        summary.completed_pomodoros,
+        if summary.completed_pomodoros == 1 {
+            ""
+        } else {
+            "s"
+        },
+        format_duration(summary.total_focus_minutes)
+    );
+    if let Some(score) = summary.focus_score {
+        body.push_str(&format!(" · Focus score: {}", score));
+    }
+    match summary.goal {
+        Some(GoalStatus::Met) => body.push_str(" · Goal met"),
+        Some(GoalStatus::Missed { by }) => body.push_str(&format!(" · {} short of goal", by)),
+        None => {}
+    }
+    body
+}
+
+/// Shows a notification that the settings were imported.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_settings_imported() {
+    show_plain(
+        "Settings Imported ⚙️",
+        "Your timings and preferences were updated.".to_string(),
+    );
+}
+
+/// Shows a notification that exporting or importing the settings file at
+/// `path` failed. Runs in a background thread to avoid blocking.
+pub fn notify_settings_file_failed(path: &Path, reason: String) {
+    show_plain(
+        "Settings Not Transferred ⚠️",
+        format!("{}: {}", display_path(path), reason),
+    );
+}
+
+/// Shows a notification that saving to the database failed with `error`.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_persist_failed(error: &str) {
+    show_plain(
+        "Pomobar couldn't save your data ⚠️",
+        format!("{}. Pomobar keeps retrying.", error),
+    );
+}
+
+/// Shows a notification that the database couldn't be opened, so nothing is
+/// saved this run. Runs in a background thread to avoid blocking.
+pub fn notify_database_unavailable(error: &str) {
+    show_plain(
+        "Pomobar isn't saving your data ⚠️",
+        format!("{}. Stats and settings last until Pomobar quits.", error),
+    );
+}
+
+/// Greets the user on the first launch.
+/// Runs in a background thread to avoid blocking.
+pub fn notify_welcome() {
+    show_plain(
+        "👋 Welcome to Pomobar!",
+        "Click the 🍅 in your menu bar to start.".to_string(),
+    );
+}
+
+/// Shows a notification that the Start at Login entry was pointed at the
+/// app's new location. Runs in a background thread to avoid blocking.
+pub fn notify_launch_agent_updated() {
+    show_plain(
+        "Pomobar moved — launch agent updated",
+        "Pomobar will start from its new location at login.".to_string(),
+    );
+}
+
+/// Shows a notification that Pomobar is already running. Blocks until it is
+/// shown, since the process exits right after.
+pub fn notify_already_running() {
+    if let Err(e) = Notification::new()
+        .summary("Pomobar is already running 🍅")
+        .body("Use the 🍅 in your menu bar.")
+        .show()
+    {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
+
+/// Shows a notification without sound or actions in a background thread.
+fn show_plain(summary: &'static str, body: String) {
+    thread::spawn(move || {
+        if let Err(e) = Notification::new().summary(summary).body(&body).show() {
+            eprintln!("Failed to show notification: {}", e);
+        }
+    });
+}
+
+/// Formats a path for a notification, with the home directory as `~`.
+fn display_path(path: &Path) -> String {
+    let home = UserDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+    abbreviate_home(path, home.as_deref())
+}
+
+/// Shortens a path inside the home directory to `~/...`.
+fn abbreviate_home(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     // Note: Notification tests are tricky because they interact with the system
@@ -67,28 +484,150 @@ mod tests {
     // Run with `cargo test -- --ignored` to execute them.
 
     use super::*;
+    use pomobar_core::format::template_vars;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_abbreviate_home() {
+        let home = Path::new("/Users/jane");
+        let path = Path::new("/Users/jane/Desktop/pomobar_backup_20240115.db");
+        assert_eq!(
+            abbreviate_home(path, Some(home)),
+            "~/Desktop/pomobar_backup_20240115.db"
+        );
+        assert_eq!(
+            abbreviate_home(Path::new("/tmp/b.db"), Some(home)),
+            "/tmp/b.db"
+        );
+        assert_eq!(abbreviate_home(path, None), path.display().to_string());
+    }
+
+    #[test]
+    fn test_daily_summary_has_the_score_and_goal() {
+        let mut summary = DailySummary {
+            date: chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            completed_pomodoros: 6,
+            total_focus_minutes: 150,
+            streak_days: 3,
+            focus_score: Some(82),
+            goal: Some(GoalStatus::Missed { by: 2 }),
+        };
+        assert_eq!(
+            format_daily_summary(&summary),
+            "Mon Jan 15: 6 pomodoros · 2h 30min · Focus score: 82 · 2 short of goal"
+        );
+
+        summary.completed_pomodoros = 1;
+        summary.focus_score = None;
+        summary.goal = Some(GoalStatus::Met);
+        assert_eq!(
+            format_daily_summary(&summary),
+            "Mon Jan 15: 1 pomodoro · 2h 30min · Goal met"
+        );
+    }
+
+    #[test]
+    fn test_allowed_respects_settings() {
+        let mut settings = Settings {
+            respect_dnd: false,
+            ..Settings::default()
+        };
+        assert!(allowed(&settings));
+
+        settings.notifications_enabled = false;
+        assert!(!allowed(&settings));
+    }
 
     #[test]
     #[ignore = "Requires system notification interaction"]
     fn test_pomodoro_notification_singular() {
-        notify_pomodoro_complete(1);
+        let (tx, _rx) = mpsc::channel();
+        let vars = template_vars(1, 25, 1, None);
+        notify_pomodoro_complete(&Settings::default(), &vars, 1, tx);
     }
 
     #[test]
     #[ignore = "Requires system notification interaction"]
     fn test_pomodoro_notification_plural() {
-        notify_pomodoro_complete(5);
+        let (tx, _rx) = mpsc::channel();
+        let vars = template_vars(5, 25, 1, None);
+        notify_pomodoro_complete(&Settings::default(), &vars, 5, tx);
     }
 
     #[test]
     #[ignore = "Requires system notification interaction"]
     fn test_break_notification() {
-        notify_break_complete();
+        let (tx, _rx) = mpsc::channel();
+        let vars = template_vars(5, 25, 1, None);
+        notify_break_complete(&Settings::default(), &vars, 5, tx);
     }
 
     #[test]
     #[ignore = "Requires system notification interaction"]
     fn test_long_break_notification() {
-        notify_long_break_start(15);
+        let (tx, _rx) = mpsc::channel();
+        let vars = template_vars(4, 25, 1, None);
+        notify_long_break_start(&Settings::default(), &vars, 4, tx);
+    }
+
+    #[test]
+    #[ignore = "Requires system notification interaction"]
+    fn test_next_pomodoro_started_notification() {
+        let vars = template_vars(4, 25, 1, None);
+        notify_next_pomodoro_started(&Settings::default(), &vars);
+    }
+
+    #[test]
+    #[ignore = "Requires system notification interaction"]
+    fn test_backup_failed_notification() {
+        notify_backup_failed(Path::new("/tmp/backup.db"), "disk full".to_string());
+    }
+
+    #[test]
+    #[ignore = "Requires system notification interaction"]
+    fn test_ending_soon_notification() {
+        let vars = template_vars(4, 25, 1, None);
+        notify_ending_soon(&Settings::default(), &vars, 60, false);
+    }
+
+    #[test]
+    fn test_format_time_left() {
+        assert_eq!(format_time_left(60), "1 minute");
+        assert_eq!(format_time_left(300), "5 minutes");
+        assert_eq!(format_time_left(30), "30 seconds");
+    }
+
+    #[test]
+    fn test_default_templates_match_previous_text() {
+        let settings = Settings::default();
+        let vars = template_vars(4, 25, 1, None);
+        assert_eq!(
+            render_template(&settings.notif_long_break_template, &with_length(&vars, 90)),
+            "You've earned a 1h 30min break. Great job staying focused!"
+        );
+        assert_eq!(
+            render_template(&settings.notif_auto_start_template, &with_length(&vars, 25)),
+            "Your next 25 min pomodoro has started."
+        );
+        assert_eq!(
+            render_template(&settings.notif_overtime_template, &with_length(&vars, 25)),
+            "25 min are up. Keep going, and click Complete when you're ready for a break."
+        );
+        assert_eq!(
+            render_template(&settings.notif_reminder_template, &vars),
+            "Your break ended a while ago."
+        );
+        let mut vars = vars;
+        vars.push(("time_left", format_time_left(60)));
+        assert_eq!(
+            render_template(&settings.notif_warning_template, &vars),
+            "1 minute left"
+        );
+    }
+
+    #[test]
+    #[ignore = "Requires system notification interaction"]
+    fn test_hotkeys_unavailable_notification() {
+        notify_hotkeys_unavailable("HotKey already registered".to_string());
     }
 }
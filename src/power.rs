@@ -0,0 +1,122 @@
+//! System sleep and wake notifications from IOKit.
+//!
+//! The timer actor advances running timers by the wall-clock time measured
+//! between ticks, which already covers time spent asleep. The notifications
+//! make that happen at the right moments: the actor ticks just before the
+//! Mac sleeps and again the moment it wakes, so a pomodoro that ran out
+//! during sleep completes on wake instead of on the next scheduled tick.
+//! In event-loop timer mode the actor doesn't tick, and the event loop's
+//! own tick deadline passes on wake anyway.
+
+use pomobar_core::actor::AppHandle;
+
+/// Starts a thread that has the actor tick on system sleep and wake.
+#[cfg(target_os = "macos")]
+pub fn observe(app: AppHandle) {
+    iokit::observe(app);
+}
+
+/// Sleep notifications are only observed on macOS.
+#[cfg(not(target_os = "macos"))]
+pub fn observe(_app: AppHandle) {}
+
+#[cfg(target_os = "macos")]
+mod iokit {
+    use super::*;
+    use core_foundation_sys::runloop::{
+        kCFRunLoopDefaultMode, CFRunLoopAddSource, CFRunLoopGetCurrent, CFRunLoopRun,
+        CFRunLoopSourceRef,
+    };
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::OnceLock;
+    use std::thread;
+
+    type IoConnect = u32;
+    type IoObject = u32;
+    type IoNotificationPortRef = *mut c_void;
+    type IoServiceInterestCallback =
+        extern "C" fn(refcon: *mut c_void, service: IoObject, message: u32, argument: *mut c_void);
+
+    /// `kIOMessageCanSystemSleep`: idle sleep is about to happen and may be vetoed.
+    const CAN_SYSTEM_SLEEP: u32 = 0xe000_0270;
+    /// `kIOMessageSystemWillSleep`: the system is going to sleep.
+    const SYSTEM_WILL_SLEEP: u32 = 0xe000_0280;
+    /// `kIOMessageSystemHasPoweredOn`: the system has woken up.
+    const SYSTEM_HAS_POWERED_ON: u32 = 0xe000_0300;
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IORegisterForSystemPower(
+            refcon: *mut c_void,
+            the_port_ref: *mut IoNotificationPortRef,
+            callback: IoServiceInterestCallback,
+            notifier: *mut IoObject,
+        ) -> IoConnect;
+        fn IONotificationPortGetRunLoopSource(notify: IoNotificationPortRef) -> CFRunLoopSourceRef;
+        fn IOAllowPowerChange(kernel_port: IoConnect, notification_id: isize) -> i32;
+    }
+
+    /// The actor to tick. The callback gets no Rust state of its own.
+    static TARGET: OnceLock<AppHandle> = OnceLock::new();
+    /// The root power domain connection, which sleep has to be acknowledged on.
+    static ROOT_PORT: AtomicU32 = AtomicU32::new(0);
+
+    pub fn observe(app: AppHandle) {
+        if TARGET.set(app).is_err() {
+            return;
+        }
+        thread::spawn(|| {
+            let mut port: IoNotificationPortRef = ptr::null_mut();
+            let mut notifier: IoObject = 0;
+            // SAFETY: the out-pointers are valid for the call, and the callback
+            // only reads statics
+            let root_port = unsafe {
+                IORegisterForSystemPower(ptr::null_mut(), &mut port, on_power_event, &mut notifier)
+            };
+            if root_port == 0 {
+                eprintln!("Failed to register for sleep notifications");
+                return;
+            }
+            ROOT_PORT.store(root_port, Ordering::SeqCst);
+            // SAFETY: `port` was just set up by IORegisterForSystemPower and is
+            // never released, so its run loop source stays valid while this
+            // thread's run loop runs
+            unsafe {
+                let source = IONotificationPortGetRunLoopSource(port);
+                CFRunLoopAddSource(CFRunLoopGetCurrent(), source, kCFRunLoopDefaultMode);
+                CFRunLoopRun();
+            }
+        });
+    }
+
+    extern "C" fn on_power_event(
+        _refcon: *mut c_void,
+        _service: IoObject,
+        message: u32,
+        argument: *mut c_void,
+    ) {
+        let Some(app) = TARGET.get() else {
+            return;
+        };
+        match message {
+            CAN_SYSTEM_SLEEP => allow_power_change(argument),
+            SYSTEM_WILL_SLEEP => {
+                // Bring the timer up to date so the wake tick measures the sleep
+                app.tick_now();
+                allow_power_change(argument);
+            }
+            SYSTEM_HAS_POWERED_ON => app.tick_now(),
+            _ => {}
+        }
+    }
+
+    /// Lets sleep go ahead. macOS waits up to 30 seconds for this otherwise.
+    fn allow_power_change(argument: *mut c_void) {
+        // SAFETY: the argument of a sleep message is its notification ID
+        unsafe {
+            IOAllowPowerChange(ROOT_PORT.load(Ordering::SeqCst), argument as isize);
+        }
+    }
+}
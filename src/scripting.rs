@@ -0,0 +1,289 @@
+//! AppleScript support: `tell application "Pomobar" to start pomodoro`.
+//!
+//! The verbs are declared in `resources/Pomobar.sdef`, which names one
+//! Cocoa class, `PomobarScriptCommand`, for all of them. That class turns
+//! the command name back into a `ScriptVerb` and runs it on the timer actor
+//! through `ipc::run_command`, so scripts follow the same rules as the menu
+//! and `pomobar-ctl`. Cocoa only loads the dictionary from an app bundle
+//! whose `Info.plist` sets `NSAppleScriptEnabled` and `OSAScriptingDefinition`.
+
+// Only the Cocoa command class calls into this module
+#![cfg_attr(not(target_os = "macos"), allow(dead_code))]
+
+use crate::ipc;
+use pomobar_core::actor::AppHandle;
+use pomobar_core::app::App;
+use pomobar_core::snapshot::MenuSnapshot;
+use pomobar_core::timer::TimerMessage;
+use std::sync::mpsc::Sender;
+
+/// A command from the scripting dictionary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptVerb {
+    Start,
+    Pause,
+    Resume,
+    Stop,
+    Status,
+}
+
+impl ScriptVerb {
+    pub const ALL: [ScriptVerb; 5] = [
+        ScriptVerb::Start,
+        ScriptVerb::Pause,
+        ScriptVerb::Resume,
+        ScriptVerb::Stop,
+        ScriptVerb::Status,
+    ];
+
+    /// Returns the verb's name in `Pomobar.sdef`.
+    pub fn command_name(self) -> &'static str {
+        match self {
+            ScriptVerb::Start => "start pomodoro",
+            ScriptVerb::Pause => "pause",
+            ScriptVerb::Resume => "resume",
+            ScriptVerb::Stop => "stop",
+            ScriptVerb::Status => "current status",
+        }
+    }
+
+    /// Looks up a verb by its name in `Pomobar.sdef`.
+    pub fn from_command_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|verb| verb.command_name() == name)
+    }
+
+    /// Returns the matching `pomobar-ctl` command.
+    fn ipc_command(self) -> &'static str {
+        match self {
+            ScriptVerb::Start => "start",
+            ScriptVerb::Pause => "pause",
+            ScriptVerb::Resume => "resume",
+            ScriptVerb::Stop => "stop",
+            ScriptVerb::Status => "status",
+        }
+    }
+}
+
+/// The record every verb returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptStatus {
+    pub state: &'static str,
+    /// Zero when no timer is running; AppleScript records have no null.
+    pub remaining_seconds: u32,
+    pub pomodoros_today: u32,
+}
+
+impl ScriptStatus {
+    fn new(app: &App) -> Self {
+        Self {
+            state: app.state.name(),
+            remaining_seconds: app.state.remaining_secs().unwrap_or(0),
+            pomodoros_today: app.session.pomodoros_completed_today,
+        }
+    }
+}
+
+/// Runs `verb` on the app. Returns the reason if the app refused it, such
+/// as pausing with no timer running.
+pub fn run_verb(app: &mut App, verb: ScriptVerb) -> Result<ScriptStatus, String> {
+    let response = ipc::run_command(app, verb.ipc_command());
+    script_result(app, response)
+}
+
+fn script_result(app: &App, response: ipc::Response) -> Result<ScriptStatus, String> {
+    match response.error {
+        Some(error) => Err(error),
+        None => Ok(ScriptStatus::new(app)),
+    }
+}
+
+/// Runs `verb` on the timer actor and reports a state change through `tx`,
+/// like a `pomobar-ctl` command, along with a refused start. Returns None if
+/// Pomobar is quitting.
+pub fn perform(
+    app: &AppHandle,
+    tx: &Sender<TimerMessage>,
+    verb: ScriptVerb,
+) -> Option<Result<ScriptStatus, String>> {
+    let (result, snapshot, refused) = app.try_call(move |app| {
+        let before = app.state.clone();
+        let response = ipc::run_command(app, verb.ipc_command());
+        let refused = response.refused;
+        let result = script_result(app, response);
+        let snapshot = (app.state != before).then(|| Box::new(MenuSnapshot::new(app)));
+        (result, snapshot, refused)
+    })?;
+    if let Some(snapshot) = snapshot {
+        let _ = tx.send(TimerMessage::StateChanged(snapshot));
+    }
+    if let Some(reason) = refused {
+        let _ = tx.send(TimerMessage::StartRefused(reason));
+    }
+    Some(result)
+}
+
+/// Registers the command class so Cocoa can find it when the first Apple
+/// event arrives. Call before the event loop starts.
+#[cfg(target_os = "macos")]
+pub fn install(app: AppHandle, tx: Sender<TimerMessage>) {
+    cocoa::install(app, tx);
+}
+
+/// AppleScript is only supported on macOS.
+#[cfg(not(target_os = "macos"))]
+pub fn install(_app: AppHandle, _tx: Sender<TimerMessage>) {}
+
+#[cfg(target_os = "macos")]
+mod cocoa {
+    use super::*;
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::{define_class, ClassType};
+    use objc2_foundation::{
+        ns_string, NSDictionary, NSNumber, NSObject, NSScriptCommand, NSString,
+    };
+    use std::sync::OnceLock;
+
+    /// `errOSAGeneralError`, shown as "Pomobar got an error: …".
+    const GENERAL_ERROR: isize = -2700;
+
+    /// The actor and menu channel the command class reports to. Cocoa
+    /// creates the command objects itself, so they can't be handed these.
+    static TARGET: OnceLock<(AppHandle, Sender<TimerMessage>)> = OnceLock::new();
+
+    define_class!(
+        // SAFETY: NSScriptCommand is meant to be subclassed to implement
+        // `performDefaultImplementation`, and the class has no Drop.
+        #[unsafe(super(NSScriptCommand, NSObject))]
+        #[name = "PomobarScriptCommand"]
+        struct PomobarScriptCommand;
+
+        impl PomobarScriptCommand {
+            #[unsafe(method_id(performDefaultImplementation))]
+            fn perform_default_implementation(&self) -> Option<Retained<AnyObject>> {
+                let name = self.commandDescription().commandName().to_string();
+                let result = match (ScriptVerb::from_command_name(&name), TARGET.get()) {
+                    (Some(verb), Some((app, tx))) => perform(app, tx, verb),
+                    _ => Some(Err(format!("Unknown command: {}", name))),
+                };
+                match result? {
+                    Ok(status) => Some(status_record(&status)),
+                    Err(error) => {
+                        self.setScriptErrorNumber(GENERAL_ERROR);
+                        self.setScriptErrorString(Some(&NSString::from_str(&error)));
+                        None
+                    }
+                }
+            }
+        }
+    );
+
+    pub fn install(app: AppHandle, tx: Sender<TimerMessage>) {
+        let _ = TARGET.set((app, tx));
+        // Defining the class registers it with the Objective-C runtime
+        let _ = PomobarScriptCommand::class();
+    }
+
+    /// Builds the `timer status` record declared in `Pomobar.sdef`.
+    fn status_record(status: &ScriptStatus) -> Retained<AnyObject> {
+        let keys = [
+            ns_string!("state"),
+            ns_string!("remaining_seconds"),
+            ns_string!("pomodoros_today"),
+        ];
+        let values: [Retained<AnyObject>; 3] = [
+            NSString::from_str(status.state).into(),
+            NSNumber::new_u32(status.remaining_seconds).into(),
+            NSNumber::new_u32(status.pomodoros_today).into(),
+        ];
+        NSDictionary::<NSString, AnyObject>::from_retained_objects(&keys, &values).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pomobar_core::actor::TimerActor;
+    use pomobar_core::models::TimerMode;
+    use pomobar_core::persistence::Database;
+    use std::sync::mpsc;
+
+    fn create_test_app() -> App {
+        let db = Database::new_in_memory().unwrap();
+        App::new_with_db(db).unwrap()
+    }
+
+    #[test]
+    fn test_command_names_round_trip() {
+        for verb in ScriptVerb::ALL {
+            assert_eq!(
+                ScriptVerb::from_command_name(verb.command_name()),
+                Some(verb)
+            );
+        }
+        assert_eq!(ScriptVerb::from_command_name("skip"), None);
+    }
+
+    #[test]
+    fn test_sdef_declares_every_verb() {
+        let sdef = include_str!("../resources/Pomobar.sdef");
+        for verb in ScriptVerb::ALL {
+            let declaration = format!("<command name=\"{}\"", verb.command_name());
+            assert!(sdef.contains(&declaration), "{}", declaration);
+        }
+        assert!(sdef.contains("<cocoa class=\"PomobarScriptCommand\"/>"));
+    }
+
+    #[test]
+    fn test_verbs_use_app_rules() {
+        let mut app = create_test_app();
+
+        let status = run_verb(&mut app, ScriptVerb::Status).unwrap();
+        assert_eq!(
+            status,
+            ScriptStatus {
+                state: "Idle",
+                remaining_seconds: 0,
+                pomodoros_today: 0,
+            }
+        );
+        assert_eq!(
+            run_verb(&mut app, ScriptVerb::Pause),
+            Err("No running timer to pause".to_string())
+        );
+
+        let status = run_verb(&mut app, ScriptVerb::Start).unwrap();
+        assert_eq!(status.state, "PomodoroActive");
+        assert_eq!(status.remaining_seconds, 25 * 60);
+        assert_eq!(
+            run_verb(&mut app, ScriptVerb::Pause).unwrap().state,
+            "PomodoroPaused"
+        );
+        assert_eq!(
+            run_verb(&mut app, ScriptVerb::Resume).unwrap().state,
+            "PomodoroActive"
+        );
+        assert_eq!(run_verb(&mut app, ScriptVerb::Stop).unwrap().state, "Idle");
+    }
+
+    #[test]
+    fn test_perform_reports_state_changes() {
+        let actor = TimerActor::spawn(create_test_app(), mpsc::channel().0, TimerMode::EventLoop);
+        let (tx, rx) = mpsc::channel();
+
+        let status = perform(&actor.handle(), &tx, ScriptVerb::Start).unwrap();
+        assert_eq!(status.unwrap().state, "PomodoroActive");
+        assert!(matches!(rx.try_recv(), Ok(TimerMessage::StateChanged(_))));
+
+        perform(&actor.handle(), &tx, ScriptVerb::Status)
+            .unwrap()
+            .unwrap();
+        assert!(rx.try_recv().is_err());
+
+        let handle = actor.handle();
+        actor.shutdown();
+        assert_eq!(perform(&handle, &tx, ScriptVerb::Status), None);
+    }
+}
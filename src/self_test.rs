@@ -0,0 +1,313 @@
+//! Timer drift self-test, run with `pomobar --self-test`.
+//!
+//! Drives `App::advance` through a simulated pomodoro on a mock clock with
+//! irregular tick intervals and a long stall, and checks that the pomodoro
+//! ends on time with exactly one completion event. A second check runs the
+//! same tick schedule through the timer actor's loop and the event-loop
+//! timer and checks that both produce the same state sequence.
+
+use pomobar_core::app::{App, CompletionEvent};
+use pomobar_core::models::TimerState;
+use pomobar_core::persistence::Database;
+use pomobar_core::timer::{EventLoopTimer, TickOutput, Ticker, TimerMessage, TICK_INTERVAL};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Shortest and longest simulated interval between two ticks, in milliseconds.
+const JITTER_MS: (u64, u64) = (500, 3000);
+
+/// One tick is delayed this long, as if the timer actor stalled.
+const STALL: Duration = Duration::from_secs(30);
+
+/// Simulated time at which the stall happens.
+const STALL_AT: Duration = Duration::from_secs(10 * 60);
+
+/// How far the countdown or the completion may be off from the true deadline.
+const TOLERANCE: Duration = Duration::from_secs(1);
+
+/// Longest a tick may wake up after it was due in the mode comparison.
+const MAX_LATENESS: Duration = Duration::from_millis(400);
+
+/// Jitter seeds used by `--self-test`.
+const SEEDS: [u64; 5] = [1, 7, 42, 1234, 987654321];
+
+/// A clock that only moves when told to.
+#[derive(Debug, Default)]
+pub struct MockClock {
+    now: Duration,
+}
+
+impl MockClock {
+    /// Time since the clock was created.
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&mut self, by: Duration) {
+        self.now += by;
+    }
+}
+
+/// Small xorshift generator so runs are reproducible without extra dependencies.
+struct Jitter(u64);
+
+impl Jitter {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn next_interval(&mut self) -> Duration {
+        let (min, max) = JITTER_MS;
+        Duration::from_millis(min + self.next_u64() % (max - min + 1))
+    }
+
+    /// Returns a duration below `max`, in whole milliseconds.
+    fn next_below(&mut self, max: Duration) -> Duration {
+        Duration::from_millis(self.next_u64() % max.as_millis() as u64)
+    }
+}
+
+/// Outcome of a drift run.
+#[derive(Debug)]
+pub struct DriftReport {
+    /// When the pomodoro should have ended.
+    pub deadline: Duration,
+    /// Clock time of the tick that reported the completion.
+    pub completed_at: Option<Duration>,
+    /// Number of completion events seen during the run.
+    pub completions: usize,
+    /// Largest difference between the countdown and the true remaining time.
+    pub max_drift: Duration,
+}
+
+/// Simulates one pomodoro with the given jitter seed and checks its timing.
+pub fn run(seed: u64) -> Result<DriftReport, String> {
+    let db = Database::new_in_memory().map_err(|e| e.to_string())?;
+    let mut app = App::new_with_db(db).map_err(|e| e.to_string())?;
+    app.settings.pomodoro_mins = 25;
+
+    let mut clock = MockClock::default();
+    let mut jitter = Jitter(seed.max(1));
+    let deadline = Duration::from_secs(app.settings.pomodoro_mins as u64 * 60);
+    let mut report = DriftReport {
+        deadline,
+        completed_at: None,
+        completions: 0,
+        max_drift: Duration::ZERO,
+    };
+    let mut stalled = false;
+
+    app.start_pomodoro();
+    // Keep going into the break to catch duplicate completions
+    while clock.now() < deadline + STALL {
+        let interval = if !stalled && clock.now() >= STALL_AT {
+            stalled = true;
+            STALL
+        } else {
+            jitter.next_interval()
+        };
+        let previous = clock.now();
+        clock.advance(interval);
+
+        let (_, event) = app.advance(interval);
+        if let Some(event) = event {
+            if !matches!(event, CompletionEvent::PomodoroComplete { .. }) {
+                return Err(format!("unexpected completion event {:?}", event));
+            }
+            report.completions += 1;
+            if report.completed_at.is_none() {
+                report.completed_at = Some(clock.now());
+                // The deadline must fall between the previous tick and this one
+                if previous >= deadline + TOLERANCE || clock.now() + TOLERANCE < deadline {
+                    return Err(format!(
+                        "completed at {:?}, deadline was {:?}",
+                        clock.now(),
+                        deadline
+                    ));
+                }
+            }
+        }
+
+        if let TimerState::PomodoroActive { remaining_secs, .. } = app.state {
+            let expected = deadline.saturating_sub(clock.now());
+            let shown = Duration::from_secs(remaining_secs as u64);
+            report.max_drift = report.max_drift.max(shown.abs_diff(expected));
+        }
+    }
+
+    if report.completions != 1 {
+        return Err(format!(
+            "expected exactly one completion, got {}",
+            report.completions
+        ));
+    }
+    if report.max_drift > TOLERANCE {
+        return Err(format!(
+            "countdown drifted {:?} from the deadline",
+            report.max_drift
+        ));
+    }
+    Ok(report)
+}
+
+/// Runs one tick schedule through both timer modes: the timer actor's loop,
+/// which ticks every time it wakes, and the event-loop timer, which also
+/// wakes for input in between and must only tick when a tick is due.
+/// Returns the number of ticks compared.
+pub fn compare_modes(seed: u64) -> Result<usize, String> {
+    let new_app = || -> Result<App, String> {
+        let db = Database::new_in_memory().map_err(|e| e.to_string())?;
+        let mut app = App::new_with_db(db).map_err(|e| e.to_string())?;
+        app.start_pomodoro();
+        Ok(app)
+    };
+    let mut thread_app = new_app()?;
+    let mut loop_app = new_app()?;
+
+    let base = Instant::now();
+    let wall_base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut thread_ticker = Ticker::new(wall_base);
+    let mut loop_timer = EventLoopTimer::new(base, wall_base);
+
+    let mut clock = MockClock::default();
+    let mut jitter = Jitter(seed.max(1));
+    let settings = &thread_app.settings;
+    let end =
+        Duration::from_secs((settings.pomodoro_mins + settings.short_break_mins + 1) as u64 * 60);
+    let mut stalled = false;
+    let mut ticks = 0;
+
+    while clock.now() < end {
+        // An input event wakes the event loop before the tick is due
+        let input = clock.now() + jitter.next_below(TICK_INTERVAL);
+        if loop_timer
+            .poll(&mut loop_app, base + input, wall_base + input, || 0.0)
+            .is_some()
+        {
+            return Err(format!("event-loop timer ticked early at {:?}", input));
+        }
+
+        // Both modes sleep one second and wake a little late, once very late
+        let lateness = if !stalled && clock.now() >= STALL_AT {
+            stalled = true;
+            STALL
+        } else {
+            jitter.next_below(MAX_LATENESS)
+        };
+        clock.advance(TICK_INTERVAL + lateness);
+        let now = clock.now();
+
+        let thread_output = thread_ticker.step(&mut thread_app, wall_base + now, 0.0);
+        let loop_output = loop_timer
+            .poll(&mut loop_app, base + now, wall_base + now, || 0.0)
+            .ok_or_else(|| format!("event-loop timer missed the tick at {:?}", now))?;
+        ticks += 1;
+
+        if thread_app.state != loop_app.state {
+            return Err(format!(
+                "states differ at {:?}: thread {:?}, event loop {:?}",
+                now, thread_app.state, loop_app.state
+            ));
+        }
+        let (thread_events, loop_events) = (events(&thread_output), events(&loop_output));
+        if thread_events != loop_events {
+            return Err(format!(
+                "messages differ at {:?}: thread {:?}, event loop {:?}",
+                now, thread_events, loop_events
+            ));
+        }
+    }
+
+    if !matches!(thread_app.state, TimerState::BreakFinished) {
+        return Err(format!(
+            "expected the break to be over, got {:?}",
+            thread_app.state
+        ));
+    }
+    Ok(ticks)
+}
+
+/// Describes a tick's messages for comparison. Tooltips are left out
+/// because they contain the current wall-clock time.
+fn events(output: &TickOutput) -> Vec<String> {
+    output
+        .messages
+        .iter()
+        .filter(|msg| !matches!(msg, TimerMessage::TooltipChanged { .. }))
+        .map(|msg| format!("{:?}", msg))
+        .collect()
+}
+
+/// Runs the self-test with a few fixed seeds and prints the results.
+pub fn run_cli() -> Result<(), String> {
+    for seed in SEEDS {
+        let report = run(seed)?;
+        println!(
+            "seed {}: completed at {:?} (deadline {:?}), max drift {:?}",
+            seed,
+            report.completed_at.unwrap_or_default(),
+            report.deadline,
+            report.max_drift
+        );
+    }
+    for seed in SEEDS {
+        let ticks = compare_modes(seed)?;
+        println!(
+            "seed {}: thread and event-loop timers agree over {} ticks",
+            seed, ticks
+        );
+    }
+    println!("self-test passed");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock() {
+        let mut clock = MockClock::default();
+        clock.advance(Duration::from_millis(1500));
+        clock.advance(Duration::from_millis(700));
+        assert_eq!(clock.now(), Duration::from_millis(2200));
+    }
+
+    #[test]
+    fn test_jitter_stays_in_range() {
+        let mut jitter = Jitter(42);
+        for _ in 0..1000 {
+            let interval = jitter.next_interval();
+            assert!(interval >= Duration::from_millis(500));
+            assert!(interval <= Duration::from_secs(3));
+        }
+    }
+
+    #[test]
+    fn test_jitter_below() {
+        let mut jitter = Jitter(7);
+        for _ in 0..1000 {
+            assert!(jitter.next_below(Duration::from_millis(400)) < Duration::from_millis(400));
+        }
+    }
+
+    #[test]
+    fn test_timer_modes_agree() {
+        for seed in SEEDS {
+            let ticks = compare_modes(seed).unwrap();
+            assert!(ticks > 0);
+        }
+    }
+
+    #[test]
+    fn test_drift_within_tolerance() {
+        for seed in SEEDS {
+            let report = run(seed).unwrap();
+            assert_eq!(report.completions, 1);
+            assert!(report.max_drift <= TOLERANCE);
+        }
+    }
+}
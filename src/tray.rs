@@ -0,0 +1,454 @@
+//! Tray icon images.
+//!
+//! The icon is a small dot drawn in code, colored by timer state, so no
+//! image files need to be bundled. It sits next to the text title. With
+//! `TrayDisplay::Icon` or `Both`, a timer with a set length draws a ring
+//! instead that fills up as the timer runs.
+//!
+//! An `icon.png` in the data directory replaces the dot for every state.
+//! It should be 22×22 pixels, or 44×44 for Retina displays. It is read once
+//! at launch, and as it can't show the ring, the title keeps the time.
+
+use pomobar_core::models::{TimerState, TrayDisplay};
+use pomobar_core::persistence;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tray_icon::{BadIcon, Icon, TrayIcon};
+
+#[derive(Error, Debug)]
+pub enum TrayError {
+    #[error("Invalid icon image: {0}")]
+    BadIcon(#[from] BadIcon),
+    #[error("Failed to set tray icon: {0}")]
+    Tray(#[from] tray_icon::Error),
+}
+
+/// Width and height of the icon in pixels.
+const ICON_SIZE: u32 = 22;
+
+/// Radius of the dot in pixels.
+const DOT_RADIUS: f32 = 8.0;
+
+/// Inner and outer radius of the progress ring in pixels.
+const RING_INNER_RADIUS: f32 = 5.5;
+const RING_OUTER_RADIUS: f32 = 9.0;
+
+/// Which icon the tray shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayIconKind {
+    Idle,
+    Pomodoro,
+    Break,
+    Paused,
+}
+
+impl TrayIconKind {
+    /// Returns the icon for a timer state.
+    pub fn from_state(state: &TimerState) -> Self {
+        match state {
+            TimerState::Idle | TimerState::BreakFinished => Self::Idle,
+            TimerState::PomodoroActive { .. }
+            | TimerState::Overtime { .. }
+            | TimerState::StopwatchActive { .. } => Self::Pomodoro,
+            TimerState::BreakActive { .. } => Self::Break,
+            TimerState::PomodoroPaused { .. }
+            | TimerState::BreakPaused { .. }
+            | TimerState::StopwatchPaused { .. } => Self::Paused,
+        }
+    }
+
+    /// Returns the fill color as RGBA.
+    fn color(self) -> [u8; 4] {
+        match self {
+            Self::Idle => [0x80, 0x80, 0x80, 0x80],
+            Self::Pomodoro => [0xDC, 0x32, 0x2F, 0xFF],
+            Self::Break => [0x2A, 0xA1, 0x98, 0xFF],
+            Self::Paused => [0xB5, 0x89, 0x00, 0xFF],
+        }
+    }
+}
+
+/// What the tray icon shows. The ring is only redrawn when its whole
+/// percentage changes, not on every tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrayIconImage {
+    pub kind: TrayIconKind,
+    /// How much of the ring is filled, or None for the dot.
+    pub ring_percent: Option<u8>,
+}
+
+impl TrayIconImage {
+    /// The icon shown before anything has run.
+    pub const IDLE: TrayIconImage = TrayIconImage {
+        kind: TrayIconKind::Idle,
+        ring_percent: None,
+    };
+
+    /// Returns the icon for a timer state. Overtime shows a full ring; the
+    /// stopwatch has no set length and keeps the dot.
+    pub fn new(state: &TimerState, display: TrayDisplay) -> Self {
+        let ring_percent = state
+            .progress_percent()
+            .filter(|_| display.shows_ring())
+            .map(|progress| (progress * 100.0).floor().clamp(0.0, 100.0) as u8);
+        Self {
+            kind: TrayIconKind::from_state(state),
+            ring_percent,
+        }
+    }
+
+    /// Draws the icon as RGBA pixels.
+    fn rgba(self) -> Vec<u8> {
+        match self.ring_percent {
+            Some(percent) => progress_icon_rgba(percent as f32 / 100.0, self.kind),
+            None => icon_rgba(self.kind),
+        }
+    }
+}
+
+/// Draws the icon as RGBA pixels: a filled dot with a smoothed edge on a
+/// transparent background.
+fn icon_rgba(kind: TrayIconKind) -> Vec<u8> {
+    let [r, g, b, a] = kind.color();
+    let center = ICON_SIZE as f32 / 2.0;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let dx = x as f32 + 0.5 - center;
+            let dy = y as f32 + 0.5 - center;
+            // Share of the pixel inside the dot, for a smooth edge
+            let coverage = (DOT_RADIUS + 0.5 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+            rgba.extend_from_slice(&[r, g, b, (a as f32 * coverage).round() as u8]);
+        }
+    }
+    rgba
+}
+
+/// Draws the progress ring as RGBA pixels: the `progress` share of the
+/// ring, clockwise from the top, in full color over a faint full ring.
+fn progress_icon_rgba(progress: f32, kind: TrayIconKind) -> Vec<u8> {
+    let [r, g, b, a] = kind.color();
+    let center = ICON_SIZE as f32 / 2.0;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let dx = x as f32 + 0.5 - center;
+            let dy = y as f32 + 0.5 - center;
+            let distance = (dx * dx + dy * dy).sqrt();
+            // Share of the pixel inside the ring, for smooth edges
+            let coverage = (RING_OUTER_RADIUS + 0.5 - distance)
+                .min(distance - RING_INNER_RADIUS + 0.5)
+                .clamp(0.0, 1.0);
+            // Turns clockwise from the top, from 0 up to 1
+            let turn = dx.atan2(-dy).rem_euclid(std::f32::consts::TAU) / std::f32::consts::TAU;
+            let alpha = if turn < progress { a } else { a / 4 };
+            rgba.extend_from_slice(&[r, g, b, (alpha as f32 * coverage).round() as u8]);
+        }
+    }
+    rgba
+}
+
+/// Returns where a user-supplied icon is looked for.
+fn user_icon_path() -> PathBuf {
+    persistence::data_dir().join("icon.png")
+}
+
+/// Reads and decodes a PNG as RGBA pixels with its width and height.
+/// Returns None if the file doesn't exist or can't be decoded.
+fn load_user_icon_rgba(path: &Path) -> Option<(Vec<u8>, u32, u32)> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            eprintln!("Failed to read tray icon {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    match image::load_from_memory(&bytes) {
+        Ok(img) => {
+            let rgba = img.into_rgba8();
+            let (width, height) = rgba.dimensions();
+            Some((rgba.into_raw(), width, height))
+        }
+        Err(e) => {
+            eprintln!("Failed to decode tray icon {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Loads a user-supplied icon. Returns None if there is no usable one.
+fn load_user_icon(path: &Path) -> Option<Icon> {
+    let (rgba, width, height) = load_user_icon_rgba(path)?;
+    match Icon::from_rgba(rgba, width, height) {
+        Ok(icon) => Some(icon),
+        Err(e) => {
+            eprintln!("Invalid tray icon, using the default: {}", e);
+            None
+        }
+    }
+}
+
+/// The images the tray icon is made from: the user's `icon.png`, read once,
+/// or the generated dot and ring.
+pub struct TrayIcons {
+    user: Option<Icon>,
+}
+
+impl TrayIcons {
+    /// Reads the user's icon, if there is one.
+    pub fn load() -> Self {
+        Self {
+            user: load_user_icon(&user_icon_path()),
+        }
+    }
+
+    /// Returns whether the user's icon is shown. It is the same in every
+    /// state, so it never needs replacing.
+    pub fn has_user_icon(&self) -> bool {
+        self.user.is_some()
+    }
+
+    /// Returns what the tray can show of `chosen`. The user's icon has no
+    /// ring, so it always comes with the text title.
+    pub fn display(&self, chosen: TrayDisplay) -> TrayDisplay {
+        if self.has_user_icon() {
+            TrayDisplay::Text
+        } else {
+            chosen
+        }
+    }
+
+    /// Creates the tray icon: the user's icon if there is one, otherwise the
+    /// generated dot or ring.
+    pub fn icon(&self, image: TrayIconImage) -> Result<Icon, TrayError> {
+        match &self.user {
+            Some(icon) => Ok(icon.clone()),
+            None => Ok(Icon::from_rgba(image.rgba(), ICON_SIZE, ICON_SIZE)?),
+        }
+    }
+
+    /// Replaces the icon of the tray.
+    pub fn set_icon(&self, tray: &TrayIcon, image: TrayIconImage) -> Result<(), TrayError> {
+        tray.set_icon(Some(self.icon(image)?))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn pixel(rgba: &[u8], x: u32, y: u32) -> &[u8] {
+        let i = ((y * ICON_SIZE + x) * 4) as usize;
+        &rgba[i..i + 4]
+    }
+
+    #[test]
+    fn test_kind_from_state() {
+        let running = TimerState::PomodoroActive {
+            remaining_secs: 100,
+            total_secs: 1500,
+        };
+        let on_break = TimerState::BreakActive {
+            is_long_break: false,
+            remaining_secs: 100,
+            total_secs: 300,
+        };
+        let break_paused = TimerState::BreakPaused {
+            is_long_break: true,
+            remaining_secs: 100,
+            total_secs: 900,
+        };
+        assert_eq!(
+            TrayIconKind::from_state(&TimerState::Idle),
+            TrayIconKind::Idle
+        );
+        assert_eq!(
+            TrayIconKind::from_state(&TimerState::BreakFinished),
+            TrayIconKind::Idle
+        );
+        assert_eq!(TrayIconKind::from_state(&running), TrayIconKind::Pomodoro);
+        assert_eq!(TrayIconKind::from_state(&on_break), TrayIconKind::Break);
+        assert_eq!(
+            TrayIconKind::from_state(&break_paused),
+            TrayIconKind::Paused
+        );
+    }
+
+    #[test]
+    fn test_icon_rgba_colors() {
+        let rgba = icon_rgba(TrayIconKind::Pomodoro);
+        assert_eq!(rgba.len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+        assert_eq!(pixel(&rgba, 11, 11), [0xDC, 0x32, 0x2F, 0xFF]);
+        // Corners stay transparent
+        assert_eq!(pixel(&rgba, 0, 0)[3], 0);
+        assert_eq!(pixel(&rgba, 21, 21)[3], 0);
+
+        assert_eq!(
+            pixel(&icon_rgba(TrayIconKind::Break), 11, 11),
+            [0x2A, 0xA1, 0x98, 0xFF]
+        );
+        assert_eq!(
+            pixel(&icon_rgba(TrayIconKind::Paused), 11, 11),
+            [0xB5, 0x89, 0x00, 0xFF]
+        );
+    }
+
+    #[test]
+    fn test_idle_icon_is_semi_transparent() {
+        let rgba = icon_rgba(TrayIconKind::Idle);
+        assert_eq!(pixel(&rgba, 11, 11), [0x80, 0x80, 0x80, 0x80]);
+    }
+
+    #[test]
+    fn test_progress_icon_golden_pixels() {
+        let red = [0xDC, 0x32, 0x2F, 0xFF];
+        let faint_red = [0xDC, 0x32, 0x2F, 0x3F];
+        // Just right of the top, right, bottom and left of the ring
+        let (top, right, bottom, left) = ((11, 3), (18, 11), (11, 18), (3, 10));
+        let at = |rgba: &[u8], (x, y)| pixel(rgba, x, y).to_vec();
+
+        let empty = progress_icon_rgba(0.0, TrayIconKind::Pomodoro);
+        assert_eq!(empty.len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+        for point in [top, right, bottom, left] {
+            assert_eq!(at(&empty, point), faint_red);
+        }
+
+        let half = progress_icon_rgba(0.5, TrayIconKind::Pomodoro);
+        assert_eq!(at(&half, top), red);
+        assert_eq!(at(&half, right), red);
+        assert_eq!(at(&half, bottom), red);
+        assert_eq!(at(&half, left), faint_red);
+
+        let full = progress_icon_rgba(1.0, TrayIconKind::Pomodoro);
+        for point in [top, right, bottom, left] {
+            assert_eq!(at(&full, point), red);
+        }
+
+        for rgba in [empty, half, full] {
+            // The middle and the corners stay transparent
+            assert_eq!(pixel(&rgba, 11, 11)[3], 0);
+            assert_eq!(pixel(&rgba, 0, 0)[3], 0);
+            assert_eq!(pixel(&rgba, 21, 21)[3], 0);
+        }
+    }
+
+    #[test]
+    fn test_progress_icon_colors() {
+        let half = |kind| progress_icon_rgba(0.5, kind);
+        assert_eq!(
+            pixel(&half(TrayIconKind::Break), 18, 11),
+            [0x2A, 0xA1, 0x98, 0xFF]
+        );
+        assert_eq!(
+            pixel(&half(TrayIconKind::Paused), 18, 11),
+            [0xB5, 0x89, 0x00, 0xFF]
+        );
+    }
+
+    #[test]
+    fn test_icon_image_from_state() {
+        let running = |remaining_secs| TimerState::PomodoroActive {
+            remaining_secs,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            TrayIconImage::new(&running(750), TrayDisplay::Text),
+            TrayIconImage {
+                kind: TrayIconKind::Pomodoro,
+                ring_percent: None,
+            }
+        );
+        assert_eq!(
+            TrayIconImage::new(&running(750), TrayDisplay::Icon).ring_percent,
+            Some(50)
+        );
+        // Whole percents, so most ticks don't redraw the ring
+        assert_eq!(
+            TrayIconImage::new(&running(749), TrayDisplay::Both),
+            TrayIconImage::new(&running(745), TrayDisplay::Both)
+        );
+        assert_eq!(
+            TrayIconImage::new(&running(0), TrayDisplay::Both).ring_percent,
+            Some(100)
+        );
+
+        let overtime = TimerState::Overtime {
+            elapsed_overtime_secs: 60,
+            total_secs: 1500,
+        };
+        assert_eq!(
+            TrayIconImage::new(&overtime, TrayDisplay::Icon).ring_percent,
+            Some(100)
+        );
+        let stopwatch = TimerState::StopwatchActive { elapsed_secs: 60 };
+        assert_eq!(
+            TrayIconImage::new(&stopwatch, TrayDisplay::Icon).ring_percent,
+            None
+        );
+        assert_eq!(
+            TrayIconImage::new(&TimerState::Idle, TrayDisplay::Icon),
+            TrayIconImage::IDLE
+        );
+    }
+
+    #[test]
+    fn test_generated_icons() {
+        let icons = TrayIcons { user: None };
+        assert!(icons.icon(TrayIconImage::IDLE).is_ok());
+        let ring = TrayIconImage {
+            kind: TrayIconKind::Break,
+            ring_percent: Some(40),
+        };
+        assert!(icons.icon(ring).is_ok());
+        assert!(!icons.has_user_icon());
+        assert_eq!(icons.display(TrayDisplay::Icon), TrayDisplay::Icon);
+    }
+
+    #[test]
+    fn test_user_icon_keeps_the_title() {
+        let icon = Icon::from_rgba(icon_rgba(TrayIconKind::Idle), ICON_SIZE, ICON_SIZE).unwrap();
+        let icons = TrayIcons { user: Some(icon) };
+        assert!(icons.has_user_icon());
+        for display in TrayDisplay::ALL {
+            assert_eq!(icons.display(display), TrayDisplay::Text);
+        }
+    }
+
+    #[test]
+    fn test_user_icon_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("icon.png");
+        let rgba = icon_rgba(TrayIconKind::Break);
+        image::save_buffer(
+            &path,
+            &rgba,
+            ICON_SIZE,
+            ICON_SIZE,
+            image::ExtendedColorType::Rgba8,
+        )
+        .unwrap();
+
+        assert_eq!(
+            load_user_icon_rgba(&path),
+            Some((rgba, ICON_SIZE, ICON_SIZE))
+        );
+    }
+
+    #[test]
+    fn test_user_icon_missing_or_broken() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("icon.png");
+        assert_eq!(load_user_icon_rgba(&path), None);
+
+        fs::write(&path, b"not a png").unwrap();
+        assert_eq!(load_user_icon_rgba(&path), None);
+        assert!(load_user_icon(&path).is_none());
+    }
+}
@@ -0,0 +1,22 @@
+//! Regression guard for timer accuracy: runs the binary's hidden
+//! `--self-test` mode, which simulates a pomodoro with irregular ticks.
+
+use std::process::Command;
+
+#[test]
+fn test_timer_drift_self_test() {
+    let output = Command::new(env!("CARGO_BIN_EXE_pomobar"))
+        .arg("--self-test")
+        .output()
+        .expect("failed to run pomobar --self-test");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.success(),
+        "self-test failed\nstdout:\n{}\nstderr:\n{}",
+        stdout,
+        stderr
+    );
+    assert!(stdout.contains("self-test passed"));
+}